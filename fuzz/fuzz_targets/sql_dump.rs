@@ -0,0 +1,13 @@
+//! Parses arbitrary text as a whole dump and extracts every table found.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use table_to_csv::{extract_table_rows, parse_sql_content};
+
+fuzz_target!(|content: &str| {
+    if let Ok((tables, content)) = parse_sql_content(content.to_string()) {
+        for table in &tables {
+            let _ = extract_table_rows(&content, table);
+        }
+    }
+});
@@ -0,0 +1,13 @@
+//! Feeds arbitrary text to the value parser as the VALUES list of an INSERT.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use table_to_csv::{extract_insert_values_with_options, ExpressionPolicy, ValueOptions};
+
+fuzz_target!(|values: &str| {
+    let content = format!("INSERT INTO t VALUES ({});\n", values);
+    let _ = extract_insert_values_with_options(&content, "t", &ValueOptions::default());
+    
+    let options = ValueOptions { expressions: ExpressionPolicy::Evaluate, ..Default::default() };
+    let _ = extract_insert_values_with_options(&content, "t", &options);
+});
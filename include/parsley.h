@@ -0,0 +1,39 @@
+/*
+ * C interface to table-to-csv, available when built with `--features ffi`.
+ * Link against the cdylib (libtable_to_csv.so / .dylib / table_to_csv.dll).
+ *
+ * Strings returned through out-parameters are owned by the dump handle and
+ * stay valid until the next call to the same iterator function or parsley_free.
+ */
+#ifndef PARSLEY_H
+#define PARSLEY_H
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct ParsleyDump ParsleyDump;
+
+/* Parse a SQL dump. Returns NULL on failure; see parsley_last_error(). */
+ParsleyDump *parsley_parse(const char *path);
+
+/* Advance to the next table: 1 = table available, 0 = no more tables, -1 = error. */
+int parsley_next_table(ParsleyDump *dump, const char **name,
+                       const char *const **columns, size_t *column_count);
+
+/* Advance to the next row of the current table: 1 = row, 0 = end of table, -1 = error. */
+int parsley_next_row(ParsleyDump *dump, const char *const **values, size_t *value_count);
+
+/* Message of the last error on this thread, or NULL. */
+const char *parsley_last_error(void);
+
+/* Release a dump handle. NULL is ignored. */
+void parsley_free(ParsleyDump *dump);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* PARSLEY_H */
@@ -0,0 +1,193 @@
+use anyhow::Result;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use crate::types::Aggregate;
+
+/// Parse an aggregate list such as `count,sum(amount),avg(amount)`
+pub fn parse_aggregates(text: &str) -> Result<Vec<Aggregate>> {
+    text.split(',')
+        .map(|item| item.trim())
+        .filter(|item| !item.is_empty())
+        .map(parse_aggregate)
+        .collect()
+}
+
+/// Parse a single aggregate: `count`, `count(col)`, `sum(col)`, `avg(col)`, `min(col)` or `max(col)`
+fn parse_aggregate(text: &str) -> Result<Aggregate> {
+    let lower = text.to_lowercase();
+    if lower == "count" || lower == "count(*)" {
+        return Ok(Aggregate::Count);
+    }
+    
+    let (function, column) = text.strip_suffix(')')
+        .and_then(|rest| rest.split_once('('))
+        .map(|(function, column)| (function.trim().to_lowercase(), column.trim().to_string()))
+        .filter(|(_, column)| !column.is_empty())
+        .ok_or_else(|| anyhow::anyhow!("Error: Invalid aggregate '{}'. Use count, count(col), sum(col), avg(col), min(col) or max(col)", text))?;
+    
+    match function.as_str() {
+        "count" => Ok(Aggregate::CountValues(column)),
+        "sum" => Ok(Aggregate::Sum(column)),
+        "avg" => Ok(Aggregate::Avg(column)),
+        "min" => Ok(Aggregate::Min(column)),
+        "max" => Ok(Aggregate::Max(column)),
+        _ => anyhow::bail!("Error: Unknown aggregate function '{}'", function),
+    }
+}
+
+/// Running state of one aggregate within one group
+#[derive(Debug, Clone, Default)]
+struct Accumulator {
+    count: usize,
+    sum: f64,
+    best: Option<String>,
+}
+
+/// Group rows by the given columns and compute the aggregates for each group.
+/// Groups are emitted in order of first appearance.
+pub fn aggregate_rows(
+    headers: &[String],
+    rows: &[Vec<String>],
+    group_by: &[String],
+    aggregates: &[Aggregate],
+) -> Result<(Vec<String>, Vec<Vec<String>>)> {
+    let column_index = |name: &str| {
+        headers.iter().position(|h| h == name)
+            .ok_or_else(|| anyhow::anyhow!("Column '{}' not found in table headers", name))
+    };
+    let group_indexes: Vec<usize> = group_by.iter()
+        .map(|name| column_index(name))
+        .collect::<Result<_>>()?;
+    let aggregate_indexes: Vec<Option<usize>> = aggregates.iter()
+        .map(|aggregate| aggregate.column().map(column_index).transpose())
+        .collect::<Result<_>>()?;
+    
+    let mut order: Vec<Vec<String>> = Vec::new();
+    let mut groups: HashMap<Vec<String>, Vec<Accumulator>> = HashMap::new();
+    
+    for row in rows {
+        let key: Vec<String> = group_indexes.iter()
+            .map(|&i| row.get(i).cloned().unwrap_or_default())
+            .collect();
+        let accumulators = groups.entry(key.clone()).or_insert_with(|| {
+            order.push(key);
+            vec![Accumulator::default(); aggregates.len()]
+        });
+        
+        for ((aggregate, index), accumulator) in aggregates.iter().zip(&aggregate_indexes).zip(accumulators.iter_mut()) {
+            let value = index.and_then(|i| row.get(i)).map(|v| v.as_str());
+            accumulate(aggregate, value, accumulator);
+        }
+    }
+    
+    let mut output_headers = group_by.to_vec();
+    output_headers.extend(aggregates.iter().map(|aggregate| aggregate.header()));
+    
+    let output_rows = order.into_iter()
+        .map(|key| {
+            let accumulators = &groups[&key];
+            let mut row = key;
+            row.extend(aggregates.iter().zip(accumulators).map(|(aggregate, acc)| finish(aggregate, acc)));
+            row
+        })
+        .collect();
+    
+    Ok((output_headers, output_rows))
+}
+
+fn accumulate(aggregate: &Aggregate, value: Option<&str>, accumulator: &mut Accumulator) {
+    if let Aggregate::Count = aggregate {
+        accumulator.count += 1;
+        return;
+    }
+    
+    // NULLs and missing values are ignored, as in SQL
+    let Some(value) = value.filter(|v| !v.is_empty() && !v.eq_ignore_ascii_case("NULL")) else {
+        return;
+    };
+    
+    match aggregate {
+        Aggregate::Count | Aggregate::CountValues(_) => accumulator.count += 1,
+        Aggregate::Sum(_) | Aggregate::Avg(_) => {
+            if let Ok(number) = value.parse::<f64>() {
+                accumulator.sum += number;
+                accumulator.count += 1;
+            }
+        }
+        Aggregate::Min(_) | Aggregate::Max(_) => {
+            let wanted = if matches!(aggregate, Aggregate::Min(_)) { Ordering::Less } else { Ordering::Greater };
+            let replace = accumulator.best.as_deref()
+                .is_none_or(|best| compare_values(value, best) == wanted);
+            if replace {
+                accumulator.best = Some(value.to_string());
+            }
+        }
+    }
+}
+
+fn finish(aggregate: &Aggregate, accumulator: &Accumulator) -> String {
+    match aggregate {
+        Aggregate::Count | Aggregate::CountValues(_) => accumulator.count.to_string(),
+        Aggregate::Sum(_) => format_number(accumulator.sum),
+        Aggregate::Avg(_) if accumulator.count == 0 => String::new(),
+        Aggregate::Avg(_) => format_number(accumulator.sum / accumulator.count as f64),
+        Aggregate::Min(_) | Aggregate::Max(_) => accumulator.best.clone().unwrap_or_default(),
+    }
+}
+
+/// Compare numerically when both values are numbers, otherwise as text
+pub(crate) fn compare_values(a: &str, b: &str) -> Ordering {
+    match (a.parse::<f64>(), b.parse::<f64>()) {
+        (Ok(a), Ok(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+        _ => a.cmp(b),
+    }
+}
+
+/// Format a number without a trailing `.0` for whole values
+pub(crate) fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        format!("{}", value as i64)
+    } else {
+        format!("{}", value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+    
+    #[test]
+    fn test_parse_aggregates() {
+        let aggregates = parse_aggregates("count,sum(amount), max(created_at)").unwrap();
+        assert_eq!(aggregates, vec![
+            Aggregate::Count,
+            Aggregate::Sum("amount".to_string()),
+            Aggregate::Max("created_at".to_string()),
+        ]);
+        assert!(parse_aggregates("median(amount)").is_err());
+    }
+    
+    #[test]
+    fn test_aggregate_rows() {
+        let headers = strings(&["status", "amount"]);
+        let rows = vec![
+            strings(&["paid", "10.5"]),
+            strings(&["open", "3"]),
+            strings(&["paid", "4.5"]),
+            strings(&["paid", "NULL"]),
+        ];
+        let aggregates = parse_aggregates("count,count(amount),sum(amount),avg(amount),min(amount)").unwrap();
+        
+        let (headers, rows) = aggregate_rows(&headers, &rows, &strings(&["status"]), &aggregates).unwrap();
+        assert_eq!(headers, vec!["status", "count", "count_amount", "sum_amount", "avg_amount", "min_amount"]);
+        assert_eq!(rows, vec![
+            strings(&["paid", "3", "2", "15", "7.5", "4.5"]),
+            strings(&["open", "1", "1", "3", "3", "3"]),
+        ]);
+    }
+}
@@ -0,0 +1,216 @@
+//! Dumps inside `.zip`, `.tar` and `.tar.gz` archives.
+//!
+//! Nightly backups often arrive as one archive holding a `.sql` file per
+//! database. Archives are recognized by their content rather than their name,
+//! so they can come from stdin or a URL too; a gzip layer is already undone by
+//! `open_sql_source`. Every `.sql` (or `.sql.gz`) member is converted on its
+//! own and written under a directory named after it, so `shop.sql` gives
+//! `shop/orders.csv`. Other members are skipped. Zip members must be stored or
+//! deflated; encrypted archives are not supported.
+
+use anyhow::{Context, Result};
+use flate2::read::MultiGzDecoder;
+use std::io::{Cursor, Read};
+
+/// A `.sql` file of an archive, decompressed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveMember {
+    /// Path of the member inside the archive
+    pub name: String,
+    pub bytes: Vec<u8>,
+}
+
+/// Check whether input is a zip or tar archive rather than a dump
+pub fn is_archive(bytes: &[u8]) -> bool {
+    is_zip(bytes) || is_tar(bytes)
+}
+
+fn is_zip(bytes: &[u8]) -> bool {
+    bytes.starts_with(b"PK\x03\x04")
+}
+
+fn is_tar(bytes: &[u8]) -> bool {
+    bytes.get(257..262) == Some(b"ustar")
+}
+
+/// The `.sql` and `.sql.gz` members of an archive, in archive order
+pub fn archive_members(bytes: &[u8]) -> Result<Vec<ArchiveMember>> {
+    let files = if is_zip(bytes) { zip_files(bytes)? } else { tar_files(bytes)? };
+    files.into_iter()
+        .filter(|(name, _)| is_dump_name(name))
+        .map(|(name, bytes)| {
+            let bytes = if name.ends_with(".gz") {
+                let mut decompressed = Vec::new();
+                MultiGzDecoder::new(bytes.as_slice()).read_to_end(&mut decompressed)
+                    .with_context(|| format!("Failed to decompress archive member '{}'", name))?;
+                decompressed
+            } else {
+                bytes
+            };
+            Ok(ArchiveMember { name, bytes })
+        })
+        .collect()
+}
+
+fn is_dump_name(name: &str) -> bool {
+    name.ends_with(".sql") || name.ends_with(".sql.gz")
+}
+
+/// Directory a member's tables are written to: its path without the `.sql`
+/// or `.sql.gz` extension. `None` for absolute paths and paths with `..`,
+/// which would write outside the output directory.
+pub fn member_namespace(name: &str) -> Option<String> {
+    let stem = name.strip_suffix(".sql.gz").or_else(|| name.strip_suffix(".sql"))?;
+    let parts: Vec<&str> = stem.split(['/', '\\']).filter(|part| !part.is_empty() && *part != ".").collect();
+    if name.starts_with(['/', '\\']) || parts.is_empty() || parts.iter().any(|part| *part == ".." || part.contains(':')) {
+        return None;
+    }
+    Some(parts.join("/"))
+}
+
+/// Regular files of a tar archive. GNU long names and pax `path` records are
+/// followed; other entry types are skipped.
+fn tar_files(bytes: &[u8]) -> Result<Vec<(String, Vec<u8>)>> {
+    let mut archive = tar::Archive::new(bytes);
+    let mut files = Vec::new();
+    
+    for entry in archive.entries().context("Invalid tar archive")? {
+        let mut entry = entry.context("Invalid tar archive")?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let name = entry.path().context("Invalid tar archive: bad entry name")?.to_string_lossy().into_owned();
+        let mut data = Vec::new();
+        entry.read_to_end(&mut data)
+            .with_context(|| format!("Failed to read archive member '{}'", name))?;
+        // The tar reader stops quietly at the end of the input
+        if data.len() as u64 != entry.size() {
+            anyhow::bail!("Error: Tar archive ends inside member '{}'", name);
+        }
+        files.push((name, data));
+    }
+    
+    Ok(files)
+}
+
+/// The dumps of a zip archive, found through its central directory
+fn zip_files(bytes: &[u8]) -> Result<Vec<(String, Vec<u8>)>> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).context("Invalid zip archive")?;
+    let mut files = Vec::new();
+    
+    for index in 0..archive.len() {
+        // Only dumps are decompressed, so other members can use any method
+        let name = archive.name_for_index(index).unwrap_or_default().to_string();
+        if !is_dump_name(&name) {
+            continue;
+        }
+        let mut file = archive.by_index(index)
+            .with_context(|| format!("Failed to read archive member '{}'", name))?;
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)
+            .with_context(|| format!("Failed to decompress archive member '{}'", name))?;
+        files.push((name, data));
+    }
+    
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+    
+    fn tar_archive(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (name, data) in entries {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            builder.append_data(&mut header, name, *data).unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+    
+    fn zip_archive(entries: &[(&str, zip::CompressionMethod, &[u8])]) -> Vec<u8> {
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        zip.add_directory("dumps/", SimpleFileOptions::default()).unwrap();
+        for (name, method, data) in entries {
+            zip.start_file(*name, SimpleFileOptions::default().compression_method(*method)).unwrap();
+            zip.write_all(data).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+    
+    #[test]
+    fn test_tar_members() {
+        let long = format!("{}/crm.sql", "nested".repeat(20));
+        let tar = tar_archive(&[
+            ("backups/shop.sql", b"INSERT INTO t VALUES (1);"),
+            ("backups/README", b"not a dump"),
+            (&long, b"CREATE TABLE c (id int);"),
+        ]);
+        
+        assert!(is_archive(&tar));
+        let members = archive_members(&tar).unwrap();
+        assert_eq!(members, vec![
+            ArchiveMember { name: "backups/shop.sql".to_string(), bytes: b"INSERT INTO t VALUES (1);".to_vec() },
+            ArchiveMember { name: long, bytes: b"CREATE TABLE c (id int);".to_vec() },
+        ]);
+    }
+    
+    #[test]
+    fn test_zip_members() {
+        let zip = zip_archive(&[
+            ("dumps/shop.sql", zip::CompressionMethod::Deflated, b"INSERT INTO orders VALUES (1);"),
+            ("dumps/crm.sql", zip::CompressionMethod::Stored, b"INSERT INTO c VALUES (2);"),
+        ]);
+        
+        assert!(is_archive(&zip));
+        let members = archive_members(&zip).unwrap();
+        assert_eq!(members.len(), 2);
+        assert_eq!(members[0].name, "dumps/shop.sql");
+        assert_eq!(members[0].bytes, b"INSERT INTO orders VALUES (1);");
+        assert_eq!(members[1].bytes, b"INSERT INTO c VALUES (2);");
+        assert!(!is_archive(b"INSERT INTO t VALUES (1);"));
+    }
+    
+    #[test]
+    fn test_truncated_or_corrupt_tar_is_an_error() {
+        let data = "INSERT INTO t VALUES (1);\n".repeat(40);
+        let tar = tar_archive(&[("shop.sql", data.as_bytes())]);
+        
+        let truncated = &tar[..512 + 100];
+        let error = archive_members(truncated).unwrap_err();
+        assert!(error.to_string().contains("ends inside member 'shop.sql'"), "{}", error);
+        
+        // A damaged header fails its checksum
+        let mut corrupt = tar.clone();
+        corrupt[0] = b'X';
+        assert!(archive_members(&corrupt).is_err());
+    }
+    
+    #[test]
+    fn test_truncated_or_corrupt_zip_is_an_error() {
+        let zip = zip_archive(&[("dumps/shop.sql", zip::CompressionMethod::Stored, b"INSERT INTO orders VALUES (1);")]);
+        
+        let error = archive_members(&zip[..zip.len() - 10]).unwrap_err();
+        assert!(error.to_string().contains("Invalid zip archive"), "{}", error);
+        
+        // A changed byte of member data fails its CRC
+        let at = zip.windows(6).position(|window| window == b"INSERT").unwrap();
+        let mut corrupt = zip.clone();
+        corrupt[at] = b'U';
+        let error = archive_members(&corrupt).unwrap_err();
+        assert!(error.to_string().contains("'dumps/shop.sql'"), "{}", error);
+    }
+    
+    #[test]
+    fn test_member_namespace() {
+        assert_eq!(member_namespace("shop.sql").as_deref(), Some("shop"));
+        assert_eq!(member_namespace("./backups/crm.sql.gz").as_deref(), Some("backups/crm"));
+        assert_eq!(member_namespace("../etc/passwd.sql"), None);
+        assert_eq!(member_namespace("/abs/shop.sql"), None);
+        assert_eq!(member_namespace("notes.txt"), None);
+    }
+}
@@ -0,0 +1,137 @@
+use anyhow::Result;
+
+/// Check whether a boolean flag such as `--quiet` was passed
+pub fn has_flag(args: &[String], flag: &str) -> bool {
+    args.iter().any(|arg| arg == flag)
+}
+
+/// Get the value following a flag, e.g. `--schema-out schema.json`
+pub fn flag_value<'a>(args: &'a [String], flag: &str) -> Result<Option<&'a str>> {
+    match args.iter().position(|arg| arg == flag) {
+        Some(pos) => match args.get(pos + 1) {
+            Some(value) if !value.starts_with("--") => Ok(Some(value)),
+            _ => anyhow::bail!("Error: {} requires a value", flag),
+        },
+        None => Ok(None),
+    }
+}
+
+/// Get the values of a flag that may be repeated, e.g. `--add-column a --add-column b`
+pub fn flag_values<'a>(args: &'a [String], flag: &str) -> Result<Vec<&'a str>> {
+    let mut values = Vec::new();
+    
+    for (pos, arg) in args.iter().enumerate() {
+        if arg == flag {
+            match args.get(pos + 1) {
+                Some(value) if !value.starts_with("--") => values.push(value.as_str()),
+                _ => anyhow::bail!("Error: {} requires a value", flag),
+            }
+        }
+    }
+    
+    Ok(values)
+}
+
+/// A command-line option as shown in the usage text
+#[derive(Debug, Clone, Copy)]
+pub struct CliOption {
+    pub flag: &'static str,
+    /// Value placeholder, e.g. `<file>`; empty for boolean flags
+    pub value: &'static str,
+    pub help: &'static str,
+    /// The option may be given more than once
+    pub repeatable: bool,
+    /// Most arguments the option takes: 0 for boolean flags, 3 for
+    /// `--date-filter <column_name> <start_date> [end_date]`
+    pub arity: usize,
+}
+
+/// Options of the convert command, in the order they are listed by `--help`
+pub const OPTIONS: &[CliOption] = &[
+    CliOption { flag: "--date-filter", value: "<column_name> <start_date> [end_date]", help: "Keep rows within a date range", repeatable: false, arity: 3 },
+    CliOption { flag: "--convert-tz", value: "<column>:<from>-><to>", help: "Rewrite datetimes of a column in another timezone, e.g. createdAt:UTC->America/New_York (repeatable)", repeatable: true, arity: 1 },
+    CliOption { flag: "--reformat-date", value: "<column>:<format>", help: "Write the dates of a column in one strftime format, e.g. createdAt:%Y-%m-%d (repeatable)", repeatable: true, arity: 1 },
+    CliOption { flag: "--policy", value: "<policy.toml>", help: "Export only the columns a compliance policy allows, masking or dropping the rest; fails on unreviewed columns", repeatable: false, arity: 1 },
+    CliOption { flag: "--mask", value: "[<table>.]<column>:<strategy>", help: "Replace a column's values: hash or fake:<name|email|address|phone|uuid> (repeatable)", repeatable: true, arity: 1 },
+    CliOption { flag: "--mask-seed", value: "<n>", help: "Seed of --mask hashes and fakes; the same seed masks values the same way (default: 0)", repeatable: false, arity: 1 },
+    CliOption { flag: "--redact", value: "<regex>=<replacement>", help: "Replace every match of a regex inside values, e.g. SSNs in free-text columns (repeatable)", repeatable: true, arity: 1 },
+    CliOption { flag: "--redact-columns", value: "<column,...>", help: "Apply --redact rules only to these columns (default: all columns)", repeatable: false, arity: 1 },
+    CliOption { flag: "--extract-column", value: "<table>:<column>", help: "Write a large column as one file per row, named by primary key, with its path in the CSV (repeatable)", repeatable: true, arity: 1 },
+    CliOption { flag: "--as-files", value: "<dir>", help: "Directory inside the output directory for the files of the preceding --extract-column", repeatable: false, arity: 1 },
+    CliOption { flag: "--watermark", value: "<table>:<column>", help: "Export only rows whose column is past the largest value of the previous run (repeatable)", repeatable: true, arity: 1 },
+    CliOption { flag: "--since-state", value: "<state.json>", help: "State file of --watermark, read at the start and updated after the tables are written", repeatable: false, arity: 1 },
+    CliOption { flag: "--keys", value: "<table>:<column>=<file>", help: "Keep only rows whose column holds one of the values listed in the file, one per line (repeatable)", repeatable: true, arity: 1 },
+    CliOption { flag: "--cascade-filter", value: "", help: "Keep only rows of related tables consistent with the filtered ones, following foreign keys", repeatable: false, arity: 0 },
+    CliOption { flag: "--config", value: "<file.toml>", help: "Per-table filters (date range, where, sample, limit) applied in order", repeatable: false, arity: 1 },
+    CliOption { flag: "--strict-epoch", value: "", help: "With --date-filter, exclude integer timestamps outside 1970-2100", repeatable: false, arity: 0 },
+    CliOption { flag: "--infer-types", value: "", help: "Detect column types from the data where the DDL has none (or only TEXT)", repeatable: false, arity: 0 },
+    CliOption { flag: "--target-schema", value: "<schema.json|ddl.sql>", help: "Reorder, rename and pad output columns to match destination tables, reporting columns that don't map", repeatable: false, arity: 1 },
+    CliOption { flag: "--databases", value: "<db,...>", help: "Convert only the tables of these databases in dumps switching databases with USE or \\connect", repeatable: false, arity: 1 },
+    CliOption { flag: "--force-headerless", value: "", help: "Export tables whose CREATE TABLE columns could not be parsed, with headers from their INSERTs (col1..colN)", repeatable: false, arity: 0 },
+    CliOption { flag: "--schema-only", value: "", help: "Write only the header row of every table, without reading its rows", repeatable: false, arity: 0 },
+    CliOption { flag: "--schema-out", value: "<file>", help: "Write parsed schema and relations as JSON", repeatable: false, arity: 1 },
+    CliOption { flag: "--emit-ddl", value: "<snowflake|bigquery|postgres|duckdb|mysql>", help: "Write CREATE TABLE statements for the written tables in a destination's dialect to schema.<dialect>.sql in the output directory", repeatable: false, arity: 1 },
+    CliOption { flag: "--load-order", value: "<file>", help: "Write the tables in an order valid for loading, after the tables their foreign keys reference, one per line", repeatable: false, arity: 1 },
+    CliOption { flag: "--number-files", value: "", help: "Prefix output file names with their position in the load order, e.g. 01_users.csv", repeatable: false, arity: 0 },
+    CliOption { flag: "--header-case", value: "<snake|camel|lower|preserve>", help: "Rename CSV headers to a naming convention", repeatable: false, arity: 1 },
+    CliOption { flag: "--file-naming", value: "<lower|preserve|schema>", help: "CSV file names from table names (collisions get a numeric suffix)", repeatable: false, arity: 1 },
+    CliOption { flag: "--locale", value: "<locale>", help: "Write CSVs for Excel in a locale, e.g. de-DE: ; delimiter and decimal commas where decimals use a comma, CRLF and a BOM", repeatable: false, arity: 1 },
+    CliOption { flag: "--rfc4180-strict", value: "", help: "Write strict RFC 4180 CSV (commas, CRLF, no BOM) and validate every file after it is written", repeatable: false, arity: 0 },
+    CliOption { flag: "--safe-excel", value: "", help: "Prefix cells starting with = + - @ with ' so spreadsheets don't run them", repeatable: false, arity: 0 },
+    CliOption { flag: "--input-encoding", value: "<label>", help: "Decode input as e.g. utf-16le, latin1 (default: BOM or UTF-8)", repeatable: false, arity: 1 },
+    CliOption { flag: "--lossy-utf8", value: "", help: "Replace invalid byte sequences with U+FFFD instead of failing", repeatable: false, arity: 0 },
+    CliOption { flag: "--expressions", value: "<verbatim|evaluate>", help: "Keep values like CAST('5' AS INT) or 1+2 as written or compute them (default: verbatim)", repeatable: false, arity: 1 },
+    CliOption { flag: "--pg-types", value: "<raw|json>", help: "Write Postgres arrays, ranges, geometric types and PostGIS geometries as JSON (default: raw)", repeatable: false, arity: 1 },
+    CliOption { flag: "--newline-in-cell", value: "<keep|space|escape>", help: "Keep line breaks inside values, replace them with a space or write them as \\n (default: keep)", repeatable: false, arity: 1 },
+    CliOption { flag: "--trim-cells", value: "<none|edges|collapse>", help: "Trim whitespace at the edges of values, or also collapse runs of it inside to one space (default: none)", repeatable: false, arity: 1 },
+    CliOption { flag: "--on-table-error", value: "<skip|fail|quarantine>", help: "Handle tables that fail to convert; quarantine writes their statements to <table>.errors.sql (default: skip)", repeatable: false, arity: 1 },
+    CliOption { flag: "--on-mismatch", value: "<keep|pad|truncate|skip|error>", help: "Handle rows whose value count differs from the columns (default: keep)", repeatable: false, arity: 1 },
+    CliOption { flag: "--output", value: "<dir|s3://bucket/prefix/|https://...>", help: "Directory, S3/GCS prefix (gzip-compressed) or HTTP endpoint for the tables", repeatable: false, arity: 1 },
+    CliOption { flag: "--post-header", value: "<name: value>", help: "Extra header for HTTP output, e.g. Authorization (repeatable)", repeatable: true, arity: 1 },
+    CliOption { flag: "--post-format", value: "<csv|jsonl>", help: "Body format of HTTP output (default: csv)", repeatable: false, arity: 1 },
+    CliOption { flag: "--post-chunk-rows", value: "<n>", help: "Send HTTP output in requests of at most n rows", repeatable: false, arity: 1 },
+    CliOption { flag: "--format", value: "<csv|template|avro|duckdb|jsonl>", help: "Output format of the per-table files, or one DuckDB file for all tables, which needs the duckdb CLI (default: csv)", repeatable: false, arity: 1 },
+    CliOption { flag: "--template", value: "<file>", help: "minijinja template rendered once per row with --format template", repeatable: false, arity: 1 },
+    CliOption { flag: "--flush-every", value: "<rows>", help: "Flush --format jsonl files every n rows, for readers tailing them during the run", repeatable: false, arity: 1 },
+    CliOption { flag: "--append", value: "", help: "Add to existing --format jsonl files instead of replacing them", repeatable: false, arity: 0 },
+    CliOption { flag: "--interactive", value: "", help: "Pick tables and a date filter in a terminal UI before converting", repeatable: false, arity: 0 },
+    CliOption { flag: "--timings", value: "", help: "Report the time every table spent extracting, filtering, transforming and writing, and the run's slowest phase", repeatable: false, arity: 0 },
+    CliOption { flag: "--deterministic", value: "", help: "Process tables in order and sort the summary for reproducible runs", repeatable: false, arity: 0 },
+    CliOption { flag: "--threads", value: "<n>", help: "Threads extracting tables (default: one per core)", repeatable: false, arity: 1 },
+    CliOption { flag: "--serial", value: "", help: "Extract and write one table at a time", repeatable: false, arity: 0 },
+    CliOption { flag: "--write-threads", value: "<n>", help: "Threads writing tables to the output (default: 2)", repeatable: false, arity: 1 },
+    CliOption { flag: "--queue-size", value: "<n>", help: "Tables waiting for a writer before extraction pauses (default: 4)", repeatable: false, arity: 1 },
+    CliOption { flag: "--max-memory", value: "<size>", help: "Spill rows waiting to be written to temp files beyond this size, e.g. 2GB", repeatable: false, arity: 1 },
+    CliOption { flag: "--temp-dir", value: "<dir>", help: "Directory for temporary files of the run (default: system temp directory)", repeatable: false, arity: 1 },
+    CliOption { flag: "--buffer-size", value: "<bytes>", help: "Write buffer of each output file (default: 8192)", repeatable: false, arity: 1 },
+    CliOption { flag: "--fsync", value: "", help: "Sync output files to disk before reporting them as created", repeatable: false, arity: 0 },
+    CliOption { flag: "--max-total-rows", value: "<n>", help: "Stop writing after n rows over all tables", repeatable: false, arity: 1 },
+    CliOption { flag: "--max-table-rows", value: "<n>", help: "Stop writing a table after n rows", repeatable: false, arity: 1 },
+    CliOption { flag: "--max-output-bytes", value: "<n>", help: "Stop writing after about n bytes of values over all tables", repeatable: false, arity: 1 },
+    CliOption { flag: "--quiet", value: "", help: "Print nothing on stdout; warnings and errors still go to stderr", repeatable: false, arity: 0 },
+    CliOption { flag: "--porcelain", value: "", help: "Print only a JSON manifest of the written files on stdout, for scripts", repeatable: false, arity: 0 },
+    CliOption { flag: "--encrypt", value: "age:<recipient>", help: "Encrypt output files to an age public key while writing them, as <file>.age (repeatable; needs --features encrypt)", repeatable: true, arity: 1 },
+    CliOption { flag: "--checksum", value: "<sha256|md5|none>", help: "Write a <file>.sha256 or <file>.md5 next to every output file (default: none)", repeatable: false, arity: 1 },
+    CliOption { flag: "--metadata", value: "", help: "Write a <table>.meta.json with column types, null counts and min/max next to every output file", repeatable: false, arity: 0 },
+    CliOption { flag: "--on-limit", value: "<abort|stop-table>", help: "Fail or keep partial tables when a limit is reached (default: abort)", repeatable: false, arity: 1 },
+    CliOption { flag: "--max-field-bytes", value: "<n>", help: "Limit the size of a single cell", repeatable: false, arity: 1 },
+    CliOption { flag: "--on-oversized-field", value: "<truncate|skip-row|error>", help: "Handle cells over --max-field-bytes (default: truncate)", repeatable: false, arity: 1 },
+    CliOption { flag: "--add-column", value: "<table>:<column>=<expression>", help: "Append a computed column (repeatable, table may be *)", repeatable: true, arity: 1 },
+    CliOption { flag: "--add-source-column", value: "", help: "Append a _source_file column with the SQL file name", repeatable: false, arity: 0 },
+    CliOption { flag: "--add-source-line", value: "", help: "Append a _source_line column with the line of the dump each row starts on", repeatable: false, arity: 0 },
+    CliOption { flag: "--add-dump-timestamp", value: "", help: "Append a _dump_timestamp column from the dump's header comments", repeatable: false, arity: 0 },
+    CliOption { flag: "--join", value: "<table>.<column>=<table>.<column>", help: "Also write a CSV joining two tables on a key", repeatable: false, arity: 1 },
+    CliOption { flag: "--select", value: "<table>.<column|*>,...", help: "Columns of the joined CSV (default: all columns of both)", repeatable: false, arity: 1 },
+    CliOption { flag: "--join-type", value: "<inner|left>", help: "Drop or keep rows without a match (default: inner)", repeatable: false, arity: 1 },
+    CliOption { flag: "--join-output", value: "<file>", help: "File name of the joined CSV (default: <left>_<right>.csv)", repeatable: false, arity: 1 },
+];
+
+/// Usage text lines for `OPTIONS`, with the descriptions aligned
+pub fn options_usage() -> Vec<String> {
+    OPTIONS.iter()
+        .map(|option| {
+            let usage = format!("{} {}", option.flag, option.value);
+            format!("  {:<53}{}", usage.trim_end(), option.help)
+        })
+        .collect()
+}
@@ -0,0 +1,377 @@
+//! Async conversion API (feature `async`).
+//!
+//! Lets a tokio service drive a conversion without `spawn_blocking`.
+//! `convert_async` runs the conversion loop of `Converter::convert` and writes
+//! through [`AsyncOutputSink`]. [`AsyncCsvSink`] writes a directory of CSV
+//! files with tokio's file IO, and with the `object-store` feature
+//! `AsyncObjectStoreSink` uploads compressed CSVs to S3 or GCS. HTTP output
+//! and the `load` targets use blocking clients and have no async sink;
+//! implement [`AsyncOutputSink`] with an async client for them. Parsing the
+//! dump is still CPU work done on the calling task.
+
+use anyhow::{Context, Result};
+use std::borrow::Cow;
+use std::future::Future;
+use std::io::{self, Cursor, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::fs::{self, File};
+use tokio::io::{AsyncWriteExt, BufWriter};
+
+use crate::convert::{Converter, Destination, DestinationTable};
+use crate::csv_writer::CsvTableSink;
+use crate::input::{decompress, read_sql};
+use crate::output_file::temp_path;
+use crate::report::ConversionReport;
+use crate::sink::TableSink;
+use crate::types::{CsvOptions, InputOptions, Table};
+
+/// Async counterpart of `OutputSink`
+pub trait AsyncOutputSink: Sync {
+    type TableSink<'a>: AsyncTableSink + Send where Self: 'a;
+    
+    /// Start writing a table. `name` is the file stem chosen for it and
+    /// `headers` are the output columns.
+    fn open_table<'a>(&'a self, table: &Table, name: &str, headers: &[String]) -> impl Future<Output = Result<Self::TableSink<'a>>> + Send;
+}
+
+/// Async counterpart of `TableSink`
+pub trait AsyncTableSink {
+    fn write_row(&mut self, row: &[String]) -> impl Future<Output = Result<()>> + Send;
+    
+    /// Write a row whose values may be borrowed from the dump, like
+    /// `TableSink::write_fields`. By default the values are copied for `write_row`.
+    fn write_fields(&mut self, row: &[Cow<'_, str>]) -> impl Future<Output = Result<()>> + Send
+    where
+        Self: Send,
+    {
+        let row: Vec<String> = row.iter().map(|value| value.to_string()).collect();
+        async move { self.write_row(&row).await }
+    }
+    
+    /// Complete the table. Returns where it was written, such as a path or URL.
+    fn finish(self) -> impl Future<Output = Result<String>> + Send;
+}
+
+/// Directory of CSV files written with async file IO. Like `CsvSink`, files
+/// are written as `<name>.csv.<pid>.tmp` and renamed when complete. Encrypted output
+/// (`CsvOptions::encrypt_to`) is not supported; use `CsvSink` for it.
+pub struct AsyncCsvSink {
+    pub dir: PathBuf,
+    pub options: CsvOptions,
+}
+
+impl AsyncOutputSink for AsyncCsvSink {
+    type TableSink<'a> = AsyncCsvTableSink;
+    
+    async fn open_table(&self, table: &Table, name: &str, headers: &[String]) -> Result<AsyncCsvTableSink> {
+        if !self.options.encrypt_to.is_empty() {
+            anyhow::bail!("Error: AsyncCsvSink cannot encrypt its output; use CsvSink with encryption");
+        }
+        let path = self.dir.join(format!("{}.csv", name));
+        let temp_path = temp_path(&path);
+        
+        let file = File::create(&temp_path).await
+            .with_context(|| format!("Failed to create output file '{}'", temp_path.display()))?;
+        let file = match self.options.buffer_size {
+            Some(size) => BufWriter::with_capacity(size, file),
+            None => BufWriter::new(file),
+        };
+        let buffer = SharedBuffer::default();
+        let csv = CsvTableSink::new(buffer.clone(), headers, &self.options, path.to_string_lossy().to_string())?
+            .with_column_types(table);
+        
+        Ok(AsyncCsvTableSink {
+            csv: Some(csv),
+            buffer,
+            file: Some(file),
+            path,
+            temp_path,
+            fsync: self.options.fsync,
+        })
+    }
+}
+
+/// One CSV file of an `AsyncCsvSink`. Dropping it without `finish` removes
+/// the temporary file.
+pub struct AsyncCsvTableSink {
+    csv: Option<CsvTableSink<SharedBuffer>>,
+    buffer: SharedBuffer,
+    file: Option<BufWriter<File>>,
+    path: PathBuf,
+    temp_path: PathBuf,
+    fsync: bool,
+}
+
+/// Collects the CSV produced synchronously until it is written to the file
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl SharedBuffer {
+    fn take(&self) -> Vec<u8> {
+        std::mem::take(&mut *self.0.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+    
+    #[cfg(feature = "object-store")]
+    fn len(&self) -> usize {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).extend_from_slice(buf);
+        Ok(buf.len())
+    }
+    
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl AsyncCsvTableSink {
+    /// Write out what the CSV writer has handed over so far
+    async fn write_pending(&mut self) -> Result<()> {
+        let pending = self.buffer.take();
+        if let Some(file) = self.file.as_mut().filter(|_| !pending.is_empty()) {
+            file.write_all(&pending).await.context("Failed to write CSV row")?;
+        }
+        Ok(())
+    }
+}
+
+impl AsyncTableSink for AsyncCsvTableSink {
+    async fn write_row(&mut self, row: &[String]) -> Result<()> {
+        if let Some(csv) = self.csv.as_mut() {
+            csv.write_row(row)?;
+        }
+        self.write_pending().await
+    }
+    
+    async fn write_fields(&mut self, row: &[Cow<'_, str>]) -> Result<()> {
+        if let Some(csv) = self.csv.as_mut() {
+            csv.write_fields(row)?;
+        }
+        self.write_pending().await
+    }
+    
+    async fn finish(mut self) -> Result<String> {
+        let Some(csv) = self.csv.take() else {
+            anyhow::bail!("CSV already finished");
+        };
+        let location = csv.location().to_string();
+        csv.into_inner()?;
+        self.write_pending().await?;
+        let Some(mut file) = self.file.take() else {
+            return Ok(location);
+        };
+        file.flush().await.context("Failed to flush output file")?;
+        if self.fsync {
+            file.get_ref().sync_all().await.context("Failed to sync output file to disk")?;
+        }
+        drop(file);
+        
+        fs::rename(&self.temp_path, &self.path).await
+            .with_context(|| format!("Failed to move '{}' to '{}'", self.temp_path.display(), self.path.display()))?;
+        Ok(location)
+    }
+}
+
+impl Drop for AsyncCsvTableSink {
+    fn drop(&mut self) {
+        if self.file.take().is_some() {
+            let _ = std::fs::remove_file(&self.temp_path);
+        }
+    }
+}
+
+/// S3 or GCS prefix the tables are uploaded to as `<name>.csv.gz` with the
+/// object store's async client (features `async` and `object-store`). Like
+/// `ObjectStoreOutput`, CSVs are compressed while they are written and
+/// uploaded in parts.
+#[cfg(feature = "object-store")]
+pub struct AsyncObjectStoreSink {
+    store: Arc<dyn object_store::ObjectStore>,
+    prefix: object_store::path::Path,
+    url: String,
+    options: CsvOptions,
+}
+
+#[cfg(feature = "object-store")]
+impl AsyncObjectStoreSink {
+    /// Upload to an `s3://bucket/prefix/` or `gs://bucket/prefix/` URL, with
+    /// credentials from the `AWS_*` and `GOOGLE_*` environment variables
+    pub fn from_url(location: &str, options: CsvOptions) -> Result<Self> {
+        let (store, prefix) = crate::cloud::connect_store(location)?;
+        Ok(AsyncObjectStoreSink { store, prefix, url: location.trim_end_matches('/').to_string(), options })
+    }
+}
+
+#[cfg(feature = "object-store")]
+impl AsyncOutputSink for AsyncObjectStoreSink {
+    type TableSink<'a> = AsyncObjectTableSink;
+    
+    async fn open_table(&self, table: &Table, name: &str, headers: &[String]) -> Result<AsyncObjectTableSink> {
+        if !self.options.encrypt_to.is_empty() {
+            anyhow::bail!("Error: AsyncObjectStoreSink cannot encrypt its output");
+        }
+        let filename = format!("{}.csv.gz", name);
+        let path = self.prefix.parts().chain(object_store::path::Path::from(filename.as_str()).parts()).collect();
+        let buffer = SharedBuffer::default();
+        let encoder = flate2::write::GzEncoder::new(buffer.clone(), flate2::Compression::default());
+        let csv = CsvTableSink::new(encoder, headers, &self.options, format!("{}/{}", self.url, filename))?
+            .with_column_types(table);
+        
+        Ok(AsyncObjectTableSink {
+            csv,
+            buffer,
+            upload: object_store::buffered::BufWriter::with_capacity(Arc::clone(&self.store), path, crate::cloud::PART_SIZE),
+        })
+    }
+}
+
+/// One compressed CSV of an `AsyncObjectStoreSink`, uploaded in parts
+#[cfg(feature = "object-store")]
+pub struct AsyncObjectTableSink {
+    csv: CsvTableSink<flate2::write::GzEncoder<SharedBuffer>>,
+    buffer: SharedBuffer,
+    upload: object_store::buffered::BufWriter,
+}
+
+#[cfg(feature = "object-store")]
+impl AsyncObjectTableSink {
+    /// Hand the compressed bytes to the upload once there is a part of them
+    async fn upload_part(&mut self) -> Result<()> {
+        if self.buffer.len() >= crate::cloud::PART_SIZE {
+            self.upload.put(self.buffer.take().into()).await.context("Failed to upload CSV")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "object-store")]
+impl AsyncTableSink for AsyncObjectTableSink {
+    async fn write_row(&mut self, row: &[String]) -> Result<()> {
+        self.csv.write_row(row)?;
+        self.upload_part().await
+    }
+    
+    async fn write_fields(&mut self, row: &[Cow<'_, str>]) -> Result<()> {
+        self.csv.write_fields(row)?;
+        self.upload_part().await
+    }
+    
+    async fn finish(mut self) -> Result<String> {
+        let url = self.csv.location().to_string();
+        self.csv.into_inner()?
+            .finish()
+            .context("Failed to compress CSV")?;
+        self.upload.put(self.buffer.take().into()).await.context("Failed to upload CSV")?;
+        self.upload.shutdown().await.context("Failed to complete upload")?;
+        Ok(url)
+    }
+}
+
+// Async sinks go to the conversion loop shared with `convert` as they are
+impl<S: AsyncOutputSink> Destination for S {
+    type Table<'a> = AsyncTable<S::TableSink<'a>> where Self: 'a;
+    
+    async fn open_table<'a>(&'a self, table: &Table, name: &str, headers: &[String]) -> Result<Self::Table<'a>> {
+        Ok(AsyncTable(AsyncOutputSink::open_table(self, table, name, headers).await?))
+    }
+}
+
+pub(crate) struct AsyncTable<T>(T);
+
+impl<T: AsyncTableSink + Send> DestinationTable for AsyncTable<T> {
+    async fn write_fields(&mut self, row: &[Cow<'_, str>]) -> Result<()> {
+        self.0.write_fields(row).await
+    }
+    
+    async fn finish(self) -> Result<(String, Option<u64>)> {
+        Ok((self.0.finish().await?, None))
+    }
+}
+
+impl Converter {
+    /// Async version of `convert`, writing every table that has rows to the sink
+    pub async fn convert_async<S: AsyncOutputSink>(&self, sql: &str, sink: &S) -> Result<ConversionReport> {
+        self.convert_to(sql, sink).await
+    }
+    
+    /// Read a dump file (plain or gzip-compressed) with async file IO and convert it
+    pub async fn convert_file_async<S: AsyncOutputSink>(&self, path: impl AsRef<Path>, input: &InputOptions, sink: &S) -> Result<ConversionReport> {
+        let bytes = fs::read(path).await.context("Failed to read SQL file")?;
+        let (content, _) = read_sql(decompress(Cursor::new(bytes))?, input)?;
+        self.convert_async(&content, sink).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_convert_async_to_csv_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let sql = "CREATE TABLE users (\n  id int,\n  name varchar(50)\n);\nINSERT INTO users VALUES (1,'Alice');\n";
+        let sink = AsyncCsvSink { dir: dir.to_path_buf(), options: CsvOptions::default() };
+        
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let conversion = runtime.block_on(Converter::default().convert_async(sql, &sink)).unwrap();
+        
+        assert_eq!(conversion.tables.len(), 1);
+        assert_eq!(conversion.tables[0].file.rows_written, 1);
+        assert_eq!(std::fs::read_to_string(dir.join("users.csv")).unwrap(), "id,name\n1,Alice\n");
+        assert!(!temp_path(&dir.join("users.csv")).exists());
+    }
+    
+    #[test]
+    fn test_async_csv_sink_rejects_encryption() {
+        let options = CsvOptions { encrypt_to: vec!["age1recipient".to_string()], ..Default::default() };
+        let sink = AsyncCsvSink { dir: std::env::temp_dir(), options };
+        
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let sql = "CREATE TABLE t (\n  id int\n);\nINSERT INTO t VALUES (1);\n";
+        let error = runtime.block_on(Converter::default().convert_async(sql, &sink)).unwrap_err();
+        
+        assert!(format!("{error:#}").contains("cannot encrypt"), "{error:#}");
+    }
+    
+    #[cfg(feature = "object-store")]
+    #[test]
+    fn test_convert_async_to_object_store() {
+        use flate2::read::GzDecoder;
+        use object_store::memory::InMemory;
+        use object_store::ObjectStore;
+        use std::io::Read;
+        
+        let store = Arc::new(InMemory::new());
+        let sink = AsyncObjectStoreSink {
+            store: store.clone(),
+            prefix: "exports".into(),
+            url: "memory://bucket/exports".to_string(),
+            options: CsvOptions::default(),
+        };
+        let sql = "CREATE TABLE users (\n  id int,\n  name varchar(50)\n);\nINSERT INTO users VALUES (1,'Alice');\n";
+        
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let conversion = runtime.block_on(Converter::default().convert_async(sql, &sink)).unwrap();
+        assert_eq!(conversion.tables[0].file.path, "memory://bucket/exports/users.csv.gz");
+        
+        let compressed = runtime.block_on(async {
+            store.get(&"exports/users.csv.gz".into()).await.unwrap().bytes().await.unwrap()
+        });
+        let mut csv = String::new();
+        GzDecoder::new(&compressed[..]).read_to_string(&mut csv).unwrap();
+        assert_eq!(csv, "id,name\n1,Alice\n");
+    }
+    
+    #[test]
+    fn test_convert_async_can_be_spawned() {
+        fn assert_send<T: Send>(_: T) {}
+        let sink = AsyncCsvSink { dir: std::env::temp_dir(), options: CsvOptions::default() };
+        assert_send(Converter::default().convert_async("", &sink));
+    }
+}
@@ -0,0 +1,377 @@
+//! Avro object container file output (feature `avro`).
+//!
+//! Every column becomes a nullable field whose type is derived from the declared
+//! SQL type: integers map to `long`, floating point types to `double`, booleans
+//! to `boolean` and everything else (including decimals and dates) to `string`.
+
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::output_file::{FileTableSink, OutputFile};
+use crate::report::WrittenFile;
+use crate::sink::{OutputSink, TableSink};
+use crate::types::{CsvOptions, Table};
+
+/// Rows written per container file block
+const BLOCK_SIZE: usize = 1000;
+
+/// Avro type a column is written as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AvroType {
+    Long,
+    Double,
+    Boolean,
+    String,
+}
+
+impl AvroType {
+    /// Derive the Avro type from a declared SQL column type
+    pub fn from_sql_type(sql_type: &str) -> AvroType {
+        let sql_type = sql_type.to_uppercase();
+        let base = sql_type.split(|c: char| c == '(' || c.is_whitespace()).next().unwrap_or("");
+        
+        // MySQL booleans are TINYINT(1)
+        if base == "TINYINT" && sql_type.starts_with("TINYINT(1)") {
+            return AvroType::Boolean;
+        }
+        match base {
+            "INT" | "INTEGER" | "SMALLINT" | "TINYINT" | "MEDIUMINT" | "BIGINT" | "INT2" | "INT4" | "INT8"
+            | "SERIAL" | "BIGSERIAL" | "SMALLSERIAL" => AvroType::Long,
+            "FLOAT" | "FLOAT4" | "FLOAT8" | "DOUBLE" | "REAL" => AvroType::Double,
+            "BOOL" | "BOOLEAN" => AvroType::Boolean,
+            _ => AvroType::String,
+        }
+    }
+    
+    fn name(self) -> &'static str {
+        match self {
+            AvroType::Long => "long",
+            AvroType::Double => "double",
+            AvroType::Boolean => "boolean",
+            AvroType::String => "string",
+        }
+    }
+}
+
+/// Build the record schema for a table. `column_types` are the declared types
+/// of `headers`, position by position; columns without one (such as derived
+/// columns) are strings.
+pub fn avro_schema(table: &str, headers: &[String], column_types: &[String]) -> Value {
+    let fields: Vec<Value> = headers.iter()
+        .enumerate()
+        .map(|(i, header)| {
+            let avro_type = column_types.get(i)
+                .map(|sql_type| AvroType::from_sql_type(sql_type))
+                .unwrap_or(AvroType::String);
+            json!({
+                "name": avro_name(header),
+                "type": ["null", avro_type.name()],
+                "default": null,
+            })
+        })
+        .collect();
+    
+    json!({
+        "type": "record",
+        "name": avro_name(table),
+        "fields": fields,
+    })
+}
+
+/// Make a name valid for Avro (`[A-Za-z_][A-Za-z0-9_]*`)
+fn avro_name(name: &str) -> String {
+    let mut avro_name: String = name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if !avro_name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        avro_name.insert(0, '_');
+    }
+    avro_name
+}
+
+/// Write the rows of a table to an Avro object container file
+pub fn write_avro(
+    filename: &str,
+    table: &str,
+    headers: &[String],
+    column_types: &[String],
+    rows: &[Vec<String>],
+) -> Result<WrittenFile> {
+    let mut file = OutputFile::create(Path::new(filename), &CsvOptions::default())
+        .context("Failed to create Avro file")?;
+    write_avro_to(&mut file, table, headers, column_types, rows)?;
+    let path = file.path().to_string_lossy().to_string();
+    let bytes = file.finish()?;
+    
+    Ok(WrittenFile {
+        path,
+        rows_written: rows.len(),
+        bytes: Some(bytes),
+        warnings: Vec::new(),
+    })
+}
+
+/// Write the rows of a table as an Avro object container to any destination
+pub fn write_avro_to<W: Write>(
+    destination: W,
+    table: &str,
+    headers: &[String],
+    column_types: &[String],
+    rows: &[Vec<String>],
+) -> Result<()> {
+    let mut sink = AvroTableSink::new(destination, table, headers, column_types, String::new())?;
+    for row in rows {
+        sink.write_row(row)?;
+    }
+    Box::new(sink).finish()?;
+    
+    Ok(())
+}
+
+/// Directory of Avro container files, one per table
+pub struct AvroSink {
+    pub dir: PathBuf,
+    /// Buffering and sync settings of the files
+    pub options: CsvOptions,
+}
+
+impl OutputSink for AvroSink {
+    fn open_table<'a>(&'a self, table: &Table, name: &str, headers: &[String]) -> Result<Box<dyn TableSink + 'a>> {
+        let path = self.dir.join(format!("{}.avro", name));
+        let file = OutputFile::create(&path, &self.options)
+            .context("Failed to create Avro file")?;
+        let location = file.path().to_string_lossy().to_string();
+        // Written columns can be dropped, reordered or computed, so their
+        // types are matched to the table's by name
+        let column_types: Vec<String> = headers.iter()
+            .map(|header| table.columns.iter().position(|column| column == header)
+                .and_then(|i| table.column_types.get(i).cloned())
+                .unwrap_or_default())
+            .collect();
+        let container = AvroTableSink::new(file, &table.name, headers, &column_types, location.clone())?;
+        Ok(Box::new(FileTableSink::new(container, AvroTableSink::into_inner, location)))
+    }
+}
+
+/// Streams the rows of one table into an Avro container, one block per `BLOCK_SIZE` rows
+struct AvroTableSink<W: Write> {
+    destination: W,
+    headers: Vec<String>,
+    types: Vec<AvroType>,
+    sync_marker: [u8; 16],
+    block: Vec<u8>,
+    block_rows: usize,
+    row_number: usize,
+    location: String,
+}
+
+impl<W: Write> AvroTableSink<W> {
+    /// Write the container header: magic, metadata map and sync marker
+    fn new(
+        mut destination: W,
+        table: &str,
+        headers: &[String],
+        column_types: &[String],
+        location: String,
+    ) -> Result<Self> {
+        let types: Vec<AvroType> = (0..headers.len())
+            .map(|i| column_types.get(i).map(|t| AvroType::from_sql_type(t)).unwrap_or(AvroType::String))
+            .collect();
+        let schema = avro_schema(table, headers, column_types).to_string();
+        let sync_marker = sync_marker();
+        
+        let mut header = b"Obj\x01".to_vec();
+        encode_long(&mut header, 2);
+        encode_bytes(&mut header, b"avro.schema");
+        encode_bytes(&mut header, schema.as_bytes());
+        encode_bytes(&mut header, b"avro.codec");
+        encode_bytes(&mut header, b"null");
+        encode_long(&mut header, 0);
+        header.extend_from_slice(&sync_marker);
+        destination.write_all(&header)?;
+        
+        Ok(AvroTableSink {
+            destination,
+            headers: headers.to_vec(),
+            types,
+            sync_marker,
+            block: Vec::new(),
+            block_rows: 0,
+            row_number: 0,
+            location,
+        })
+    }
+    
+    fn write_block(&mut self) -> Result<()> {
+        if self.block_rows == 0 {
+            return Ok(());
+        }
+        let mut block_header = Vec::new();
+        encode_long(&mut block_header, self.block_rows as i64);
+        encode_long(&mut block_header, self.block.len() as i64);
+        self.destination.write_all(&block_header)?;
+        self.destination.write_all(&self.block)?;
+        self.destination.write_all(&self.sync_marker)?;
+        
+        self.block.clear();
+        self.block_rows = 0;
+        Ok(())
+    }
+    
+    /// Write the last block and give back the destination
+    fn into_inner(mut self) -> Result<W> {
+        self.write_block()?;
+        Ok(self.destination)
+    }
+}
+
+impl<W: Write> TableSink for AvroTableSink<W> {
+    fn write_row(&mut self, row: &[String]) -> Result<()> {
+        self.row_number += 1;
+        for (j, avro_type) in self.types.iter().enumerate() {
+            let value = row.get(j).map(String::as_str).unwrap_or("NULL");
+            encode_value(&mut self.block, *avro_type, value)
+                .with_context(|| format!("Row {} column '{}'", self.row_number, self.headers[j]))?;
+        }
+        
+        self.block_rows += 1;
+        if self.block_rows == BLOCK_SIZE {
+            self.write_block()?;
+        }
+        Ok(())
+    }
+    
+    fn finish(mut self: Box<Self>) -> Result<String> {
+        self.write_block()?;
+        self.destination.flush()
+            .context("Failed to flush Avro writer")?;
+        Ok(self.location)
+    }
+}
+
+/// Encode a value as the `["null", type]` union
+fn encode_value(buffer: &mut Vec<u8>, avro_type: AvroType, value: &str) -> Result<()> {
+    if value == "NULL" {
+        encode_long(buffer, 0);
+        return Ok(());
+    }
+    
+    encode_long(buffer, 1);
+    match avro_type {
+        AvroType::Long => {
+            let number: i64 = value.trim().parse()
+                .with_context(|| format!("'{}' is not an integer", value))?;
+            encode_long(buffer, number);
+        }
+        AvroType::Double => {
+            let number: f64 = value.trim().parse()
+                .with_context(|| format!("'{}' is not a number", value))?;
+            buffer.extend_from_slice(&number.to_le_bytes());
+        }
+        AvroType::Boolean => {
+            let flag = match value.trim().to_lowercase().as_str() {
+                "1" | "true" | "t" => true,
+                "0" | "false" | "f" => false,
+                _ => anyhow::bail!("'{}' is not a boolean", value),
+            };
+            buffer.push(flag as u8);
+        }
+        AvroType::String => encode_bytes(buffer, value.as_bytes()),
+    }
+    
+    Ok(())
+}
+
+/// Zig-zag variable-length encoding used for Avro ints and longs
+fn encode_long(buffer: &mut Vec<u8>, value: i64) {
+    let mut n = ((value << 1) ^ (value >> 63)) as u64;
+    while n >= 0x80 {
+        buffer.push((n as u8) | 0x80);
+        n >>= 7;
+    }
+    buffer.push(n as u8);
+}
+
+/// Length-prefixed bytes, also used for strings
+fn encode_bytes(buffer: &mut Vec<u8>, bytes: &[u8]) {
+    encode_long(buffer, bytes.len() as i64);
+    buffer.extend_from_slice(bytes);
+}
+
+/// Random 16-byte marker separating the blocks of a container file
+fn sync_marker() -> [u8; 16] {
+    let mut marker = [0; 16];
+    for half in marker.chunks_mut(8) {
+        half.copy_from_slice(&RandomState::new().build_hasher().finish().to_le_bytes());
+    }
+    marker
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_avro_schema() {
+        let headers = vec!["id".to_string(), "price".to_string(), "active".to_string(), "created-at".to_string()];
+        let types = vec!["BIGINT".to_string(), "DOUBLE PRECISION".to_string(), "tinyint(1)".to_string()];
+        
+        let schema = avro_schema("order items", &headers, &types);
+        assert_eq!(schema["name"], "order_items");
+        let field_types: Vec<&Value> = schema["fields"].as_array().unwrap().iter().map(|f| &f["type"][1]).collect();
+        assert_eq!(field_types, vec!["long", "double", "boolean", "string"]);
+        assert_eq!(schema["fields"][3]["name"], "created_at");
+    }
+    
+    #[test]
+    fn test_write_avro_to() {
+        let headers = vec!["id".to_string(), "name".to_string()];
+        let types = vec!["INT".to_string(), "TEXT".to_string()];
+        let rows = vec![
+            vec!["1".to_string(), "Al".to_string()],
+            vec!["-2".to_string(), "NULL".to_string()],
+        ];
+        
+        let mut output = Vec::new();
+        write_avro_to(&mut output, "users", &headers, &types, &rows).unwrap();
+        assert!(output.starts_with(b"Obj\x01"));
+        
+        // Block: 2 rows, 9 bytes of data, then the sync marker
+        let marker = &output[output.len() - 16..];
+        let block_start = output.windows(16).position(|w| w == marker).unwrap() + 16;
+        assert_eq!(&output[block_start..output.len() - 16], &[
+            4, 18,
+            2, 2, 2, 4, b'A', b'l',
+            2, 3, 0,
+        ][..]);
+        
+        let bad_rows = vec![vec!["one".to_string(), "x".to_string()]];
+        assert!(write_avro_to(Vec::new(), "users", &headers, &types, &bad_rows).is_err());
+    }
+    
+    #[test]
+    fn test_sink_types_follow_written_columns() {
+        let dir = tempfile::tempdir().unwrap();
+        let sink = AvroSink { dir: dir.path().to_path_buf(), options: CsvOptions::default() };
+        let table = Table {
+            name: "users".to_string(),
+            columns: vec!["name".to_string(), "secret".to_string(), "id".to_string()],
+            column_types: vec!["TEXT".to_string(), "TEXT".to_string(), "INTEGER".to_string()],
+            ..Default::default()
+        };
+        // `secret` dropped, the rest reordered and a computed column added
+        let headers = vec!["id".to_string(), "name".to_string(), "source".to_string()];
+        
+        let location = crate::sink::write_table(&sink, &table, "users", &headers, &[vec!["7".to_string(), "Al".to_string(), "prod".to_string()]]).unwrap();
+        
+        let output = std::fs::read(location).unwrap();
+        let start = output.iter().position(|&b| b == b'{').unwrap();
+        let schema: Value = serde_json::Deserializer::from_slice(&output[start..]).into_iter().next().unwrap().unwrap();
+        let field_types: Vec<&Value> = schema["fields"].as_array().unwrap().iter().map(|f| &f["type"][1]).collect();
+        assert_eq!(field_types, vec!["long", "string", "string"]);
+    }
+}
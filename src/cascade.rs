@@ -0,0 +1,198 @@
+//! Referentially consistent subsets (`--cascade-filter`).
+//!
+//! Tables restricted by a filter are the starting point. A table referenced by
+//! a restricted table keeps only the rows those tables reference, and a table
+//! referencing a restricted table keeps only the rows whose foreign keys point
+//! at kept rows (or are NULL). This is repeated until nothing changes, so the
+//! restriction travels along chains of foreign keys in both directions.
+
+use std::collections::HashSet;
+
+use crate::types::{ForeignKey, Table};
+
+/// Separates the values of composite keys
+const KEY_SEPARATOR: char = '\u{1f}';
+
+/// How a table's rows were chosen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Restriction {
+    /// Not related to any restricted table; all rows are kept
+    Unrestricted,
+    /// Restricted by its own filters
+    Filtered,
+    /// Rows referenced by restricted tables
+    Referenced,
+    /// Rows referencing kept rows of restricted tables
+    Referencing,
+}
+
+/// Restrict related tables to the rows consistent with the filtered ones.
+/// `tables`, `rows` and `filtered` are parallel: the rows of each table after
+/// its own filters, and whether those filters applied to it. Rows are removed
+/// in place; returns how each table was restricted.
+pub fn cascade_filter(tables: &[&Table], rows: &mut [Vec<Vec<String>>], filtered: &[bool]) -> Vec<Restriction> {
+    let mut restrictions: Vec<Restriction> = filtered.iter()
+        .map(|&filtered| if filtered { Restriction::Filtered } else { Restriction::Unrestricted })
+        .collect();
+    // Rows kept in each table, recomputed from all rows on every pass
+    let mut kept: Vec<Vec<bool>> = rows.iter().map(|rows| vec![true; rows.len()]).collect();
+    let find = |name: &str| tables.iter().position(|t| t.name == name)
+        .or_else(|| tables.iter().position(|t| t.name.eq_ignore_ascii_case(name)));
+    
+    // Every pass can only change tables that are not filtered, so this bounds
+    // the passes even if a cycle of foreign keys keeps changing the subsets
+    for _ in 0..=2 * tables.len() {
+        let mut changed = false;
+        
+        for target in 0..tables.len() {
+            if restrictions[target] == Restriction::Filtered {
+                continue;
+            }
+            let referencing: Vec<(usize, &ForeignKey)> = (0..tables.len())
+                .filter(|&other| other != target && restrictions[other] != Restriction::Unrestricted)
+                .flat_map(|other| tables[other].foreign_keys.iter()
+                    .filter(|fk| find(&fk.references_table) == Some(target))
+                    .map(move |fk| (other, fk)))
+                .collect();
+            let referenced: Vec<(usize, &ForeignKey)> = tables[target].foreign_keys.iter()
+                .filter_map(|fk| find(&fk.references_table).map(|parent| (parent, fk)))
+                .filter(|&(parent, _)| parent != target && restrictions[parent] != Restriction::Unrestricted)
+                .collect();
+            
+            let (restriction, mask) = if !referencing.is_empty() {
+                // Foreign keys can point at different columns, e.g. a primary and a unique key
+                let references: Vec<(Vec<usize>, HashSet<String>)> = referencing.iter()
+                    .map(|(child, fk)| {
+                        let child_columns = column_indexes(tables[*child], &fk.columns);
+                        let keys = rows[*child].iter().zip(&kept[*child])
+                            .filter(|(_, kept)| **kept)
+                            .filter_map(|(row, _)| key_of(row, &child_columns))
+                            .collect();
+                        (column_indexes(tables[target], &referenced_columns(tables[target], fk)), keys)
+                    })
+                    .collect();
+                let mask = rows[target].iter()
+                    .map(|row| references.iter().any(|(columns, keys)| {
+                        key_of(row, columns).is_some_and(|key| keys.contains(&key))
+                    }))
+                    .collect();
+                (Restriction::Referenced, mask)
+            } else if !referenced.is_empty() {
+                let checks: Vec<(Vec<usize>, HashSet<String>)> = referenced.iter()
+                    .map(|(parent, fk)| {
+                        let parent_columns = column_indexes(tables[*parent], &referenced_columns(tables[*parent], fk));
+                        let keys = rows[*parent].iter().zip(&kept[*parent])
+                            .filter(|(_, kept)| **kept)
+                            .filter_map(|(row, _)| key_of(row, &parent_columns))
+                            .collect();
+                        (column_indexes(tables[target], &fk.columns), keys)
+                    })
+                    .collect();
+                let mask = rows[target].iter()
+                    .map(|row| checks.iter().all(|(columns, keys)| {
+                        // A NULL foreign key references nothing and is always consistent
+                        key_of(row, columns).is_none_or(|key| keys.contains(&key))
+                    }))
+                    .collect();
+                (Restriction::Referencing, mask)
+            } else {
+                continue;
+            };
+            
+            if restriction != restrictions[target] || mask != kept[target] {
+                restrictions[target] = restriction;
+                kept[target] = mask;
+                changed = true;
+            }
+        }
+        
+        if !changed {
+            break;
+        }
+    }
+    
+    for (rows, kept) in rows.iter_mut().zip(&kept) {
+        let mut keep = kept.iter();
+        rows.retain(|_| *keep.next().unwrap_or(&true));
+    }
+    restrictions
+}
+
+/// Columns a foreign key points at: the listed ones, or the primary key
+fn referenced_columns(table: &Table, fk: &ForeignKey) -> Vec<String> {
+    if fk.references_columns.is_empty() {
+        table.primary_key.clone()
+    } else {
+        fk.references_columns.clone()
+    }
+}
+
+fn column_indexes(table: &Table, columns: &[String]) -> Vec<usize> {
+    columns.iter()
+        .filter_map(|column| table.columns.iter().position(|c| c == column))
+        .collect()
+}
+
+/// The key of a row, or `None` if any part is NULL or the columns are unknown
+fn key_of(row: &[String], columns: &[usize]) -> Option<String> {
+    if columns.is_empty() {
+        return None;
+    }
+    let mut key = String::new();
+    for (i, &column) in columns.iter().enumerate() {
+        let value = row.get(column).filter(|value| !value.eq_ignore_ascii_case("NULL"))?;
+        if i > 0 {
+            key.push(KEY_SEPARATOR);
+        }
+        key.push_str(value);
+    }
+    Some(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_sql_content;
+    
+    const SCHEMA: &str = "CREATE TABLE users (id int PRIMARY KEY, name text);\n\
+        CREATE TABLE orders (id int PRIMARY KEY, user_id int REFERENCES users(id), createdAt date);\n\
+        CREATE TABLE order_items (order_id int, product_id int, FOREIGN KEY (order_id) REFERENCES orders(id), FOREIGN KEY (product_id) REFERENCES products(id));\n\
+        CREATE TABLE products (id int PRIMARY KEY, name text);\n\
+        CREATE TABLE sessions (id int, user_id int REFERENCES users(id));\n\
+        CREATE TABLE settings (k text, v text);\n";
+    
+    fn rows(values: &[&[&str]]) -> Vec<Vec<String>> {
+        values.iter().map(|row| row.iter().map(|v| v.to_string()).collect()).collect()
+    }
+    
+    #[test]
+    fn test_cascade_follows_foreign_keys_both_ways() {
+        let (tables, _) = parse_sql_content(SCHEMA.to_string()).unwrap();
+        let tables: Vec<&Table> = tables.iter().collect();
+        let mut data = vec![
+            rows(&[&["1", "Ann"], &["2", "Bob"], &["3", "Cy"]]),
+            // orders after the date filter: only order 10 of user 1 survived
+            rows(&[&["10", "1", "2024-05-01"]]),
+            rows(&[&["10", "100"], &["11", "101"], &["10", "NULL"]]),
+            rows(&[&["100", "Pen"], &["101", "Ink"]]),
+            rows(&[&["1", "1"], &["2", "2"], &["3", "NULL"]]),
+            rows(&[&["theme", "dark"]]),
+        ];
+        
+        let restrictions = cascade_filter(&tables, &mut data, &[false, true, false, false, false, false]);
+        
+        assert_eq!(restrictions, vec![
+            Restriction::Referenced,
+            Restriction::Filtered,
+            Restriction::Referencing,
+            Restriction::Referenced,
+            Restriction::Referencing,
+            Restriction::Unrestricted,
+        ]);
+        assert_eq!(data[0], rows(&[&["1", "Ann"]]));
+        assert_eq!(data[2], rows(&[&["10", "100"], &["10", "NULL"]]));
+        assert_eq!(data[3], rows(&[&["100", "Pen"]]));
+        assert_eq!(data[4], rows(&[&["1", "1"], &["3", "NULL"]]));
+        assert_eq!(data[5].len(), 1);
+    }
+}
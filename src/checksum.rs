@@ -0,0 +1,154 @@
+//! Checksum sidecar files for generated outputs (`--checksum`).
+//!
+//! Every file written gets a `<file>.sha256` (or `.md5`) next to it in the
+//! format of `sha256sum`/`md5sum`, so transfers can be verified with
+//! `sha256sum -c orders.csv.sha256`.
+
+use anyhow::{Context, Result};
+use md5::Md5;
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::args::flag_value;
+use crate::sink::{OutputSink, TableSink};
+use crate::types::Table;
+
+/// Digest written next to every output file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumAlgorithm {
+    Sha256,
+    Md5,
+}
+
+impl ChecksumAlgorithm {
+    /// Extension of the sidecar files
+    pub fn extension(self) -> &'static str {
+        match self {
+            ChecksumAlgorithm::Sha256 => "sha256",
+            ChecksumAlgorithm::Md5 => "md5",
+        }
+    }
+}
+
+/// Parse the --checksum option from command line. `none` (the default) gives `None`.
+pub fn parse_checksum(args: &[String]) -> Result<Option<ChecksumAlgorithm>> {
+    match flag_value(args, "--checksum")? {
+        None | Some("none") => Ok(None),
+        Some("sha256") => Ok(Some(ChecksumAlgorithm::Sha256)),
+        Some("md5") => Ok(Some(ChecksumAlgorithm::Md5)),
+        Some(other) => anyhow::bail!("Error: Invalid --checksum '{}'. Use one of: sha256, md5, none", other),
+    }
+}
+
+/// Hex digest of a file's content
+pub fn file_digest(path: &Path, algorithm: ChecksumAlgorithm) -> Result<String> {
+    let mut file = File::open(path)
+        .with_context(|| format!("Failed to read '{}' for its checksum", path.display()))?;
+    let digest = match algorithm {
+        ChecksumAlgorithm::Sha256 => hex(&hash_reader::<Sha256>(&mut file)?),
+        ChecksumAlgorithm::Md5 => hex(&hash_reader::<Md5>(&mut file)?),
+    };
+    Ok(digest)
+}
+
+fn hash_reader<D: Digest + Write>(reader: &mut impl io::Read) -> Result<Vec<u8>> {
+    let mut hasher = D::new();
+    io::copy(reader, &mut hasher)?;
+    Ok(hasher.finalize().to_vec())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Write the sidecar of a file, `<digest>  <file name>` as `sha256sum` prints
+/// it. Returns the sidecar's path.
+pub fn write_checksum_file(path: &Path, algorithm: ChecksumAlgorithm) -> Result<String> {
+    let digest = file_digest(path, algorithm)?;
+    let file_name = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+    let sidecar = format!("{}.{}", path.display(), algorithm.extension());
+    fs::write(&sidecar, format!("{}  {}\n", digest, file_name))
+        .with_context(|| format!("Failed to write checksum file '{}'", sidecar))?;
+    Ok(sidecar)
+}
+
+/// Wraps a sink and writes a checksum sidecar for every file it finishes.
+/// Outputs that are not local files, such as uploads, are left without one.
+pub struct ChecksumSink {
+    inner: Box<dyn OutputSink>,
+    algorithm: ChecksumAlgorithm,
+}
+
+impl ChecksumSink {
+    pub fn new(inner: Box<dyn OutputSink>, algorithm: ChecksumAlgorithm) -> Self {
+        ChecksumSink { inner, algorithm }
+    }
+}
+
+impl OutputSink for ChecksumSink {
+    fn open_table<'a>(&'a self, table: &Table, name: &str, headers: &[String]) -> Result<Box<dyn TableSink + 'a>> {
+        Ok(Box::new(ChecksumTableSink {
+            inner: self.inner.open_table(table, name, headers)?,
+            algorithm: self.algorithm,
+        }))
+    }
+}
+
+struct ChecksumTableSink<'a> {
+    inner: Box<dyn TableSink + 'a>,
+    algorithm: ChecksumAlgorithm,
+}
+
+impl TableSink for ChecksumTableSink<'_> {
+    fn write_row(&mut self, row: &[String]) -> Result<()> {
+        self.inner.write_row(row)
+    }
+    
+    fn finish(self: Box<Self>) -> Result<String> {
+        Ok(self.finish_with_size()?.0)
+    }
+    
+    fn finish_with_size(self: Box<Self>) -> Result<(String, Option<u64>)> {
+        let (location, size) = self.inner.finish_with_size()?;
+        let path = Path::new(&location);
+        if location.contains("://") || !path.is_file() {
+            eprintln!("Warning: No checksum written for '{}', which is not a local file", location);
+        } else {
+            write_checksum_file(path, self.algorithm)?;
+        }
+        Ok((location, size))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::csv_writer::CsvSink;
+    use crate::sink::write_table;
+    use crate::types::CsvOptions;
+    
+    #[test]
+    fn test_checksum_sidecar_matches_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let sink = ChecksumSink::new(Box::new(CsvSink { dir: dir.path().to_path_buf(), options: CsvOptions::default() }), ChecksumAlgorithm::Sha256);
+        let table = Table { name: "t".to_string(), columns: vec!["id".to_string()], ..Default::default() };
+        
+        let location = write_table(&sink, &table, "t", &table.columns, &[vec!["1".to_string()]]).unwrap();
+        
+        // sha256 of "id\n1\n"
+        let sidecar = fs::read_to_string(format!("{}.sha256", location)).unwrap();
+        assert_eq!(sidecar, "7cde7fb64fd82bd152710cf238e017b9ab46c0592483edc067ba4f6c75fac108  t.csv\n");
+        assert_eq!(file_digest(Path::new(&location), ChecksumAlgorithm::Md5).unwrap().len(), 32);
+    }
+    
+    #[test]
+    fn test_parse_checksum() {
+        let args = |value: &str| vec!["--checksum".to_string(), value.to_string()];
+        assert_eq!(parse_checksum(&args("sha256")).unwrap(), Some(ChecksumAlgorithm::Sha256));
+        assert_eq!(parse_checksum(&args("none")).unwrap(), None);
+        assert_eq!(parse_checksum(&[]).unwrap(), None);
+        assert!(parse_checksum(&args("crc32")).is_err());
+    }
+}
@@ -0,0 +1,181 @@
+//! Shell completions and man page (feature `completions`).
+//!
+//! The command line is parsed by hand, so this builds an equivalent clap
+//! command from the option table in `args` for the generators to work from.
+
+use anyhow::{Context, Result};
+use clap::builder::PossibleValuesParser;
+use clap::{Arg, ArgAction, Command, ValueHint};
+use clap_complete::Shell;
+use std::io::Write;
+
+use crate::args::{CliOption, OPTIONS};
+
+/// Describe the full command line, including subcommands, as a clap command
+pub fn command(bin_name: &str) -> Command {
+    let sql_file = || Arg::new("sql_file")
+        .required(true)
+        .help("SQL dump to read (- for stdin)")
+        .value_hint(ValueHint::FilePath);
+    let output = || Arg::new("output")
+        .long("output")
+        .value_name("file")
+        .help("Write the result to a file instead of stdout")
+        .value_hint(ValueHint::FilePath);
+    
+    Command::new(bin_name.to_string())
+        .about(env!("CARGO_PKG_DESCRIPTION"))
+        .version(env!("CARGO_PKG_VERSION"))
+        .arg(sql_file().required(false))
+        .args(OPTIONS.iter().map(option_arg))
+        .arg(Arg::new("generate-man")
+            .long("generate-man")
+            .action(ArgAction::SetTrue)
+            .help("Print the man page in roff format"))
+        .subcommand(Command::new("query")
+            .about("Run a read-only SELECT over the dump and print CSV")
+            .arg(sql_file())
+            .arg(Arg::new("statement").required(true).help("SELECT statement"))
+            .arg(output()))
+        .subcommand(Command::new("head")
+            .about("Print the first rows of a table as an aligned text table")
+            .arg(sql_file())
+            .arg(Arg::new("table").required(true))
+            .arg(Arg::new("rows").short('n').value_name("rows").help("Number of rows to show (default: 10)")))
+        .subcommand(Command::new("diff")
+            .about("Compare two dumps and write added, removed and changed rows")
+            .arg(Arg::new("old_sql_file").required(true).value_hint(ValueHint::FilePath))
+            .arg(Arg::new("new_sql_file").required(true).value_hint(ValueHint::FilePath))
+            .arg(Arg::new("key").long("key").value_name("table:col,...").action(ArgAction::Append)
+                .help("Key columns rows are matched on (repeatable)"))
+            .arg(Arg::new("summary").long("summary").action(ArgAction::SetTrue)
+                .help("Only print the number of differences per table")))
+        .subcommand(Command::new("load")
+            .about("Create the dump's tables in a database and bulk-load their rows")
+            .arg(sql_file())
+            .arg(Arg::new("target").long("target").required(true).value_name("url")
+                .help("postgres:// or mysql:// URL of the target database"))
+            .arg(Arg::new("table").long("table").value_name("name").action(ArgAction::Append)
+                .help("Only load this table (repeatable)")))
+        .subcommand(Command::new("aggregate")
+            .about("Write grouped summaries of a table")
+            .arg(sql_file())
+            .arg(Arg::new("table").long("table").required(true).value_name("name"))
+            .arg(Arg::new("group-by").long("group-by").value_name("col,..."))
+            .arg(Arg::new("agg").long("agg").value_name("count,sum(col),..."))
+            .arg(output()))
+        .subcommand(Command::new("generate")
+            .about("Write a synthetic dump (or files in any --format) for the tables of a schema")
+            .arg(Arg::new("schema").long("schema").required(true).value_name("file").value_hint(ValueHint::FilePath)
+                .help("SQL file with the CREATE TABLE statements"))
+            .arg(Arg::new("rows").long("rows").value_name("n").help("Rows per table (default: 1000)"))
+            .arg(Arg::new("seed").long("seed").value_name("n").help("Seed the values are derived from (default: 0)"))
+            .arg(Arg::new("format").long("format").value_name("sql|csv|...").help("sql for a dump (default), or an output format"))
+            .arg(output()))
+        .subcommand(Command::new("completions")
+            .about("Print a shell completion script")
+            .arg(Arg::new("shell").required(true).value_parser(["bash", "zsh", "fish"])))
+}
+
+/// Build the clap argument for an option from its usage placeholder:
+/// `<a|b>` lists the possible values and `[x]` marks an optional value
+fn option_arg(option: &CliOption) -> Arg {
+    let id = option.flag.trim_start_matches("--");
+    let arg = Arg::new(id).long(id).help(option.help);
+    
+    let tokens = value_tokens(option.value);
+    if tokens.is_empty() {
+        return arg.action(ArgAction::SetTrue);
+    }
+    let required = tokens.iter().filter(|t| t.starts_with('<')).count();
+    let names: Vec<&str> = tokens.iter().map(|t| t.trim_matches(['<', '>', '[', ']'])).collect();
+    let arg = arg.action(ArgAction::Append)
+        .num_args(required..=tokens.len())
+        .value_names(names);
+    
+    match possible_values(&tokens) {
+        Some(values) => arg.value_parser(PossibleValuesParser::new(values)),
+        None if option.value.contains("file") => arg.value_hint(ValueHint::FilePath),
+        None if option.value.contains("dir") => arg.value_hint(ValueHint::DirPath),
+        None => arg,
+    }
+}
+
+/// Split a placeholder like `<column_name> <start_date> [end_date]` into its
+/// values; spaces inside brackets (`<name: value>`) do not separate values
+fn value_tokens(value: &'static str) -> Vec<&'static str> {
+    let mut tokens = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in value.char_indices() {
+        match c {
+            '<' | '[' => depth += 1,
+            '>' | ']' => depth -= 1,
+            ' ' if depth == 0 => {
+                tokens.push(&value[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    tokens.push(&value[start..]);
+    tokens.retain(|token| !token.is_empty());
+    tokens
+}
+
+/// Values of a single `<a|b|c>` placeholder made of plain words
+fn possible_values(tokens: &[&'static str]) -> Option<Vec<&'static str>> {
+    let [token] = tokens else { return None };
+    let inner = token.strip_prefix('<')?.strip_suffix('>')?;
+    let values: Vec<&str> = inner.split('|').collect();
+    let plain = values.iter().all(|v| !v.is_empty() && v.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'));
+    (values.len() > 1 && plain).then_some(values)
+}
+
+/// Write the completion script for `bash`, `zsh` or `fish`
+pub fn write_completions(shell: &str, bin_name: &str, destination: &mut dyn Write) -> Result<()> {
+    let shell = match shell {
+        "bash" => Shell::Bash,
+        "zsh" => Shell::Zsh,
+        "fish" => Shell::Fish,
+        other => anyhow::bail!("Error: Unsupported shell '{}'. Use one of: bash, zsh, fish", other),
+    };
+    clap_complete::generate(shell, &mut command(bin_name), bin_name, destination);
+    Ok(())
+}
+
+/// Write the man page in roff format
+pub fn write_man_page(bin_name: &str, destination: &mut dyn Write) -> Result<()> {
+    clap_mangen::Man::new(command(bin_name))
+        .render(destination)
+        .context("Failed to write man page")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_command_is_valid() {
+        command("parsley-csv").debug_assert();
+    }
+    
+    #[test]
+    fn test_option_values() {
+        assert_eq!(value_tokens("<column_name> <start_date> [end_date]"), vec!["<column_name>", "<start_date>", "[end_date]"]);
+        assert_eq!(value_tokens("<name: value>"), vec!["<name: value>"]);
+        assert_eq!(possible_values(&value_tokens("<snake|camel|lower|preserve>")), Some(vec!["snake", "camel", "lower", "preserve"]));
+        assert_eq!(possible_values(&value_tokens("<dir|s3://bucket/prefix/|https://...>")), None);
+    }
+    
+    #[test]
+    fn test_write_completions() {
+        let mut script = Vec::new();
+        write_completions("bash", "parsley-csv", &mut script).unwrap();
+        let script = String::from_utf8(script).unwrap();
+        assert!(script.contains("--header-case"));
+        assert!(script.contains("completions"));
+        
+        assert!(write_completions("powershell", "parsley-csv", &mut Vec::new()).is_err());
+    }
+}
@@ -0,0 +1,203 @@
+//! S3 and GCS output (feature `object-store`).
+//!
+//! CSVs are gzip-compressed while they are written and uploaded in parts, so
+//! large tables never have to be stored locally. Credentials and regions come
+//! from the usual `AWS_*` and `GOOGLE_*` environment variables. Dumps can also
+//! be read straight from a bucket.
+
+use anyhow::{Context, Result};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use object_store::buffered::BufWriter;
+use object_store::path::Path;
+use object_store::ObjectStore;
+use std::io::{self, Write};
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::runtime::Runtime;
+
+use crate::csv_writer::CsvTableSink;
+use crate::sink::{OutputSink, TableSink};
+use crate::types::{CsvOptions, Table};
+
+/// Bytes collected before they are handed to the multipart upload
+pub(crate) const PART_SIZE: usize = 8 * 1024 * 1024;
+
+/// Check whether an output location is an object store URL rather than a local path
+pub fn is_object_store_url(location: &str) -> bool {
+    ["s3://", "s3a://", "gs://"].iter().any(|scheme| location.starts_with(scheme))
+}
+
+/// A bucket and key prefix that tables are uploaded to as `<name>.csv.gz`
+pub struct ObjectStoreOutput {
+    store: Arc<dyn ObjectStore>,
+    prefix: Path,
+    url: String,
+    runtime: Runtime,
+    options: CsvOptions,
+}
+
+impl ObjectStoreOutput {
+    /// Connect to the bucket of an `s3://bucket/prefix/` or `gs://bucket/prefix/` URL
+    pub fn from_url(location: &str, options: CsvOptions) -> Result<Self> {
+        let (store, prefix) = connect_store(location)?;
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .context("Failed to start async runtime")?;
+        
+        Ok(ObjectStoreOutput {
+            store,
+            prefix,
+            url: location.trim_end_matches('/').to_string(),
+            runtime,
+            options,
+        })
+    }
+    
+    /// Start a streaming upload of `<prefix>/<filename>`
+    pub fn create(&self, filename: &str) -> ObjectWriter<'_> {
+        let path = self.prefix.parts().chain(Path::from(filename).parts()).collect();
+        ObjectWriter {
+            runtime: &self.runtime,
+            writer: BufWriter::with_capacity(Arc::clone(&self.store), path, PART_SIZE),
+            buffer: Vec::with_capacity(PART_SIZE),
+        }
+    }
+    
+    /// Full URL of an uploaded file
+    pub fn url_of(&self, filename: &str) -> String {
+        format!("{}/{}", self.url, filename)
+    }
+}
+
+/// The bucket of an `s3://bucket/prefix/` or `gs://bucket/prefix/` URL and the prefix in it
+pub(crate) fn connect_store(location: &str) -> Result<(Arc<dyn ObjectStore>, Path)> {
+    let url = url::Url::parse(location)
+        .with_context(|| format!("Invalid output URL '{}'", location))?;
+    let store_options = std::env::vars()
+        .filter(|(key, _)| key.starts_with("AWS_") || key.starts_with("GOOGLE_"))
+        .map(|(key, value)| (key.to_lowercase(), value));
+    let (store, prefix) = object_store::parse_url_opts(&url, store_options)
+        .with_context(|| format!("Failed to configure object store for '{}'", location))?;
+    Ok((Arc::from(store), prefix))
+}
+
+/// Download a whole object, e.g. a dump at `s3://bucket/dumps/2024-06-01.sql.gz`
+pub fn download(location: &str) -> Result<Vec<u8>> {
+    let output = ObjectStoreOutput::from_url(location, CsvOptions::default())?;
+    let bytes = output.runtime.block_on(async {
+        output.store.get(&output.prefix).await?.bytes().await
+    }).with_context(|| format!("Failed to download '{}'", location))?;
+    
+    Ok(bytes.to_vec())
+}
+
+/// Synchronous writer feeding a multipart upload
+pub struct ObjectWriter<'a> {
+    runtime: &'a Runtime,
+    writer: BufWriter,
+    buffer: Vec<u8>,
+}
+
+impl ObjectWriter<'_> {
+    /// Upload the remaining bytes and complete the upload
+    pub fn finish(mut self) -> Result<()> {
+        let buffer = std::mem::take(&mut self.buffer);
+        self.runtime.block_on(async {
+            self.writer.put(buffer.into()).await?;
+            self.writer.shutdown().await
+        }).context("Failed to complete upload")
+    }
+}
+
+impl Write for ObjectWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        if self.buffer.len() >= PART_SIZE {
+            self.flush()?;
+        }
+        Ok(buf.len())
+    }
+    
+    fn flush(&mut self) -> io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let buffer = std::mem::take(&mut self.buffer);
+        self.runtime.block_on(self.writer.put(buffer.into()))
+            .map_err(io::Error::other)
+    }
+}
+
+impl OutputSink for ObjectStoreOutput {
+    fn open_table<'a>(&'a self, table: &Table, name: &str, headers: &[String]) -> Result<Box<dyn TableSink + 'a>> {
+        let filename = format!("{}.csv.gz", name);
+        let encoder = GzEncoder::new(self.create(&filename), Compression::default());
+        Ok(Box::new(GzCsvTableSink {
+            csv: CsvTableSink::new(encoder, headers, &self.options, self.url_of(&filename))?
+                .with_column_types(table),
+        }))
+    }
+}
+
+/// Compresses the CSV of one table into a multipart upload
+struct GzCsvTableSink<'a> {
+    csv: CsvTableSink<GzEncoder<ObjectWriter<'a>>>,
+}
+
+impl TableSink for GzCsvTableSink<'_> {
+    fn write_row(&mut self, row: &[String]) -> Result<()> {
+        self.csv.write_row(row)
+    }
+    
+    fn finish(self: Box<Self>) -> Result<String> {
+        let url = self.csv.location().to_string();
+        self.csv.into_inner()?
+            .finish()
+            .context("Failed to compress CSV")?
+            .finish()?;
+        Ok(url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_is_object_store_url() {
+        assert!(is_object_store_url("s3://bucket/exports/"));
+        assert!(is_object_store_url("gs://bucket"));
+        assert!(!is_object_store_url("exports/"));
+        assert!(!is_object_store_url("https://example.com/"));
+    }
+    
+    #[test]
+    fn test_object_store_sink_uploads_compressed_csv() {
+        use crate::sink::write_table;
+        use flate2::read::GzDecoder;
+        use object_store::memory::InMemory;
+        use std::io::Read;
+        
+        let output = ObjectStoreOutput {
+            store: Arc::new(InMemory::new()),
+            prefix: Path::from("exports/daily"),
+            url: "memory://bucket/exports/daily".to_string(),
+            runtime: tokio::runtime::Builder::new_multi_thread().enable_all().build().unwrap(),
+            options: CsvOptions::default(),
+        };
+        let headers = vec!["id".to_string(), "name".to_string()];
+        let rows = vec![vec!["1".to_string(), "Alice".to_string()]];
+        
+        let url = write_table(&output, &Table::default(), "users", &headers, &rows).unwrap();
+        assert_eq!(url, "memory://bucket/exports/daily/users.csv.gz");
+        
+        let compressed = output.runtime.block_on(async {
+            output.store.get(&Path::from("exports/daily/users.csv.gz")).await.unwrap().bytes().await.unwrap()
+        });
+        let mut csv = String::new();
+        GzDecoder::new(&compressed[..]).read_to_string(&mut csv).unwrap();
+        assert_eq!(csv, "id,name\n1,Alice\n");
+    }
+}
@@ -0,0 +1,268 @@
+//! Large columns written as one file per row (`--extract-column`).
+//!
+//! `--extract-column users:avatar --as-files avatars/` writes every avatar of
+//! the users table to its own file under `avatars/`, named by the row's
+//! primary key (percent-encoded, so every key gets its own file), and puts
+//! the file's path in the CSV instead. Paths are relative to the output
+//! directory, so the CSV and the files can be moved together.
+//! Hex-encoded binary values (MySQL `0x...`, Postgres bytea `\x...`) of binary
+//! columns are written as bytes.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::args::{flag_value, flag_values};
+use crate::types::Table;
+
+/// A column written as files, and the directory they go to
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileColumn {
+    pub table: String,
+    pub column: String,
+    /// Directory of the files, relative to the output directory
+    pub dir: String,
+}
+
+/// Columns written as files, from --extract-column and --as-files
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FileColumns {
+    pub columns: Vec<FileColumn>,
+    /// Output directory the file paths are relative to
+    pub output_dir: PathBuf,
+}
+
+impl FileColumns {
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty()
+    }
+}
+
+/// Parse --extract-column options. Each one is followed by the --as-files
+/// directory its files are written to, e.g.
+/// `--extract-column users:avatar --as-files avatars/ --extract-column posts:body --as-files bodies/`
+pub fn parse_file_columns(args: &[String]) -> Result<FileColumns> {
+    let mut columns = Vec::new();
+    let mut pending: Option<(String, String)> = None;
+    
+    for (pos, arg) in args.iter().enumerate() {
+        let value = || match args.get(pos + 1) {
+            Some(value) if !value.starts_with("--") => Ok(value.as_str()),
+            _ => Err(anyhow::anyhow!("Error: {} requires a value", arg)),
+        };
+        match arg.as_str() {
+            "--extract-column" => {
+                if let Some((table, column)) = pending.take() {
+                    anyhow::bail!("Error: --extract-column {}:{} needs an --as-files directory after it", table, column);
+                }
+                pending = Some(parse_column_spec(value()?)?);
+            }
+            "--as-files" => {
+                let Some((table, column)) = pending.take() else {
+                    anyhow::bail!("Error: --as-files must follow an --extract-column");
+                };
+                let dir = value()?.trim_end_matches(['/', '\\']).to_string();
+                if dir.is_empty() || PathBuf::from(&dir).is_absolute() || dir.split(['/', '\\']).any(|part| part == "..") {
+                    anyhow::bail!("Error: --as-files '{}' must be a directory inside the output directory", value()?);
+                }
+                if columns.iter().any(|c: &FileColumn| c.dir == dir) {
+                    anyhow::bail!("Error: --as-files '{}' is given to more than one column; use a directory per column", dir);
+                }
+                columns.push(FileColumn { table, column, dir });
+            }
+            _ => {}
+        }
+    }
+    if let Some((table, column)) = pending {
+        anyhow::bail!("Error: --extract-column {}:{} needs an --as-files directory after it", table, column);
+    }
+    if columns.is_empty() {
+        return Ok(FileColumns::default());
+    }
+    
+    let output_dir = flag_value(args, "--output")?.unwrap_or(".");
+    if output_dir.contains("://") {
+        anyhow::bail!("Error: --extract-column only supports output to a local directory");
+    }
+    if !flag_values(args, "--encrypt")?.is_empty() {
+        anyhow::bail!("Error: --extract-column writes plain files and cannot be combined with --encrypt");
+    }
+    
+    Ok(FileColumns { columns, output_dir: PathBuf::from(output_dir) })
+}
+
+fn parse_column_spec(spec: &str) -> Result<(String, String)> {
+    match spec.split_once(':') {
+        Some((table, column)) if !table.is_empty() && !column.is_empty() => Ok((table.to_string(), column.to_string())),
+        _ => anyhow::bail!("Error: Invalid --extract-column '{}'. Use <table>:<column>, e.g. users:avatar", spec),
+    }
+}
+
+/// Write the values of a table's extracted columns to files named by the
+/// row's primary key, replacing each value with the file's relative path.
+/// Empty and NULL values are kept. Returns the number of files written.
+pub fn extract_column_files(
+    table: &Table,
+    headers: &[String],
+    rows: &mut [Vec<String>],
+    file_columns: &FileColumns,
+) -> Result<usize> {
+    let mut written = 0;
+    
+    for file_column in file_columns.columns.iter().filter(|c| c.table == table.name) {
+        let Some(index) = headers.iter().position(|h| h == &file_column.column) else {
+            eprintln!("Warning: Column '{}' given to --extract-column is not in table '{}'", file_column.column, table.name);
+            continue;
+        };
+        let key_columns: Vec<usize> = table.primary_key.iter()
+            .filter_map(|key| headers.iter().position(|h| h == key))
+            .collect();
+        if key_columns.is_empty() || key_columns.len() != table.primary_key.len() {
+            anyhow::bail!("Error: Table '{}' has no primary key to name the files of --extract-column", table.name);
+        }
+        let binary = table.columns.iter().position(|c| c == &file_column.column)
+            .and_then(|i| table.column_types.get(i))
+            .is_some_and(|column_type| is_binary_type(column_type));
+        let dir = file_columns.output_dir.join(&file_column.dir);
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create directory '{}'", dir.display()))?;
+        
+        for row in rows.iter_mut() {
+            let Some(value) = row.get(index).filter(|value| !value.is_empty() && !value.eq_ignore_ascii_case("NULL")) else {
+                continue;
+            };
+            let key: Vec<&str> = key_columns.iter().map(|&i| row.get(i).map(String::as_str).unwrap_or_default()).collect();
+            let (bytes, extension) = match binary.then(|| decode_hex(value)).flatten() {
+                Some(bytes) => {
+                    let extension = sniff_extension(&bytes);
+                    (bytes, extension)
+                }
+                None => (value.as_bytes().to_vec(), "txt"),
+            };
+            let file_name = format!("{}.{}", file_stem(&key), extension);
+            let path = dir.join(&file_name);
+            fs::write(&path, bytes)
+                .with_context(|| format!("Failed to write '{}'", path.display()))?;
+            row[index] = format!("{}/{}", file_column.dir, file_name);
+            written += 1;
+        }
+    }
+    
+    Ok(written)
+}
+
+fn is_binary_type(column_type: &str) -> bool {
+    let upper = column_type.to_uppercase();
+    upper.contains("BLOB") || upper.contains("BINARY") || upper.starts_with("BYTEA")
+}
+
+/// Bytes of a hex literal: MySQL `0x89504E47` or Postgres bytea `\x89504e47`
+fn decode_hex(value: &str) -> Option<Vec<u8>> {
+    let digits = value.strip_prefix("0x").or_else(|| value.strip_prefix("\\x"))?;
+    if digits.len() % 2 != 0 {
+        return None;
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(digits.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// File extension of common binary formats by their leading bytes
+fn sniff_extension(bytes: &[u8]) -> &'static str {
+    match bytes {
+        [0x89, b'P', b'N', b'G', ..] => "png",
+        [0xFF, 0xD8, 0xFF, ..] => "jpg",
+        [b'G', b'I', b'F', b'8', ..] => "gif",
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => "webp",
+        [b'%', b'P', b'D', b'F', ..] => "pdf",
+        [b'P', b'K', 0x03, 0x04, ..] => "zip",
+        _ => "bin",
+    }
+}
+
+/// File name of a row from its primary key, joined by `_` for composite keys.
+/// Bytes other than ASCII letters, digits, `-` and `.` are percent-encoded,
+/// `_` and `%` included, so different keys never share a file. A leading `.`
+/// is encoded too, so no file is hidden.
+fn file_stem(key: &[&str]) -> String {
+    let mut stem = String::new();
+    for (i, part) in key.iter().enumerate() {
+        if i > 0 {
+            stem.push('_');
+        }
+        for byte in part.bytes() {
+            if byte.is_ascii_alphanumeric() || byte == b'-' || (byte == b'.' && !stem.is_empty()) {
+                stem.push(byte as char);
+            } else {
+                stem.push_str(&format!("%{:02X}", byte));
+            }
+        }
+    }
+    stem
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+    
+    #[test]
+    fn test_parse_file_columns() {
+        let file_columns = parse_file_columns(&args("t dump.sql --output out --extract-column users:avatar --as-files avatars/ --extract-column posts:body --as-files bodies")).unwrap();
+        assert_eq!(file_columns.output_dir, PathBuf::from("out"));
+        assert_eq!(file_columns.columns, vec![
+            FileColumn { table: "users".to_string(), column: "avatar".to_string(), dir: "avatars".to_string() },
+            FileColumn { table: "posts".to_string(), column: "body".to_string(), dir: "bodies".to_string() },
+        ]);
+        
+        assert!(parse_file_columns(&args("t dump.sql")).unwrap().is_empty());
+        assert!(parse_file_columns(&args("t dump.sql --extract-column users:avatar")).is_err());
+        assert!(parse_file_columns(&args("t dump.sql --as-files avatars")).is_err());
+        assert!(parse_file_columns(&args("t dump.sql --extract-column users --as-files avatars")).is_err());
+        assert!(parse_file_columns(&args("t dump.sql --extract-column users:avatar --as-files ../avatars")).is_err());
+        assert!(parse_file_columns(&args("t dump.sql --extract-column a:x --as-files d --extract-column b:y --as-files d")).is_err());
+    }
+    
+    #[test]
+    fn test_extract_column_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let table = Table {
+            name: "users".to_string(),
+            columns: args("id avatar bio"),
+            column_types: args("INT LONGBLOB TEXT"),
+            primary_key: vec!["id".to_string()],
+            ..Default::default()
+        };
+        let file_columns = FileColumns {
+            columns: vec![
+                FileColumn { table: "users".to_string(), column: "avatar".to_string(), dir: "avatars".to_string() },
+                FileColumn { table: "users".to_string(), column: "bio".to_string(), dir: "bios".to_string() },
+            ],
+            output_dir: dir.to_path_buf(),
+        };
+        let mut rows = vec![
+            vec!["1".to_string(), "0x89504E470D0A".to_string(), "Hello".to_string()],
+            vec!["a/b".to_string(), "NULL".to_string(), String::new()],
+        ];
+        
+        let written = extract_column_files(&table, &table.columns, &mut rows, &file_columns).unwrap();
+        
+        assert_eq!(written, 2);
+        assert_eq!(rows[0], vec!["1", "avatars/1.png", "bios/1.txt"]);
+        assert_eq!(rows[1], vec!["a/b", "NULL", ""]);
+        assert_eq!(fs::read(dir.join("avatars/1.png")).unwrap(), vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A]);
+        assert_eq!(fs::read_to_string(dir.join("bios/1.txt")).unwrap(), "Hello");
+        assert_eq!(file_stem(&["a/b", "2"]), "a%2Fb_2");
+        assert_eq!(file_stem(&["a_b", "2"]), "a%5Fb_2");
+        assert_eq!(file_stem(&["a", "b_2"]), "a_b%5F2");
+        assert_eq!(file_stem(&["..", "é"]), "%2E._%C3%A9");
+        
+        let keyless = Table { primary_key: Vec::new(), ..table };
+        assert!(extract_column_files(&keyless, &keyless.columns, &mut rows, &file_columns).is_err());
+    }
+}
@@ -0,0 +1,273 @@
+use anyhow::{Context, Result};
+use std::borrow::Cow;
+use std::future::Future;
+use std::task::{self, Poll, Waker};
+
+use crate::cancel::CancellationToken;
+use crate::csv_writer::csv_filenames;
+use crate::date_filter::retain_dated_rows;
+use crate::parser::{find_skipped_inserts, for_each_table_row, parse_sql_content};
+use crate::report::{ConversionReport, TableReport, WrittenFile};
+use crate::sink::{MemorySink, OutputSink, TableSink};
+use crate::types::{CsvOptions, DateFilter, Table, ValueOptions};
+use crate::warnings::CollectedWarnings;
+
+/// The rows of a table ready to be written. Values are borrowed from the dump
+/// until they are written.
+pub(crate) struct TableRows<'c> {
+    pub rows: Vec<Vec<Cow<'c, str>>>,
+    /// Rows in the dump, before the date filter
+    pub rows_in: usize,
+    /// Warnings raised while reading the rows
+    pub warnings: Vec<String>,
+}
+
+/// Converts SQL dump text to any output sink from library code
+#[derive(Debug, Clone, Default)]
+pub struct Converter {
+    pub options: CsvOptions,
+    pub value_options: ValueOptions,
+    /// Only rows in the date range are written. Dates that can't be parsed
+    /// exclude the row and are listed in the table's warnings.
+    pub date_filter: Option<DateFilter>,
+    /// Checked before every table, while its rows are extracted and before
+    /// every row is written; once cancelled, the conversion stops and returns
+    /// what was written so far
+    pub cancellation: Option<CancellationToken>,
+}
+
+impl Converter {
+    pub fn new(options: CsvOptions) -> Self {
+        Converter { options, ..Default::default() }
+    }
+    
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+    
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.cancellation.as_ref().is_some_and(CancellationToken::is_cancelled)
+    }
+    
+    /// Extract the rows of a table to write. Extraction stops early once the
+    /// conversion is cancelled.
+    pub(crate) fn table_rows<'c>(&self, content: &'c str, table: &Table) -> Result<TableRows<'c>> {
+        let mut rows = Vec::new();
+        for_each_table_row(content, table, &self.value_options, |row| {
+            rows.push(row);
+            !self.is_cancelled()
+        }).with_context(|| format!("Error extracting values for table '{}'", table.name))?;
+        let rows_in = rows.len();
+        let Some(ref filter) = self.date_filter else {
+            return Ok(TableRows { rows, rows_in, warnings: Vec::new() });
+        };
+        
+        let warnings = CollectedWarnings::default();
+        retain_dated_rows(&table.columns, &mut rows, filter, &warnings)
+            .with_context(|| format!("Error applying date filter to table '{}'", table.name))?;
+        Ok(TableRows { rows, rows_in, warnings: warnings.into_inner() })
+    }
+    
+    /// Convert every table that has rows and write it to the sink
+    pub fn convert(&self, sql: &str, sink: &dyn OutputSink) -> Result<ConversionReport> {
+        run_ready(self.convert_to(sql, &SyncDestination(sink)))
+    }
+    
+    /// The conversion loop of `convert` and `convert_async`
+    pub(crate) async fn convert_to<D: Destination>(&self, sql: &str, destination: &D) -> Result<ConversionReport> {
+        let mut conversion = ConversionReport::default();
+        if self.is_cancelled() {
+            conversion.cancelled = true;
+            return Ok(conversion);
+        }
+        
+        let content = sql.replace("\r\n", "\n");
+        let (tables, content) = parse_sql_content(content)?;
+        conversion.skipped_statements = find_skipped_inserts(&content)?;
+        
+        for (table, filename) in tables.iter().zip(csv_filenames(&tables, self.options.file_naming)) {
+            if self.is_cancelled() {
+                conversion.cancelled = true;
+                break;
+            }
+            
+            let TableRows { rows, rows_in, warnings } = self.table_rows(&content, table)?;
+            // A table cancelled while it was extracted is left out
+            if self.is_cancelled() {
+                conversion.cancelled = true;
+                break;
+            }
+            if rows.is_empty() {
+                continue;
+            }
+            
+            let mut table_sink = destination.open_table(table, filename.trim_end_matches(".csv"), &table.columns).await
+                .with_context(|| format!("Error writing CSV for table '{}'", table.name))?;
+            for row in &rows {
+                if self.is_cancelled() {
+                    conversion.cancelled = true;
+                    return Ok(conversion);
+                }
+                table_sink.write_fields(row).await
+                    .with_context(|| format!("Error writing CSV for table '{}'", table.name))?;
+            }
+            let (path, bytes) = table_sink.finish().await
+                .with_context(|| format!("Error writing CSV for table '{}'", table.name))?;
+            
+            conversion.tables.push(TableReport {
+                table: table.name.clone(),
+                rows_in,
+                rows_filtered: rows_in - rows.len(),
+                duration_ms: None,
+                timings: None,
+                file: WrittenFile { path, rows_written: rows.len(), bytes, warnings },
+            });
+        }
+        
+        Ok(conversion)
+    }
+}
+
+/// A sink the conversion loop writes to, synchronous or async
+pub(crate) trait Destination {
+    type Table<'a>: DestinationTable where Self: 'a;
+    
+    async fn open_table<'a>(&'a self, table: &Table, name: &str, headers: &[String]) -> Result<Self::Table<'a>>;
+}
+
+/// A table being written by the conversion loop
+pub(crate) trait DestinationTable {
+    async fn write_fields(&mut self, row: &[Cow<'_, str>]) -> Result<()>;
+    
+    /// Complete the table. Returns where it was written and its size, when known.
+    async fn finish(self) -> Result<(String, Option<u64>)>;
+}
+
+/// An `OutputSink`, whose writes are all done by the time they return
+struct SyncDestination<'s>(&'s dyn OutputSink);
+
+impl Destination for SyncDestination<'_> {
+    type Table<'a> = Box<dyn TableSink + 'a> where Self: 'a;
+    
+    async fn open_table<'a>(&'a self, table: &Table, name: &str, headers: &[String]) -> Result<Self::Table<'a>> {
+        self.0.open_table(table, name, headers)
+    }
+}
+
+impl DestinationTable for Box<dyn TableSink + '_> {
+    async fn write_fields(&mut self, row: &[Cow<'_, str>]) -> Result<()> {
+        TableSink::write_fields(self.as_mut(), row)
+    }
+    
+    async fn finish(self) -> Result<(String, Option<u64>)> {
+        self.finish_with_size()
+    }
+}
+
+/// Run a future that never waits, as the conversion loop does with a `SyncDestination`
+fn run_ready<F: Future>(future: F) -> F::Output {
+    let mut future = std::pin::pin!(future);
+    match future.as_mut().poll(&mut task::Context::from_waker(Waker::noop())) {
+        Poll::Ready(output) => output,
+        Poll::Pending => unreachable!("a synchronous sink never waits"),
+    }
+}
+
+/// Convert SQL dump text to CSV entirely in memory, without touching the
+/// filesystem or spawning threads. Returns `(file name, CSV text)` for every
+/// table that has rows.
+pub fn convert_sql_to_csv(sql: &str) -> Result<Vec<(String, String)>> {
+    convert_sql_to_csv_with_options(sql, &CsvOptions::default())
+}
+
+/// Convert SQL dump text to CSV in memory using the given output options
+pub fn convert_sql_to_csv_with_options(sql: &str, options: &CsvOptions) -> Result<Vec<(String, String)>> {
+    let sink = MemorySink::new(options.clone());
+    Converter::new(options.clone()).convert(sql, &sink)?;
+    Ok(sink.into_files())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sink::TableSink;
+    use chrono::NaiveDate;
+    
+    const DUMP: &str = "CREATE TABLE `a` (\n  `id` int\n);\nINSERT INTO `a` VALUES (1);\n\
+        CREATE TABLE `b` (\n  `id` int\n);\nINSERT INTO `b` VALUES (2);\n";
+    
+    #[test]
+    fn test_converter_reports_written_tables() {
+        let sink = MemorySink::default();
+        let conversion = Converter::default().convert(DUMP, &sink).unwrap();
+        
+        assert!(!conversion.cancelled);
+        assert_eq!(conversion.tables.len(), 2);
+        assert_eq!(conversion.tables[0].table, "a");
+        assert_eq!(conversion.tables[0].file, WrittenFile {
+            path: "a.csv".to_string(),
+            rows_written: 1,
+            bytes: Some("id\n1\n".len() as u64),
+            warnings: Vec::new(),
+        });
+    }
+    
+    #[test]
+    fn test_cancelled_converter_returns_finished_tables() {
+        /// Cancels the conversion when table `b` is opened
+        struct CancelOnB {
+            inner: MemorySink,
+            token: CancellationToken,
+        }
+        impl OutputSink for CancelOnB {
+            fn open_table<'a>(&'a self, table: &Table, name: &str, headers: &[String]) -> Result<Box<dyn TableSink + 'a>> {
+                if name == "b" {
+                    self.token.cancel();
+                }
+                self.inner.open_table(table, name, headers)
+            }
+        }
+        
+        let token = CancellationToken::new();
+        let sink = CancelOnB { inner: MemorySink::default(), token: token.clone() };
+        let conversion = Converter::default().with_cancellation(token).convert(DUMP, &sink).unwrap();
+        
+        assert!(conversion.cancelled);
+        assert_eq!(conversion.tables.len(), 1);
+        assert_eq!(sink.inner.into_files().len(), 1);
+    }
+    
+    #[test]
+    fn test_extraction_stops_once_cancelled() {
+        let sql = "CREATE TABLE a (\n  id int\n);\nINSERT INTO a VALUES (1),(2),(3);\nINSERT INTO a VALUES (4);\n";
+        let (tables, content) = parse_sql_content(sql.to_string()).unwrap();
+        let token = CancellationToken::new();
+        token.cancel();
+        
+        let converter = Converter::default().with_cancellation(token);
+        assert_eq!(converter.table_rows(&content, &tables[0]).unwrap().rows.len(), 1);
+        let conversion = converter.convert(sql, &MemorySink::default()).unwrap();
+        assert!(conversion.cancelled);
+        assert!(conversion.tables.is_empty());
+    }
+    
+    #[test]
+    fn test_date_filter_warnings_are_reported() {
+        let sql = "CREATE TABLE events (\n  id int,\n  at date\n);\n\
+            INSERT INTO events VALUES (1,'2024-03-01');\nINSERT INTO events VALUES (2,'soon');\n";
+        let converter = Converter {
+            date_filter: Some(DateFilter {
+                column_name: "at".to_string(),
+                start_date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+                end_date: NaiveDate::from_ymd_opt(2024, 12, 31).unwrap(),
+                strict_epoch: false,
+            }),
+            ..Default::default()
+        };
+        
+        let conversion = converter.convert(sql, &MemorySink::default()).unwrap();
+        assert_eq!(conversion.tables[0].file.rows_written, 1);
+        assert_eq!(conversion.tables[0].file.warnings, vec!["Could not parse date value 'soon', excluding row"]);
+    }
+}
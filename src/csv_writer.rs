@@ -1,26 +1,382 @@
 use anyhow::{Context, Result};
-use csv::Writer;
+use csv::{Terminator, Writer, WriterBuilder};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::args::{flag_value, has_flag};
+use crate::locale::{decimal_comma, is_numeric_type};
+use crate::output_file::{FileTableSink, OutputFile};
+use crate::report::WrittenFile;
+use crate::sink::{OutputSink, TableSink};
+use crate::types::{CsvOptions, FileNaming, HeaderCase, Table};
+
+/// Written at the start of files for locales that ask for it
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
 
 /// Write data to CSV file
-pub fn write_csv(filename: &str, headers: &[String], rows: &[Vec<String>]) -> Result<()> {
-    let mut writer = Writer::from_path(filename)
+pub fn write_csv(filename: &str, headers: &[String], rows: &[Vec<String>]) -> Result<WrittenFile> {
+    write_csv_with_options(filename, headers, rows, &CsvOptions::default())
+}
+
+/// Write data to CSV file using the given output options
+pub fn write_csv_with_options(
+    filename: &str,
+    headers: &[String],
+    rows: &[Vec<String>],
+    options: &CsvOptions,
+) -> Result<WrittenFile> {
+    let mut file = OutputFile::create(Path::new(filename), options)
         .context("Failed to create CSV file")?;
+    write_csv_to(&mut file, headers, rows, options)?;
+    let path = file.path().to_string_lossy().to_string();
+    let bytes = file.finish()?;
     
-    // Write headers
-    writer.write_record(headers)
-        .context("Failed to write CSV headers")?;
-    
-    // Write data rows
+    Ok(WrittenFile {
+        path,
+        rows_written: rows.len(),
+        bytes: Some(bytes),
+        warnings: Vec::new(),
+    })
+}
+
+/// Write data as CSV to any destination, such as stdout
+pub fn write_csv_to<W: Write>(
+    destination: W,
+    headers: &[String],
+    rows: &[Vec<String>],
+    options: &CsvOptions,
+) -> Result<()> {
+    let mut sink = CsvTableSink::new(destination, headers, options, String::new())?;
     for row in rows {
-        writer.write_record(row)
-            .context("Failed to write CSV row")?;
+        sink.write_row(row)?;
+    }
+    sink.into_inner()?;
+    
+    Ok(())
+}
+
+/// Directory of CSV files, one per table
+pub struct CsvSink {
+    pub dir: PathBuf,
+    pub options: CsvOptions,
+}
+
+impl OutputSink for CsvSink {
+    fn open_table<'a>(&'a self, table: &Table, name: &str, headers: &[String]) -> Result<Box<dyn TableSink + 'a>> {
+        let path = self.dir.join(format!("{}.csv", name));
+        let file = OutputFile::create(&path, &self.options)
+            .context("Failed to create CSV file")?;
+        let location = file.path().to_string_lossy().to_string();
+        let csv = CsvTableSink::new(file, headers, &self.options, location.clone())?
+            .with_column_types(table);
+        Ok(Box::new(FileTableSink::new(csv, CsvTableSink::into_inner, location)))
+    }
+}
+
+/// Writes the rows of one table as CSV to any destination
+pub struct CsvTableSink<W: Write> {
+    writer: Writer<W>,
+    safe_excel: bool,
+    decimal_comma: bool,
+    /// Columns whose decimals get a comma; empty unless the locale uses one
+    numeric_columns: Vec<bool>,
+    /// Output columns before header case conversion, kept to match column types
+    headers: Vec<String>,
+    location: String,
+}
+
+impl<W: Write> CsvTableSink<W> {
+    /// Start a CSV and write its header row. `location` is reported by `finish`.
+    pub fn new(mut destination: W, headers: &[String], options: &CsvOptions, location: String) -> Result<Self> {
+        let converted = convert_headers(headers, options.header_case)?;
+        let locale = options.locale;
+        
+        if locale.bom {
+            destination.write_all(UTF8_BOM).context("Failed to write byte order mark")?;
+        }
+        let mut writer = WriterBuilder::new()
+            .delimiter(locale.delimiter)
+            .terminator(if locale.crlf { Terminator::CRLF } else { Terminator::Any(b'\n') })
+            .from_writer(destination);
+        writer.write_record(&converted)
+            .context("Failed to write CSV headers")?;
+        
+        Ok(CsvTableSink {
+            writer,
+            safe_excel: options.safe_excel,
+            decimal_comma: locale.decimal_comma,
+            numeric_columns: Vec::new(),
+            headers: if locale.decimal_comma { headers.to_vec() } else { Vec::new() },
+            location,
+        })
     }
     
-    writer.flush()
-        .context("Failed to flush CSV writer")?;
+    /// Write the decimals of the table's numeric columns, by their declared
+    /// types, with a comma if the locale asks for it. Output columns are
+    /// matched to the table's by name.
+    pub fn with_column_types(mut self, table: &Table) -> Self {
+        if self.decimal_comma {
+            self.numeric_columns = self.headers.iter()
+                .map(|header| table.columns.iter().position(|c| c == header)
+                    .and_then(|i| table.column_types.get(i))
+                    .is_some_and(|t| is_numeric_type(t)))
+                .collect();
+        }
+        self
+    }
     
-    println!("Created {} with {} rows", filename, rows.len());
+    /// Where the CSV is written, as passed to `new`
+    pub fn location(&self) -> &str {
+        &self.location
+    }
     
-    Ok(())
+    /// Flush the CSV and return the destination
+    pub fn into_inner(self) -> Result<W> {
+        self.writer.into_inner()
+            .map_err(|e| anyhow::anyhow!("Failed to flush CSV writer: {}", e.error()))
+    }
+}
+
+impl<W: Write> CsvTableSink<W> {
+    fn write_cells<S: AsRef<str>>(&mut self, row: &[S]) -> Result<()> {
+        if self.safe_excel || !self.numeric_columns.is_empty() {
+            for (i, cell) in row.iter().enumerate() {
+                let cell = match self.numeric_columns.get(i) {
+                    Some(true) => decimal_comma(cell.as_ref()),
+                    _ => Cow::Borrowed(cell.as_ref()),
+                };
+                let field = if self.safe_excel { escape_formula(&cell) } else { Cow::Borrowed(cell.as_ref()) };
+                self.writer.write_field(field.as_bytes())
+                    .context("Failed to write CSV row")?;
+            }
+            self.writer.write_record(None::<&[u8]>)
+        } else {
+            self.writer.write_record(row.iter().map(|cell| cell.as_ref().as_bytes()))
+        }.context("Failed to write CSV row")
+    }
+}
+
+impl<W: Write> TableSink for CsvTableSink<W> {
+    fn write_row(&mut self, row: &[String]) -> Result<()> {
+        self.write_cells(row)
+    }
+    
+    fn write_fields(&mut self, row: &[Cow<'_, str>]) -> Result<()> {
+        self.write_cells(row)
+    }
+    
+    fn finish(self: Box<Self>) -> Result<String> {
+        let location = self.location.clone();
+        self.into_inner()?;
+        Ok(location)
+    }
+}
+
+/// Parse --safe-excel flag from command line
+pub fn parse_safe_excel(args: &[String]) -> bool {
+    has_flag(args, "--safe-excel")
 }
 
+/// Prefix a cell that would be evaluated as a formula with a single quote,
+/// following the OWASP guidance on CSV injection. Plain numbers such as `-5`
+/// are left alone.
+pub fn escape_formula(cell: &str) -> Cow<'_, str> {
+    let is_formula = cell.starts_with(['=', '+', '-', '@', '\t', '\r']);
+    if is_formula && cell.parse::<f64>().is_err() {
+        Cow::Owned(format!("'{}", cell))
+    } else {
+        Cow::Borrowed(cell)
+    }
+}
+
+/// Parse --header-case option from command line
+pub fn parse_header_case(args: &[String]) -> Result<HeaderCase> {
+    match flag_value(args, "--header-case")? {
+        None | Some("preserve") => Ok(HeaderCase::Preserve),
+        Some("snake") => Ok(HeaderCase::Snake),
+        Some("camel") => Ok(HeaderCase::Camel),
+        Some("lower") => Ok(HeaderCase::Lower),
+        Some(other) => anyhow::bail!(
+            "Error: Invalid --header-case '{}'. Use one of: snake, camel, lower, preserve",
+            other
+        ),
+    }
+}
+
+/// Parse --file-naming option from command line
+pub fn parse_file_naming(args: &[String]) -> Result<FileNaming> {
+    match flag_value(args, "--file-naming")? {
+        None | Some("lower") => Ok(FileNaming::Lower),
+        Some("preserve") => Ok(FileNaming::Preserve),
+        Some("schema") => Ok(FileNaming::SchemaPrefix),
+        Some(other) => anyhow::bail!(
+            "Error: Invalid --file-naming '{}'. Use one of: lower, preserve, schema",
+            other
+        ),
+    }
+}
+
+/// Choose a CSV file name for every table. Names that would collide (compared
+/// case-insensitively, as on Windows and macOS file systems) get a numeric suffix.
+pub fn csv_filenames(tables: &[Table], naming: FileNaming) -> Vec<String> {
+    let mut taken = HashSet::new();
+    
+    tables.iter()
+        .map(|table| {
+            let stem = match (naming, &table.schema) {
+                (FileNaming::Lower, _) => table.name.to_lowercase(),
+                (FileNaming::Preserve, _) => table.name.clone(),
+                (FileNaming::SchemaPrefix, Some(schema)) => format!("{}.{}", schema, table.name).to_lowercase(),
+                (FileNaming::SchemaPrefix, None) => table.name.to_lowercase(),
+            };
+            
+            let mut filename = format!("{}.csv", stem);
+            let mut suffix = 2;
+            while !taken.insert(filename.to_lowercase()) {
+                filename = format!("{}_{}.csv", stem, suffix);
+                suffix += 1;
+            }
+            filename
+        })
+        .collect()
+}
+
+/// Apply a naming convention to all headers, failing if two columns end up with the same name
+pub fn convert_headers(headers: &[String], case: HeaderCase) -> Result<Vec<String>> {
+    let converted: Vec<String> = headers.iter()
+        .map(|header| convert_header(header, case))
+        .collect();
+    
+    let mut seen: HashMap<&str, &str> = HashMap::new();
+    for (original, header) in headers.iter().zip(&converted) {
+        if let Some(previous) = seen.insert(header, original) {
+            anyhow::bail!(
+                "Columns '{}' and '{}' both map to header '{}'",
+                previous, original, header
+            );
+        }
+    }
+    
+    Ok(converted)
+}
+
+/// Apply a naming convention to a single header
+fn convert_header(header: &str, case: HeaderCase) -> String {
+    match case {
+        HeaderCase::Preserve => header.to_string(),
+        HeaderCase::Lower => header.to_lowercase(),
+        HeaderCase::Snake => split_words(header).join("_"),
+        HeaderCase::Camel => split_words(header).iter()
+            .enumerate()
+            .map(|(i, word)| {
+                let mut chars = word.chars();
+                match chars.next() {
+                    Some(first) if i > 0 => first.to_uppercase().chain(chars).collect(),
+                    _ => word.clone(),
+                }
+            })
+            .collect(),
+    }
+}
+
+/// Split an identifier into lowercase words at separators and case boundaries
+/// (`HTTPStatusCode` -> `http`, `status`, `code`)
+fn split_words(identifier: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    
+    for part in identifier.split(|c: char| !c.is_alphanumeric()) {
+        let chars: Vec<char> = part.chars().collect();
+        let mut current = String::new();
+        
+        for (i, &c) in chars.iter().enumerate() {
+            let prev = i.checked_sub(1).map(|p| chars[p]);
+            let next = chars.get(i + 1);
+            let boundary = c.is_uppercase() && prev.is_some_and(|p| {
+                p.is_lowercase() || p.is_ascii_digit()
+                    || (p.is_uppercase() && next.is_some_and(|n| n.is_lowercase()))
+            });
+            
+            if boundary && !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            current.extend(c.to_lowercase());
+        }
+        
+        if !current.is_empty() {
+            words.push(current);
+        }
+    }
+    
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_convert_header() {
+        assert_eq!(convert_header("callSessionId", HeaderCase::Snake), "call_session_id");
+        assert_eq!(convert_header("HTTPStatusCode", HeaderCase::Snake), "http_status_code");
+        assert_eq!(convert_header("created-at", HeaderCase::Snake), "created_at");
+        assert_eq!(convert_header("call_session_id", HeaderCase::Camel), "callSessionId");
+        assert_eq!(convert_header("CallSession", HeaderCase::Camel), "callSession");
+        assert_eq!(convert_header("callSessionId", HeaderCase::Lower), "callsessionid");
+        assert_eq!(convert_header("callSessionId", HeaderCase::Preserve), "callSessionId");
+    }
+    
+    #[test]
+    fn test_escape_formula() {
+        assert_eq!(escape_formula("=HYPERLINK(\"http://x\")"), "'=HYPERLINK(\"http://x\")");
+        assert_eq!(escape_formula("+1+1"), "'+1+1");
+        assert_eq!(escape_formula("@SUM(A1)"), "'@SUM(A1)");
+        assert_eq!(escape_formula("\tcmd"), "'\tcmd");
+        assert_eq!(escape_formula("-12.5"), "-12.5");
+        assert_eq!(escape_formula("a=b"), "a=b");
+    }
+    
+    #[test]
+    fn test_csv_filenames_avoid_collisions() {
+        let table = |schema: Option<&str>, name: &str| Table {
+            name: name.to_string(),
+            schema: schema.map(|s| s.to_string()),
+            ..Default::default()
+        };
+        let tables = vec![
+            table(None, "Users"),
+            table(None, "users"),
+            table(Some("audit"), "users"),
+            table(None, "users_2"),
+        ];
+        
+        assert_eq!(csv_filenames(&tables, FileNaming::Lower), vec!["users.csv", "users_2.csv", "users_3.csv", "users_2_2.csv"]);
+        assert_eq!(csv_filenames(&tables, FileNaming::Preserve), vec!["Users.csv", "users_2.csv", "users_3.csv", "users_2_2.csv"]);
+        assert_eq!(csv_filenames(&tables, FileNaming::SchemaPrefix), vec!["users.csv", "users_2.csv", "audit.users.csv", "users_2_2.csv"]);
+    }
+    
+    #[test]
+    fn test_german_locale_output() {
+        let options = CsvOptions { locale: crate::locale::csv_locale("de-DE").unwrap(), ..Default::default() };
+        let headers = vec!["name".to_string(), "price".to_string()];
+        let mut sink = CsvTableSink::new(Vec::new(), &headers, &options, String::new()).unwrap()
+            .with_column_types(&Table {
+                columns: headers.clone(),
+                column_types: vec!["VARCHAR(20)".to_string(), "DECIMAL(10,2)".to_string()],
+                ..Default::default()
+            });
+        sink.write_row(&["Tee; grün".to_string(), "3.50".to_string()]).unwrap();
+        sink.write_row(&["1.5".to_string(), "NULL".to_string()]).unwrap();
+        
+        let output = String::from_utf8(sink.into_inner().unwrap()).unwrap();
+        assert_eq!(output, "\u{FEFF}name;price\r\n\"Tee; grün\";3,50\r\n1.5;NULL\r\n");
+    }
+    
+    #[test]
+    fn test_convert_headers_detects_collisions() {
+        let headers = vec!["userId".to_string(), "user_id".to_string()];
+        assert!(convert_headers(&headers, HeaderCase::Snake).is_err());
+        assert!(convert_headers(&headers, HeaderCase::Preserve).is_ok());
+    }
+}
@@ -0,0 +1,77 @@
+//! Selecting the databases of a dump to convert (`--databases`).
+//!
+//! Dumps of several databases, such as `mysqldump --all-databases` or
+//! `pg_dumpall`, switch between them with `USE db;` or `\connect db`. Their
+//! tables are named `db.table`, so tables of the same name in different
+//! databases are written to files of their own, `shop.orders.csv` and
+//! `crm.orders.csv`. `--databases shop,crm` converts only the tables of the
+//! databases listed.
+
+use anyhow::Result;
+
+use crate::args::flag_value;
+use crate::types::Table;
+
+/// Parse --databases option from command line, a comma-separated list
+pub fn parse_databases(args: &[String]) -> Result<Option<Vec<String>>> {
+    let Some(list) = flag_value(args, "--databases")? else {
+        return Ok(None);
+    };
+    let databases: Vec<String> = list.split(',')
+        .map(|database| database.trim().to_string())
+        .filter(|database| !database.is_empty())
+        .collect();
+    if databases.is_empty() {
+        anyhow::bail!("Error: --databases requires at least one database name");
+    }
+    Ok(Some(databases))
+}
+
+/// Keep only the tables of the selected databases. Returns the selected
+/// databases without any table in the dump.
+pub fn select_databases(tables: &mut Vec<Table>, selected: &[String]) -> Vec<String> {
+    let missing = selected.iter()
+        .filter(|database| !tables.iter().any(|table| table.database.as_ref() == Some(*database)))
+        .cloned()
+        .collect();
+    tables.retain(|table| table.database.as_ref().is_some_and(|database| selected.contains(database)));
+    missing
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{extract_table_rows, parse_sql_content};
+    
+    #[test]
+    fn test_tables_of_several_databases_stay_apart() {
+        let content = "\
+CREATE DATABASE shop;
+USE `shop`;
+CREATE TABLE orders (id int, total int);
+INSERT INTO orders VALUES (1, 10), (2, 20);
+CREATE DATABASE crm;
+USE `crm`;
+CREATE TABLE orders (id int, customer text);
+INSERT INTO orders VALUES (1, 'Ann');
+CREATE TABLE notes (id int);
+".to_string();
+        let (mut tables, content) = parse_sql_content(content).unwrap();
+        let names: Vec<&str> = tables.iter().map(|table| table.name.as_str()).collect();
+        assert_eq!(names, vec!["shop.orders", "crm.orders", "crm.notes"]);
+        assert_eq!(extract_table_rows(&content, &tables[0]).unwrap(), vec![vec!["1", "10"], vec!["2", "20"]]);
+        assert_eq!(extract_table_rows(&content, &tables[1]).unwrap(), vec![vec!["1", "Ann"]]);
+        
+        let missing = select_databases(&mut tables, &["crm".to_string(), "billing".to_string()]);
+        assert_eq!(missing, vec!["billing"]);
+        assert_eq!(tables.iter().map(|table| table.name.as_str()).collect::<Vec<_>>(), vec!["crm.orders", "crm.notes"]);
+    }
+    
+    #[test]
+    fn test_parse_databases() {
+        let args = |line: &str| line.split_whitespace().map(str::to_string).collect::<Vec<_>>();
+        assert_eq!(parse_databases(&args("t dump.sql")).unwrap(), None);
+        assert_eq!(parse_databases(&args("t dump.sql --databases shop,crm")).unwrap(), Some(vec!["shop".to_string(), "crm".to_string()]));
+        assert!(parse_databases(&args("t dump.sql --databases ,")).is_err());
+    }
+}
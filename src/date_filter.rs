@@ -1,7 +1,66 @@
 use anyhow::{Context, Result};
-use chrono::{NaiveDate, NaiveDateTime, DateTime, FixedOffset};
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, DateTime, FixedOffset};
+use std::fmt;
 
+use crate::args::has_flag;
 use crate::types::DateFilter;
+use crate::warnings::{StderrWarnings, WarningHandler};
+
+/// Integer timestamps at or above this are read as milliseconds. As seconds
+/// they would be past the year 5000; as milliseconds they are after 1973.
+const MILLIS_THRESHOLD: i64 = 100_000_000_000;
+
+/// Last year accepted for integer timestamps with `--strict-epoch`
+const MAX_EPOCH_YEAR: i32 = 2100;
+
+/// Unit of integer timestamps in a date column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EpochUnit {
+    Seconds,
+    Milliseconds,
+}
+
+impl fmt::Display for EpochUnit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EpochUnit::Seconds => write!(f, "seconds"),
+            EpochUnit::Milliseconds => write!(f, "milliseconds"),
+        }
+    }
+}
+
+impl EpochUnit {
+    /// The unit of a single timestamp, from its magnitude
+    fn of(timestamp: i64) -> Self {
+        if timestamp.unsigned_abs() >= MILLIS_THRESHOLD as u64 {
+            EpochUnit::Milliseconds
+        } else {
+            EpochUnit::Seconds
+        }
+    }
+    
+    /// The unit of a column, from its largest integer value. Returns `None`
+    /// when the column holds no integers.
+    pub fn detect<'a>(values: impl IntoIterator<Item = &'a str>) -> Option<Self> {
+        values.into_iter()
+            .filter_map(|value| value.trim().parse::<i64>().ok())
+            .map(i64::unsigned_abs)
+            .max()
+            .map(|largest| EpochUnit::of(largest.min(i64::MAX as u64) as i64))
+    }
+    
+    fn to_datetime(self, timestamp: i64) -> Option<NaiveDateTime> {
+        let datetime = match self {
+            EpochUnit::Seconds => DateTime::from_timestamp(timestamp, 0),
+            EpochUnit::Milliseconds => DateTime::from_timestamp_millis(timestamp),
+        };
+        datetime.map(|datetime| datetime.naive_utc())
+    }
+    
+    fn to_date(self, timestamp: i64) -> Option<NaiveDate> {
+        self.to_datetime(timestamp).map(|datetime| datetime.date())
+    }
+}
 
 /// Parse date filter arguments from command line
 pub fn parse_date_filter(args: &[String]) -> Result<Option<DateFilter>> {
@@ -25,7 +84,7 @@ pub fn parse_date_filter(args: &[String]) -> Result<Option<DateFilter>> {
             .context(format!("Invalid start date '{}'. Use format: YYYY-MM-DD", start_date_str))?;
         
         // Parse end date or use today's date
-        let end_date = if args.len() > pos + 3 {
+        let end_date = if args.len() > pos + 3 && !args[pos + 3].starts_with("--") {
             let end_date_str = &args[pos + 3];
             NaiveDate::parse_from_str(end_date_str, "%Y-%m-%d")
                 .context(format!("Invalid end date '{}'. Use format: YYYY-MM-DD", end_date_str))?
@@ -43,53 +102,124 @@ pub fn parse_date_filter(args: &[String]) -> Result<Option<DateFilter>> {
             column_name,
             start_date,
             end_date,
+            strict_epoch: has_flag(args, "--strict-epoch"),
         }))
+    } else if has_flag(args, "--strict-epoch") {
+        anyhow::bail!("Error: --strict-epoch requires --date-filter")
     } else {
         Ok(None)
     }
 }
 
-/// Apply date filter to rows
+/// Apply date filter to rows, printing a warning for every date that can't be parsed
 pub fn apply_date_filter(
     headers: &[String],
     rows: &[Vec<String>],
     filter: &DateFilter,
 ) -> Result<Vec<Vec<String>>> {
+    apply_date_filter_with_warnings(headers, rows, filter, &StderrWarnings)
+}
+
+/// Apply date filter to rows, sending a warning to the handler for every date
+/// that can't be parsed. When the column holds integer timestamps, the unit
+/// they are read in (seconds or milliseconds) is reported to the handler too.
+pub fn apply_date_filter_with_warnings(
+    headers: &[String],
+    rows: &[Vec<String>],
+    filter: &DateFilter,
+    warnings: &dyn WarningHandler,
+) -> Result<Vec<Vec<String>>> {
+    let mut kept: Vec<&Vec<String>> = rows.iter().collect();
+    retain_dated_rows(headers, &mut kept, filter, warnings)?;
+    Ok(kept.into_iter().cloned().collect())
+}
+
+/// Keep the rows in the date range, like `apply_date_filter_with_warnings`,
+/// for rows of any text type
+pub(crate) fn retain_dated_rows<R: AsRef<[S]>, S: AsRef<str>>(
+    headers: &[String],
+    rows: &mut Vec<R>,
+    filter: &DateFilter,
+    warnings: &dyn WarningHandler,
+) -> Result<()> {
+    retain_dated_batch(headers, rows, filter, &mut EpochDetection::default(), warnings)
+}
+
+/// Epoch unit of a date column filtered in batches, decided on the first
+/// batch with rows and kept for the rest of the table
+#[derive(Debug, Default)]
+pub struct EpochDetection(Option<Option<EpochUnit>>);
+
+/// Keep the rows of one batch of a table in the date range, like
+/// `retain_dated_rows`, reading integers in the unit found for the table
+pub fn retain_dated_batch<R: AsRef<[S]>, S: AsRef<str>>(
+    headers: &[String],
+    rows: &mut Vec<R>,
+    filter: &DateFilter,
+    detection: &mut EpochDetection,
+    warnings: &dyn WarningHandler,
+) -> Result<()> {
     // Find the column index for the date column
     let column_index = headers.iter().position(|h| h == &filter.column_name)
         .ok_or_else(|| anyhow::anyhow!("Column '{}' not found in table headers", filter.column_name))?;
+    if rows.is_empty() {
+        return Ok(());
+    }
+    
+    // One unit for the whole column, so small and large values are not read differently
+    let epoch_unit = *detection.0.get_or_insert_with(|| {
+        let unit = EpochUnit::detect(rows.iter().filter_map(|row| row.as_ref().get(column_index)).map(AsRef::as_ref));
+        if let Some(unit) = unit {
+            warnings.warn(format!("Reading integer values of column '{}' as epoch {}", filter.column_name, unit));
+        }
+        unit
+    });
     
     // Filter rows based on date range
-    let filtered: Vec<Vec<String>> = rows.iter()
-        .filter(|row| {
-            if column_index >= row.len() {
-                return false;
-            }
-            
-            let date_value = &row[column_index];
-            
-            // Try to parse the date value
-            match parse_date_value(date_value) {
-                Some(date) => {
-                    date >= filter.start_date && date <= filter.end_date
-                }
+    rows.retain(|row| {
+        let Some(date_value) = row.as_ref().get(column_index).map(AsRef::as_ref) else {
+            return false;
+        };
+        
+        if let (Ok(timestamp), Some(unit)) = (date_value.trim().parse::<i64>(), epoch_unit) {
+            let date = unit.to_date(timestamp)
+                .filter(|date| !filter.strict_epoch || (timestamp > 0 && date.year() <= MAX_EPOCH_YEAR));
+            return match date {
+                Some(date) => date >= filter.start_date && date <= filter.end_date,
                 None => {
-                    eprintln!("Warning: Could not parse date value '{}', excluding row", date_value);
+                    warnings.warn(format!("Epoch value '{}' is out of range, excluding row", date_value));
                     false
                 }
+            };
+        }
+        
+        // Try to parse the date value
+        match parse_date_value(date_value) {
+            Some(date) => {
+                date >= filter.start_date && date <= filter.end_date
+            }
+            None => {
+                warnings.warn(format!("Could not parse date value '{}', excluding row", date_value));
+                false
             }
-        })
-        .cloned()
-        .collect();
+        }
+    });
     
-    Ok(filtered)
+    Ok(())
 }
 
 /// Parse a date value from various formats
-fn parse_date_value(value: &str) -> Option<NaiveDate> {
+pub(crate) fn parse_date_value(value: &str) -> Option<NaiveDate> {
+    parse_datetime_value(value).map(|datetime| datetime.date())
+}
+
+/// Parse a date or datetime value from the formats accepted by the date filter.
+/// Dates without a time are at midnight; datetimes with an offset keep their
+/// local time.
+pub(crate) fn parse_datetime_value(value: &str) -> Option<NaiveDateTime> {
     // Try to parse ISO 8601 with timezone first (most common in databases)
     if let Ok(datetime) = DateTime::<FixedOffset>::parse_from_rfc3339(value) {
-        return Some(datetime.date_naive());
+        return Some(datetime.naive_local());
     }
     
     // Try various date formats without timezone
@@ -106,26 +236,20 @@ fn parse_date_value(value: &str) -> Option<NaiveDate> {
     // Try to parse as NaiveDateTime
     for format in &formats[..5] {
         if let Ok(datetime) = NaiveDateTime::parse_from_str(value, format) {
-            return Some(datetime.date());
+            return Some(datetime);
         }
     }
     
     // Try to parse as just a date (no time component)
     for format in &formats {
         if let Ok(date) = NaiveDate::parse_from_str(value, format) {
-            return Some(date);
+            return Some(date.and_time(NaiveTime::MIN));
         }
     }
     
-    // Try to parse timestamps (milliseconds since epoch)
+    // Try to parse timestamps (seconds or milliseconds since epoch, by magnitude)
     if let Ok(timestamp) = value.parse::<i64>() {
-        // Try both seconds and milliseconds
-        if let Some(datetime) = chrono::DateTime::from_timestamp(timestamp / 1000, 0) {
-            return Some(datetime.date_naive());
-        }
-        if let Some(datetime) = chrono::DateTime::from_timestamp(timestamp, 0) {
-            return Some(datetime.date_naive());
-        }
+        return EpochUnit::of(timestamp).to_datetime(timestamp);
     }
     
     None
@@ -134,7 +258,8 @@ fn parse_date_value(value: &str) -> Option<NaiveDate> {
 #[cfg(test)]
 mod tests {
     use super::*;
-
+    use crate::warnings::CollectedWarnings;
+    
     #[test]
     fn test_parse_date_value() {
         // ISO 8601 date
@@ -149,5 +274,39 @@ mod tests {
         // Invalid date
         assert!(parse_date_value("not-a-date").is_none());
     }
+    
+    #[test]
+    fn test_epoch_unit_detection() {
+        assert_eq!(EpochUnit::detect(["1705312800", "0"]), Some(EpochUnit::Seconds));
+        assert_eq!(EpochUnit::detect(["1705312800000", "not a number"]), Some(EpochUnit::Milliseconds));
+        assert_eq!(EpochUnit::detect(["2024-01-15"]), None);
+        assert_eq!(parse_date_value("1705312800"), NaiveDate::from_ymd_opt(2024, 1, 15));
+        assert_eq!(parse_date_value("1705312800000"), NaiveDate::from_ymd_opt(2024, 1, 15));
+    }
+    
+    #[test]
+    fn test_strict_epoch_excludes_out_of_range_values() {
+        let headers = vec!["id".to_string(), "createdAt".to_string()];
+        let rows: Vec<Vec<String>> = [("1", "1705312800000"), ("2", "0"), ("3", "-86400000"), ("4", "9999999999999")]
+            .iter()
+            .map(|(id, at)| vec![id.to_string(), at.to_string()])
+            .collect();
+        let mut filter = DateFilter {
+            column_name: "createdAt".to_string(),
+            start_date: NaiveDate::from_ymd_opt(1960, 1, 1).unwrap(),
+            end_date: NaiveDate::from_ymd_opt(2300, 1, 1).unwrap(),
+            strict_epoch: false,
+        };
+        
+        let warnings = CollectedWarnings::default();
+        let filtered = apply_date_filter_with_warnings(&headers, &rows, &filter, &warnings).unwrap();
+        assert_eq!(filtered.len(), 4);
+        assert_eq!(warnings.into_inner(), vec!["Reading integer values of column 'createdAt' as epoch milliseconds"]);
+        
+        filter.strict_epoch = true;
+        let warnings = CollectedWarnings::default();
+        let filtered = apply_date_filter_with_warnings(&headers, &rows, &filter, &warnings).unwrap();
+        assert_eq!(filtered, vec![rows[0].clone()]);
+        assert_eq!(warnings.into_inner().len(), 4);
+    }
 }
-
@@ -0,0 +1,100 @@
+use anyhow::Result;
+use chrono::format::{Item, StrftimeItems};
+
+use crate::args::flag_values;
+use crate::date_filter::parse_datetime_value;
+use crate::types::DateReformat;
+use crate::warnings::WarningHandler;
+
+/// Parse --reformat-date options from command line, e.g.
+/// `--reformat-date createdAt:%Y-%m-%d` (repeatable)
+pub fn parse_date_reformats(args: &[String]) -> Result<Vec<DateReformat>> {
+    flag_values(args, "--reformat-date")?
+        .into_iter()
+        .map(parse_date_reformat)
+        .collect()
+}
+
+/// Parse a single `<column>:<format>` specification. The format uses strftime
+/// syntax and may itself contain `:`.
+pub fn parse_date_reformat(spec: &str) -> Result<DateReformat> {
+    let Some((column, format)) = spec.split_once(':').filter(|(column, format)| !column.is_empty() && !format.is_empty()) else {
+        anyhow::bail!("Error: Invalid --reformat-date '{}'. Use <column>:<format>, e.g. createdAt:%Y-%m-%d", spec);
+    };
+    if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+        anyhow::bail!("Error: Invalid date format '{}' in --reformat-date '{}'", format, spec);
+    }
+    
+    Ok(DateReformat {
+        column: column.to_string(),
+        format: format.to_string(),
+    })
+}
+
+/// Rewrite the values of date columns in a single format. Values are read with
+/// the formats the date filter accepts; empty and NULL values are kept, and
+/// other values that cannot be read are kept with a warning. Returns the number
+/// of values that could not be converted.
+pub fn apply_date_reformats(
+    headers: &[String],
+    rows: &mut [Vec<String>],
+    reformats: &[DateReformat],
+    warnings: &dyn WarningHandler,
+) -> usize {
+    let mut unconverted = 0;
+    
+    for reformat in reformats {
+        let Some(index) = headers.iter().position(|h| h == &reformat.column) else {
+            continue;
+        };
+        for cell in rows.iter_mut().filter_map(|row| row.get_mut(index)) {
+            if cell.is_empty() || cell.eq_ignore_ascii_case("NULL") {
+                continue;
+            }
+            match parse_datetime_value(cell.trim()) {
+                Some(datetime) => *cell = datetime.format(&reformat.format).to_string(),
+                None => {
+                    unconverted += 1;
+                    warnings.warn(format!("Could not read date '{}' in column '{}', keeping it", cell, reformat.column));
+                }
+            }
+        }
+    }
+    
+    unconverted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::warnings::CollectedWarnings;
+    
+    #[test]
+    fn test_parse_date_reformat() {
+        let reformat = parse_date_reformat("createdAt:%Y-%m-%d %H:%M").unwrap();
+        assert_eq!(reformat.column, "createdAt");
+        assert_eq!(reformat.format, "%Y-%m-%d %H:%M");
+        
+        assert!(parse_date_reformat("createdAt").is_err());
+        assert!(parse_date_reformat("createdAt:%Q").is_err());
+    }
+    
+    #[test]
+    fn test_apply_date_reformats() {
+        let headers = vec!["id".to_string(), "createdAt".to_string()];
+        let mut rows: Vec<Vec<String>> = ["2024-01-15 14:30:00", "01/15/2024", "2024-01-15T14:30:00+02:00", "1705329000000", "soon", "NULL"]
+            .iter()
+            .enumerate()
+            .map(|(i, value)| vec![i.to_string(), value.to_string()])
+            .collect();
+        let reformats = vec![parse_date_reformat("createdAt:%d.%m.%Y %H:%M").unwrap()];
+        let warnings = CollectedWarnings::default();
+        
+        let unconverted = apply_date_reformats(&headers, &mut rows, &reformats, &warnings);
+        
+        let values: Vec<&str> = rows.iter().map(|row| row[1].as_str()).collect();
+        assert_eq!(values, vec!["15.01.2024 14:30", "15.01.2024 00:00", "15.01.2024 14:30", "15.01.2024 14:30", "soon", "NULL"]);
+        assert_eq!(unconverted, 1);
+        assert_eq!(warnings.into_inner(), vec!["Could not read date 'soon' in column 'createdAt', keeping it"]);
+    }
+}
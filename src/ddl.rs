@@ -0,0 +1,126 @@
+//! CREATE TABLE statements for the destination of the output (`--emit-ddl`).
+//!
+//! `--emit-ddl snowflake` writes `schema.snowflake.sql` next to the output
+//! files, with a CREATE TABLE statement for every table written, so loading
+//! them needs no hand-written DDL. Each statement has the columns of the
+//! written file under its headers, with the dump's declared types translated
+//! to the destination's (see `load::create_table_sql`). Columns without a
+//! declared type, such as those added by `--add-column`, are text.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::args::flag_value;
+use crate::csv_writer::convert_headers;
+use crate::load::{create_table_sql, Dialect};
+use crate::types::{HeaderCase, Table};
+
+/// Dialects accepted by --emit-ddl
+const DDL_DIALECTS: &str = "snowflake, bigquery, postgres, duckdb, mysql";
+
+/// Where and in which dialect the DDL is written
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DdlTarget {
+    pub dialect: Dialect,
+    /// The output directory
+    pub dir: PathBuf,
+}
+
+/// Parse --emit-ddl option from command line. The DDL goes to the output
+/// directory, so object storage and HTTP outputs are not supported.
+pub fn parse_emit_ddl(args: &[String]) -> Result<Option<DdlTarget>> {
+    let Some(name) = flag_value(args, "--emit-ddl")? else {
+        return Ok(None);
+    };
+    let dialect = Dialect::from_name(name)
+        .ok_or_else(|| anyhow::anyhow!("Error: Invalid --emit-ddl '{}'. Use one of: {}", name, DDL_DIALECTS))?;
+    let output_dir = flag_value(args, "--output")?.unwrap_or(".");
+    if output_dir.contains("://") {
+        anyhow::bail!("Error: --emit-ddl only supports output to a local directory");
+    }
+    Ok(Some(DdlTarget { dialect, dir: PathBuf::from(output_dir) }))
+}
+
+/// The table as it was written: its columns are the file's `headers`, in the
+/// given case, each typed like the table's column of the same name
+pub fn written_table(table: &Table, headers: &[String], case: HeaderCase) -> Result<Table> {
+    let columns = convert_headers(headers, case)?;
+    let column_types = headers.iter()
+        .map(|header| table.columns.iter().position(|column| column == header)
+            .and_then(|i| table.column_types.get(i).cloned())
+            .unwrap_or_default())
+        .collect();
+    // The key is only kept when all of its columns were written
+    let primary_key = table.primary_key.iter()
+        .map(|key| headers.iter().position(|header| header == key).map(|i| columns[i].clone()))
+        .collect::<Option<Vec<String>>>()
+        .unwrap_or_default();
+    
+    Ok(Table {
+        name: table.name.clone(),
+        columns,
+        column_types,
+        primary_key,
+        ..Default::default()
+    })
+}
+
+/// Write the CREATE TABLE statements of the tables to `schema.<dialect>.sql`
+/// in the output directory. Returns the path of the file.
+pub fn write_ddl(target: &DdlTarget, tables: &[Table]) -> Result<PathBuf> {
+    let sql: String = tables.iter()
+        .map(|table| format!("{};\n\n", create_table_sql(table, target.dialect)))
+        .collect();
+    
+    fs::create_dir_all(&target.dir)
+        .with_context(|| format!("Failed to create directory '{}'", target.dir.display()))?;
+    let path = target.dir.join(format!("schema.{}.sql", target.dialect.name()));
+    fs::write(&path, sql)
+        .with_context(|| format!("Failed to write '{}'", path.display()))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+    
+    #[test]
+    fn test_parse_emit_ddl() {
+        assert_eq!(parse_emit_ddl(&args("t dump.sql")).unwrap(), None);
+        assert_eq!(
+            parse_emit_ddl(&args("t dump.sql --output out --emit-ddl BigQuery")).unwrap(),
+            Some(DdlTarget { dialect: Dialect::BigQuery, dir: PathBuf::from("out") })
+        );
+        assert!(parse_emit_ddl(&args("t dump.sql --emit-ddl oracle")).is_err());
+        assert!(parse_emit_ddl(&args("t dump.sql --output s3://b/x --emit-ddl snowflake")).is_err());
+    }
+    
+    #[test]
+    fn test_written_table() {
+        let table = Table {
+            name: "users".to_string(),
+            columns: vec!["userId".to_string(), "email".to_string(), "password".to_string()],
+            column_types: vec!["INT".to_string(), "VARCHAR(255)".to_string(), "TEXT".to_string()],
+            primary_key: vec!["userId".to_string()],
+            ..Default::default()
+        };
+        let headers = vec!["userId".to_string(), "email".to_string(), "exported_at".to_string()];
+        
+        let written = written_table(&table, &headers, HeaderCase::Snake).unwrap();
+        assert_eq!(written.columns, vec!["user_id", "email", "exported_at"]);
+        assert_eq!(written.column_types, vec!["INT", "VARCHAR(255)", ""]);
+        assert_eq!(written.primary_key, vec!["user_id"]);
+        assert_eq!(create_table_sql(&written, Dialect::DuckDb), "\
+CREATE TABLE IF NOT EXISTS \"users\" (
+  \"user_id\" BIGINT,
+  \"email\" VARCHAR,
+  \"exported_at\" VARCHAR,
+  PRIMARY KEY (\"user_id\")
+)");
+    }
+}
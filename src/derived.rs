@@ -0,0 +1,376 @@
+use anyhow::{Context, Result};
+use chrono::Datelike;
+use regex::Regex;
+use std::path::Path;
+
+use crate::args::{flag_values, has_flag};
+use crate::date_filter::parse_date_value;
+use crate::types::{DerivedColumn, Expression};
+
+/// Parse --add-column options from command line, e.g. `orders:export_date=NOW`
+/// or `users:full_name=concat(first_name, ' ', last_name)`
+pub fn parse_derived_columns(args: &[String]) -> Result<Vec<DerivedColumn>> {
+    flag_values(args, "--add-column")?
+        .into_iter()
+        .map(parse_derived_column)
+        .collect()
+}
+
+/// Parse a single `<table>:<column>=<expression>` definition
+pub fn parse_derived_column(spec: &str) -> Result<DerivedColumn> {
+    let (target, expression) = spec.split_once('=')
+        .ok_or_else(|| anyhow::anyhow!("Error: --add-column '{}' must look like <table>:<column>=<expression>", spec))?;
+    let (table, name) = target.split_once(':')
+        .ok_or_else(|| anyhow::anyhow!("Error: --add-column '{}' must look like <table>:<column>=<expression>", spec))?;
+    
+    if table.trim().is_empty() || name.trim().is_empty() {
+        anyhow::bail!("Error: --add-column '{}' needs both a table and a column name", spec);
+    }
+    
+    Ok(DerivedColumn {
+        table: table.trim().to_string(),
+        name: name.trim().to_string(),
+        expression: parse_expression(expression)
+            .with_context(|| format!("Invalid expression in --add-column '{}'", spec))?,
+    })
+}
+
+/// Build provenance columns from --add-source-column (`_source_file`) and
+/// --add-dump-timestamp (`_dump_timestamp`), added to every table
+pub fn parse_source_columns(args: &[String], sql_file: &str, content: &str) -> Vec<DerivedColumn> {
+    let mut columns = Vec::new();
+    
+    if has_flag(args, "--add-source-column") {
+        let file_name = Path::new(sql_file)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| sql_file.to_string());
+        columns.push(DerivedColumn {
+            table: "*".to_string(),
+            name: "_source_file".to_string(),
+            expression: Expression::Literal(file_name),
+        });
+    }
+    
+    if has_flag(args, "--add-dump-timestamp") {
+        let timestamp = find_dump_timestamp(content);
+        if timestamp.is_none() {
+            eprintln!("Warning: No dump timestamp found in header comments of '{}'", sql_file);
+        }
+        columns.push(DerivedColumn {
+            table: "*".to_string(),
+            name: "_dump_timestamp".to_string(),
+            expression: Expression::Literal(timestamp.unwrap_or_default()),
+        });
+    }
+    
+    columns
+}
+
+/// Find the dump creation time in the comments written by common dump tools
+/// (mysqldump, pg_dump, phpMyAdmin)
+pub fn find_dump_timestamp(content: &str) -> Option<String> {
+    let timestamp_regex = Regex::new(
+        r"(?im)^--\s*(?:Dump completed on|Started on|Completed on|Generation Time:|Dump date:|Date:)\s*(.+?)\s*$"
+    ).ok()?;
+    
+    timestamp_regex.captures(content)
+        .map(|captures| captures[1].to_string())
+}
+
+/// Append the derived columns that apply to a table to its headers and rows
+pub fn apply_derived_columns(
+    table_name: &str,
+    headers: &[String],
+    rows: Vec<Vec<String>>,
+    derived: &[DerivedColumn],
+) -> Result<(Vec<String>, Vec<Vec<String>>)> {
+    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    apply_derived_columns_at(table_name, headers, rows, derived, &now)
+}
+
+/// Append the derived columns like `apply_derived_columns`, with `now` as the
+/// time of `now()`, so every batch of a table gets the same time
+pub fn apply_derived_columns_at(
+    table_name: &str,
+    headers: &[String],
+    rows: Vec<Vec<String>>,
+    derived: &[DerivedColumn],
+    now: &str,
+) -> Result<(Vec<String>, Vec<Vec<String>>)> {
+    let columns: Vec<&DerivedColumn> = derived.iter()
+        .filter(|column| column.table == "*" || column.table == table_name)
+        .collect();
+    if columns.is_empty() {
+        return Ok((headers.to_vec(), rows));
+    }
+    
+    for column in &columns {
+        check_columns_exist(&column.expression, headers)
+            .with_context(|| format!("Cannot compute derived column '{}'", column.name))?;
+    }
+    
+    let mut new_headers = headers.to_vec();
+    new_headers.extend(columns.iter().map(|column| column.name.clone()));
+    
+    let new_rows = rows.into_iter()
+        .map(|mut row| {
+            let values: Vec<String> = columns.iter()
+                .map(|column| evaluate(&column.expression, headers, &row, now))
+                .collect();
+            row.extend(values);
+            row
+        })
+        .collect();
+    
+    Ok((new_headers, new_rows))
+}
+
+/// Make sure every column referenced by an expression exists in the table
+fn check_columns_exist(expression: &Expression, headers: &[String]) -> Result<()> {
+    match expression {
+        Expression::Now | Expression::Literal(_) => Ok(()),
+        Expression::Column(name) => {
+            if headers.iter().any(|h| h == name) {
+                Ok(())
+            } else {
+                anyhow::bail!("Column '{}' not found in table headers", name)
+            }
+        }
+        Expression::Concat(parts) => parts.iter()
+            .try_for_each(|part| check_columns_exist(part, headers)),
+        Expression::Substring { value, .. }
+        | Expression::Year(value)
+        | Expression::Month(value)
+        | Expression::Day(value)
+        | Expression::Upper(value)
+        | Expression::Lower(value) => check_columns_exist(value, headers),
+    }
+}
+
+/// Compute the value of an expression for one row
+fn evaluate(expression: &Expression, headers: &[String], row: &[String], now: &str) -> String {
+    match expression {
+        Expression::Now => now.to_string(),
+        Expression::Literal(value) => value.clone(),
+        Expression::Column(name) => headers.iter()
+            .position(|h| h == name)
+            .and_then(|index| row.get(index))
+            .cloned()
+            .unwrap_or_default(),
+        Expression::Concat(parts) => parts.iter()
+            .map(|part| evaluate(part, headers, row, now))
+            .collect(),
+        Expression::Substring { value, start, length } => {
+            let value = evaluate(value, headers, row, now);
+            let chars = value.chars().skip(start.saturating_sub(1));
+            match length {
+                Some(length) => chars.take(*length).collect(),
+                None => chars.collect(),
+            }
+        }
+        Expression::Year(value) => parse_date_value(&evaluate(value, headers, row, now))
+            .map(|date| date.year().to_string())
+            .unwrap_or_default(),
+        Expression::Month(value) => parse_date_value(&evaluate(value, headers, row, now))
+            .map(|date| date.month().to_string())
+            .unwrap_or_default(),
+        Expression::Day(value) => parse_date_value(&evaluate(value, headers, row, now))
+            .map(|date| date.day().to_string())
+            .unwrap_or_default(),
+        Expression::Upper(value) => evaluate(value, headers, row, now).to_uppercase(),
+        Expression::Lower(value) => evaluate(value, headers, row, now).to_lowercase(),
+    }
+}
+
+/// Token of the derived column expression language
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Identifier(String),
+    Literal(String),
+    OpenParen,
+    CloseParen,
+    Comma,
+}
+
+/// Parse an expression such as `NOW`, `'text'`, `column` or `func(arg, ...)`
+pub fn parse_expression(text: &str) -> Result<Expression> {
+    let tokens = tokenize(text)?;
+    let mut pos = 0;
+    let expression = parse_tokens(&tokens, &mut pos)?;
+    
+    if pos != tokens.len() {
+        anyhow::bail!("Unexpected input after expression in '{}'", text);
+    }
+    
+    Ok(expression)
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '(' => tokens.push(Token::OpenParen),
+            ')' => tokens.push(Token::CloseParen),
+            ',' => tokens.push(Token::Comma),
+            '\'' => {
+                // Quoted string literal with '' as an escaped quote
+                let mut value = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        Some('\'') if chars.get(i + 1) == Some(&'\'') => {
+                            value.push('\'');
+                            i += 1;
+                        }
+                        Some('\'') => break,
+                        Some(&ch) => value.push(ch),
+                        None => anyhow::bail!("Unterminated string literal"),
+                    }
+                    i += 1;
+                }
+                tokens.push(Token::Literal(value));
+            }
+            c if c.is_whitespace() => {}
+            c if c.is_alphanumeric() || c == '_' || c == '-' || c == '.' => {
+                let start = i;
+                while i + 1 < chars.len()
+                    && (chars[i + 1].is_alphanumeric() || matches!(chars[i + 1], '_' | '-' | '.'))
+                {
+                    i += 1;
+                }
+                let word: String = chars[start..=i].iter().collect();
+                if word.chars().next().is_some_and(|first| first.is_ascii_digit() || first == '-') {
+                    tokens.push(Token::Literal(word));
+                } else {
+                    tokens.push(Token::Identifier(word));
+                }
+            }
+            other => anyhow::bail!("Unexpected character '{}'", other),
+        }
+        i += 1;
+    }
+    
+    Ok(tokens)
+}
+
+fn parse_tokens(tokens: &[Token], pos: &mut usize) -> Result<Expression> {
+    let token = tokens.get(*pos)
+        .ok_or_else(|| anyhow::anyhow!("Expected an expression"))?;
+    *pos += 1;
+    
+    match token {
+        Token::Literal(value) => Ok(Expression::Literal(value.clone())),
+        Token::Identifier(name) if tokens.get(*pos) == Some(&Token::OpenParen) => {
+            *pos += 1;
+            let mut arguments = Vec::new();
+            if tokens.get(*pos) != Some(&Token::CloseParen) {
+                loop {
+                    arguments.push(parse_tokens(tokens, pos)?);
+                    match tokens.get(*pos) {
+                        Some(Token::Comma) => *pos += 1,
+                        Some(Token::CloseParen) => break,
+                        _ => anyhow::bail!("Expected ',' or ')' in call to {}()", name),
+                    }
+                }
+            }
+            *pos += 1;
+            build_function(name, arguments)
+        }
+        Token::Identifier(name) if name.eq_ignore_ascii_case("now") => Ok(Expression::Now),
+        Token::Identifier(name) => Ok(Expression::Column(name.clone())),
+        other => anyhow::bail!("Unexpected {:?}", other),
+    }
+}
+
+fn build_function(name: &str, mut arguments: Vec<Expression>) -> Result<Expression> {
+    let single = |arguments: Vec<Expression>| -> Result<Box<Expression>> {
+        match <[Expression; 1]>::try_from(arguments) {
+            Ok([argument]) => Ok(Box::new(argument)),
+            Err(_) => anyhow::bail!("{}() takes exactly one argument", name),
+        }
+    };
+    
+    match name.to_lowercase().as_str() {
+        "now" if arguments.is_empty() => Ok(Expression::Now),
+        "concat" => Ok(Expression::Concat(arguments)),
+        "year" => Ok(Expression::Year(single(arguments)?)),
+        "month" => Ok(Expression::Month(single(arguments)?)),
+        "day" => Ok(Expression::Day(single(arguments)?)),
+        "upper" => Ok(Expression::Upper(single(arguments)?)),
+        "lower" => Ok(Expression::Lower(single(arguments)?)),
+        "substr" | "substring" if (2..=3).contains(&arguments.len()) => {
+            let number = |argument: &Expression| match argument {
+                Expression::Literal(value) => value.parse::<usize>()
+                    .map_err(|_| anyhow::anyhow!("{}() positions must be positive numbers", name)),
+                _ => anyhow::bail!("{}() positions must be numbers", name),
+            };
+            let length = arguments.get(2).map(number).transpose()?;
+            let start = number(&arguments[1])?;
+            arguments.truncate(1);
+            Ok(Expression::Substring {
+                value: single(arguments)?,
+                start,
+                length,
+            })
+        }
+        _ => anyhow::bail!("Unknown function {}() with {} arguments", name, arguments.len()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_parse_derived_column() {
+        let column = parse_derived_column("orders:export_date=NOW").unwrap();
+        assert_eq!(column.table, "orders");
+        assert_eq!(column.name, "export_date");
+        assert_eq!(column.expression, Expression::Now);
+        
+        let column = parse_derived_column("users:label=concat(name, ' <', email, '>')").unwrap();
+        assert_eq!(column.expression, Expression::Concat(vec![
+            Expression::Column("name".to_string()),
+            Expression::Literal(" <".to_string()),
+            Expression::Column("email".to_string()),
+            Expression::Literal(">".to_string()),
+        ]));
+        
+        assert!(parse_derived_column("export_date=NOW").is_err());
+        assert!(parse_derived_column("orders:x=substr(name)").is_err());
+    }
+    
+    #[test]
+    fn test_find_dump_timestamp() {
+        let mysql = "-- MySQL dump 10.13\n--\n-- Host: localhost\nCREATE TABLE t (a INT);\n-- Dump completed on 2024-01-15 10:20:30\n";
+        assert_eq!(find_dump_timestamp(mysql).as_deref(), Some("2024-01-15 10:20:30"));
+        
+        let postgres = "--\n-- PostgreSQL database dump\n--\n-- Started on 2024-02-01 08:00:00 UTC\n";
+        assert_eq!(find_dump_timestamp(postgres).as_deref(), Some("2024-02-01 08:00:00 UTC"));
+        
+        assert_eq!(find_dump_timestamp("CREATE TABLE t (a INT);"), None);
+    }
+    
+    #[test]
+    fn test_apply_derived_columns() {
+        let headers = vec!["name".to_string(), "created_at".to_string()];
+        let rows = vec![vec!["Alice".to_string(), "2024-03-15T10:00:00".to_string()]];
+        let derived = vec![
+            parse_derived_column("users:created_year=year(created_at)").unwrap(),
+            parse_derived_column("users:initials=upper(substr(name, 1, 2))").unwrap(),
+            parse_derived_column("orders:ignored='x'").unwrap(),
+        ];
+        
+        let (headers, rows) = apply_derived_columns("users", &headers, rows, &derived).unwrap();
+        assert_eq!(headers, vec!["name", "created_at", "created_year", "initials"]);
+        assert_eq!(rows[0], vec!["Alice", "2024-03-15T10:00:00", "2024", "AL"]);
+        
+        let missing = vec![parse_derived_column("users:x=year(updated_at)").unwrap()];
+        assert!(apply_derived_columns("users", &headers, vec![], &missing).is_err());
+    }
+}
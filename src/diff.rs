@@ -0,0 +1,184 @@
+use anyhow::Result;
+use std::collections::HashMap;
+
+use crate::args::flag_values;
+
+/// Header of the column listing which columns of a changed row differ
+pub const CHANGED_COLUMNS_HEADER: &str = "_changed_columns";
+
+/// Rows that differ between two versions of a table
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TableDiff {
+    /// Rows only in the new dump
+    pub added: Vec<Vec<String>>,
+    /// Rows only in the old dump
+    pub removed: Vec<Vec<String>>,
+    /// New version of rows whose key exists in both dumps but whose values differ,
+    /// followed by the names of the changed columns
+    pub changed: Vec<Vec<String>>,
+}
+
+impl TableDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Parse repeatable --key options from command line, e.g. `--key users:id --key order_lines:order_id,line_no`
+pub fn parse_diff_keys(args: &[String]) -> Result<HashMap<String, Vec<String>>> {
+    let mut keys = HashMap::new();
+    
+    for key in flag_values(args, "--key")? {
+        let (table, columns) = key.split_once(':')
+            .ok_or_else(|| anyhow::anyhow!("Error: --key '{}' must look like <table>:<column>[,<column>...]", key))?;
+        let columns: Vec<String> = columns.split(',')
+            .map(|c| c.trim().to_string())
+            .filter(|c| !c.is_empty())
+            .collect();
+        if table.trim().is_empty() || columns.is_empty() {
+            anyhow::bail!("Error: --key '{}' must look like <table>:<column>[,<column>...]", key);
+        }
+        keys.insert(table.trim().to_string(), columns);
+    }
+    
+    Ok(keys)
+}
+
+/// Compare two versions of a table. Rows are matched on the key columns;
+/// without a key, whole rows are compared and nothing is reported as changed.
+/// Old rows are aligned to the new headers by column name, so added or dropped
+/// columns show up as changed values.
+pub fn diff_rows(
+    old_headers: &[String],
+    old_rows: &[Vec<String>],
+    new_headers: &[String],
+    new_rows: &[Vec<String>],
+    key: &[String],
+) -> Result<TableDiff> {
+    // Project old rows onto the new column layout
+    let old_positions: Vec<Option<usize>> = new_headers.iter()
+        .map(|header| old_headers.iter().position(|h| h == header))
+        .collect();
+    let align = |row: &Vec<String>| -> Vec<String> {
+        old_positions.iter()
+            .map(|position| position.and_then(|p| row.get(p)).cloned().unwrap_or_default())
+            .collect()
+    };
+    
+    let mut diff = TableDiff::default();
+    
+    if key.is_empty() {
+        // Multiset comparison of whole rows
+        let mut remaining: HashMap<Vec<String>, usize> = HashMap::new();
+        for row in old_rows {
+            *remaining.entry(align(row)).or_insert(0) += 1;
+        }
+        for row in new_rows {
+            match remaining.get_mut(row) {
+                Some(count) if *count > 0 => *count -= 1,
+                _ => diff.added.push(row.clone()),
+            }
+        }
+        for row in old_rows {
+            let aligned = align(row);
+            if let Some(count) = remaining.get_mut(&aligned)
+                && *count > 0
+            {
+                *count -= 1;
+                diff.removed.push(row.clone());
+            }
+        }
+        return Ok(diff);
+    }
+    
+    let key_positions = |headers: &[String]| -> Result<Vec<usize>> {
+        key.iter()
+            .map(|column| headers.iter().position(|h| h == column)
+                .ok_or_else(|| anyhow::anyhow!("Key column '{}' not found", column)))
+            .collect()
+    };
+    let old_key = key_positions(old_headers)?;
+    let new_key = key_positions(new_headers)?;
+    let key_of = |row: &Vec<String>, positions: &[usize]| -> Vec<String> {
+        positions.iter().map(|&p| row.get(p).cloned().unwrap_or_default()).collect()
+    };
+    
+    let mut old_by_key: HashMap<Vec<String>, &Vec<String>> = HashMap::new();
+    for row in old_rows {
+        old_by_key.entry(key_of(row, &old_key)).or_insert(row);
+    }
+    
+    for row in new_rows {
+        match old_by_key.remove(&key_of(row, &new_key)) {
+            None => diff.added.push(row.clone()),
+            Some(old_row) => {
+                let old_row = align(old_row);
+                let changed_columns: Vec<&str> = new_headers.iter()
+                    .enumerate()
+                    .filter(|(i, _)| old_row.get(*i) != row.get(*i))
+                    .map(|(_, header)| header.as_str())
+                    .collect();
+                if !changed_columns.is_empty() {
+                    let mut changed = row.clone();
+                    changed.push(changed_columns.join(","));
+                    diff.changed.push(changed);
+                }
+            }
+        }
+    }
+    
+    // Keep removed rows in their original order
+    for row in old_rows {
+        if old_by_key.remove(&key_of(row, &old_key)).is_some() {
+            diff.removed.push(row.clone());
+        }
+    }
+    
+    Ok(diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+    
+    #[test]
+    fn test_diff_rows_by_key() {
+        let old_headers = strings(&["id", "name"]);
+        let old_rows = vec![strings(&["1", "Alice"]), strings(&["2", "Bob"]), strings(&["3", "Carol"])];
+        let new_headers = strings(&["id", "name"]);
+        let new_rows = vec![strings(&["1", "Alice"]), strings(&["3", "Caroline"]), strings(&["4", "Dan"])];
+        
+        let diff = diff_rows(&old_headers, &old_rows, &new_headers, &new_rows, &strings(&["id"])).unwrap();
+        assert_eq!(diff.added, vec![strings(&["4", "Dan"])]);
+        assert_eq!(diff.removed, vec![strings(&["2", "Bob"])]);
+        assert_eq!(diff.changed, vec![strings(&["3", "Caroline", "name"])]);
+        
+        assert!(diff_rows(&old_headers, &old_rows, &new_headers, &new_rows, &strings(&["uuid"])).is_err());
+    }
+    
+    #[test]
+    fn test_diff_rows_without_key() {
+        let headers = strings(&["id", "name"]);
+        let old_rows = vec![strings(&["1", "a"]), strings(&["1", "a"]), strings(&["2", "b"])];
+        let new_rows = vec![strings(&["1", "a"]), strings(&["2", "b"]), strings(&["3", "c"])];
+        
+        let diff = diff_rows(&headers, &old_rows, &headers, &new_rows, &[]).unwrap();
+        assert_eq!(diff.added, vec![strings(&["3", "c"])]);
+        assert_eq!(diff.removed, vec![strings(&["1", "a"])]);
+        assert!(diff.changed.is_empty());
+    }
+    
+    #[test]
+    fn test_parse_diff_keys() {
+        let args = strings(&["diff", "a.sql", "b.sql", "--key", "users:id", "--key", "lines:order_id,line_no"]);
+        let keys = parse_diff_keys(&args).unwrap();
+        assert_eq!(keys["users"], vec!["id"]);
+        assert_eq!(keys["lines"], vec!["order_id", "line_no"]);
+        
+        assert!(parse_diff_keys(&strings(&["--key", "users"])).is_err());
+    }
+}
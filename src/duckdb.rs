@@ -0,0 +1,174 @@
+//! DuckDB database output (feature `duckdb`).
+//!
+//! `--format duckdb --output exports.duckdb` writes every table into one
+//! DuckDB database file instead of a directory of files, with the column types
+//! `--emit-ddl duckdb` would declare. Rows are staged as CSV in the run's
+//! temporary workspace and loaded by the `duckdb` command line tool, which
+//! must be on `PATH`. DuckDB allows a single writer per file, so tables are
+//! loaded one at a time as they finish. A table already in the file is
+//! replaced. Empty cells of typed columns are loaded as NULL, and a value that
+//! does not fit its column's type fails the table.
+
+use anyhow::{Context, Result};
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+
+use crate::csv_writer::CsvTableSink;
+use crate::ddl::written_table;
+use crate::load::{column_type_sql, create_table_sql, quote_identifier, Dialect};
+use crate::sink::{OutputSink, TableSink};
+use crate::types::{CsvOptions, Table};
+use crate::workspace::temp_file_path;
+
+/// Command line tool loading the staged tables
+const DUCKDB_COMMAND: &str = "duckdb";
+
+/// A DuckDB database file the tables are loaded into
+pub struct DuckDbSink {
+    path: PathBuf,
+    /// Only --header-case is used; the staged CSVs are plain
+    options: CsvOptions,
+    /// Held while a table is loaded, as DuckDB allows one writer per file
+    writer: Mutex<()>,
+}
+
+impl DuckDbSink {
+    /// Write tables into the database at `path`, creating its directory.
+    /// Fails if the `duckdb` command line tool cannot be run.
+    pub fn new(path: &str, options: &CsvOptions) -> Result<Self> {
+        check_command(DUCKDB_COMMAND)?;
+        if let Some(dir) = Path::new(path).parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create output directory '{}'", dir.display()))?;
+        }
+        Ok(DuckDbSink {
+            path: PathBuf::from(path),
+            options: CsvOptions { header_case: options.header_case, ..Default::default() },
+            writer: Mutex::new(()),
+        })
+    }
+}
+
+impl OutputSink for DuckDbSink {
+    fn open_table<'a>(&'a self, table: &Table, name: &str, headers: &[String]) -> Result<Box<dyn TableSink + 'a>> {
+        let staged = temp_file_path(&format!("duckdb-{}.csv", name))?;
+        let file = File::create(&staged)
+            .with_context(|| format!("Failed to create '{}'", staged.display()))?;
+        Ok(Box::new(DuckDbTableSink {
+            csv: CsvTableSink::new(BufWriter::new(file), headers, &self.options, String::new())?,
+            table: written_table(table, headers, self.options.header_case)?,
+            staged,
+            sink: self,
+        }))
+    }
+}
+
+/// Stages the rows of one table until it is loaded
+struct DuckDbTableSink<'a> {
+    csv: CsvTableSink<BufWriter<File>>,
+    /// The table as it is created in the database
+    table: Table,
+    staged: PathBuf,
+    sink: &'a DuckDbSink,
+}
+
+impl TableSink for DuckDbTableSink<'_> {
+    fn write_row(&mut self, row: &[String]) -> Result<()> {
+        self.csv.write_row(row)
+    }
+    
+    fn finish(self: Box<Self>) -> Result<String> {
+        let DuckDbTableSink { csv, table, staged, sink } = *self;
+        let loaded = csv.into_inner().and_then(|_| {
+            let _writer = sink.writer.lock().unwrap_or_else(|e| e.into_inner());
+            run_duckdb(&sink.path, &load_script(&table, &staged))
+        });
+        let _ = fs::remove_file(&staged);
+        loaded.with_context(|| format!("Failed to load table '{}' into '{}'", table.name, sink.path.display()))?;
+        Ok(format!("{}#{}", sink.path.display(), table.name))
+    }
+}
+
+/// SQL replacing the table in the database with the rows of the staged CSV
+fn load_script(table: &Table, staged: &Path) -> String {
+    let name = quote_identifier(&table.name, Dialect::DuckDb);
+    let values: Vec<String> = table.columns.iter()
+        .zip(&table.column_types)
+        .map(|(column, column_type)| {
+            let column = quote_identifier(column, Dialect::DuckDb);
+            // Text keeps its empty strings
+            match column_type_sql(column_type, Dialect::DuckDb) {
+                text if text == "VARCHAR" => column,
+                target => format!("CAST(NULLIF({}, '') AS {})", column, target),
+            }
+        })
+        .collect();
+    
+    format!(
+        "BEGIN;\nDROP TABLE IF EXISTS {name};\n{create};\nINSERT INTO {name} SELECT {values} FROM read_csv('{path}', header = true, all_varchar = true, nullstr = 'NULL');\nCOMMIT;\n",
+        name = name,
+        create = create_table_sql(table, Dialect::DuckDb),
+        values = values.join(", "),
+        path = staged.to_string_lossy().replace('\'', "''"),
+    )
+}
+
+/// Make sure the command line tool runs before any table is converted
+fn check_command(command: &str) -> Result<()> {
+    match Command::new(command).arg("--version").output() {
+        Ok(output) if output.status.success() => Ok(()),
+        _ => anyhow::bail!(
+            "Error: --format duckdb needs the DuckDB command line tool '{}' on PATH (see https://duckdb.org/docs/installation/)",
+            command
+        ),
+    }
+}
+
+/// Run a script against the database with the `duckdb` command line tool
+fn run_duckdb(database: &Path, script: &str) -> Result<()> {
+    let output = Command::new(DUCKDB_COMMAND)
+        .arg(database)
+        .arg("-c")
+        .arg(script)
+        .output()
+        .with_context(|| format!("Failed to run '{}'; --format duckdb needs the DuckDB command line tool on PATH", DUCKDB_COMMAND))?;
+    if !output.status.success() {
+        anyhow::bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_load_script() {
+        let table = Table {
+            name: "users".to_string(),
+            columns: vec!["id".to_string(), "name".to_string(), "created_at".to_string()],
+            column_types: vec!["int(11)".to_string(), "varchar(50)".to_string(), "datetime".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(load_script(&table, Path::new("/tmp/it's/duckdb-users.csv")), "\
+BEGIN;
+DROP TABLE IF EXISTS \"users\";
+CREATE TABLE IF NOT EXISTS \"users\" (
+  \"id\" BIGINT,
+  \"name\" VARCHAR,
+  \"created_at\" TIMESTAMP
+);
+INSERT INTO \"users\" SELECT CAST(NULLIF(\"id\", '') AS BIGINT), \"name\", CAST(NULLIF(\"created_at\", '') AS TIMESTAMP) FROM read_csv('/tmp/it''s/duckdb-users.csv', header = true, all_varchar = true, nullstr = 'NULL');
+COMMIT;
+");
+    }
+    
+    #[test]
+    fn test_missing_command_is_reported() {
+        let error = check_command("parsley-no-such-duckdb").unwrap_err();
+        assert!(error.to_string().contains("on PATH"));
+    }
+}
@@ -0,0 +1,97 @@
+//! Encrypted output files (`--encrypt age:<recipient>`).
+//!
+//! Files are encrypted with [age](https://age-encryption.org) while they are
+//! written, so the plaintext never reaches the disk, and get an `.age`
+//! extension after their own, e.g. `users.csv.age`. They can be decrypted with
+//! `age -d -i key.txt users.csv.age`. Needs the `encrypt` feature.
+
+use anyhow::Result;
+
+use crate::args::flag_values;
+
+/// Extension appended to the name of encrypted files
+pub const ENCRYPTED_EXTENSION: &str = "age";
+
+/// Parse --encrypt options (repeatable) from command line. Returns the age
+/// recipients (`age1...` public keys) output files are encrypted to.
+pub fn parse_encryption(args: &[String]) -> Result<Vec<String>> {
+    flag_values(args, "--encrypt")?
+        .into_iter()
+        .map(parse_recipient)
+        .collect()
+}
+
+/// Parse a single `age:<recipient>` specification
+pub fn parse_recipient(spec: &str) -> Result<String> {
+    let Some(recipient) = spec.strip_prefix("age:") else {
+        anyhow::bail!("Error: Invalid --encrypt '{}'. Use age:<recipient>, e.g. age:age1ql3z7hjy54pw3hyww5ayyfg7zqgvc7w3j2elw8zmrj2kg5sfn9aqmcac8p", spec);
+    };
+    check_recipient(recipient)
+        .map_err(|e| anyhow::anyhow!("Error: Invalid age recipient '{}' in --encrypt: {}", recipient, e))?;
+    Ok(recipient.to_string())
+}
+
+#[cfg(feature = "encrypt")]
+fn check_recipient(recipient: &str) -> Result<(), &'static str> {
+    recipient.parse::<age::x25519::Recipient>().map(|_| ())
+}
+
+#[cfg(not(feature = "encrypt"))]
+fn check_recipient(_recipient: &str) -> Result<(), &'static str> {
+    Err("this build has no encryption support (build with --features encrypt)")
+}
+
+/// Wrap a destination so everything written to it is encrypted to the
+/// recipients. The stream must be finished to write the last block.
+#[cfg(feature = "encrypt")]
+pub fn encrypting_writer<W: std::io::Write>(recipients: &[String], destination: W) -> Result<age::stream::StreamWriter<W>> {
+    let recipients = recipients.iter()
+        .map(|recipient| recipient.parse::<age::x25519::Recipient>()
+            .map_err(|e| anyhow::anyhow!("Invalid age recipient '{}': {}", recipient, e)))
+        .collect::<Result<Vec<_>>>()?;
+    let encryptor = age::Encryptor::with_recipients(recipients.iter().map(|r| r as &dyn age::Recipient))?;
+    Ok(encryptor.wrap_output(destination)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_parse_recipient() {
+        assert!(parse_recipient("gpg:ABCDEF").is_err());
+        assert!(parse_recipient("age:not-a-key").is_err());
+        #[cfg(feature = "encrypt")]
+        {
+            let key = "age1ql3z7hjy54pw3hyww5ayyfg7zqgvc7w3j2elw8zmrj2kg5sfn9aqmcac8p";
+            assert_eq!(parse_recipient(&format!("age:{}", key)).unwrap(), key);
+        }
+    }
+    
+    #[cfg(feature = "encrypt")]
+    #[test]
+    fn test_encrypted_output_file_decrypts() {
+        use crate::output_file::OutputFile;
+        use crate::types::CsvOptions;
+        use std::io::{Read, Write};
+        
+        let identity = age::x25519::Identity::generate();
+        let options = CsvOptions { encrypt_to: vec![identity.to_public().to_string()], ..Default::default() };
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("users.csv");
+        
+        let mut file = OutputFile::create(&path, &options).unwrap();
+        assert_eq!(file.path(), path.with_extension("csv.age"));
+        file.write_all(b"id,name\n1,Ann\n").unwrap();
+        let encrypted_path = file.path().to_path_buf();
+        file.finish().unwrap();
+        
+        let encrypted = std::fs::read(&encrypted_path).unwrap();
+        assert!(!encrypted.windows(3).any(|w| w == b"Ann"));
+        let decryptor = age::Decryptor::new(&encrypted[..]).unwrap();
+        let mut reader = decryptor.decrypt(std::iter::once(&identity as &dyn age::Identity)).unwrap();
+        let mut plaintext = String::new();
+        reader.read_to_string(&mut plaintext).unwrap();
+        assert_eq!(plaintext, "id,name\n1,Ann\n");
+    }
+}
@@ -0,0 +1,151 @@
+//! Options from `PARSLEY_*` environment variables.
+//!
+//! Every option listed by `--help` can also be set with an environment
+//! variable named after its flag: `--output` is `PARSLEY_OUTPUT` and
+//! `--on-mismatch` is `PARSLEY_ON_MISMATCH`. Boolean flags are turned on by
+//! `1`, `true` or `yes` and left off by `0`, `false`, `no` or an empty value.
+//! Options taking several arguments, such as `--date-filter`, take them
+//! separated by spaces, and repeatable options take one value per `;`.
+//! `PARSLEY_INPUT` names the dump when none is given on the command line.
+//! An option given on the command line is used instead of its variable.
+
+use anyhow::Result;
+
+use crate::args::{has_flag, CliOption, OPTIONS};
+
+/// Prefix of the environment variables read
+pub const ENV_PREFIX: &str = "PARSLEY_";
+
+/// Variable naming the dump to convert
+pub const INPUT_VAR: &str = "PARSLEY_INPUT";
+
+/// Name of the environment variable of an option, e.g. `PARSLEY_ON_MISMATCH`
+pub fn env_var_name(flag: &str) -> String {
+    format!("{}{}", ENV_PREFIX, flag.trim_start_matches('-').replace('-', "_").to_ascii_uppercase())
+}
+
+/// Add the options set by `PARSLEY_*` variables in `vars` to the command-line
+/// arguments. Variables that name no option are reported as warnings.
+pub fn args_with_env(mut args: Vec<String>, vars: impl IntoIterator<Item = (String, String)>) -> Result<(Vec<String>, Vec<String>)> {
+    let mut vars: Vec<(String, String)> = vars.into_iter()
+        .filter(|(name, _)| name.starts_with(ENV_PREFIX))
+        .collect();
+    // Options are added in the same order whatever the environment's order
+    vars.sort();
+    let mut warnings = Vec::new();
+    
+    for (name, value) in vars {
+        if name == INPUT_VAR {
+            // The dump is the first argument, unless one was given
+            let has_input = args.get(1).is_some_and(|arg| !arg.starts_with("--"));
+            if !has_input && !value.is_empty() {
+                args.insert(1, value);
+            }
+            continue;
+        }
+        let Some(option) = OPTIONS.iter().find(|option| env_var_name(option.flag) == name) else {
+            warnings.push(format!("Warning: Environment variable {} is not an option and is ignored", name));
+            continue;
+        };
+        if has_flag(&args, option.flag) {
+            continue;
+        }
+        args.extend(option_args(option, &name, &value)?);
+    }
+    
+    Ok((args, warnings))
+}
+
+/// Command-line arguments for an option set to `value`
+fn option_args(option: &CliOption, name: &str, value: &str) -> Result<Vec<String>> {
+    if option.arity == 0 {
+        return match value.trim().to_ascii_lowercase().as_str() {
+            "1" | "true" | "yes" => Ok(vec![option.flag.to_string()]),
+            "" | "0" | "false" | "no" => Ok(Vec::new()),
+            _ => anyhow::bail!("Error: Invalid {} '{}'. Use true or false", name, value),
+        };
+    }
+    
+    let values: Vec<&str> = if option.repeatable {
+        value.split(';').collect()
+    } else {
+        vec![value]
+    };
+    let mut args = Vec::new();
+    for value in values.into_iter().map(str::trim).filter(|value| !value.is_empty()) {
+        args.push(option.flag.to_string());
+        // `<column_name> <start_date> [end_date]` takes one argument per word
+        if option.arity > 1 {
+            let words: Vec<&str> = value.split_whitespace().collect();
+            if words.len() > option.arity {
+                anyhow::bail!("Error: Invalid {} '{}'. Use {}", name, value, option.value);
+            }
+            args.extend(words.into_iter().map(str::to_string));
+        } else {
+            args.push(value.to_string());
+        }
+    }
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn vars(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        vars.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+    }
+    
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+    
+    #[test]
+    fn test_env_var_name() {
+        assert_eq!(env_var_name("--output"), "PARSLEY_OUTPUT");
+        assert_eq!(env_var_name("--on-mismatch"), "PARSLEY_ON_MISMATCH");
+    }
+    
+    #[test]
+    fn test_args_with_env() {
+        let (merged, warnings) = args_with_env(args("parsley --format csv"), vars(&[
+            ("PARSLEY_INPUT", "/data/dump.sql"),
+            ("PARSLEY_OUTPUT", "/out"),
+            ("PARSLEY_FORMAT", "avro"),
+            ("PARSLEY_QUIET", "true"),
+            ("PARSLEY_FSYNC", "0"),
+            ("PARSLEY_DATE_FILTER", "createdAt 2024-01-01 2024-12-31"),
+            ("PARSLEY_MASK", "users.email:hash; users.name:fake:name"),
+            ("PARSLEY_OUTPTU", "/typo"),
+            ("HOME", "/root"),
+        ])).unwrap();
+        
+        assert_eq!(merged, args("parsley /data/dump.sql --format csv \
+            --date-filter createdAt 2024-01-01 2024-12-31 \
+            --mask users.email:hash --mask users.name:fake:name \
+            --output /out --quiet"));
+        assert_eq!(warnings, vec!["Warning: Environment variable PARSLEY_OUTPTU is not an option and is ignored"]);
+    }
+    
+    #[test]
+    fn test_command_line_input_wins() {
+        let (merged, _) = args_with_env(args("parsley dump.sql"), vars(&[("PARSLEY_INPUT", "other.sql")])).unwrap();
+        assert_eq!(merged, args("parsley dump.sql"));
+        assert!(args_with_env(args("parsley"), vars(&[("PARSLEY_QUIET", "maybe")])).is_err());
+        assert!(args_with_env(args("parsley"), vars(&[("PARSLEY_DATE_FILTER", "createdAt 2024-01-01 2024-12-31 extra")])).is_err());
+    }
+    
+    #[test]
+    fn test_usage_text_matches_option_fields() {
+        for option in OPTIONS {
+            assert_eq!(option.arity == 0, option.value.is_empty(), "{}", option.flag);
+            assert_eq!(option.repeatable, option.help.contains("(repeatable"), "{}", option.flag);
+        }
+    }
+    
+    #[test]
+    fn test_post_header_is_one_argument() {
+        let (merged, _) = args_with_env(args("parsley"), vars(&[("PARSLEY_POST_HEADER", "Authorization: Bearer x; X-Team: data")])).unwrap();
+        assert_eq!(merged, vec!["parsley", "--post-header", "Authorization: Bearer x", "--post-header", "X-Team: data"]);
+    }
+}
@@ -0,0 +1,197 @@
+//! C ABI for embedding the converter without spawning a subprocess.
+//! See `include/parsley.h` for the C declarations.
+//!
+//! Strings returned through out-parameters are owned by the handle and stay
+//! valid until the next call to the same iterator function or `parsley_free`.
+
+use std::cell::RefCell;
+use std::ffi::{CStr, CString, c_char, c_int};
+use std::ptr;
+
+use crate::parser::{extract_table_rows, parse_sql_file};
+use crate::types::Table;
+
+/// Opaque handle to a parsed dump
+pub struct ParsleyDump {
+    tables: Vec<Table>,
+    content: String,
+    next_table: usize,
+    table_name: CString,
+    columns: Vec<CString>,
+    column_ptrs: Vec<*const c_char>,
+    rows: std::vec::IntoIter<Vec<String>>,
+    row: Vec<CString>,
+    row_ptrs: Vec<*const c_char>,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: String) {
+    LAST_ERROR.with(|error| *error.borrow_mut() = Some(to_c_string(&message)));
+}
+
+/// Convert to a C string, dropping interior NUL bytes that C can't represent
+fn to_c_string(value: &str) -> CString {
+    CString::new(value.replace('\0', "")).unwrap_or_default()
+}
+
+/// Parse the SQL file at `path`. Returns NULL on failure; see `parsley_last_error`.
+///
+/// # Safety
+/// `path` must be a valid NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn parsley_parse(path: *const c_char) -> *mut ParsleyDump {
+    if path.is_null() {
+        set_last_error("path is NULL".to_string());
+        return ptr::null_mut();
+    }
+    let path = unsafe { CStr::from_ptr(path) }.to_string_lossy().to_string();
+    
+    match parse_sql_file(&path) {
+        Ok((tables, content)) => Box::into_raw(Box::new(ParsleyDump {
+            tables,
+            content,
+            next_table: 0,
+            table_name: CString::default(),
+            columns: Vec::new(),
+            column_ptrs: Vec::new(),
+            rows: Vec::new().into_iter(),
+            row: Vec::new(),
+            row_ptrs: Vec::new(),
+        })),
+        Err(e) => {
+            set_last_error(format!("{:#}", e));
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Advance to the next table. Returns 1 and fills the out-parameters when a
+/// table is available, 0 when all tables have been read and -1 on error.
+///
+/// # Safety
+/// `dump` must come from `parsley_parse`; the out-parameters must be valid pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn parsley_next_table(
+    dump: *mut ParsleyDump,
+    name: *mut *const c_char,
+    columns: *mut *const *const c_char,
+    column_count: *mut usize,
+) -> c_int {
+    let Some(dump) = (unsafe { dump.as_mut() }) else {
+        set_last_error("dump is NULL".to_string());
+        return -1;
+    };
+    let Some(table) = dump.tables.get(dump.next_table) else {
+        return 0;
+    };
+    dump.next_table += 1;
+    
+    let rows = match extract_table_rows(&dump.content, table) {
+        Ok(rows) => rows,
+        Err(e) => {
+            set_last_error(format!("Error extracting values for table '{}': {:#}", table.name, e));
+            return -1;
+        }
+    };
+    
+    dump.table_name = to_c_string(&table.name);
+    dump.columns = table.columns.iter().map(|c| to_c_string(c)).collect();
+    dump.column_ptrs = dump.columns.iter().map(|c| c.as_ptr()).collect();
+    dump.rows = rows.into_iter();
+    
+    unsafe {
+        *name = dump.table_name.as_ptr();
+        *columns = dump.column_ptrs.as_ptr();
+        *column_count = dump.column_ptrs.len();
+    }
+    1
+}
+
+/// Advance to the next row of the current table. Returns 1 and fills the
+/// out-parameters when a row is available, 0 at the end of the table and -1 on error.
+///
+/// # Safety
+/// `dump` must come from `parsley_parse`; the out-parameters must be valid pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn parsley_next_row(
+    dump: *mut ParsleyDump,
+    values: *mut *const *const c_char,
+    value_count: *mut usize,
+) -> c_int {
+    let Some(dump) = (unsafe { dump.as_mut() }) else {
+        set_last_error("dump is NULL".to_string());
+        return -1;
+    };
+    let Some(row) = dump.rows.next() else {
+        return 0;
+    };
+    
+    dump.row = row.iter().map(|v| to_c_string(v)).collect();
+    dump.row_ptrs = dump.row.iter().map(|v| v.as_ptr()).collect();
+    
+    unsafe {
+        *values = dump.row_ptrs.as_ptr();
+        *value_count = dump.row_ptrs.len();
+    }
+    1
+}
+
+/// Message of the last error on this thread, or NULL. Valid until the next failing call.
+#[unsafe(no_mangle)]
+pub extern "C" fn parsley_last_error() -> *const c_char {
+    LAST_ERROR.with(|error| {
+        error.borrow().as_ref().map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Release a handle returned by `parsley_parse`. NULL is ignored.
+///
+/// # Safety
+/// `dump` must come from `parsley_parse` and must not be used afterwards.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn parsley_free(dump: *mut ParsleyDump) {
+    if !dump.is_null() {
+        drop(unsafe { Box::from_raw(dump) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_iterate_tables_and_rows() {
+        let path = CString::new("test.sql").unwrap();
+        unsafe {
+            let dump = parsley_parse(path.as_ptr());
+            assert!(!dump.is_null());
+            
+            let mut name = ptr::null();
+            let mut columns = ptr::null();
+            let mut column_count = 0;
+            assert_eq!(parsley_next_table(dump, &mut name, &mut columns, &mut column_count), 1);
+            assert_eq!(CStr::from_ptr(name).to_str().unwrap(), "users");
+            assert_eq!(column_count, 3);
+            
+            let mut values = ptr::null();
+            let mut value_count = 0;
+            let mut names = Vec::new();
+            while parsley_next_row(dump, &mut values, &mut value_count) == 1 {
+                assert_eq!(value_count, 3);
+                names.push(CStr::from_ptr(*values.add(1)).to_str().unwrap().to_string());
+            }
+            assert_eq!(names, vec!["Alice Smith", "Bob Johnson", "Charlie Brown"]);
+            
+            assert_eq!(parsley_next_table(dump, &mut name, &mut columns, &mut column_count), 1);
+            assert_eq!(parsley_next_table(dump, &mut name, &mut columns, &mut column_count), 0);
+            parsley_free(dump);
+        }
+        
+        let missing = CString::new("missing.sql").unwrap();
+        assert!(unsafe { parsley_parse(missing.as_ptr()) }.is_null());
+        assert!(!parsley_last_error().is_null());
+    }
+}
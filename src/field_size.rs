@@ -0,0 +1,126 @@
+use anyhow::Result;
+use std::fmt;
+
+use crate::args::flag_value;
+use crate::types::{FieldSizeLimit, OversizedFieldPolicy};
+
+/// Appended to truncated values so consumers can tell they are incomplete
+pub const TRUNCATION_MARKER: &str = "...[truncated]";
+
+/// Parse --max-field-bytes and --on-oversized-field options from command line
+pub fn parse_field_size_limit(args: &[String]) -> Result<Option<FieldSizeLimit>> {
+    let policy = match flag_value(args, "--on-oversized-field")? {
+        None | Some("truncate") => OversizedFieldPolicy::Truncate,
+        Some("skip-row") => OversizedFieldPolicy::SkipRow,
+        Some("error") => OversizedFieldPolicy::Error,
+        Some(other) => anyhow::bail!(
+            "Error: Invalid --on-oversized-field '{}'. Use one of: truncate, skip-row, error",
+            other
+        ),
+    };
+    
+    let Some(max_bytes) = flag_value(args, "--max-field-bytes")? else {
+        if flag_value(args, "--on-oversized-field")?.is_some() {
+            anyhow::bail!("Error: --on-oversized-field requires --max-field-bytes");
+        }
+        return Ok(None);
+    };
+    let max_bytes = max_bytes.parse::<usize>()
+        .ok()
+        .filter(|&n| n > 0)
+        .ok_or_else(|| anyhow::anyhow!("Error: Invalid --max-field-bytes '{}'. Use a positive number of bytes", max_bytes))?;
+    
+    Ok(Some(FieldSizeLimit { max_bytes, policy }))
+}
+
+impl fmt::Display for OversizedFieldPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            OversizedFieldPolicy::Truncate => "truncate",
+            OversizedFieldPolicy::SkipRow => "skip-row",
+            OversizedFieldPolicy::Error => "error",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Enforce the field size limit on every cell.
+/// Returns the adjusted rows and the number of cells that exceeded the limit.
+pub fn apply_field_size_limit(
+    headers: &[String],
+    rows: Vec<Vec<String>>,
+    limit: &FieldSizeLimit,
+) -> Result<(Vec<Vec<String>>, usize)> {
+    let mut oversized = 0;
+    let mut adjusted = Vec::with_capacity(rows.len());
+    
+    for (i, mut row) in rows.into_iter().enumerate() {
+        let mut keep = true;
+        
+        for (j, cell) in row.iter_mut().enumerate() {
+            if cell.len() <= limit.max_bytes {
+                continue;
+            }
+            
+            oversized += 1;
+            match limit.policy {
+                OversizedFieldPolicy::Error => anyhow::bail!(
+                    "Row {} column '{}' has {} bytes, more than --max-field-bytes {}",
+                    i + 1, headers.get(j).map(String::as_str).unwrap_or("?"), cell.len(), limit.max_bytes
+                ),
+                OversizedFieldPolicy::SkipRow => keep = false,
+                OversizedFieldPolicy::Truncate => truncate_field(cell, limit.max_bytes),
+            }
+        }
+        
+        if keep {
+            adjusted.push(row);
+        }
+    }
+    
+    Ok((adjusted, oversized))
+}
+
+/// Shorten a value to at most `max_bytes` bytes including the truncation
+/// marker, without splitting a UTF-8 character
+fn truncate_field(cell: &mut String, max_bytes: usize) {
+    // Limits smaller than the marker itself get a plain cut
+    let budget = max_bytes.checked_sub(TRUNCATION_MARKER.len());
+    let mut end = budget.unwrap_or(max_bytes);
+    while !cell.is_char_boundary(end) {
+        end -= 1;
+    }
+    
+    cell.truncate(end);
+    if budget.is_some() {
+        cell.push_str(TRUNCATION_MARKER);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn rows() -> Vec<Vec<String>> {
+        vec![
+            vec!["1".to_string(), "short".to_string()],
+            vec!["2".to_string(), format!("é{}", "x".repeat(40))],
+        ]
+    }
+    
+    #[test]
+    fn test_apply_field_size_limit() {
+        let headers = vec!["id".to_string(), "payload".to_string()];
+        let limit = |policy| FieldSizeLimit { max_bytes: 20, policy };
+        
+        let (truncated, oversized) = apply_field_size_limit(&headers, rows(), &limit(OversizedFieldPolicy::Truncate)).unwrap();
+        assert_eq!(oversized, 1);
+        assert_eq!(truncated[1][1], "éxxxx...[truncated]");
+        assert!(truncated[1][1].len() <= 20);
+        
+        let (skipped, _) = apply_field_size_limit(&headers, rows(), &limit(OversizedFieldPolicy::SkipRow)).unwrap();
+        assert_eq!(skipped, vec![vec!["1", "short"]]);
+        
+        assert!(apply_field_size_limit(&headers, rows(), &limit(OversizedFieldPolicy::Error)).is_err());
+    }
+}
@@ -0,0 +1,333 @@
+//! Per-table filters from a TOML config file (`--config`).
+//!
+//! Each table lists its filters in order, and every filter works on the rows
+//! the previous one kept:
+//!
+//! ```toml
+//! [tables.orders]
+//! filters = [
+//!     { type = "date-range", column = "createdAt", start = "2024-01-01", end = "2024-06-30" },
+//!     { type = "where", condition = "status = 'paid' AND amount > 10" },
+//!     { type = "sample", fraction = 0.1, seed = 7 },
+//!     { type = "limit", rows = 1000 },
+//! ]
+//! ```
+//!
+//! A table can also set `trim_cells` per column, see [`crate::trim`].
+
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::args::flag_value;
+use crate::date_filter::{retain_dated_batch, EpochDetection};
+use crate::query::filter_rows;
+use crate::trim::parse_trim_mode;
+use crate::types::{DateFilter, TrimCells};
+use crate::warnings::WarningHandler;
+
+/// One step of a table's filter list
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterSpec {
+    /// Rows whose date column is within the range, as with `--date-filter`.
+    /// Without an end date the range ends today.
+    DateRange {
+        column: String,
+        start: NaiveDate,
+        end: Option<NaiveDate>,
+        strict_epoch: bool,
+    },
+    /// Rows matching a condition written as in a query's WHERE clause
+    Where(String),
+    /// The first rows
+    Limit(usize),
+    /// About `fraction` of the rows, picked the same way on every run with the same seed
+    Sample { fraction: f64, seed: u64 },
+}
+
+/// Filters of every table, by table name
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FilterConfig {
+    pub tables: BTreeMap<String, Vec<FilterSpec>>,
+    /// `--trim-cells` modes of columns, by table name and column name
+    pub trim_cells: BTreeMap<String, BTreeMap<String, TrimCells>>,
+}
+
+impl FilterConfig {
+    /// The filters of a table, empty if it has none
+    pub fn filters_for(&self, table: &str) -> &[FilterSpec] {
+        self.tables.get(table).map(Vec::as_slice).unwrap_or_default()
+    }
+    
+    /// The trim modes set for columns of a table
+    pub fn trim_cells_for(&self, table: &str) -> &BTreeMap<String, TrimCells> {
+        static NONE: BTreeMap<String, TrimCells> = BTreeMap::new();
+        self.trim_cells.get(table).unwrap_or(&NONE)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawConfig {
+    #[serde(default)]
+    tables: BTreeMap<String, RawTable>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawTable {
+    #[serde(default)]
+    filters: Vec<RawFilter>,
+    #[serde(default)]
+    trim_cells: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case", deny_unknown_fields)]
+enum RawFilter {
+    DateRange {
+        column: String,
+        start: String,
+        end: Option<String>,
+        #[serde(default)]
+        strict_epoch: bool,
+    },
+    Where { condition: String },
+    Limit { rows: usize },
+    Sample {
+        fraction: f64,
+        #[serde(default)]
+        seed: u64,
+    },
+}
+
+/// Parse the --config option from command line and load the file
+pub fn parse_filter_config(args: &[String]) -> Result<FilterConfig> {
+    match flag_value(args, "--config")? {
+        Some(path) => load_filter_config(path),
+        None => Ok(FilterConfig::default()),
+    }
+}
+
+/// Read per-table filters from a TOML file
+pub fn load_filter_config(path: impl AsRef<Path>) -> Result<FilterConfig> {
+    let path = path.as_ref();
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read config file '{}'", path.display()))?;
+    parse_filter_config_str(&text)
+        .with_context(|| format!("Error in config file '{}'", path.display()))
+}
+
+/// Parse per-table filters from TOML text
+pub fn parse_filter_config_str(text: &str) -> Result<FilterConfig> {
+    let raw: RawConfig = toml::from_str(text)?;
+    let mut config = FilterConfig::default();
+    
+    for (table, raw_table) in raw.tables {
+        let filters = raw_table.filters.into_iter()
+            .map(filter_spec)
+            .collect::<Result<Vec<_>>>()
+            .with_context(|| format!("Invalid filter for table '{}'", table))?;
+        let trim_cells = raw_table.trim_cells.iter()
+            .map(|(column, mode)| Ok((column.clone(), parse_trim_mode(mode)?)))
+            .collect::<Result<BTreeMap<_, _>>>()
+            .with_context(|| format!("Invalid trim_cells for table '{}'", table))?;
+        if !trim_cells.is_empty() {
+            config.trim_cells.insert(table.clone(), trim_cells);
+        }
+        config.tables.insert(table, filters);
+    }
+    
+    Ok(config)
+}
+
+fn filter_spec(raw: RawFilter) -> Result<FilterSpec> {
+    let date = |value: &str| NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .with_context(|| format!("Invalid date '{}'. Use format: YYYY-MM-DD", value));
+    
+    Ok(match raw {
+        RawFilter::DateRange { column, start, end, strict_epoch } => {
+            let start = date(&start)?;
+            let end = end.as_deref().map(date).transpose()?;
+            if end.is_some_and(|end| start > end) {
+                anyhow::bail!("Start date must be before or equal to end date");
+            }
+            FilterSpec::DateRange { column, start, end, strict_epoch }
+        }
+        RawFilter::Where { condition } => FilterSpec::Where(condition),
+        RawFilter::Limit { rows } => FilterSpec::Limit(rows),
+        RawFilter::Sample { fraction, seed } => {
+            if !(fraction > 0.0 && fraction <= 1.0) {
+                anyhow::bail!("Sample fraction must be greater than 0 and at most 1, got {}", fraction);
+            }
+            FilterSpec::Sample { fraction, seed }
+        }
+    })
+}
+
+/// Apply a table's filters in order
+pub fn apply_filters(
+    headers: &[String],
+    rows: Vec<Vec<String>>,
+    filters: &[FilterSpec],
+    warnings: &dyn WarningHandler,
+) -> Result<Vec<Vec<String>>> {
+    apply_filters_to_batch(headers, rows, filters, &mut FilterProgress::default(), warnings)
+}
+
+/// What the filters of a table filtered in batches carry from one batch to
+/// the next: the rows each filter has seen, for `limit` and `sample`, and the
+/// epoch unit of each `date-range` column
+#[derive(Debug, Default)]
+pub struct FilterProgress {
+    seen: Vec<usize>,
+    epochs: Vec<EpochDetection>,
+}
+
+/// Apply a table's filters in order to the next batch of its rows, as
+/// `apply_filters` does to all of them
+pub fn apply_filters_to_batch(
+    headers: &[String],
+    rows: Vec<Vec<String>>,
+    filters: &[FilterSpec],
+    progress: &mut FilterProgress,
+    warnings: &dyn WarningHandler,
+) -> Result<Vec<Vec<String>>> {
+    let mut rows = rows;
+    progress.seen.resize(filters.len(), 0);
+    progress.epochs.resize_with(filters.len(), EpochDetection::default);
+    
+    for (i, filter) in filters.iter().enumerate() {
+        let seen = progress.seen[i];
+        progress.seen[i] += rows.len();
+        rows = match filter {
+            FilterSpec::DateRange { column, start, end, strict_epoch } => {
+                let date_filter = DateFilter {
+                    column_name: column.clone(),
+                    start_date: *start,
+                    end_date: end.unwrap_or_else(|| chrono::Local::now().date_naive()),
+                    strict_epoch: *strict_epoch,
+                };
+                retain_dated_batch(headers, &mut rows, &date_filter, &mut progress.epochs[i], warnings)?;
+                rows
+            }
+            FilterSpec::Where(condition) => filter_rows(condition, headers, rows)
+                .with_context(|| format!("Error in where filter '{}'", condition))?,
+            FilterSpec::Limit(limit) => {
+                rows.truncate(limit.saturating_sub(seen));
+                rows
+            }
+            FilterSpec::Sample { fraction, seed } => rows.into_iter()
+                .enumerate()
+                .filter(|(i, _)| sample_point(*seed, (seen + i) as u64) < *fraction)
+                .map(|(_, row)| row)
+                .collect(),
+        };
+    }
+    
+    Ok(rows)
+}
+
+/// A number in [0, 1) determined by the seed and the row's position (splitmix64)
+fn sample_point(seed: u64, index: u64) -> f64 {
+    let mut z = seed.wrapping_add(index.wrapping_add(1).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+    (z >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::warnings::CollectedWarnings;
+    
+    const CONFIG: &str = r#"
+[tables.orders]
+filters = [
+    { type = "date-range", column = "createdAt", start = "2024-01-01", end = "2024-12-31" },
+    { type = "where", condition = "status = 'paid'" },
+    { type = "limit", rows = 2 },
+]
+
+[tables.events]
+filters = [{ type = "sample", fraction = 0.5, seed = 7 }]
+trim_cells = { name = "collapse" }
+"#;
+
+    fn orders() -> (Vec<String>, Vec<Vec<String>>) {
+        let headers = vec!["id".to_string(), "status".to_string(), "createdAt".to_string()];
+        let rows = [
+            ("1", "paid", "2023-12-31"),
+            ("2", "paid", "2024-01-05"),
+            ("3", "open", "2024-02-01"),
+            ("4", "paid", "2024-03-01"),
+            ("5", "paid", "2024-04-01"),
+        ].iter()
+            .map(|(id, status, at)| vec![id.to_string(), status.to_string(), at.to_string()])
+            .collect();
+        (headers, rows)
+    }
+    
+    #[test]
+    fn test_parse_filter_config() {
+        let config = parse_filter_config_str(CONFIG).unwrap();
+        assert_eq!(config.filters_for("orders").len(), 3);
+        assert_eq!(config.filters_for("orders")[2], FilterSpec::Limit(2));
+        assert_eq!(config.filters_for("events"), &[FilterSpec::Sample { fraction: 0.5, seed: 7 }]);
+        assert!(config.filters_for("users").is_empty());
+        assert_eq!(config.trim_cells_for("events").get("name"), Some(&TrimCells::Collapse));
+        assert!(config.trim_cells_for("orders").is_empty());
+        
+        assert!(parse_filter_config_str("[tables.t]\nfilters = [{ type = \"sample\", fraction = 2.0 }]").is_err());
+        assert!(parse_filter_config_str("[tables.t]\nfilters = [{ type = \"top\", rows = 1 }]").is_err());
+        assert!(parse_filter_config_str("[tables.t]\ntrim_cells = { name = \"all\" }").is_err());
+    }
+    
+    #[test]
+    fn test_filters_are_applied_in_order() {
+        let config = parse_filter_config_str(CONFIG).unwrap();
+        let (headers, rows) = orders();
+        
+        let filtered = apply_filters(&headers, rows, config.filters_for("orders"), &CollectedWarnings::default()).unwrap();
+        let ids: Vec<&str> = filtered.iter().map(|row| row[0].as_str()).collect();
+        assert_eq!(ids, vec!["2", "4"]);
+    }
+    
+    #[test]
+    fn test_sample_is_reproducible() {
+        let headers = vec!["id".to_string()];
+        let rows: Vec<Vec<String>> = (0..1000).map(|i| vec![i.to_string()]).collect();
+        let sample = [FilterSpec::Sample { fraction: 0.25, seed: 42 }];
+        
+        let first = apply_filters(&headers, rows.clone(), &sample, &CollectedWarnings::default()).unwrap();
+        let second = apply_filters(&headers, rows, &sample, &CollectedWarnings::default()).unwrap();
+        assert_eq!(first, second);
+        assert!((200..300).contains(&first.len()));
+    }
+    
+    #[test]
+    fn test_filters_in_batches_match_filters_on_all_rows() {
+        let headers = vec!["id".to_string(), "at".to_string()];
+        let rows: Vec<Vec<String>> = (0..100).map(|i| vec![i.to_string(), (1_700_000_000 + i * 86_400).to_string()]).collect();
+        let filters = [
+            FilterSpec::DateRange { column: "at".to_string(), start: NaiveDate::from_ymd_opt(2023, 12, 1).unwrap(), end: None, strict_epoch: false },
+            FilterSpec::Sample { fraction: 0.5, seed: 3 },
+            FilterSpec::Limit(20),
+        ];
+        let warnings = CollectedWarnings::default();
+        let all = apply_filters(&headers, rows.clone(), &filters, &warnings).unwrap();
+        
+        let mut progress = FilterProgress::default();
+        let batched: Vec<Vec<String>> = rows.chunks(7)
+            .flat_map(|batch| apply_filters_to_batch(&headers, batch.to_vec(), &filters, &mut progress, &warnings).unwrap())
+            .collect();
+        assert_eq!(batched, all);
+        assert_eq!(all.len(), 20);
+        // The epoch unit is reported once per pass over the table
+        assert_eq!(warnings.into_inner().iter().filter(|w| w.contains("epoch seconds")).count(), 2);
+    }
+}
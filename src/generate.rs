@@ -0,0 +1,385 @@
+//! Synthetic data for a schema (`generate` subcommand).
+//!
+//! `generate --schema schema.sql --rows 10000` parses the CREATE TABLE
+//! statements of a schema and writes a dump with that many rows per table, for
+//! benchmarking the converter and seeding test environments. Values follow the
+//! declared types (and, for text, hints in the column name such as `email`),
+//! primary keys count up from 1, unique columns get distinct values and
+//! foreign keys hold values of the rows they reference. Every value is derived
+//! from `--seed`, the table, the column and the row, so runs with the same seed
+//! write the same data.
+
+use anyhow::{Context, Result};
+use fake::Fake;
+use fake::faker::address::en::CityName;
+use fake::faker::lorem::en::Words;
+use rand_chacha::ChaCha8Rng;
+use rand_chacha::rand_core::{RngCore, SeedableRng};
+use std::io::Write;
+
+use crate::args::flag_value;
+use crate::load::{column_kind, ColumnKind};
+use crate::load_order::load_order;
+use crate::mask::{mask_value, FakeKind, MaskStrategy};
+use crate::statements::{split_statements, StatementKind};
+use crate::types::Table;
+use crate::value::SqlValue;
+
+/// Rows per INSERT statement of a generated dump
+const ROWS_PER_INSERT: usize = 500;
+
+/// References followed from a foreign key to the key it points at, e.g.
+/// `order_items.order_id` -> `orders.id`, before giving up on a chain
+const MAX_REFERENCE_DEPTH: usize = 8;
+
+/// Share of NULLs in nullable columns, in percent
+const NULL_PERCENT: u64 = 5;
+
+/// Options of the `generate` subcommand
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenerateOptions {
+    /// Rows written per table
+    pub rows: usize,
+    pub seed: u64,
+}
+
+/// Parse --rows (default 1000) and --seed (default 0) of `generate`
+pub fn parse_generate_options(args: &[String]) -> Result<GenerateOptions> {
+    let rows = match flag_value(args, "--rows")? {
+        Some(value) => value.parse::<usize>()
+            .map_err(|_| anyhow::anyhow!("Error: Invalid --rows '{}'. Use a non-negative number", value))?,
+        None => 1000,
+    };
+    let seed = match flag_value(args, "--seed")? {
+        Some(value) => value.parse::<u64>()
+            .map_err(|_| anyhow::anyhow!("Error: Invalid --seed '{}'. Use a non-negative integer", value))?,
+        None => 0,
+    };
+    Ok(GenerateOptions { rows, seed })
+}
+
+/// Columns values are generated for: all but generated columns, which dumps
+/// leave out of their INSERT statements
+pub fn generated_columns(table: &Table) -> Vec<String> {
+    table.columns.iter()
+        .filter(|column| !table.generated_columns.contains(column))
+        .cloned()
+        .collect()
+}
+
+/// Writes the rows of the tables of a schema
+pub struct DataGenerator<'a> {
+    tables: &'a [Table],
+    options: GenerateOptions,
+}
+
+impl<'a> DataGenerator<'a> {
+    pub fn new(tables: &'a [Table], options: GenerateOptions) -> Self {
+        DataGenerator { tables, options }
+    }
+    
+    /// Values of a row of a table, for the columns of `generated_columns`
+    pub fn row(&self, table: &Table, row: usize) -> Vec<SqlValue> {
+        generated_columns(table).iter()
+            .map(|column| self.value(table, column, row, 0))
+            .collect()
+    }
+    
+    fn value(&self, table: &Table, column: &str, row: usize, depth: usize) -> SqlValue {
+        let index = table.columns.iter().position(|c| c == column).unwrap_or_default();
+        let column_type = table.column_types.get(index).map(String::as_str).unwrap_or("");
+        // The first primary key column counts up, which keeps composite keys
+        // unique. pg_dump declares keys in a later ALTER TABLE, so without a
+        // primary key an `id` column counts up instead.
+        let sequential = match table.primary_key.first() {
+            Some(key) => key == column,
+            None => column.eq_ignore_ascii_case("id"),
+        };
+        let unique = sequential || table.unique_constraints.iter().any(|columns| columns == &[column]);
+        let mut rng = ChaCha8Rng::seed_from_u64(value_seed(self.options.seed, &table.name, column, row));
+        
+        let reference = table.foreign_keys.iter()
+            .find_map(|key| Some((key, key.columns.iter().position(|c| c == column)?)))
+            .and_then(|(key, i)| Some((self.table(&key.references_table)?, key.references_columns.get(i)?)))
+            .filter(|_| depth < MAX_REFERENCE_DEPTH && self.options.rows > 0);
+        let nullable = !table.primary_key.iter().any(|key| key == column)
+            && !table.not_null_columns.iter().any(|c| c == column);
+        if nullable && !unique && below(&mut rng, 100) < NULL_PERCENT {
+            return SqlValue::Null;
+        }
+        if let Some((referenced, referenced_column)) = reference {
+            let referenced_row = match sequential {
+                true => row % self.options.rows,
+                false => below(&mut rng, self.options.rows as u64) as usize,
+            };
+            return self.value(referenced, referenced_column, referenced_row, depth + 1);
+        }
+        
+        let kind = column_kind(column_type);
+        if unique && matches!(kind, ColumnKind::Integer | ColumnKind::UnsignedBigInt) {
+            return SqlValue::Integer(row as i64 + 1);
+        }
+        let value = typed_value(&mut rng, column_type, kind, column, self.options.seed);
+        match value {
+            SqlValue::Text(text) if unique => SqlValue::Text(unique_text(&text, row, text_length(column_type))),
+            value => value,
+        }
+    }
+    
+    fn table(&self, name: &str) -> Option<&'a Table> {
+        self.tables.iter().find(|table| table.name == name)
+            .or_else(|| self.tables.iter().find(|table| table.name.eq_ignore_ascii_case(name)))
+    }
+}
+
+/// Seed of a single value, from the run's seed, the table, the column and the
+/// row (FNV-1a, which is the same on every platform and release)
+fn value_seed(seed: u64, table: &str, column: &str, row: usize) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    let bytes = seed.to_le_bytes().into_iter()
+        .chain(table.bytes())
+        .chain([0])
+        .chain(column.bytes())
+        .chain([0])
+        .chain((row as u64).to_le_bytes());
+    for byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// A plausible value of a column type
+fn typed_value(rng: &mut ChaCha8Rng, column_type: &str, kind: ColumnKind, column: &str, seed: u64) -> SqlValue {
+    if let Some(element_type) = column_type.trim().strip_suffix("[]") {
+        return SqlValue::Text(array_value(rng, element_type, column, seed));
+    }
+    let upper = column_type.to_uppercase();
+    match kind {
+        ColumnKind::Boolean => SqlValue::Bool(below(rng, 2) == 1),
+        // MySQL booleans
+        ColumnKind::Integer if upper.starts_with("TINYINT(1)") => SqlValue::Integer(below(rng, 2) as i64),
+        ColumnKind::Integer if upper.starts_with("TINYINT") => SqlValue::Integer(below(rng, 101) as i64),
+        ColumnKind::Integer | ColumnKind::UnsignedBigInt => SqlValue::Integer(below(rng, 100_000) as i64 + 1),
+        ColumnKind::Float => SqlValue::Float(below(rng, 100_000) as f64 / 100.0),
+        ColumnKind::Decimal(precision_scale) => {
+            let (precision, scale) = precision_scale.unwrap_or((10, 2));
+            let digits = precision.saturating_sub(scale).min(6);
+            let whole = below(rng, 10u64.pow(digits));
+            SqlValue::Text(match scale {
+                0 => whole.to_string(),
+                scale => {
+                    let scale = scale.min(6);
+                    format!("{}.{:0width$}", whole, below(rng, 10u64.pow(scale)), width = scale as usize)
+                }
+            })
+        }
+        ColumnKind::Date => SqlValue::Text(date(below(rng, DAYS)).format("%Y-%m-%d").to_string()),
+        ColumnKind::Timestamp | ColumnKind::TimestampTz => {
+            let day = date(below(rng, DAYS));
+            let time = chrono::NaiveTime::from_num_seconds_from_midnight_opt(below(rng, 86_400) as u32, 0).unwrap_or_default();
+            let suffix = if kind == ColumnKind::TimestampTz { "+00" } else { "" };
+            SqlValue::Text(format!("{} {}{}", day.format("%Y-%m-%d"), time.format("%H:%M:%S"), suffix))
+        }
+        ColumnKind::Time => {
+            let time = chrono::NaiveTime::from_num_seconds_from_midnight_opt(below(rng, 86_400) as u32, 0).unwrap_or_default();
+            SqlValue::Text(time.format("%H:%M:%S").to_string())
+        }
+        ColumnKind::Json => SqlValue::Text(format!("{{\"id\": {}}}", below(rng, 100_000) + 1)),
+        ColumnKind::Text => SqlValue::Text(text_value(rng, column_type, column, seed)),
+    }
+}
+
+/// A Postgres array literal of one to three elements, e.g. `{"a b",c}`
+fn array_value(rng: &mut ChaCha8Rng, element_type: &str, column: &str, seed: u64) -> String {
+    let count = below(rng, 3) + 1;
+    let elements: Vec<String> = (0..count)
+        .map(|_| match typed_value(rng, element_type, column_kind(element_type), column, seed) {
+            SqlValue::Text(text) => format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\"")),
+            value => value.to_string(),
+        })
+        .collect();
+    format!("{{{}}}", elements.join(","))
+}
+
+/// A number from 0 to `n - 1`
+fn below(rng: &mut ChaCha8Rng, n: u64) -> u64 {
+    rng.next_u64() % n
+}
+
+/// Days of 2020 to 2024, the span dates are drawn from
+const DAYS: u64 = 1827;
+
+fn date(offset: u64) -> chrono::NaiveDate {
+    let start = chrono::NaiveDate::from_ymd_opt(2020, 1, 1).unwrap_or_default();
+    start + chrono::Days::new(offset)
+}
+
+/// Text for a column, guided by its type (`UUID`, `ENUM(...)`) and name
+/// (`email`, `name`, `phone`, ...), cut to the declared length
+fn text_value(rng: &mut ChaCha8Rng, column_type: &str, column: &str, seed: u64) -> String {
+    let upper = column_type.to_uppercase();
+    if let Some(values) = upper.starts_with("ENUM(").then(|| enum_values(column_type)).filter(|values| !values.is_empty()) {
+        return values[below(rng, values.len() as u64) as usize].clone();
+    }
+    let name = column.to_lowercase();
+    let mut fake = |kind| mask_value(&rng.next_u64().to_string(), MaskStrategy::Fake(kind), seed);
+    let text = if upper.starts_with("UUID") || name.contains("uuid") || name == "guid" {
+        fake(FakeKind::Uuid)
+    } else if name.contains("email") {
+        fake(FakeKind::Email)
+    } else if name.contains("phone") {
+        fake(FakeKind::Phone)
+    } else if name.contains("address") || name.contains("street") {
+        fake(FakeKind::Address)
+    } else if name.contains("name") {
+        fake(FakeKind::Name)
+    } else if name.contains("city") {
+        CityName().fake_with_rng(rng)
+    } else {
+        let words: Vec<String> = Words(1..5).fake_with_rng(rng);
+        words.join(" ")
+    };
+    match text_length(column_type) {
+        Some(length) => text.chars().take(length).collect(),
+        None => text,
+    }
+}
+
+/// Quoted values of `ENUM('a','b')`
+fn enum_values(column_type: &str) -> Vec<String> {
+    let Some((_, list)) = column_type.split_once('(') else {
+        return Vec::new();
+    };
+    list.trim_end_matches(')')
+        .split(',')
+        .map(|value| value.trim().trim_matches('\'').to_string())
+        .filter(|value| !value.is_empty())
+        .collect()
+}
+
+/// Length of `VARCHAR(n)` and `CHAR(n)`
+fn text_length(column_type: &str) -> Option<usize> {
+    let upper = column_type.to_uppercase();
+    if !upper.contains("CHAR(") {
+        return None;
+    }
+    upper.split_once('(')?.1.split(')').next()?.trim().parse().ok()
+}
+
+/// Make a text distinct by adding the row number, before the `@` of emails,
+/// keeping within the declared length
+fn unique_text(text: &str, row: usize, length: Option<usize>) -> String {
+    let (text, domain) = match text.split_once('@') {
+        Some((local, domain)) => (local, format!("@{}", domain)),
+        None => (text, String::new()),
+    };
+    let suffix = format!("-{}{}", row + 1, domain);
+    let keep = length.map_or(usize::MAX, |length| length.saturating_sub(suffix.chars().count()));
+    format!("{}{}", text.chars().take(keep).collect::<String>(), suffix)
+}
+
+/// A value as an SQL literal
+fn sql_literal(value: &SqlValue) -> String {
+    match value {
+        SqlValue::Text(text) => format!("'{}'", text.replace('\'', "''")),
+        SqlValue::Bool(value) => if *value { "TRUE" } else { "FALSE" }.to_string(),
+        value => value.to_string(),
+    }
+}
+
+/// Write a dump of the schema's statements followed by INSERTs of generated
+/// rows. Tables are filled in foreign key order, so a database can load the
+/// dump with its constraints in place.
+pub fn write_generated_dump<W: Write>(mut out: W, schema: &str, tables: &[Table], options: &GenerateOptions) -> Result<()> {
+    writeln!(out, "-- Generated by table-to-csv: {} rows per table, seed {}\n", options.rows, options.seed)?;
+    for statement in split_statements(schema) {
+        match statement.kind() {
+            StatementKind::Insert => continue,
+            StatementKind::UseDatabase if statement.text.starts_with('\\') => writeln!(out, "{}\n", statement.text)?,
+            _ => writeln!(out, "{};\n", statement.text)?,
+        }
+    }
+    
+    // Identifiers are quoted the way the schema quotes them
+    let quote = if schema.contains('`') { '`' } else { '"' };
+    let quoted = |name: &str| format!("{}{}{}", quote, name.replace(quote, &format!("{}{}", quote, quote)), quote);
+    let generator = DataGenerator::new(tables, options.clone());
+    for name in load_order(tables).tables {
+        let Some(table) = tables.iter().find(|table| table.name == name) else {
+            continue;
+        };
+        let table_name = match &table.schema {
+            Some(schema) => format!("{}.{}", quoted(schema), quoted(&table.name)),
+            None => quoted(&table.name),
+        };
+        let columns: Vec<String> = generated_columns(table).iter().map(|column| quoted(column)).collect();
+        for start in (0..options.rows).step_by(ROWS_PER_INSERT) {
+            writeln!(out, "INSERT INTO {} ({}) VALUES", table_name, columns.join(", "))?;
+            let end = (start + ROWS_PER_INSERT).min(options.rows);
+            for row in start..end {
+                let values: Vec<String> = generator.row(table, row).iter().map(sql_literal).collect();
+                let separator = if row + 1 == end { ";" } else { "," };
+                writeln!(out, "({}){}", values.join(", "), separator)?;
+            }
+        }
+        writeln!(out)?;
+    }
+    
+    out.flush().context("Failed to write generated dump")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_sql_content;
+    
+    const SCHEMA: &str = "\
+CREATE TABLE users (id INT PRIMARY KEY, email VARCHAR(100) UNIQUE NOT NULL, status ENUM('active','banned') NOT NULL, joined DATE NOT NULL);
+CREATE TABLE orders (id INT PRIMARY KEY, user_id INT NOT NULL, total DECIMAL(8,2) NOT NULL, FOREIGN KEY (user_id) REFERENCES users(id));
+";
+
+    #[test]
+    fn test_generated_rows_follow_the_schema() {
+        let (tables, _) = parse_sql_content(SCHEMA.to_string()).unwrap();
+        let options = GenerateOptions { rows: 20, seed: 7 };
+        let generator = DataGenerator::new(&tables, options.clone());
+        let users = &tables[0];
+        let orders = &tables[1];
+        
+        let emails: std::collections::HashSet<String> = (0..20)
+            .map(|row| generator.row(users, row)[1].to_string())
+            .collect();
+        assert_eq!(emails.len(), 20);
+        for row in 0..20 {
+            let user = generator.row(users, row);
+            assert_eq!(user[0], SqlValue::Integer(row as i64 + 1));
+            assert!(["active", "banned"].contains(&user[2].to_string().as_str()));
+            assert!(chrono::NaiveDate::parse_from_str(&user[3].to_string(), "%Y-%m-%d").is_ok());
+            
+            let order = generator.row(orders, row);
+            let SqlValue::Integer(user_id) = order[1] else { panic!("user_id is {:?}", order[1]) };
+            assert!((1..=20).contains(&user_id));
+            let total = order[2].to_string();
+            assert!(total.split_once('.').is_some_and(|(whole, cents)| whole.len() <= 6 && cents.len() == 2));
+        }
+        assert_eq!(unique_text("ann@example.com", 4, Some(100)), "ann-5@example.com");
+        assert_eq!(unique_text("abcdef", 9, Some(5)), "ab-10");
+        // The same seed gives the same data
+        assert_eq!(generator.row(orders, 3), DataGenerator::new(&tables, options).row(orders, 3));
+    }
+    
+    #[test]
+    fn test_generated_dump_converts_back() {
+        let (tables, _) = parse_sql_content(SCHEMA.to_string()).unwrap();
+        let mut dump = Vec::new();
+        write_generated_dump(&mut dump, SCHEMA, &tables, &GenerateOptions { rows: 501, seed: 0 }).unwrap();
+        let dump = String::from_utf8(dump).unwrap();
+        
+        assert_eq!(dump.matches("INSERT INTO \"users\" (\"id\", \"email\", \"status\", \"joined\") VALUES").count(), 2);
+        let (parsed, content) = parse_sql_content(dump).unwrap();
+        let rows = crate::parser::extract_table_rows(&content, &parsed[1]).unwrap();
+        assert_eq!(rows.len(), 501);
+    }
+}
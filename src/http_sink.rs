@@ -0,0 +1,250 @@
+//! HTTP POST output and HTTP(S) input (feature `http`).
+//!
+//! Each table is sent to an ingestion endpoint as CSV or JSON Lines, optionally
+//! split into chunks of a fixed number of rows. Dumps can be read from an
+//! HTTP(S) URL, streamed as they download without being saved first.
+
+use anyhow::{Context, Result};
+use std::io::Read;
+
+use crate::args::{flag_value, flag_values};
+use crate::csv_writer::write_csv_to;
+use crate::jsonl::write_jsonl_to;
+use crate::sink::{OutputSink, TableSink};
+use crate::types::{CsvOptions, Table};
+
+/// Body format of the POST requests
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PostFormat {
+    #[default]
+    Csv,
+    Jsonl,
+}
+
+/// An endpoint that every table is POSTed to
+pub struct HttpOutput {
+    /// Endpoint URL; `{table}` is replaced by the table name
+    url: String,
+    headers: Vec<(String, String)>,
+    format: PostFormat,
+    /// Rows per request, or everything in one request
+    chunk_rows: Option<usize>,
+    options: CsvOptions,
+    agent: ureq::Agent,
+}
+
+/// Check whether an output location is an HTTP endpoint rather than a local path
+pub fn is_http_url(location: &str) -> bool {
+    location.starts_with("http://") || location.starts_with("https://")
+}
+
+/// Stream the body of a GET request to a URL. A non-2xx response is an error.
+pub fn open_url(url: &str) -> Result<impl Read + Send + 'static> {
+    let response = ureq::get(url)
+        .call()
+        .with_context(|| format!("Failed to download '{}'", url))?;
+    Ok(response.into_body().into_reader())
+}
+
+impl HttpOutput {
+    /// Configure the endpoint from `--post-header`, `--post-format` and
+    /// `--post-chunk-rows` options
+    pub fn from_args(url: &str, args: &[String], options: CsvOptions) -> Result<Self> {
+        let headers = flag_values(args, "--post-header")?
+            .into_iter()
+            .map(|header| {
+                header.split_once(':')
+                    .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+                    .ok_or_else(|| anyhow::anyhow!("Error: --post-header '{}' must look like '<name>: <value>'", header))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let format = match flag_value(args, "--post-format")? {
+            None | Some("csv") => PostFormat::Csv,
+            Some("jsonl") => PostFormat::Jsonl,
+            Some(other) => anyhow::bail!("Error: Invalid --post-format '{}'. Use one of: csv, jsonl", other),
+        };
+        let chunk_rows = flag_value(args, "--post-chunk-rows")?
+            .map(|rows| rows.parse::<usize>().ok().filter(|&n| n > 0)
+                .ok_or_else(|| anyhow::anyhow!("Error: Invalid --post-chunk-rows '{}'. Use a positive number", rows)))
+            .transpose()?;
+        
+        Ok(HttpOutput {
+            url: url.to_string(),
+            headers,
+            format,
+            chunk_rows,
+            options,
+            agent: ureq::Agent::new_with_defaults(),
+        })
+    }
+    
+    /// Endpoint a table is sent to
+    pub fn url_for(&self, table: &str) -> String {
+        self.url.replace("{table}", table)
+    }
+    
+    /// POST one chunk of a table's rows. Every request carries `X-Parsley-Table`
+    /// and, when chunked, `X-Parsley-Chunk` (1-based) headers.
+    fn post_chunk(&self, table: &str, headers: &[String], rows: &[Vec<String>], chunk: usize) -> Result<()> {
+        let url = self.url_for(table);
+        let body = self.body(headers, rows)?;
+        let mut request = self.agent.post(&url)
+            .header("X-Parsley-Table", table)
+            .content_type(match self.format {
+                PostFormat::Csv => "text/csv; charset=utf-8",
+                PostFormat::Jsonl => "application/x-ndjson",
+            });
+        if self.chunk_rows.is_some() {
+            request = request.header("X-Parsley-Chunk", chunk.to_string());
+        }
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+        
+        request.send(&body[..])
+            .with_context(|| format!("POST to {} failed", url))?;
+        
+        Ok(())
+    }
+    
+    /// Serialize a chunk of rows; every CSV chunk repeats the header row
+    fn body(&self, headers: &[String], rows: &[Vec<String>]) -> Result<Vec<u8>> {
+        let mut body = Vec::new();
+        match self.format {
+            PostFormat::Csv => write_csv_to(&mut body, headers, rows, &self.options)?,
+            PostFormat::Jsonl => write_jsonl_to(&mut body, headers, rows, &self.options)?,
+        }
+        Ok(body)
+    }
+}
+
+impl OutputSink for HttpOutput {
+    fn open_table<'a>(&'a self, table: &Table, _name: &str, headers: &[String]) -> Result<Box<dyn TableSink + 'a>> {
+        Ok(Box::new(HttpTableSink {
+            output: self,
+            table: table.name.clone(),
+            headers: headers.to_vec(),
+            rows: Vec::new(),
+            chunks: 0,
+        }))
+    }
+}
+
+/// Collects the rows of one table and posts them a chunk at a time
+struct HttpTableSink<'a> {
+    output: &'a HttpOutput,
+    table: String,
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+    /// Requests sent so far
+    chunks: usize,
+}
+
+impl HttpTableSink<'_> {
+    fn post_rows(&mut self) -> Result<()> {
+        self.chunks += 1;
+        self.output.post_chunk(&self.table, &self.headers, &self.rows, self.chunks)?;
+        self.rows.clear();
+        Ok(())
+    }
+}
+
+impl TableSink for HttpTableSink<'_> {
+    fn write_row(&mut self, row: &[String]) -> Result<()> {
+        self.rows.push(row.to_vec());
+        if Some(self.rows.len()) == self.output.chunk_rows {
+            self.post_rows()?;
+        }
+        Ok(())
+    }
+    
+    fn finish(mut self: Box<Self>) -> Result<String> {
+        if !self.rows.is_empty() {
+            self.post_rows()?;
+        }
+        Ok(self.output.url_for(&self.table))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread;
+    
+    /// Accept `count` requests and return their raw header blocks and bodies
+    fn serve(listener: TcpListener, count: usize) -> thread::JoinHandle<Vec<(String, String)>> {
+        thread::spawn(move || {
+            (0..count).map(|_| {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut head = String::new();
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line == "\r\n" {
+                        break;
+                    }
+                    if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                        length = value.trim().parse().unwrap();
+                    }
+                    head.push_str(&line);
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                reader.get_mut().write_all(b"HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n").unwrap();
+                (head, String::from_utf8(body).unwrap())
+            }).collect()
+        })
+    }
+    
+    #[test]
+    fn test_http_sink_posts_chunks() {
+        use crate::sink::write_table;
+        
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/ingest/{{table}}", listener.local_addr().unwrap());
+        let server = serve(listener, 2);
+        
+        let args: Vec<String> = ["--post-format", "jsonl", "--post-chunk-rows", "2", "--post-header", "Authorization: Bearer secret"]
+            .iter().map(|s| s.to_string()).collect();
+        let output = HttpOutput::from_args(&url, &args, CsvOptions::default()).unwrap();
+        let table = Table { name: "users".to_string(), ..Default::default() };
+        let headers = vec!["id".to_string()];
+        let rows: Vec<Vec<String>> = (1..=3).map(|i| vec![i.to_string()]).collect();
+        assert!(write_table(&output, &table, "users", &headers, &rows).unwrap().ends_with("/ingest/users"));
+        
+        let requests = server.join().unwrap();
+        assert!(requests[0].0.starts_with("POST /ingest/users "));
+        assert!(requests[0].0.to_lowercase().contains("authorization: bearer secret"));
+        assert!(requests[1].0.to_lowercase().contains("x-parsley-chunk: 2"));
+        assert_eq!(requests[0].1, "{\"id\":\"1\"}\n{\"id\":\"2\"}\n");
+        assert_eq!(requests[1].1, "{\"id\":\"3\"}\n");
+    }
+    
+    #[test]
+    fn test_dump_is_streamed_from_url() {
+        use crate::input::{open_sql_source, read_sql};
+        use crate::types::InputOptions;
+        use flate2::write::GzEncoder;
+        
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(b"CREATE TABLE t (id int);\nINSERT INTO t VALUES (1);\n").unwrap();
+        let body = encoder.finish().unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/dump.sql.gz", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0; 1024];
+            let _ = stream.read(&mut request).unwrap();
+            stream.write_all(format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", body.len()).as_bytes()).unwrap();
+            stream.write_all(&body).unwrap();
+        });
+        
+        let (content, _) = read_sql(open_sql_source(&url).unwrap(), &InputOptions::default()).unwrap();
+        server.join().unwrap();
+        assert_eq!(content, "CREATE TABLE t (id int);\nINSERT INTO t VALUES (1);\n");
+    }
+}
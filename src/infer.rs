@@ -0,0 +1,171 @@
+use chrono::{NaiveDate, NaiveDateTime};
+
+use crate::args::has_flag;
+use crate::types::Table;
+
+/// Type of a column detected from its values
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InferredType {
+    Integer,
+    Float,
+    Boolean,
+    Date,
+    Timestamp,
+    String,
+}
+
+impl InferredType {
+    /// SQL type recorded for the column, understood by the Avro and database outputs
+    pub fn sql_type(self) -> &'static str {
+        match self {
+            InferredType::Integer => "BIGINT",
+            InferredType::Float => "DOUBLE",
+            InferredType::Boolean => "BOOLEAN",
+            InferredType::Date => "DATE",
+            InferredType::Timestamp => "TIMESTAMP",
+            InferredType::String => "TEXT",
+        }
+    }
+    
+    /// Lowercase name shown in reports, e.g. `integer`
+    pub fn name(self) -> &'static str {
+        match self {
+            InferredType::Integer => "integer",
+            InferredType::Float => "float",
+            InferredType::Boolean => "boolean",
+            InferredType::Date => "date",
+            InferredType::Timestamp => "timestamp",
+            InferredType::String => "string",
+        }
+    }
+    
+    /// Narrowest type of a single non-empty value
+    pub(crate) fn of_value(value: &str) -> InferredType {
+        let value = value.trim();
+        if value.parse::<i64>().is_ok() {
+            InferredType::Integer
+        } else if value.parse::<f64>().is_ok_and(f64::is_finite) {
+            InferredType::Float
+        } else if ["true", "false", "t", "f"].iter().any(|b| value.eq_ignore_ascii_case(b)) {
+            InferredType::Boolean
+        } else if NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok() {
+            InferredType::Date
+        } else if is_timestamp(value) {
+            InferredType::Timestamp
+        } else {
+            InferredType::String
+        }
+    }
+    
+    /// Type that can hold values of both types
+    pub(crate) fn widen(self, other: InferredType) -> InferredType {
+        use InferredType::*;
+        match (self, other) {
+            (a, b) if a == b => a,
+            (Integer, Float) | (Float, Integer) => Float,
+            (Date, Timestamp) | (Timestamp, Date) => Timestamp,
+            _ => String,
+        }
+    }
+}
+
+fn is_timestamp(value: &str) -> bool {
+    ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"].iter()
+        .any(|format| NaiveDateTime::parse_from_str(value, format).is_ok())
+        || chrono::DateTime::parse_from_rfc3339(value).is_ok()
+}
+
+/// Parse --infer-types flag from command line
+pub fn parse_infer_types(args: &[String]) -> bool {
+    has_flag(args, "--infer-types")
+}
+
+/// Detect the type of each column from its values. `NULL` and empty cells are
+/// ignored; columns without any other values are strings.
+pub fn infer_column_types(column_count: usize, rows: &[Vec<String>]) -> Vec<InferredType> {
+    (0..column_count)
+        .map(|i| {
+            rows.iter()
+                .filter_map(|row| row.get(i))
+                .filter(|value| !value.is_empty() && *value != "NULL")
+                .map(|value| InferredType::of_value(value))
+                .reduce(InferredType::widen)
+                .unwrap_or(InferredType::String)
+        })
+        .collect()
+}
+
+/// Whether a declared type says nothing about the values, as in SQLite dumps
+/// where every column is TEXT
+fn is_text_type(sql_type: &str) -> bool {
+    let base = sql_type.split(['(', ' ']).next().unwrap_or("").to_uppercase();
+    matches!(base.as_str(), "TEXT" | "VARCHAR" | "CHAR" | "CHARACTER" | "STRING" | "CLOB")
+}
+
+/// Fill in column types from the data where the DDL gives none. Columns without
+/// a declared type are always inferred; if every column is a text type, all of
+/// them are. Inferred columns are listed in `inferred_columns` for the schema report.
+pub fn apply_type_inference(table: &mut Table, rows: &[Vec<String>]) {
+    table.column_types.resize(table.columns.len(), String::new());
+    let all_text = table.column_types.iter().all(|t| t.is_empty() || is_text_type(t));
+    let inferred = infer_column_types(table.columns.len(), rows);
+    
+    for (i, inferred_type) in inferred.into_iter().enumerate() {
+        if table.column_types[i].is_empty() || all_text {
+            table.column_types[i] = inferred_type.sql_type().to_string();
+            table.inferred_columns.push(table.columns[i].clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+    
+    #[test]
+    fn test_infer_column_types() {
+        let rows = vec![
+            strings(&["1", "1.5", "true", "2024-01-01", "2024-01-01 10:00:00", "a", "NULL"]),
+            strings(&["2", "2", "F", "2024-02-01", "2024-02-01", "3", "NULL"]),
+        ];
+        
+        assert_eq!(infer_column_types(7, &rows), vec![
+            InferredType::Integer,
+            InferredType::Float,
+            InferredType::Boolean,
+            InferredType::Date,
+            InferredType::Timestamp,
+            InferredType::String,
+            InferredType::String,
+        ]);
+    }
+    
+    #[test]
+    fn test_apply_type_inference() {
+        let rows = vec![strings(&["1", "x"])];
+        
+        // Only untyped columns are filled in when other types are declared
+        let mut table = Table {
+            columns: strings(&["id", "total"]),
+            column_types: strings(&["", "DECIMAL(10,2)"]),
+            ..Default::default()
+        };
+        apply_type_inference(&mut table, &rows);
+        assert_eq!(table.column_types, vec!["BIGINT", "DECIMAL(10,2)"]);
+        assert_eq!(table.inferred_columns, vec!["id"]);
+        
+        // Tables declaring nothing but text types are inferred entirely
+        let mut table = Table {
+            columns: strings(&["id", "name"]),
+            column_types: strings(&["TEXT", "VARCHAR(20)"]),
+            ..Default::default()
+        };
+        apply_type_inference(&mut table, &rows);
+        assert_eq!(table.column_types, vec!["BIGINT", "TEXT"]);
+        assert_eq!(table.inferred_columns, vec!["id", "name"]);
+    }
+}
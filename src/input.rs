@@ -0,0 +1,246 @@
+use anyhow::{Context, Result};
+use encoding_rs::{DecoderResult, Encoding, UTF_8};
+use flate2::bufread::MultiGzDecoder;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+use crate::args::{flag_value, has_flag};
+use crate::types::InputOptions;
+
+/// A readable dump: a file, stdin, a decompression stream or a download
+pub trait SqlSource: BufRead + Send {}
+
+impl<T: BufRead + Send> SqlSource for T {}
+
+/// Open a dump location: `-` for stdin, a local path, with the `object-store`
+/// feature an `s3://` or `gs://` URL, or with the `http` feature an `http(s)://`
+/// URL, which is streamed as it downloads. Gzip-compressed input is decompressed.
+pub fn open_sql_source(location: &str) -> Result<Box<dyn SqlSource>> {
+    if location == "-" {
+        return decompress(BufReader::new(io::stdin()));
+    }
+    #[cfg(feature = "object-store")]
+    if crate::cloud::is_object_store_url(location) {
+        let bytes = crate::cloud::download(location)?;
+        return decompress(io::Cursor::new(bytes));
+    }
+    #[cfg(feature = "http")]
+    if crate::http_sink::is_http_url(location) {
+        return decompress(BufReader::new(crate::http_sink::open_url(location)?));
+    }
+    if location.contains("://") {
+        anyhow::bail!("Error: Unsupported input '{}'. Reading from s3:// and gs:// requires building with --features object-store, and from http(s):// with --features http", location);
+    }
+    
+    let file = File::open(location)
+        .with_context(|| format!("Failed to open SQL file '{}'", location))?;
+    decompress(BufReader::new(file))
+}
+
+/// Wrap a source in a gzip decoder if it starts with the gzip magic bytes
+pub(crate) fn decompress<R: BufRead + Send + 'static>(mut source: R) -> Result<Box<dyn SqlSource>> {
+    let is_gzip = source.fill_buf()
+        .context("Failed to read SQL input")?
+        .starts_with(&[0x1f, 0x8b]);
+    if is_gzip {
+        Ok(Box::new(BufReader::new(MultiGzDecoder::new(source))))
+    } else {
+        Ok(Box::new(source))
+    }
+}
+
+/// Read a dump from any source into a string, decoding it from the detected or
+/// requested encoding. Returns the content and the number of invalid sequences
+/// replaced in lossy mode.
+pub fn read_sql<R: BufRead>(mut source: R, options: &InputOptions) -> Result<(String, usize)> {
+    let mut bytes = Vec::new();
+    source.read_to_end(&mut bytes)
+        .context("Failed to read SQL input")?;
+    
+    decode_sql_vec(bytes, options)
+}
+
+/// Read a SQL file into a string, decoding it from the detected or requested encoding.
+/// Returns the content and the number of invalid sequences replaced in lossy mode.
+pub fn read_sql_file<P: AsRef<Path>>(sql_file_path: P, options: &InputOptions) -> Result<(String, usize)> {
+    let file = File::open(sql_file_path)
+        .context("Failed to read SQL file")?;
+    
+    read_sql(decompress(BufReader::new(file))?, options)
+}
+
+/// Decode raw dump bytes and normalize CRLF line endings to LF.
+/// Returns the content and the number of invalid sequences replaced in lossy mode.
+pub fn decode_sql_bytes(bytes: &[u8], options: &InputOptions) -> Result<(String, usize)> {
+    // A byte order mark wins over everything else, then the requested encoding, then UTF-8
+    let (encoding, bom_len) = match Encoding::for_bom(bytes) {
+        Some((encoding, bom_len)) => (encoding, bom_len),
+        None => (options.encoding.unwrap_or(UTF_8), 0),
+    };
+    
+    let (content, replaced) = if options.lossy {
+        decode_lossy(encoding, &bytes[bom_len..])
+    } else {
+        let content = encoding
+            .decode_without_bom_handling_and_without_replacement(&bytes[bom_len..])
+            .with_context(|| match options.encoding {
+                Some(_) => format!("SQL file is not valid {}. Use --lossy-utf8 to replace invalid bytes", encoding.name()),
+                None => format!(
+                    "SQL file is not valid {}. Use --input-encoding to specify its encoding or --lossy-utf8 to replace invalid bytes",
+                    encoding.name()
+                ),
+            })?;
+        (content.into_owned(), 0)
+    };
+    
+    if content.contains('\r') {
+        Ok((content.replace("\r\n", "\n"), replaced))
+    } else {
+        Ok((content, replaced))
+    }
+}
+
+const UTF_8_BOM: [u8; 3] = [0xEF, 0xBB, 0xBF];
+
+/// Decode raw dump bytes like `decode_sql_bytes`, taking them so that UTF-8
+/// content becomes the string without being copied
+pub fn decode_sql_vec(mut bytes: Vec<u8>, options: &InputOptions) -> Result<(String, usize)> {
+    let bom_len = match Encoding::for_bom(&bytes) {
+        Some((encoding, bom_len)) if encoding == UTF_8 => bom_len,
+        None if options.encoding.is_none_or(|encoding| encoding == UTF_8) => 0,
+        _ => return decode_sql_bytes(&bytes, options),
+    };
+    bytes.drain(..bom_len);
+    
+    let content = match String::from_utf8(bytes) {
+        Ok(content) => content,
+        // Invalid bytes are reported or replaced as usual, with the byte order mark back
+        Err(e) => {
+            let mut bytes = e.into_bytes();
+            bytes.splice(..0, UTF_8_BOM[..bom_len].iter().copied());
+            return decode_sql_bytes(&bytes, options);
+        }
+    };
+    if content.contains('\r') {
+        Ok((content.replace("\r\n", "\n"), 0))
+    } else {
+        Ok((content, 0))
+    }
+}
+
+/// Decode bytes replacing each malformed sequence with U+FFFD, counting the replacements
+fn decode_lossy(encoding: &'static Encoding, bytes: &[u8]) -> (String, usize) {
+    let mut decoder = encoding.new_decoder_without_bom_handling();
+    let capacity = decoder.max_utf8_buffer_length_without_replacement(bytes.len())
+        .unwrap_or(bytes.len());
+    let mut content = String::with_capacity(capacity);
+    let mut input = bytes;
+    let mut replaced = 0;
+    
+    loop {
+        let (result, read) = decoder.decode_to_string_without_replacement(input, &mut content, true);
+        input = &input[read..];
+        match result {
+            DecoderResult::InputEmpty => break,
+            DecoderResult::OutputFull => content.reserve(input.len().max(16) * 3),
+            DecoderResult::Malformed(_, _) => {
+                content.push(char::REPLACEMENT_CHARACTER);
+                replaced += 1;
+            }
+        }
+    }
+    
+    (content, replaced)
+}
+
+/// Parse --input-encoding option from command line (e.g. utf-16le, latin1, windows-1252)
+pub fn parse_input_encoding(args: &[String]) -> Result<Option<&'static Encoding>> {
+    match flag_value(args, "--input-encoding")? {
+        Some(label) => Encoding::for_label(label.as_bytes())
+            .map(Some)
+            .ok_or_else(|| anyhow::anyhow!("Error: Unknown --input-encoding '{}'", label)),
+        None => Ok(None),
+    }
+}
+
+/// Parse --lossy-utf8 option from command line
+pub fn parse_lossy_utf8(args: &[String]) -> bool {
+    has_flag(args, "--lossy-utf8")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_decode_utf16le_with_bom() {
+        let mut bytes = vec![0xFF, 0xFE];
+        for unit in "INSERT INTO t VALUES(1);\r\n".encode_utf16() {
+            bytes.extend_from_slice(&unit.to_le_bytes());
+        }
+        
+        let (content, _) = decode_sql_bytes(&bytes, &InputOptions::default()).unwrap();
+        assert_eq!(content, "INSERT INTO t VALUES(1);\n");
+    }
+    
+    #[test]
+    fn test_decode_sql_vec_matches_decode_sql_bytes() {
+        let lossy = InputOptions { lossy: true, ..Default::default() };
+        let inputs: [&[u8]; 4] = [
+            b"INSERT INTO t VALUES('Jos\xc3\xa9');\r\n",
+            b"\xef\xbb\xbfINSERT INTO t VALUES(1);",
+            b"\xef\xbb\xbfINSERT INTO t VALUES('a\xff');",
+            b"\xff\xfeI\0N\0",
+        ];
+        for bytes in inputs {
+            for options in [&InputOptions::default(), &lossy] {
+                let expected = decode_sql_bytes(bytes, options).map_err(|e| e.to_string());
+                assert_eq!(decode_sql_vec(bytes.to_vec(), options).map_err(|e| e.to_string()), expected);
+            }
+        }
+    }
+    
+    #[test]
+    fn test_decode_latin1_requires_encoding() {
+        let bytes = b"INSERT INTO t VALUES('Jos\xe9');";
+        assert!(decode_sql_bytes(bytes, &InputOptions::default()).is_err());
+        
+        let options = InputOptions {
+            encoding: Encoding::for_label(b"latin1"),
+            ..Default::default()
+        };
+        let (content, _) = decode_sql_bytes(bytes, &options).unwrap();
+        assert_eq!(content, "INSERT INTO t VALUES('José');");
+    }
+    
+    #[test]
+    fn test_decode_lossy_utf8_counts_replacements() {
+        let bytes = b"INSERT INTO t VALUES('a\xff', 'b\xfe\xfd');";
+        let options = InputOptions {
+            lossy: true,
+            ..Default::default()
+        };
+        
+        let (content, replaced) = decode_sql_bytes(bytes, &options).unwrap();
+        assert_eq!(content, "INSERT INTO t VALUES('a\u{FFFD}', 'b\u{FFFD}\u{FFFD}');");
+        assert_eq!(replaced, 3);
+    }
+    
+    #[test]
+    fn test_read_sql_decompresses_gzip() {
+        use flate2::write::GzEncoder;
+        use std::io::Write;
+        
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(b"INSERT INTO t VALUES(1);\r\n").unwrap();
+        let compressed = encoder.finish().unwrap();
+        
+        let source = decompress(io::Cursor::new(compressed)).unwrap();
+        let (content, _) = read_sql(source, &InputOptions::default()).unwrap();
+        assert_eq!(content, "INSERT INTO t VALUES(1);\n");
+        
+        let source = decompress(io::Cursor::new(b"CREATE TABLE t (id INT);".to_vec())).unwrap();
+        assert_eq!(read_sql(source, &InputOptions::default()).unwrap().0, "CREATE TABLE t (id INT);");
+    }
+}
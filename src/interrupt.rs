@@ -0,0 +1,76 @@
+//! Ctrl-C handling of the command line.
+//!
+//! By default Ctrl-C (or SIGTERM) removes the run's temporary files and exits
+//! with status 130 at once. A conversion registers a `CancellationToken`
+//! instead, and the first Ctrl-C only cancels it: no further table is
+//! started, tables already handed to the writers are finished and synced to
+//! disk, and the manifest lists the tables that were not converted before the
+//! run exits with status 130. A second Ctrl-C exits at once.
+
+use anyhow::Result;
+use std::fs::File;
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::cancel::CancellationToken;
+use crate::workspace::remove_temp_files;
+
+/// Exit status of a run stopped by Ctrl-C (128 + SIGINT, as shells report it)
+pub const INTERRUPTED_EXIT_CODE: i32 = 130;
+
+/// Token cancelled by the first Ctrl-C, when a conversion registered one
+static TOKEN: Mutex<Option<CancellationToken>> = Mutex::new(None);
+
+/// Have the first Ctrl-C cancel `token` instead of exiting
+pub fn cancel_on_interrupt(token: CancellationToken) {
+    *TOKEN.lock().unwrap_or_else(|e| e.into_inner()) = Some(token);
+}
+
+/// Remove the temporary files and exit with `INTERRUPTED_EXIT_CODE`
+pub fn exit_interrupted() -> ! {
+    let removed = remove_temp_files();
+    if removed > 0 {
+        eprintln!("Removed {} unfinished output files", removed);
+    }
+    std::process::exit(INTERRUPTED_EXIT_CODE);
+}
+
+/// Handle Ctrl-C and SIGTERM for the rest of the process
+#[cfg(unix)]
+pub fn handle_interrupts() -> Result<()> {
+    use anyhow::Context;
+    use signal_hook::consts::{SIGINT, SIGTERM};
+    
+    let mut signals = signal_hook::iterator::Signals::new([SIGINT, SIGTERM])
+        .context("Failed to install the Ctrl-C handler")?;
+    std::thread::spawn(move || {
+        for _ in signals.forever() {
+            let token = TOKEN.lock().unwrap_or_else(|e| e.into_inner()).clone();
+            match token {
+                Some(token) if !token.is_cancelled() => {
+                    token.cancel();
+                    eprintln!("\nInterrupted - finishing the tables being written (press Ctrl-C again to stop at once)");
+                }
+                _ => {
+                    eprintln!("\nInterrupted");
+                    exit_interrupted();
+                }
+            }
+        }
+    });
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn handle_interrupts() -> Result<()> {
+    Ok(())
+}
+
+/// Sync written files to disk, so the tables finished before an interrupt
+/// survive a crash right after it. Outputs that are not local files are left
+/// as they are.
+pub fn sync_to_disk<'a>(paths: impl IntoIterator<Item = &'a str>) {
+    for path in paths.into_iter().map(Path::new).filter(|path| path.is_file()) {
+        let _ = File::open(path).and_then(|file| file.sync_all());
+    }
+}
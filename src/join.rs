@@ -0,0 +1,165 @@
+use anyhow::Result;
+use std::collections::HashMap;
+
+use crate::args::flag_value;
+use crate::types::JoinSpec;
+
+/// Parse --join, --select and --join-type options from command line, e.g.
+/// `--join orders.user_id=users.id --select orders.*,users.email`
+pub fn parse_join(args: &[String]) -> Result<Option<JoinSpec>> {
+    let Some(condition) = flag_value(args, "--join")? else {
+        return Ok(None);
+    };
+    
+    let (left, right) = condition.split_once('=')
+        .ok_or_else(|| anyhow::anyhow!("Error: --join '{}' must look like <table>.<column>=<table>.<column>", condition))?;
+    let (left_table, left_column) = split_qualified(left)?;
+    let (right_table, right_column) = split_qualified(right)?;
+    
+    let select = match flag_value(args, "--select")? {
+        Some(select) => select.split(',')
+            .map(split_qualified)
+            .collect::<Result<Vec<_>>>()?,
+        None => vec![
+            (left_table.clone(), "*".to_string()),
+            (right_table.clone(), "*".to_string()),
+        ],
+    };
+    for (table, _) in &select {
+        if table != &left_table && table != &right_table {
+            anyhow::bail!("Error: --select references table '{}' which is not part of the join", table);
+        }
+    }
+    
+    let keep_unmatched = match flag_value(args, "--join-type")? {
+        None | Some("inner") => false,
+        Some("left") => true,
+        Some(other) => anyhow::bail!("Error: Invalid --join-type '{}'. Use one of: inner, left", other),
+    };
+    
+    Ok(Some(JoinSpec {
+        left_table,
+        left_column,
+        right_table,
+        right_column,
+        select,
+        keep_unmatched,
+    }))
+}
+
+/// Split `table.column` into its parts
+fn split_qualified(name: &str) -> Result<(String, String)> {
+    let name = name.trim();
+    match name.rsplit_once('.') {
+        Some((table, column)) if !table.is_empty() && !column.is_empty() => {
+            Ok((table.to_string(), column.to_string()))
+        }
+        _ => anyhow::bail!("Error: '{}' must be qualified as <table>.<column>", name),
+    }
+}
+
+/// Join the rows of two tables with an in-memory hash join on the right table.
+/// Returns the selected headers and joined rows.
+pub fn join_tables(
+    spec: &JoinSpec,
+    left_headers: &[String],
+    left_rows: &[Vec<String>],
+    right_headers: &[String],
+    right_rows: &[Vec<String>],
+) -> Result<(Vec<String>, Vec<Vec<String>>)> {
+    let column_index = |headers: &[String], table: &str, column: &str| {
+        headers.iter().position(|h| h == column)
+            .ok_or_else(|| anyhow::anyhow!("Column '{}' not found in table '{}'", column, table))
+    };
+    let left_key = column_index(left_headers, &spec.left_table, &spec.left_column)?;
+    let right_key = column_index(right_headers, &spec.right_table, &spec.right_column)?;
+    
+    // Resolve the selection into (is_left, column index, header) triples
+    let mut selected: Vec<(bool, usize, String)> = Vec::new();
+    for (table, column) in &spec.select {
+        let is_left = table == &spec.left_table;
+        let headers = if is_left { left_headers } else { right_headers };
+        if column == "*" {
+            selected.extend(headers.iter().enumerate().map(|(i, h)| (is_left, i, h.clone())));
+        } else {
+            selected.push((is_left, column_index(headers, table, column)?, column.clone()));
+        }
+    }
+    
+    // Qualify headers that would otherwise appear twice
+    let headers: Vec<String> = selected.iter()
+        .map(|(is_left, _, header)| {
+            if selected.iter().filter(|(_, _, other)| other == header).count() > 1 {
+                let table = if *is_left { &spec.left_table } else { &spec.right_table };
+                format!("{}.{}", table, header)
+            } else {
+                header.clone()
+            }
+        })
+        .collect();
+    
+    // Build the hash table on the right side
+    let mut index: HashMap<&str, Vec<&Vec<String>>> = HashMap::new();
+    for row in right_rows {
+        if let Some(key) = row.get(right_key) {
+            index.entry(key.as_str()).or_default().push(row);
+        }
+    }
+    
+    let project = |left: &Vec<String>, right: Option<&Vec<String>>| -> Vec<String> {
+        selected.iter()
+            .map(|(is_left, i, _)| {
+                let row = if *is_left { Some(left) } else { right };
+                row.and_then(|row| row.get(*i)).cloned().unwrap_or_default()
+            })
+            .collect()
+    };
+    
+    let mut joined = Vec::new();
+    for left in left_rows {
+        let matches = left.get(left_key).and_then(|key| index.get(key.as_str()));
+        match matches {
+            Some(rights) => joined.extend(rights.iter().map(|right| project(left, Some(right)))),
+            None if spec.keep_unmatched => joined.push(project(left, None)),
+            None => {}
+        }
+    }
+    
+    Ok((headers, joined))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+    
+    #[test]
+    fn test_join_tables() {
+        let args = strings(&["prog", "dump.sql", "--join", "orders.user_id=users.id", "--select", "orders.*,users.email"]);
+        let spec = parse_join(&args).unwrap().unwrap();
+        
+        let order_headers = strings(&["id", "user_id"]);
+        let orders = vec![strings(&["10", "1"]), strings(&["11", "2"]), strings(&["12", "9"])];
+        let user_headers = strings(&["id", "email"]);
+        let users = vec![strings(&["1", "a@example.com"]), strings(&["2", "b@example.com"])];
+        
+        let (headers, rows) = join_tables(&spec, &order_headers, &orders, &user_headers, &users).unwrap();
+        assert_eq!(headers, vec!["id", "user_id", "email"]);
+        assert_eq!(rows, vec![
+            strings(&["10", "1", "a@example.com"]),
+            strings(&["11", "2", "b@example.com"]),
+        ]);
+        
+        let left_spec = JoinSpec {
+            select: vec![("orders".to_string(), "*".to_string()), ("users".to_string(), "*".to_string())],
+            keep_unmatched: true,
+            ..spec
+        };
+        let (headers, rows) = join_tables(&left_spec, &order_headers, &orders, &user_headers, &users).unwrap();
+        assert_eq!(headers, vec!["orders.id", "user_id", "users.id", "email"]);
+        assert_eq!(rows[2], strings(&["12", "9", "", ""]));
+    }
+}
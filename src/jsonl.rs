@@ -0,0 +1,211 @@
+//! JSON Lines output: the body of `--post-format jsonl` and `--format jsonl`.
+//!
+//! `--format jsonl` writes `<table>.jsonl` straight under its final name
+//! instead of through a temporary file, so a process tailing it sees rows
+//! while the conversion runs. `--flush-every N` flushes the file every N rows
+//! and `--append` adds to files left by an earlier run instead of replacing
+//! them. An interrupted run leaves the rows written so far.
+
+use anyhow::{Context, Result};
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+use crate::args::{flag_value, has_flag};
+use crate::csv_writer::convert_headers;
+use crate::sink::{OutputSink, TableSink};
+use crate::types::{CsvOptions, OutputFormat, Table};
+
+/// Parse --flush-every and --append, which only apply to --format jsonl
+pub fn parse_jsonl_format(args: &[String]) -> Result<OutputFormat> {
+    let flush_every = flag_value(args, "--flush-every")?
+        .map(|rows| rows.parse::<usize>()
+            .ok()
+            .filter(|&n| n > 0)
+            .ok_or_else(|| anyhow::anyhow!("Error: Invalid --flush-every '{}'. Use a positive number of rows", rows)))
+        .transpose()?;
+    Ok(OutputFormat::Jsonl { flush_every, append: has_flag(args, "--append") })
+}
+
+/// Fail when --flush-every or --append is given with another format
+pub fn check_jsonl_flags(args: &[String]) -> Result<()> {
+    for flag in ["--flush-every", "--append"] {
+        if has_flag(args, flag) {
+            anyhow::bail!("Error: {} requires --format jsonl", flag);
+        }
+    }
+    Ok(())
+}
+
+/// JSON keys of the output columns, after header case conversion
+fn json_keys(headers: &[String], options: &CsvOptions) -> Result<Vec<String>> {
+    let headers = convert_headers(headers, options.header_case)?;
+    Ok(headers.iter()
+        .map(serde_json::to_string)
+        .collect::<Result<_, _>>()?)
+}
+
+/// One row as a JSON object followed by a newline
+fn json_line(keys: &[String], row: &[String]) -> Result<String> {
+    let mut line = String::from("{");
+    for (i, (key, value)) in keys.iter().zip(row).enumerate() {
+        if i > 0 {
+            line.push(',');
+        }
+        line.push_str(key);
+        line.push(':');
+        if value == "NULL" {
+            line.push_str("null");
+        } else {
+            line.push_str(&serde_json::to_string(value)?);
+        }
+    }
+    line.push_str("}\n");
+    Ok(line)
+}
+
+/// Write rows as JSON Lines, one object per row with keys in column order.
+/// Unquoted SQL NULLs become JSON `null`; every other value is a string.
+pub fn write_jsonl_to<W: Write>(
+    mut destination: W,
+    headers: &[String],
+    rows: &[Vec<String>],
+    options: &CsvOptions,
+) -> Result<()> {
+    let keys = json_keys(headers, options)?;
+    
+    for row in rows {
+        destination.write_all(json_line(&keys, row)?.as_bytes())
+            .context("Failed to write JSON line")?;
+    }
+    
+    destination.flush()
+        .context("Failed to flush JSON Lines writer")?;
+    
+    Ok(())
+}
+
+/// Writes every table to `<name>.jsonl` in a directory (`--format jsonl`)
+pub struct JsonlSink {
+    pub dir: PathBuf,
+    pub options: CsvOptions,
+    /// Rows between flushes; without it the file is written as the buffer fills
+    pub flush_every: Option<usize>,
+    /// Add to existing files instead of replacing them
+    pub append: bool,
+}
+
+impl OutputSink for JsonlSink {
+    fn open_table<'a>(&'a self, _table: &Table, name: &str, headers: &[String]) -> Result<Box<dyn TableSink + 'a>> {
+        let path = self.dir.join(format!("{}.jsonl", name));
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(self.append)
+            .truncate(!self.append)
+            .open(&path)
+            .with_context(|| format!("Failed to create JSON Lines file '{}'", path.display()))?;
+        let writer = match self.options.buffer_size {
+            Some(size) => BufWriter::with_capacity(size, file),
+            None => BufWriter::new(file),
+        };
+        Ok(Box::new(JsonlTableSink {
+            writer,
+            keys: json_keys(headers, &self.options)?,
+            flush_every: self.flush_every,
+            unflushed: 0,
+            written: 0,
+            fsync: self.options.fsync,
+            location: path.to_string_lossy().to_string(),
+        }))
+    }
+}
+
+/// Appends the rows of one table to its JSON Lines file
+struct JsonlTableSink {
+    writer: BufWriter<File>,
+    keys: Vec<String>,
+    flush_every: Option<usize>,
+    /// Rows written since the last flush
+    unflushed: usize,
+    /// Bytes written by this run
+    written: u64,
+    fsync: bool,
+    location: String,
+}
+
+impl TableSink for JsonlTableSink {
+    fn write_row(&mut self, row: &[String]) -> Result<()> {
+        let line = json_line(&self.keys, row)?;
+        self.writer.write_all(line.as_bytes())
+            .context("Failed to write JSON line")?;
+        self.written += line.len() as u64;
+        self.unflushed += 1;
+        if self.flush_every.is_some_and(|rows| self.unflushed >= rows) {
+            self.writer.flush().context("Failed to flush JSON Lines file")?;
+            self.unflushed = 0;
+        }
+        Ok(())
+    }
+    
+    fn finish(self: Box<Self>) -> Result<String> {
+        Ok(self.finish_with_size()?.0)
+    }
+    
+    fn finish_with_size(self: Box<Self>) -> Result<(String, Option<u64>)> {
+        let file = self.writer.into_inner()
+            .map_err(|e| anyhow::anyhow!("Failed to flush JSON Lines file: {}", e.error()))?;
+        if self.fsync {
+            file.sync_all().context("Failed to sync JSON Lines file to disk")?;
+        }
+        Ok((self.location, Some(self.written)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_write_jsonl_to() {
+        let headers = vec!["id".to_string(), "note".to_string()];
+        let rows = vec![
+            vec!["1".to_string(), "say \"hi\"".to_string()],
+            vec!["2".to_string(), "NULL".to_string()],
+        ];
+        
+        let mut output = Vec::new();
+        write_jsonl_to(&mut output, &headers, &rows, &CsvOptions::default()).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "\
+{\"id\":\"1\",\"note\":\"say \\\"hi\\\"\"}
+{\"id\":\"2\",\"note\":null}
+");
+    }
+    
+    #[test]
+    fn test_jsonl_sink_flushes_and_appends() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let path = dir.join("users.jsonl");
+        let headers = vec!["id".to_string()];
+        let table = Table { name: "users".to_string(), ..Default::default() };
+        let sink = |append| JsonlSink { dir: dir.to_path_buf(), options: CsvOptions::default(), flush_every: Some(2), append };
+        
+        let sink_run = sink(false);
+        let mut table_sink = sink_run.open_table(&table, "users", &headers).unwrap();
+        table_sink.write_row(&["1".to_string()]).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "");
+        table_sink.write_row(&["2".to_string()]).unwrap();
+        // Readable before the table is finished
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "{\"id\":\"1\"}\n{\"id\":\"2\"}\n");
+        table_sink.finish().unwrap();
+        
+        let sink_run = sink(true);
+        let mut table_sink = sink_run.open_table(&table, "users", &headers).unwrap();
+        table_sink.write_row(&["3".to_string()]).unwrap();
+        assert_eq!(table_sink.finish_with_size().unwrap().1, Some(11));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "{\"id\":\"1\"}\n{\"id\":\"2\"}\n{\"id\":\"3\"}\n");
+        
+        assert!(parse_jsonl_format(&["t".to_string(), "--flush-every".to_string(), "0".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,91 @@
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::fs;
+
+use crate::args::flag_values;
+use crate::types::KeyFilter;
+
+/// Parse --keys options from command line, e.g. `--keys users:id=ids.txt`
+/// (repeatable), and read the key files
+pub fn parse_key_filters(args: &[String]) -> Result<Vec<KeyFilter>> {
+    flag_values(args, "--keys")?
+        .into_iter()
+        .map(parse_key_filter)
+        .collect()
+}
+
+/// Parse a single `<table>:<column>=<file>` specification and read the file
+pub fn parse_key_filter(spec: &str) -> Result<KeyFilter> {
+    let (table, column, path) = spec.split_once(':')
+        .and_then(|(table, rest)| rest.split_once('=').map(|(column, path)| (table, column, path)))
+        .filter(|(table, column, path)| !table.is_empty() && !column.is_empty() && !path.is_empty())
+        .ok_or_else(|| anyhow::anyhow!("Error: Invalid --keys '{}'. Use <table>:<column>=<file>, e.g. users:id=ids.txt", spec))?;
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read key file '{}'", path))?;
+    
+    Ok(KeyFilter {
+        table: table.to_string(),
+        column: column.to_string(),
+        keys: parse_keys(&text),
+    })
+}
+
+/// Key values, one per line. Surrounding whitespace, blank lines and lines
+/// starting with `#` are ignored.
+pub fn parse_keys(text: &str) -> HashSet<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Keep the rows of a table whose key column holds one of the listed values.
+/// A table with several key filters keeps the rows matching all of them.
+pub fn apply_key_filters(
+    table: &str,
+    headers: &[String],
+    rows: Vec<Vec<String>>,
+    filters: &[KeyFilter],
+) -> Result<Vec<Vec<String>>> {
+    let mut rows = rows;
+    
+    for filter in filters.iter().filter(|filter| filter.table == table) {
+        let index = headers.iter().position(|h| h == &filter.column)
+            .ok_or_else(|| anyhow::anyhow!("Error: Column '{}' not found in table '{}' for --keys", filter.column, table))?;
+        rows.retain(|row| row.get(index).is_some_and(|value| filter.keys.contains(value)));
+    }
+    
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_apply_key_filters() {
+        let headers = vec!["id".to_string(), "name".to_string()];
+        let rows: Vec<Vec<String>> = [("1", "Ann"), ("2", "Bob"), ("3", "Cy")].iter()
+            .map(|(id, name)| vec![id.to_string(), name.to_string()])
+            .collect();
+        let filters = vec![KeyFilter {
+            table: "users".to_string(),
+            column: "id".to_string(),
+            keys: parse_keys("# support ticket 42\n 1\n\n3 \n"),
+        }];
+        
+        let kept = apply_key_filters("users", &headers, rows.clone(), &filters).unwrap();
+        assert_eq!(kept, vec![rows[0].clone(), rows[2].clone()]);
+        
+        assert_eq!(apply_key_filters("orders", &headers, rows.clone(), &filters).unwrap(), rows);
+        assert!(apply_key_filters("users", &["uid".to_string()], rows, &filters).is_err());
+    }
+    
+    #[test]
+    fn test_parse_key_filter_spec() {
+        assert!(parse_key_filter("users:id").is_err());
+        assert!(parse_key_filter("users=ids.txt").is_err());
+        assert!(parse_key_filter("users:id=/nonexistent/ids.txt").is_err());
+    }
+}
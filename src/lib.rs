@@ -1,11 +1,146 @@
+pub mod args;
+pub mod env_config;
 pub mod types;
+pub mod value;
+pub mod input;
+pub mod archive;
 pub mod parser;
+pub mod statements;
+pub mod databases;
 pub mod csv_writer;
+pub mod locale;
+pub mod template;
+pub mod sink;
+pub mod output_file;
+pub mod writer_pool;
+pub mod limits;
+pub mod checksum;
+pub mod metadata;
+pub mod rfc4180;
+pub mod encrypt;
+pub mod spill;
+pub mod workspace;
+pub mod interrupt;
+pub mod jsonl;
 pub mod date_filter;
+pub mod mismatch;
+pub mod field_size;
+pub mod timezone;
+pub mod date_format;
+pub mod redact;
+pub mod trim;
+pub mod mask;
+pub mod policy;
+pub mod column_files;
+pub mod table_errors;
+pub mod derived;
+pub mod join;
+pub mod merge;
+pub mod cascade;
+pub mod watermark;
+pub mod keys;
+pub mod aggregate;
+pub mod query;
+pub mod filters;
+pub mod preview;
+pub mod infer;
+pub mod profile;
+pub mod sql_expr;
+pub mod pg_types;
+pub mod newlines;
+pub mod diff;
+pub mod load;
+pub mod ddl;
+pub mod schema;
+pub mod load_order;
+pub mod generate;
+pub mod target_schema;
+pub mod verify;
+pub mod report;
+pub mod warnings;
+pub mod cancel;
+pub mod convert;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "avro")]
+pub mod avro;
+#[cfg(feature = "duckdb")]
+pub mod duckdb;
+#[cfg(feature = "async")]
+pub mod async_convert;
+#[cfg(feature = "object-store")]
+pub mod cloud;
+#[cfg(feature = "http")]
+pub mod http_sink;
+#[cfg(feature = "tui")]
+pub mod tui;
+#[cfg(feature = "completions")]
+pub mod cli;
 
 // Re-export commonly used items
-pub use types::{Table, DateFilter};
-pub use parser::{parse_sql_file, parse_table_columns, extract_insert_values};
-pub use csv_writer::write_csv;
-pub use date_filter::{parse_date_filter, apply_date_filter};
+pub use types::{Table, Column, Index, ForeignKey, InsertStatement, DateFilter, HeaderCase, FileNaming, CsvOptions, CsvLocale, OutputFormat, InputOptions, MismatchPolicy, ExpressionPolicy, PgTypeFormat, NewlinePolicy, TrimCells, ValueOptions, FieldSizeLimit, OversizedFieldPolicy, TzConversion, KeyFilter, DateReformat, DerivedColumn, Expression, JoinSpec, Aggregate, NumberedRow};
+pub use archive::{archive_members, is_archive, member_namespace, ArchiveMember};
+pub use input::{read_sql_file, read_sql, open_sql_source, SqlSource, decode_sql_bytes, decode_sql_vec, parse_input_encoding, parse_lossy_utf8};
+pub use statements::{split_statements, split_statements_as, statement_kind, DumpDialect, Statement, StatementKind, Statements};
+pub use parser::{parse_sql_file, parse_sql, parse_sql_content, parse_table_columns, parse_table_definition, extract_insert_values, extract_insert_values_with_options, extract_table_rows, extract_table_rows_with_options, extract_insert_values_with_lines, extract_table_rows_with_lines, table_statements, find_skipped_inserts, parse_insert_statement, parse_insert_statement_with_options, parse_create_table, extract_insert_typed_values, for_each_table_row, for_each_table_row_with_lines};
+pub use value::{ParsedValue, SqlValue};
+pub use csv_writer::{write_csv, write_csv_with_options, write_csv_to, CsvSink, CsvTableSink, parse_header_case, parse_file_naming, parse_safe_excel, escape_formula, csv_filenames, convert_headers};
+pub use locale::{parse_csv_locale, csv_locale, is_numeric_type, decimal_comma};
+pub use output_file::{parse_buffer_size, parse_fsync, remove_stale_temp_files, output_path, OutputFile};
+pub use encrypt::{parse_encryption, parse_recipient, ENCRYPTED_EXTENSION};
+pub use limits::{parse_output_limits, LimitPolicy, LimitedSink, OutputLimits};
+pub use checksum::{parse_checksum, file_digest, write_checksum_file, ChecksumAlgorithm, ChecksumSink};
+pub use rfc4180::{parse_rfc4180_strict, validate_rfc4180_file, Rfc4180Sink, Rfc4180Validator, RFC4180_LOCALE};
+pub use metadata::{parse_metadata, metadata_path, MetadataSink, TableMetadata, ColumnMetadata};
+pub use spill::{parse_max_memory, write_buffered_table, MemoryBudget, RowBuffer, SPILL_BATCH_ROWS};
+pub use env_config::{args_with_env, env_var_name};
+pub use workspace::{parse_temp_dir, remove_temp_files, temp_file_path, TempWorkspace};
+pub use interrupt::{cancel_on_interrupt, exit_interrupted, handle_interrupts, sync_to_disk, INTERRUPTED_EXIT_CODE};
+pub use writer_pool::{configure_threads, parse_threads, parse_writer_pool_options, with_writer_pool, TableWriter, WriteJob, WriteResult, WriterPoolOptions};
+pub use jsonl::{write_jsonl_to, parse_jsonl_format, check_jsonl_flags, JsonlSink};
+pub use template::{parse_output_format, write_template, write_template_to, TemplateSink};
+pub use sink::{parse_output_sink, write_table, OutputSink, TableSink, MemorySink};
+pub use date_filter::{parse_date_filter, apply_date_filter, apply_date_filter_with_warnings, retain_dated_batch, EpochDetection};
+pub use mismatch::{parse_mismatch_policy, apply_mismatch_policy, apply_mismatch_policy_with_lines, apply_mismatch_policy_to_batch};
+pub use timezone::{parse_tz_conversions, parse_tz_conversion, convert_timezone, apply_tz_conversions};
+pub use date_format::{parse_date_reformats, parse_date_reformat, apply_date_reformats};
+pub use mask::{parse_masking, parse_column_mask, parse_mask_strategy, mask_value, apply_masking, ColumnMask, FakeKind, MaskStrategy, Masking};
+pub use policy::{parse_policy, load_policy, parse_policy_str, apply_policy, ColumnRule, Policy, TablePolicy, UnreviewedAction};
+pub use column_files::{parse_file_columns, extract_column_files, FileColumn, FileColumns};
+pub use databases::{parse_databases, select_databases};
+pub use table_errors::{parse_table_error_policy, quarantine_table, TableErrorPolicy};
+pub use redact::{parse_redaction, parse_redact_rule, redact_value, apply_redaction, RedactRule, Redaction};
+pub use trim::{parse_trim_cells, parse_trim_mode, trim_cell, apply_trim_cells};
+pub use field_size::{parse_field_size_limit, apply_field_size_limit, TRUNCATION_MARKER};
+pub use derived::{parse_derived_columns, parse_derived_column, parse_source_columns, find_dump_timestamp, parse_expression, apply_derived_columns, apply_derived_columns_at};
+pub use join::{parse_join, join_tables};
+pub use merge::{merge_schemas, pad_shard_row, MergedTable};
+pub use cascade::{cascade_filter, Restriction};
+pub use watermark::{parse_incremental, parse_watermark, load_state, save_state, compare_values, apply_watermark, largest_watermark, Incremental, TableWatermark, Watermark, WatermarkState};
+pub use keys::{parse_key_filters, parse_key_filter, parse_keys, apply_key_filters};
+pub use aggregate::{parse_aggregates, aggregate_rows};
+pub use query::{execute_query, filter_rows, RowSet};
+pub use filters::{parse_filter_config, load_filter_config, parse_filter_config_str, apply_filters, apply_filters_to_batch, FilterConfig, FilterProgress, FilterSpec};
+pub use sql_expr::{parse_expression_policy, evaluate_expression};
+pub use pg_types::{parse_pg_type_format, apply_pg_type_format};
+pub use newlines::{parse_newline_policy, normalize_newlines};
+pub use infer::{parse_infer_types, infer_column_types, apply_type_inference, InferredType};
+pub use profile::{parse_max_rows, profile_table, render_profile, ColumnProfile, TableProfile, ValueCount};
+pub use preview::{parse_preview_rows, render_text_table, DEFAULT_PREVIEW_ROWS};
+pub use diff::{parse_diff_keys, diff_rows, TableDiff, CHANGED_COLUMNS_HEADER};
+pub use schema::write_schema_json;
+pub use load_order::{load_order, write_load_order, numbered_filenames, LoadOrder};
+pub use generate::{parse_generate_options, generated_columns, write_generated_dump, DataGenerator, GenerateOptions};
+pub use target_schema::{parse_target_schema, load_target_schema, find_target, map_columns, apply_column_mapping, ColumnMapping};
+pub use load::{parse_load_target, connect, create_table_sql, column_type_sql, load_table, Dialect, LoadTarget};
+pub use ddl::{parse_emit_ddl, written_table, write_ddl, DdlTarget};
+pub use verify::{parse_row_count_hints, verify_row_counts, RowCountDiscrepancy};
+pub use cancel::CancellationToken;
+pub use report::{ConversionReport, TableReport, FailedTable, SkippedStatement, TableTimings, RunTimings, WrittenFile};
+pub use warnings::{WarningHandler, StderrWarnings, CollectedWarnings};
+pub use convert::{convert_sql_to_csv, convert_sql_to_csv_with_options, Converter};
 
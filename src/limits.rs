@@ -0,0 +1,250 @@
+//! Row and size limits on the output, so an unexpectedly large table cannot
+//! fill the destination.
+
+use anyhow::Result;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+use crate::args::flag_value;
+use crate::sink::{OutputSink, TableSink};
+use crate::types::Table;
+
+/// What happens when a limit is reached
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LimitPolicy {
+    /// Fail the table being written and every table after it
+    #[default]
+    Abort,
+    /// Keep the rows written so far and skip the rest of the table; once a
+    /// global limit is reached, later tables get only their header
+    StopTable,
+}
+
+/// Limits on what a run writes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OutputLimits {
+    /// Rows over all tables
+    pub max_total_rows: Option<u64>,
+    /// Rows of a single table
+    pub max_table_rows: Option<u64>,
+    /// Bytes over all tables, counted as the size of the values plus one
+    /// separator per value (quoting is not included)
+    pub max_output_bytes: Option<u64>,
+    pub policy: LimitPolicy,
+}
+
+/// Parse --max-total-rows, --max-table-rows, --max-output-bytes and --on-limit
+/// options from command line. Returns `None` if no limit is set.
+pub fn parse_output_limits(args: &[String]) -> Result<Option<OutputLimits>> {
+    let limit = |flag: &str| -> Result<Option<u64>> {
+        flag_value(args, flag)?
+            .map(|value| value.parse::<u64>()
+                .ok()
+                .filter(|&n| n > 0)
+                .ok_or_else(|| anyhow::anyhow!("Error: Invalid {} '{}'. Use a positive number", flag, value)))
+            .transpose()
+    };
+    let policy = match flag_value(args, "--on-limit")? {
+        None | Some("abort") => LimitPolicy::Abort,
+        Some("stop-table") => LimitPolicy::StopTable,
+        Some(other) => anyhow::bail!("Error: Invalid --on-limit '{}'. Use one of: abort, stop-table", other),
+    };
+    
+    let limits = OutputLimits {
+        max_total_rows: limit("--max-total-rows")?,
+        max_table_rows: limit("--max-table-rows")?,
+        max_output_bytes: limit("--max-output-bytes")?,
+        policy,
+    };
+    if limits.max_total_rows.is_none() && limits.max_table_rows.is_none() && limits.max_output_bytes.is_none() {
+        if flag_value(args, "--on-limit")?.is_some() {
+            anyhow::bail!("Error: --on-limit requires --max-total-rows, --max-table-rows or --max-output-bytes");
+        }
+        return Ok(None);
+    }
+    
+    Ok(Some(limits))
+}
+
+/// Wraps a sink and enforces output limits on everything written through it
+pub struct LimitedSink {
+    inner: Box<dyn OutputSink>,
+    limits: OutputLimits,
+    rows: AtomicU64,
+    bytes: AtomicU64,
+    /// A global limit was reached
+    exhausted: AtomicBool,
+}
+
+impl LimitedSink {
+    pub fn new(inner: Box<dyn OutputSink>, limits: OutputLimits) -> Self {
+        LimitedSink {
+            inner,
+            limits,
+            rows: AtomicU64::new(0),
+            bytes: AtomicU64::new(0),
+            exhausted: AtomicBool::new(false),
+        }
+    }
+    
+    /// Rows and bytes written so far over all tables
+    pub fn totals(&self) -> (u64, u64) {
+        (self.rows.load(Ordering::SeqCst), self.bytes.load(Ordering::SeqCst))
+    }
+    
+    /// Count a row against the global limits. Returns the name of the option
+    /// whose limit it would exceed, without counting it.
+    fn reserve(&self, row_bytes: u64) -> Option<&'static str> {
+        if self.exhausted.load(Ordering::SeqCst) {
+            return Some(self.exhausted_limit());
+        }
+        let rows = self.rows.fetch_add(1, Ordering::SeqCst) + 1;
+        let bytes = self.bytes.fetch_add(row_bytes, Ordering::SeqCst) + row_bytes;
+        
+        let exceeded = if self.limits.max_total_rows.is_some_and(|max| rows > max) {
+            Some("--max-total-rows")
+        } else if self.limits.max_output_bytes.is_some_and(|max| bytes > max) {
+            Some("--max-output-bytes")
+        } else {
+            None
+        };
+        if exceeded.is_some() {
+            self.rows.fetch_sub(1, Ordering::SeqCst);
+            self.bytes.fetch_sub(row_bytes, Ordering::SeqCst);
+            self.exhausted.store(true, Ordering::SeqCst);
+        }
+        exceeded
+    }
+    
+    fn exhausted_limit(&self) -> &'static str {
+        let (rows, _) = self.totals();
+        if self.limits.max_total_rows.is_some_and(|max| rows >= max) {
+            "--max-total-rows"
+        } else {
+            "--max-output-bytes"
+        }
+    }
+    
+    fn limit_value(&self, option: &str) -> u64 {
+        match option {
+            "--max-total-rows" => self.limits.max_total_rows,
+            "--max-table-rows" => self.limits.max_table_rows,
+            _ => self.limits.max_output_bytes,
+        }.unwrap_or_default()
+    }
+}
+
+impl OutputSink for LimitedSink {
+    fn open_table<'a>(&'a self, table: &Table, name: &str, headers: &[String]) -> Result<Box<dyn TableSink + 'a>> {
+        if self.limits.policy == LimitPolicy::Abort && self.exhausted.load(Ordering::SeqCst) {
+            let option = self.exhausted_limit();
+            anyhow::bail!("Error: Output limit {} {} reached, table '{}' not written", option, self.limit_value(option), table.name);
+        }
+        
+        Ok(Box::new(LimitedTableSink {
+            inner: self.inner.open_table(table, name, headers)?,
+            sink: self,
+            table: table.name.clone(),
+            rows: 0,
+            stopped: false,
+        }))
+    }
+}
+
+struct LimitedTableSink<'a> {
+    inner: Box<dyn TableSink + 'a>,
+    sink: &'a LimitedSink,
+    table: String,
+    rows: u64,
+    stopped: bool,
+}
+
+impl TableSink for LimitedTableSink<'_> {
+    fn write_row(&mut self, row: &[String]) -> Result<()> {
+        if self.stopped {
+            return Ok(());
+        }
+        
+        let exceeded = if self.sink.limits.max_table_rows.is_some_and(|max| self.rows >= max) {
+            Some("--max-table-rows")
+        } else {
+            let row_bytes = row.iter().map(|cell| cell.len() as u64 + 1).sum();
+            self.sink.reserve(row_bytes)
+        };
+        
+        match (exceeded, self.sink.limits.policy) {
+            (None, _) => {
+                self.rows += 1;
+                self.inner.write_row(row)
+            }
+            (Some(option), LimitPolicy::Abort) => anyhow::bail!(
+                "Error: Output limit {} {} exceeded while writing table '{}' (use --on-limit stop-table to keep partial tables)",
+                option, self.sink.limit_value(option), self.table
+            ),
+            (Some(option), LimitPolicy::StopTable) => {
+                eprintln!(
+                    "Warning: Stopped table '{}' after {} rows: {} {} reached",
+                    self.table, self.rows, option, self.sink.limit_value(option)
+                );
+                self.stopped = true;
+                Ok(())
+            }
+        }
+    }
+    
+    fn finish(self: Box<Self>) -> Result<String> {
+        self.inner.finish()
+    }
+    
+    fn finish_with_size(self: Box<Self>) -> Result<(String, Option<u64>)> {
+        self.inner.finish_with_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sink::{write_table, MemorySink};
+    
+    fn table(name: &str) -> Table {
+        Table { name: name.to_string(), columns: vec!["id".to_string()], ..Default::default() }
+    }
+    
+    fn rows(count: usize) -> Vec<Vec<String>> {
+        (0..count).map(|i| vec![i.to_string()]).collect()
+    }
+    
+    #[test]
+    fn test_stop_table_keeps_rows_within_limits() {
+        let limits = OutputLimits { max_table_rows: Some(3), max_total_rows: Some(5), policy: LimitPolicy::StopTable, ..Default::default() };
+        let sink = LimitedSink::new(Box::new(MemorySink::default()), limits);
+        let headers = vec!["id".to_string()];
+        
+        write_table(&sink, &table("a"), "a", &headers, &rows(10)).unwrap();
+        write_table(&sink, &table("b"), "b", &headers, &rows(10)).unwrap();
+        write_table(&sink, &table("c"), "c", &headers, &rows(10)).unwrap();
+        assert_eq!(sink.totals().0, 5);
+    }
+    
+    #[test]
+    fn test_abort_fails_the_table_and_later_tables() {
+        let limits = OutputLimits { max_output_bytes: Some(10), ..Default::default() };
+        let sink = LimitedSink::new(Box::new(MemorySink::default()), limits);
+        let headers = vec!["id".to_string()];
+        
+        let error = write_table(&sink, &table("a"), "a", &headers, &rows(10)).unwrap_err();
+        assert!(error.to_string().contains("--max-output-bytes 10"));
+        assert!(write_table(&sink, &table("b"), "b", &headers, &rows(1)).is_err());
+    }
+    
+    #[test]
+    fn test_parse_output_limits() {
+        let args: Vec<String> = ["--max-total-rows", "1000", "--on-limit", "stop-table"].iter().map(|s| s.to_string()).collect();
+        let limits = parse_output_limits(&args).unwrap().unwrap();
+        assert_eq!(limits.max_total_rows, Some(1000));
+        assert_eq!(limits.policy, LimitPolicy::StopTable);
+        
+        assert_eq!(parse_output_limits(&[]).unwrap(), None);
+        let args: Vec<String> = ["--on-limit", "abort"].iter().map(|s| s.to_string()).collect();
+        assert!(parse_output_limits(&args).is_err());
+    }
+}
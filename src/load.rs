@@ -0,0 +1,417 @@
+use anyhow::{Context, Result};
+
+use crate::args::flag_value;
+use crate::csv_writer::write_csv_to;
+use crate::types::{CsvOptions, Table};
+
+/// SQL dialect of a load target or of emitted DDL (`--emit-ddl`), used to
+/// quote identifiers and map column types
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dialect {
+    Postgres,
+    MySql,
+    Snowflake,
+    BigQuery,
+    DuckDb,
+}
+
+impl Dialect {
+    /// Parse a dialect name such as `snowflake`
+    pub fn from_name(name: &str) -> Option<Dialect> {
+        match name.to_ascii_lowercase().as_str() {
+            "postgres" | "postgresql" => Some(Dialect::Postgres),
+            "mysql" => Some(Dialect::MySql),
+            "snowflake" => Some(Dialect::Snowflake),
+            "bigquery" => Some(Dialect::BigQuery),
+            "duckdb" => Some(Dialect::DuckDb),
+            _ => None,
+        }
+    }
+    
+    /// Lowercase name, e.g. `bigquery`
+    pub fn name(self) -> &'static str {
+        match self {
+            Dialect::Postgres => "postgres",
+            Dialect::MySql => "mysql",
+            Dialect::Snowflake => "snowflake",
+            Dialect::BigQuery => "bigquery",
+            Dialect::DuckDb => "duckdb",
+        }
+    }
+    
+    /// Type of columns declared without one
+    fn text_type(self) -> &'static str {
+        match self {
+            Dialect::BigQuery => "STRING",
+            Dialect::Snowflake | Dialect::DuckDb => "VARCHAR",
+            Dialect::Postgres | Dialect::MySql => "TEXT",
+        }
+    }
+}
+
+/// A connection to a database that tables can be created and bulk-loaded in
+pub trait LoadTarget {
+    fn dialect(&self) -> Dialect;
+    
+    /// Run a statement that returns no rows, such as CREATE TABLE
+    fn execute(&mut self, sql: &str) -> Result<()>;
+    
+    /// Bulk-load CSV data (with a header row) into an existing table.
+    /// Returns the number of loaded rows.
+    fn load_csv(&mut self, table: &Table, csv: Vec<u8>) -> Result<u64>;
+}
+
+/// Parse --target option from command line, e.g. `--target postgres://user@localhost/db`
+pub fn parse_load_target(args: &[String]) -> Result<&str> {
+    flag_value(args, "--target")?
+        .ok_or_else(|| anyhow::anyhow!("Error: load requires --target <postgres://...|mysql://...>"))
+}
+
+/// Connect to the database at the given URL. Each driver is behind its own feature.
+pub fn connect(url: &str) -> Result<Box<dyn LoadTarget>> {
+    let scheme = url.split_once("://").map(|(scheme, _)| scheme).unwrap_or("");
+    
+    match scheme {
+        #[cfg(feature = "postgres")]
+        "postgres" | "postgresql" => {
+            let client = postgres::Client::connect(url, postgres::NoTls)
+                .context("Failed to connect to PostgreSQL")?;
+            Ok(Box::new(client))
+        }
+        #[cfg(feature = "mysql")]
+        "mysql" => {
+            let opts = mysql::Opts::from_url(url).context("Invalid MySQL URL")?;
+            let conn = mysql::Conn::new(opts).context("Failed to connect to MySQL")?;
+            Ok(Box::new(conn))
+        }
+        #[cfg(not(feature = "postgres"))]
+        "postgres" | "postgresql" => anyhow::bail!("Error: Loading into PostgreSQL requires building with --features postgres"),
+        #[cfg(not(feature = "mysql"))]
+        "mysql" => anyhow::bail!("Error: Loading into MySQL requires building with --features mysql"),
+        _ => anyhow::bail!("Error: Unsupported --target '{}'. Use a postgres:// or mysql:// URL", url),
+    }
+}
+
+/// Build a CREATE TABLE statement for the target from a parsed table schema.
+/// Columns without a declared type are created as text.
+pub fn create_table_sql(table: &Table, dialect: Dialect) -> String {
+    let mut definitions: Vec<String> = table.columns.iter()
+        .enumerate()
+        .map(|(i, column)| {
+            let column_type = column_type_sql(table.column_types.get(i).map_or("", String::as_str), dialect);
+            format!("{} {}", quote_identifier(column, dialect), column_type)
+        })
+        .collect();
+    if !table.primary_key.is_empty() {
+        let columns: Vec<String> = table.primary_key.iter()
+            .map(|column| quote_identifier(column, dialect))
+            .collect();
+        // BigQuery only accepts keys it does not enforce
+        let enforcement = if dialect == Dialect::BigQuery { " NOT ENFORCED" } else { "" };
+        definitions.push(format!("PRIMARY KEY ({}){}", columns.join(", "), enforcement));
+    }
+    
+    format!(
+        "CREATE TABLE IF NOT EXISTS {} (\n  {}\n)",
+        quote_identifier(&table.name, dialect),
+        definitions.join(",\n  ")
+    )
+}
+
+/// Type of a column in the target dialect. Columns without a declared type are text.
+pub fn column_type_sql(column_type: &str, dialect: Dialect) -> String {
+    if column_type.is_empty() {
+        dialect.text_type().to_string()
+    } else {
+        target_type(column_type, dialect)
+    }
+}
+
+/// Adapt a declared column type to the target dialect where the spelling differs
+fn target_type(column_type: &str, dialect: Dialect) -> String {
+    let upper = column_type.to_uppercase();
+    match dialect {
+        // MySQL display widths and UNSIGNED are not valid in PostgreSQL
+        Dialect::Postgres => {
+            let base = upper.split(['(', ' ']).next().unwrap_or("");
+            match base {
+                "TINYINT" | "SMALLINT" | "MEDIUMINT" => "SMALLINT".to_string(),
+                "INT" | "INTEGER" if upper.contains("UNSIGNED") => "BIGINT".to_string(),
+                "INT" | "INTEGER" => "INTEGER".to_string(),
+                "BIGINT" => "BIGINT".to_string(),
+                "DOUBLE" | "FLOAT" => "DOUBLE PRECISION".to_string(),
+                "DATETIME" => "TIMESTAMP".to_string(),
+                "LONGTEXT" | "MEDIUMTEXT" | "TINYTEXT" => "TEXT".to_string(),
+                "BLOB" | "LONGBLOB" | "MEDIUMBLOB" | "TINYBLOB" => "BYTEA".to_string(),
+                _ => column_type.to_string(),
+            }
+        }
+        Dialect::MySql => match upper.as_str() {
+            "SERIAL" => "BIGINT UNSIGNED".to_string(),
+            "BOOLEAN" | "BOOL" => "TINYINT(1)".to_string(),
+            "DOUBLE PRECISION" => "DOUBLE".to_string(),
+            "BYTEA" => "LONGBLOB".to_string(),
+            _ if upper.starts_with("TIMESTAMP WITH") => "TIMESTAMP".to_string(),
+            _ if upper.starts_with("CHARACTER VARYING") => upper.replacen("CHARACTER VARYING", "VARCHAR", 1),
+            _ => column_type.to_string(),
+        },
+        Dialect::Snowflake | Dialect::BigQuery | Dialect::DuckDb => warehouse_type(column_kind(column_type), dialect),
+    }
+}
+
+/// What a declared column type holds, however the dump's dialect spells it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ColumnKind {
+    Boolean,
+    Integer,
+    /// `BIGINT UNSIGNED`, whose values can exceed a signed 64-bit integer
+    UnsignedBigInt,
+    Float,
+    /// `DECIMAL(p,s)` with its precision and scale, if given
+    Decimal(Option<(u32, u32)>),
+    Date,
+    Timestamp,
+    TimestampTz,
+    Time,
+    Json,
+    /// Everything else, including binary columns, which are written as `0x` hex text
+    Text,
+}
+
+/// Classify a declared column type
+pub(crate) fn column_kind(column_type: &str) -> ColumnKind {
+    let upper = column_type.to_uppercase();
+    let base = upper.split(['(', ' ']).next().unwrap_or("");
+    match base {
+        "BOOL" | "BOOLEAN" => ColumnKind::Boolean,
+        "BIGINT" | "INT8" if upper.contains("UNSIGNED") => ColumnKind::UnsignedBigInt,
+        "TINYINT" | "SMALLINT" | "MEDIUMINT" | "INT" | "INTEGER" | "BIGINT" | "INT2" | "INT4" | "INT8"
+        | "SMALLSERIAL" | "SERIAL" | "BIGSERIAL" => ColumnKind::Integer,
+        "FLOAT" | "FLOAT4" | "FLOAT8" | "DOUBLE" | "REAL" => ColumnKind::Float,
+        "DECIMAL" | "NUMERIC" | "NUMBER" | "DEC" => {
+            let precision_scale = upper.split_once('(')
+                .and_then(|(_, rest)| rest.split_once(')'))
+                .and_then(|(params, _)| {
+                    let mut params = params.split(',').map(|param| param.trim().parse::<u32>());
+                    let precision = params.next()?.ok()?;
+                    let scale = params.next().map_or(Some(0), |scale| scale.ok())?;
+                    Some((precision, scale))
+                });
+            ColumnKind::Decimal(precision_scale)
+        }
+        "DATE" => ColumnKind::Date,
+        "TIMESTAMPTZ" => ColumnKind::TimestampTz,
+        "TIMESTAMP" if upper.contains("WITH TIME ZONE") => ColumnKind::TimestampTz,
+        "DATETIME" | "TIMESTAMP" => ColumnKind::Timestamp,
+        "TIME" | "TIMETZ" => ColumnKind::Time,
+        "JSON" | "JSONB" => ColumnKind::Json,
+        _ => ColumnKind::Text,
+    }
+}
+
+/// Type of a column in Snowflake, BigQuery or DuckDB. Decimals without a
+/// precision, such as Postgres `NUMERIC`, keep their fractions as floats
+/// (BigQuery has `BIGNUMERIC` for them).
+fn warehouse_type(kind: ColumnKind, dialect: Dialect) -> String {
+    let (snowflake, bigquery, duckdb) = match kind {
+        ColumnKind::Boolean => ("BOOLEAN", "BOOL", "BOOLEAN"),
+        ColumnKind::Integer => ("NUMBER(38,0)", "INT64", "BIGINT"),
+        ColumnKind::UnsignedBigInt => ("NUMBER(38,0)", "NUMERIC", "UBIGINT"),
+        ColumnKind::Float => ("FLOAT", "FLOAT64", "DOUBLE"),
+        ColumnKind::Decimal(None) => ("FLOAT", "BIGNUMERIC", "DOUBLE"),
+        ColumnKind::Decimal(Some((precision, scale))) => {
+            let decimal = format!("({},{})", precision, scale);
+            return match dialect {
+                Dialect::Snowflake if precision <= 38 => format!("NUMBER{}", decimal),
+                // NUMERIC holds 29 digits before the point and 9 after
+                Dialect::BigQuery if precision - scale.min(precision) <= 29 && scale <= 9 => format!("NUMERIC{}", decimal),
+                Dialect::BigQuery => "BIGNUMERIC".to_string(),
+                Dialect::DuckDb if precision <= 38 => format!("DECIMAL{}", decimal),
+                _ => warehouse_type(ColumnKind::Decimal(None), dialect),
+            };
+        }
+        ColumnKind::Date => ("DATE", "DATE", "DATE"),
+        ColumnKind::Timestamp => ("TIMESTAMP_NTZ", "DATETIME", "TIMESTAMP"),
+        ColumnKind::TimestampTz => ("TIMESTAMP_TZ", "TIMESTAMP", "TIMESTAMPTZ"),
+        ColumnKind::Time => ("TIME", "TIME", "TIME"),
+        ColumnKind::Json => ("VARIANT", "JSON", "JSON"),
+        ColumnKind::Text => ("VARCHAR", "STRING", "VARCHAR"),
+    };
+    match dialect {
+        Dialect::Snowflake => snowflake,
+        Dialect::BigQuery => bigquery,
+        _ => duckdb,
+    }.to_string()
+}
+
+/// Quote an identifier for the target dialect
+pub fn quote_identifier(identifier: &str, dialect: Dialect) -> String {
+    match dialect {
+        Dialect::Postgres | Dialect::Snowflake | Dialect::DuckDb => format!("\"{}\"", identifier.replace('"', "\"\"")),
+        Dialect::MySql => format!("`{}`", identifier.replace('`', "``")),
+        Dialect::BigQuery => format!("`{}`", identifier.replace('`', "\\`")),
+    }
+}
+
+/// Create a table on the target and bulk-load its rows. Returns the number of loaded rows.
+pub fn load_table(target: &mut dyn LoadTarget, table: &Table, rows: &[Vec<String>]) -> Result<u64> {
+    target.execute(&create_table_sql(table, target.dialect()))
+        .with_context(|| format!("Error creating table '{}'", table.name))?;
+    
+    let mut csv = Vec::new();
+    write_csv_to(&mut csv, &table.columns, rows, &CsvOptions::default())?;
+    target.load_csv(table, csv)
+        .with_context(|| format!("Error loading rows into table '{}'", table.name))
+}
+
+/// Column list of a table for COPY / LOAD DATA statements
+#[cfg(any(feature = "postgres", feature = "mysql"))]
+fn column_list(table: &Table, dialect: Dialect) -> String {
+    table.columns.iter()
+        .map(|column| quote_identifier(column, dialect))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(feature = "postgres")]
+impl LoadTarget for postgres::Client {
+    fn dialect(&self) -> Dialect {
+        Dialect::Postgres
+    }
+    
+    fn execute(&mut self, sql: &str) -> Result<()> {
+        self.batch_execute(sql)?;
+        Ok(())
+    }
+    
+    fn load_csv(&mut self, table: &Table, csv: Vec<u8>) -> Result<u64> {
+        use std::io::Write;
+        
+        // Unquoted NULL is how the extractor represents SQL NULL
+        let statement = format!(
+            "COPY {} ({}) FROM STDIN WITH (FORMAT csv, HEADER true, NULL 'NULL')",
+            quote_identifier(&table.name, Dialect::Postgres),
+            column_list(table, Dialect::Postgres)
+        );
+        let mut writer = self.copy_in(&statement)?;
+        writer.write_all(&csv)?;
+        Ok(writer.finish()?)
+    }
+}
+
+#[cfg(feature = "mysql")]
+impl LoadTarget for mysql::Conn {
+    fn dialect(&self) -> Dialect {
+        Dialect::MySql
+    }
+    
+    fn execute(&mut self, sql: &str) -> Result<()> {
+        use mysql::prelude::Queryable;
+        
+        self.query_drop(sql)?;
+        Ok(())
+    }
+    
+    fn load_csv(&mut self, table: &Table, csv: Vec<u8>) -> Result<u64> {
+        use mysql::prelude::Queryable;
+        use std::io::Write;
+        
+        // Serve the CSV from memory instead of a file on the client
+        self.set_local_infile_handler(Some(mysql::LocalInfileHandler::new(move |_, stream| {
+            stream.write_all(&csv)
+        })));
+        
+        // With an empty escape character, unquoted NULL is read as SQL NULL
+        let statement = format!(
+            "LOAD DATA LOCAL INFILE 'parsley.csv' INTO TABLE {} CHARACTER SET utf8mb4 \
+             FIELDS TERMINATED BY ',' OPTIONALLY ENCLOSED BY '\"' ESCAPED BY '' \
+             LINES TERMINATED BY '\\n' IGNORE 1 LINES ({})",
+            quote_identifier(&table.name, Dialect::MySql),
+            column_list(table, Dialect::MySql)
+        );
+        self.query_drop(statement)?;
+        let loaded = self.affected_rows();
+        self.set_local_infile_handler(None);
+        
+        Ok(loaded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn users() -> Table {
+        Table {
+            name: "users".to_string(),
+            columns: vec!["id".to_string(), "email".to_string(), "active".to_string()],
+            column_types: vec!["int(11) unsigned".to_string(), "VARCHAR(255)".to_string(), String::new()],
+            primary_key: vec!["id".to_string()],
+            ..Default::default()
+        }
+    }
+    
+    #[test]
+    fn test_create_table_sql() {
+        assert_eq!(create_table_sql(&users(), Dialect::Postgres), "\
+CREATE TABLE IF NOT EXISTS \"users\" (
+  \"id\" BIGINT,
+  \"email\" VARCHAR(255),
+  \"active\" TEXT,
+  PRIMARY KEY (\"id\")
+)");
+        assert_eq!(create_table_sql(&users(), Dialect::MySql), "\
+CREATE TABLE IF NOT EXISTS `users` (
+  `id` int(11) unsigned,
+  `email` VARCHAR(255),
+  `active` TEXT,
+  PRIMARY KEY (`id`)
+)");
+    }
+    
+    #[test]
+    fn test_create_table_sql_for_warehouses() {
+        let table = Table {
+            name: "orders".to_string(),
+            columns: ["id", "total", "amount", "paid", "created_at", "shipped_at", "meta", "note"].map(str::to_string).to_vec(),
+            column_types: ["bigint unsigned", "DECIMAL(10,2)", "numeric", "tinyint(1)", "datetime", "timestamp with time zone", "jsonb", ""]
+                .map(str::to_string).to_vec(),
+            primary_key: vec!["id".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(create_table_sql(&table, Dialect::Snowflake), "\
+CREATE TABLE IF NOT EXISTS \"orders\" (
+  \"id\" NUMBER(38,0),
+  \"total\" NUMBER(10,2),
+  \"amount\" FLOAT,
+  \"paid\" NUMBER(38,0),
+  \"created_at\" TIMESTAMP_NTZ,
+  \"shipped_at\" TIMESTAMP_TZ,
+  \"meta\" VARIANT,
+  \"note\" VARCHAR,
+  PRIMARY KEY (\"id\")
+)");
+        assert_eq!(create_table_sql(&table, Dialect::BigQuery), "\
+CREATE TABLE IF NOT EXISTS `orders` (
+  `id` NUMERIC,
+  `total` NUMERIC(10,2),
+  `amount` BIGNUMERIC,
+  `paid` INT64,
+  `created_at` DATETIME,
+  `shipped_at` TIMESTAMP,
+  `meta` JSON,
+  `note` STRING,
+  PRIMARY KEY (`id`) NOT ENFORCED
+)");
+        assert_eq!(create_table_sql(&table, Dialect::DuckDb).lines().skip(1).take(3).collect::<Vec<_>>(), vec![
+            "  \"id\" UBIGINT,",
+            "  \"total\" DECIMAL(10,2),",
+            "  \"amount\" DOUBLE,",
+        ]);
+    }
+    
+    #[test]
+    fn test_connect_rejects_unknown_targets() {
+        assert!(connect("sqlite://local.db").is_err());
+    }
+}
@@ -0,0 +1,207 @@
+//! Dependency order of the tables for loading (`--load-order`, `--number-files`).
+//!
+//! A table that references another through a foreign key is loaded after it,
+//! so constraints hold while the files are applied one by one. The order keeps
+//! the dump's order wherever foreign keys allow it. Tables that reference each
+//! other in a cycle cannot all come after the ones they reference; they are
+//! reported, and taken in dump order once nothing else can be placed. A table
+//! referencing itself does not constrain the order.
+
+use anyhow::{Context, Result};
+use std::fs;
+
+use crate::types::Table;
+
+/// Tables in the order they can be loaded, and the cycles that prevent a
+/// valid order
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LoadOrder {
+    pub tables: Vec<String>,
+    /// Tables referencing each other through foreign keys, in dump order
+    pub cycles: Vec<Vec<String>>,
+}
+
+/// Order tables so every table comes after the tables its foreign keys
+/// reference. References to tables not in `tables` are ignored.
+pub fn load_order(tables: &[Table]) -> LoadOrder {
+    let find = |name: &str| tables.iter().position(|t| t.name == name)
+        .or_else(|| tables.iter().position(|t| t.name.eq_ignore_ascii_case(name)));
+    let parents: Vec<Vec<usize>> = tables.iter()
+        .enumerate()
+        .map(|(i, table)| table.foreign_keys.iter()
+            .filter_map(|fk| find(&fk.references_table))
+            .filter(|&parent| parent != i)
+            .collect())
+        .collect();
+    
+    let mut placed = vec![false; tables.len()];
+    let mut order = Vec::new();
+    while order.len() < tables.len() {
+        let next = (0..tables.len())
+            .find(|&i| !placed[i] && parents[i].iter().all(|&parent| placed[parent]))
+            // Only cycles are left: break one at its first table
+            .or_else(|| (0..tables.len()).find(|&i| !placed[i]))
+            .unwrap();
+        placed[next] = true;
+        order.push(next);
+    }
+    
+    LoadOrder {
+        tables: order.into_iter().map(|i| tables[i].name.clone()).collect(),
+        cycles: cycles(&parents).into_iter()
+            .map(|cycle| cycle.into_iter().map(|i| tables[i].name.clone()).collect())
+            .collect(),
+    }
+}
+
+/// Groups of two or more tables that reach each other through foreign keys,
+/// found with Tarjan's strongly connected components algorithm. The walk
+/// keeps its own stack, so long chains of references cannot overflow it.
+fn cycles(parents: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    const UNVISITED: usize = usize::MAX;
+    // Visit number of each table, and the lowest visit number it reaches
+    let mut index = vec![UNVISITED; parents.len()];
+    let mut low = vec![0; parents.len()];
+    let mut on_stack = vec![false; parents.len()];
+    let mut stack = Vec::new();
+    let mut visited = 0;
+    let mut cycles = Vec::new();
+    
+    for root in 0..parents.len() {
+        if index[root] != UNVISITED {
+            continue;
+        }
+        // Tables being walked, with the next of their parents to follow
+        let mut walk = vec![(root, 0)];
+        index[root] = visited;
+        low[root] = visited;
+        visited += 1;
+        stack.push(root);
+        on_stack[root] = true;
+        
+        while let Some(&mut (table, ref mut next)) = walk.last_mut() {
+            if let Some(&parent) = parents[table].get(*next) {
+                *next += 1;
+                if index[parent] == UNVISITED {
+                    index[parent] = visited;
+                    low[parent] = visited;
+                    visited += 1;
+                    stack.push(parent);
+                    on_stack[parent] = true;
+                    walk.push((parent, 0));
+                } else if on_stack[parent] {
+                    low[table] = low[table].min(index[parent]);
+                }
+                continue;
+            }
+            
+            walk.pop();
+            if let Some(&(child_of, _)) = walk.last() {
+                low[child_of] = low[child_of].min(low[table]);
+            }
+            if low[table] == index[table] {
+                let mut cycle = Vec::new();
+                while let Some(member) = stack.pop() {
+                    on_stack[member] = false;
+                    cycle.push(member);
+                    if member == table {
+                        break;
+                    }
+                }
+                if cycle.len() > 1 {
+                    cycle.sort_unstable();
+                    cycles.push(cycle);
+                }
+            }
+        }
+    }
+    
+    // In dump order, by each cycle's first table
+    cycles.sort_unstable_by_key(|cycle| cycle[0]);
+    cycles
+}
+
+/// Write the load order to a file, one table per line
+pub fn write_load_order(filename: &str, order: &LoadOrder) -> Result<()> {
+    let text: String = order.tables.iter().map(|table| format!("{}\n", table)).collect();
+    fs::write(filename, text)
+        .with_context(|| format!("Failed to write load order file '{}'", filename))
+}
+
+/// Prefix each table's file name with its position in the load order, e.g.
+/// `01_users.csv`, so sorting the files by name gives the load order
+pub fn numbered_filenames(tables: &[Table], filenames: Vec<String>, order: &LoadOrder) -> Vec<String> {
+    let width = order.tables.len().to_string().len().max(2);
+    tables.iter()
+        .zip(filenames)
+        .map(|(table, filename)| match order.tables.iter().position(|name| *name == table.name) {
+            Some(position) => format!("{:0width$}_{}", position + 1, filename, width = width),
+            None => filename,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ForeignKey;
+    
+    fn table(name: &str, references: &[&str]) -> Table {
+        Table {
+            name: name.to_string(),
+            foreign_keys: references.iter()
+                .map(|parent| ForeignKey {
+                    columns: vec![format!("{}_id", parent)],
+                    references_table: parent.to_string(),
+                    references_columns: vec!["id".to_string()],
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+    
+    #[test]
+    fn test_load_order() {
+        let tables = vec![
+            table("order_items", &["orders", "products"]),
+            table("orders", &["users"]),
+            table("employees", &["employees"]),
+            table("products", &[]),
+            table("users", &["countries"]),
+        ];
+        let order = load_order(&tables);
+        // countries is not in the dump, and employees only references itself
+        assert_eq!(order.tables, vec!["employees", "products", "users", "orders", "order_items"]);
+        assert!(order.cycles.is_empty());
+        
+        let filenames = tables.iter().map(|t| format!("{}.csv", t.name)).collect();
+        assert_eq!(numbered_filenames(&tables, filenames, &order), vec![
+            "05_order_items.csv", "04_orders.csv", "01_employees.csv", "02_products.csv", "03_users.csv",
+        ]);
+    }
+    
+    #[test]
+    fn test_cycles_are_reported_and_broken_in_dump_order() {
+        let tables = vec![
+            table("invoices", &["payments"]),
+            table("payments", &["invoices", "accounts"]),
+            table("accounts", &[]),
+            table("refunds", &["payments"]),
+        ];
+        let order = load_order(&tables);
+        assert_eq!(order.tables, vec!["accounts", "invoices", "payments", "refunds"]);
+        assert_eq!(order.cycles, vec![vec!["invoices".to_string(), "payments".to_string()]]);
+    }
+    
+    #[test]
+    fn test_cycles_in_a_large_schema() {
+        // A chain of 10000 tables, each referencing the next, closed by the
+        // last referencing the first, next to two separate cycles
+        let mut parents: Vec<Vec<usize>> = (0..10_000).map(|i| vec![(i + 1) % 10_000]).collect();
+        parents.extend([vec![10_001], vec![10_002], vec![10_000], vec![10_004], vec![10_003]]);
+        let found = cycles(&parents);
+        assert_eq!(found.len(), 3);
+        assert_eq!(found[0], (0..10_000).collect::<Vec<_>>());
+        assert_eq!(found[1..], [vec![10_000, 10_001, 10_002], vec![10_003, 10_004]]);
+    }
+}
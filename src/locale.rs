@@ -0,0 +1,111 @@
+use anyhow::Result;
+use std::borrow::Cow;
+
+use crate::args::flag_value;
+use crate::rfc4180::{parse_rfc4180_strict, RFC4180_LOCALE};
+use crate::types::CsvLocale;
+
+/// Languages that write decimals with a comma, so Excel in these locales
+/// expects `;` between fields
+const DECIMAL_COMMA_LANGUAGES: [&str; 22] = [
+    "bg", "cs", "da", "de", "el", "es", "et", "fi", "fr", "hr", "hu", "id",
+    "it", "lt", "lv", "nb", "nl", "pl", "pt", "ro", "ru", "sv",
+];
+
+/// Languages that write decimals with a point
+const DECIMAL_POINT_LANGUAGES: [&str; 8] = ["en", "he", "hi", "ja", "ko", "ms", "th", "zh"];
+
+/// Regions whose decimal separator differs from the rest of their language
+const DECIMAL_POINT_REGIONS: [&str; 4] = ["de-CH", "fr-CH", "it-CH", "es-MX"];
+
+/// Parse the --locale option, e.g. `--locale de-DE`. Every locale uses Excel's
+/// CRLF line endings and a byte order mark; locales writing decimals with a
+/// comma also get `;` as the delimiter and decimal commas in numeric columns.
+pub fn parse_csv_locale(args: &[String]) -> Result<CsvLocale> {
+    match flag_value(args, "--locale")? {
+        Some(_) if parse_rfc4180_strict(args) => {
+            anyhow::bail!("Error: --locale cannot be combined with --rfc4180-strict, which writes comma-separated CRLF files without a byte order mark")
+        }
+        Some(locale) => csv_locale(locale),
+        None if parse_rfc4180_strict(args) => Ok(RFC4180_LOCALE),
+        None => Ok(CsvLocale::default()),
+    }
+}
+
+/// The CSV conventions of a locale such as `de-DE`, `de_DE` or `de`
+pub fn csv_locale(locale: &str) -> Result<CsvLocale> {
+    let normalized = locale.replace('_', "-");
+    let language = normalized.split('-').next().unwrap_or("").to_ascii_lowercase();
+    let region_uses_point = DECIMAL_POINT_REGIONS.iter().any(|region| region.eq_ignore_ascii_case(&normalized));
+    
+    let decimal_comma = if DECIMAL_COMMA_LANGUAGES.contains(&language.as_str()) {
+        !region_uses_point
+    } else if DECIMAL_POINT_LANGUAGES.contains(&language.as_str()) {
+        false
+    } else {
+        anyhow::bail!("Error: Unknown --locale '{}'. Use a language and region such as de-DE, fr-FR or en-US", locale);
+    };
+    
+    Ok(CsvLocale {
+        delimiter: if decimal_comma { b';' } else { b',' },
+        decimal_comma,
+        crlf: true,
+        bom: true,
+    })
+}
+
+/// Whether a declared column type holds numbers
+pub fn is_numeric_type(column_type: &str) -> bool {
+    let column_type = column_type.trim().to_ascii_lowercase();
+    let base = column_type.split('(').next().unwrap_or("").trim();
+    base.contains("int") || base.starts_with("float") || base.starts_with("double") || matches!(
+        base,
+        "numeric" | "decimal" | "dec" | "number" | "real" | "money" | "smallmoney" | "fixed"
+    )
+}
+
+/// Write a plain decimal number with a comma, `3.14` as `3,14`. Other values,
+/// including integers and NULL, are returned unchanged.
+pub fn decimal_comma(value: &str) -> Cow<'_, str> {
+    let digits = value.strip_prefix(['-', '+']).unwrap_or(value);
+    let mantissa = digits.split(['e', 'E']).next().unwrap_or("");
+    let is_decimal = mantissa.split_once('.')
+        .is_some_and(|(whole, fraction)| {
+            !fraction.is_empty()
+                && whole.bytes().all(|b| b.is_ascii_digit())
+                && fraction.bytes().all(|b| b.is_ascii_digit())
+        });
+    if is_decimal && value.parse::<f64>().is_ok() {
+        Cow::Owned(value.replacen('.', ",", 1))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_csv_locale_presets() {
+        let german = csv_locale("de-DE").unwrap();
+        assert_eq!(german, CsvLocale { delimiter: b';', decimal_comma: true, crlf: true, bom: true });
+        assert_eq!(csv_locale("fr_FR").unwrap(), german);
+        assert_eq!(csv_locale("de-CH").unwrap().delimiter, b',');
+        assert_eq!(csv_locale("en-US").unwrap(), CsvLocale { delimiter: b',', decimal_comma: false, crlf: true, bom: true });
+        assert!(csv_locale("xx-YY").is_err());
+    }
+    
+    #[test]
+    fn test_decimal_comma() {
+        assert_eq!(decimal_comma("3.14"), "3,14");
+        assert_eq!(decimal_comma("-0.5"), "-0,5");
+        assert_eq!(decimal_comma("1.5e3"), "1,5e3");
+        assert_eq!(decimal_comma("42"), "42");
+        assert_eq!(decimal_comma("NULL"), "NULL");
+        assert_eq!(decimal_comma("1.2.3"), "1.2.3");
+        assert!(is_numeric_type("DECIMAL(10,2)"));
+        assert!(is_numeric_type("bigint unsigned"));
+        assert!(!is_numeric_type("varchar(20)"));
+    }
+}
@@ -0,0 +1,230 @@
+//! Column masking for exports to staging environments (`--mask`).
+//!
+//! A masked column has every value replaced, either by a hash or by a
+//! realistic fake of the same kind (`fake:name`, `fake:email`, ...). Fakes are
+//! drawn from a generator seeded with the original value and `--mask-seed`,
+//! so a value is replaced the same way in every table and on every run, and
+//! keys that join tables still match after masking.
+
+use anyhow::Result;
+use fake::Fake;
+use fake::faker::address::en::{BuildingNumber, CityName, StreetName, StreetSuffix};
+use fake::faker::internet::en::SafeEmail;
+use fake::faker::name::en::Name;
+use fake::faker::phone_number::en::PhoneNumber;
+use rand_chacha::ChaCha8Rng;
+use rand_chacha::rand_core::{RngCore, SeedableRng};
+use sha2::{Digest, Sha256};
+
+use crate::args::{flag_value, flag_values};
+
+/// Kind of realistic value a `fake:` mask writes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FakeKind {
+    Name,
+    Email,
+    Address,
+    Phone,
+    Uuid,
+}
+
+impl FakeKind {
+    fn name(self) -> &'static str {
+        match self {
+            FakeKind::Name => "name",
+            FakeKind::Email => "email",
+            FakeKind::Address => "address",
+            FakeKind::Phone => "phone",
+            FakeKind::Uuid => "uuid",
+        }
+    }
+}
+
+/// How the values of a masked column are replaced
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaskStrategy {
+    /// Hex SHA-256 of the seed and the value
+    Hash,
+    /// A fake value of a kind, the same for equal values
+    Fake(FakeKind),
+}
+
+/// A column to mask, in one table or in every table that has it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnMask {
+    pub table: Option<String>,
+    pub column: String,
+    pub strategy: MaskStrategy,
+}
+
+/// Masked columns and the seed their replacements are derived from
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Masking {
+    pub masks: Vec<ColumnMask>,
+    pub seed: u64,
+}
+
+/// Parse --mask options (repeatable) and --mask-seed from command line
+pub fn parse_masking(args: &[String]) -> Result<Masking> {
+    let masks = flag_values(args, "--mask")?
+        .into_iter()
+        .map(parse_column_mask)
+        .collect::<Result<Vec<_>>>()?;
+    let seed = match flag_value(args, "--mask-seed")? {
+        Some(value) => value.parse::<u64>()
+            .map_err(|_| anyhow::anyhow!("Error: Invalid --mask-seed '{}'. Use a non-negative integer", value))?,
+        None => 0,
+    };
+    
+    Ok(Masking { masks, seed })
+}
+
+/// Parse a single `[<table>.]<column>:<strategy>` specification, e.g.
+/// `users.email:fake:email` or `ssn:hash`
+pub fn parse_column_mask(spec: &str) -> Result<ColumnMask> {
+    let invalid = || anyhow::anyhow!(
+        "Error: Invalid --mask '{}'. Use [<table>.]<column>:<strategy>, where the strategy is hash or fake:<name|email|address|phone|uuid>",
+        spec
+    );
+    let (target, strategy) = spec.split_once(':').ok_or_else(invalid)?;
+    let strategy = parse_mask_strategy(strategy)
+        .map_err(|e| anyhow::anyhow!("Error: {} in --mask '{}'", e, spec))?;
+    let (table, column) = match target.split_once('.') {
+        Some((table, column)) => (Some(table.to_string()), column),
+        None => (None, target),
+    };
+    if column.is_empty() || table.as_deref() == Some("") {
+        return Err(invalid());
+    }
+    
+    Ok(ColumnMask { table, column: column.to_string(), strategy })
+}
+
+/// Parse a masking strategy: `hash` or `fake:<name|email|address|phone|uuid>`
+pub fn parse_mask_strategy(strategy: &str) -> Result<MaskStrategy> {
+    if strategy == "hash" {
+        return Ok(MaskStrategy::Hash);
+    }
+    let Some(kind) = strategy.strip_prefix("fake:") else {
+        anyhow::bail!("Unknown masking strategy '{}'. Use hash or fake:<name|email|address|phone|uuid>", strategy);
+    };
+    let kind = match kind {
+        "name" => FakeKind::Name,
+        "email" => FakeKind::Email,
+        "address" => FakeKind::Address,
+        "phone" => FakeKind::Phone,
+        "uuid" => FakeKind::Uuid,
+        kind => anyhow::bail!("Unknown fake kind '{}'. Use name, email, address, phone or uuid", kind),
+    };
+    Ok(MaskStrategy::Fake(kind))
+}
+
+/// The replacement of a single value
+pub fn mask_value(value: &str, strategy: MaskStrategy, seed: u64) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(seed.to_le_bytes());
+    if let MaskStrategy::Fake(kind) = strategy {
+        hasher.update(kind.name());
+    }
+    hasher.update(value.as_bytes());
+    let digest = hasher.finalize();
+    
+    let MaskStrategy::Fake(kind) = strategy else {
+        return digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+    };
+    let rng = &mut ChaCha8Rng::from_seed(digest.into());
+    match kind {
+        FakeKind::Name => Name().fake_with_rng(rng),
+        FakeKind::Email => SafeEmail().fake_with_rng(rng),
+        FakeKind::Address => {
+            let number: String = BuildingNumber().fake_with_rng(rng);
+            let street: String = StreetName().fake_with_rng(rng);
+            let suffix: String = StreetSuffix().fake_with_rng(rng);
+            let city: String = CityName().fake_with_rng(rng);
+            format!("{} {} {}, {}", number, street, suffix, city)
+        }
+        FakeKind::Phone => PhoneNumber().fake_with_rng(rng),
+        FakeKind::Uuid => {
+            let mut bytes = [0u8; 16];
+            rng.fill_bytes(&mut bytes);
+            // Version 4, RFC 4122 variant
+            bytes[6] = (bytes[6] & 0x0f) | 0x40;
+            bytes[8] = (bytes[8] & 0x3f) | 0x80;
+            let hex: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+            format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+        }
+    }
+}
+
+/// Replace the values of a table's masked columns. Empty and NULL values are kept.
+pub fn apply_masking(table: &str, headers: &[String], rows: &mut [Vec<String>], masking: &Masking) {
+    for mask in &masking.masks {
+        if mask.table.as_deref().is_some_and(|t| t != table) {
+            continue;
+        }
+        let Some(index) = headers.iter().position(|h| h == &mask.column) else {
+            continue;
+        };
+        for cell in rows.iter_mut().filter_map(|row| row.get_mut(index)) {
+            if cell.is_empty() || cell.eq_ignore_ascii_case("NULL") {
+                continue;
+            }
+            *cell = mask_value(cell, mask.strategy, masking.seed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_parse_column_mask() {
+        let mask = parse_column_mask("users.email:fake:email").unwrap();
+        assert_eq!(mask.table.as_deref(), Some("users"));
+        assert_eq!(mask.column, "email");
+        assert_eq!(mask.strategy, MaskStrategy::Fake(FakeKind::Email));
+        assert_eq!(parse_column_mask("ssn:hash").unwrap().table, None);
+        
+        assert!(parse_column_mask("ssn").is_err());
+        assert!(parse_column_mask("ssn:fake:iban").is_err());
+        assert!(parse_column_mask("ssn:blur").is_err());
+    }
+    
+    #[test]
+    fn test_fakes_are_stable_and_keep_their_format() {
+        let email = mask_value("ann@corp.example", MaskStrategy::Fake(FakeKind::Email), 7);
+        assert_eq!(email, mask_value("ann@corp.example", MaskStrategy::Fake(FakeKind::Email), 7));
+        assert_ne!(email, mask_value("ann@corp.example", MaskStrategy::Fake(FakeKind::Email), 8));
+        assert!(email.contains('@'));
+        
+        let uuid = mask_value("42", MaskStrategy::Fake(FakeKind::Uuid), 0);
+        assert_eq!(uuid.len(), 36);
+        assert_eq!(&uuid[14..15], "4");
+        assert_eq!(mask_value("42", MaskStrategy::Hash, 0).len(), 64);
+    }
+    
+    #[test]
+    fn test_apply_masking() {
+        let masking = Masking {
+            masks: vec![parse_column_mask("users.name:fake:name").unwrap(), parse_column_mask("phone:fake:phone").unwrap()],
+            seed: 1,
+        };
+        let headers = vec!["name".to_string(), "phone".to_string()];
+        let mut users = vec![
+            vec!["Ann Smith".to_string(), "555-0100".to_string()],
+            vec!["Ann Smith".to_string(), "NULL".to_string()],
+        ];
+        let mut admins = users.clone();
+        
+        apply_masking("users", &headers, &mut users, &masking);
+        apply_masking("admins", &headers, &mut admins, &masking);
+        
+        assert_ne!(users[0][0], "Ann Smith");
+        assert_eq!(users[0][0], users[1][0]);
+        assert_ne!(users[0][1], "555-0100");
+        assert_eq!(users[1][1], "NULL");
+        assert_eq!(admins[0][0], "Ann Smith");
+        assert_eq!(admins[0][1], users[0][1]);
+    }
+}
@@ -0,0 +1,120 @@
+//! Combining the tables of sharded dumps (`merge` subcommand).
+//!
+//! Shards such as `dump_0.sql`..`dump_9.sql` usually define the same tables,
+//! but a shard dumped before a migration can lack columns added at the end of
+//! a table. Such shards are reconciled by writing the missing trailing columns
+//! empty; shards whose columns differ in name or order are rejected.
+
+use anyhow::Result;
+
+use crate::types::Table;
+
+/// A table as written from all shards
+#[derive(Debug, Clone)]
+pub struct MergedTable {
+    /// The shard's definition with the most columns
+    pub table: Table,
+    /// Index of the table in every shard, `None` for shards without it
+    pub sources: Vec<Option<usize>>,
+    /// Shards lacking trailing columns, with the columns they lack
+    pub reconciled: Vec<(String, Vec<String>)>,
+}
+
+/// Match the tables of the shards by name and check that every shard's
+/// columns are the same as, or a leading part of, the widest definition.
+/// `shards` holds the name and tables of each dump. Tables are returned in
+/// the order they first appear.
+pub fn merge_schemas(shards: &[(&str, &[Table])]) -> Result<Vec<MergedTable>> {
+    let mut merged: Vec<MergedTable> = Vec::new();
+    
+    for (shard, (_, tables)) in shards.iter().enumerate() {
+        for (index, table) in tables.iter().enumerate() {
+            let entry = match merged.iter_mut().find(|m| m.table.name == table.name) {
+                Some(entry) => entry,
+                None => {
+                    merged.push(MergedTable {
+                        table: table.clone(),
+                        sources: vec![None; shards.len()],
+                        reconciled: Vec::new(),
+                    });
+                    merged.last_mut().expect("just pushed")
+                }
+            };
+            entry.sources[shard] = Some(index);
+            if table.columns.len() > entry.table.columns.len() {
+                entry.table = table.clone();
+            }
+        }
+    }
+    
+    let mut conflicts = Vec::new();
+    for entry in &mut merged {
+        for (shard, index) in entry.sources.iter().enumerate() {
+            let Some(index) = index else {
+                continue;
+            };
+            let (name, tables) = shards[shard];
+            let columns = &tables[*index].columns;
+            if !entry.table.columns.starts_with(columns) {
+                conflicts.push(format!(
+                    "  - {} in {}: ({}) does not match ({})",
+                    entry.table.name, name, columns.join(", "), entry.table.columns.join(", ")
+                ));
+            } else if columns.len() < entry.table.columns.len() {
+                entry.reconciled.push((name.to_string(), entry.table.columns[columns.len()..].to_vec()));
+            }
+        }
+    }
+    if !conflicts.is_empty() {
+        anyhow::bail!("Error: Tables have incompatible columns across shards:\n{}", conflicts.join("\n"));
+    }
+    
+    Ok(merged)
+}
+
+/// Widen a shard's row to the merged columns by inserting empty values after
+/// the shard's own columns, before anything appended to them (`_source_line`)
+pub fn pad_shard_row(row: &mut Vec<String>, shard_columns: usize, merged_columns: usize) {
+    if merged_columns > shard_columns {
+        let at = shard_columns.min(row.len());
+        row.splice(at..at, std::iter::repeat_n(String::new(), merged_columns - shard_columns));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_sql_content;
+    
+    fn tables(sql: &str) -> Vec<Table> {
+        parse_sql_content(sql.to_string()).unwrap().0
+    }
+    
+    #[test]
+    fn test_merge_schemas_reconciles_trailing_columns() {
+        let old = tables("CREATE TABLE orders (id int, amount int);\nCREATE TABLE users (id int);\n");
+        let new = tables("CREATE TABLE orders (id int, amount int, note text);\nCREATE TABLE refunds (id int);\n");
+        
+        let merged = merge_schemas(&[("dump_0.sql", &old), ("dump_1.sql", &new)]).unwrap();
+        
+        let names: Vec<&str> = merged.iter().map(|m| m.table.name.as_str()).collect();
+        assert_eq!(names, vec!["orders", "users", "refunds"]);
+        assert_eq!(merged[0].table.columns, vec!["id", "amount", "note"]);
+        assert_eq!(merged[0].sources, vec![Some(0), Some(0)]);
+        assert_eq!(merged[0].reconciled, vec![("dump_0.sql".to_string(), vec!["note".to_string()])]);
+        assert_eq!(merged[2].sources, vec![None, Some(1)]);
+        
+        let mut row = vec!["1".to_string(), "10".to_string(), "7".to_string()];
+        pad_shard_row(&mut row, 2, 3);
+        assert_eq!(row, vec!["1", "10", "", "7"]);
+    }
+    
+    #[test]
+    fn test_merge_schemas_rejects_reordered_columns() {
+        let a = tables("CREATE TABLE orders (id int, amount int);\n");
+        let b = tables("CREATE TABLE orders (amount int, id int);\n");
+        
+        let error = merge_schemas(&[("a.sql", &a), ("b.sql", &b)]).unwrap_err().to_string();
+        assert!(error.contains("orders in b.sql: (amount, id) does not match (id, amount)"));
+    }
+}
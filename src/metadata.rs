@@ -0,0 +1,260 @@
+//! Column statistics sidecar files (`--metadata`).
+//!
+//! Every table written gets a `<table>.meta.json` next to its file, listing
+//! the columns with their declared and inferred types, null counts and
+//! minimum and maximum values, so a loader can create typed external tables
+//! without reading the data. The statistics are gathered from the rows as they
+//! are written, after masking, filtering and derived columns.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::args::has_flag;
+use crate::csv_writer::convert_headers;
+use crate::infer::InferredType;
+use crate::sink::{OutputSink, TableSink};
+use crate::types::{HeaderCase, Table};
+
+/// Parse --metadata flag from command line
+pub fn parse_metadata(args: &[String]) -> bool {
+    has_flag(args, "--metadata")
+}
+
+/// Content of a `.meta.json` file
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TableMetadata {
+    pub table: String,
+    /// Name of the file the rows were written to
+    pub file: String,
+    pub rows: usize,
+    pub columns: Vec<ColumnMetadata>,
+}
+
+/// Statistics of one column. NULL and empty cells count as nulls and are left
+/// out of the minimum and maximum.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ColumnMetadata {
+    /// Header as written to the file
+    pub name: String,
+    /// Type declared by the dump, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub declared_type: Option<String>,
+    /// Type detected from the values, e.g. `integer`
+    pub inferred_type: &'static str,
+    pub nulls: usize,
+    pub min: Option<String>,
+    pub max: Option<String>,
+}
+
+/// Running statistics of a column
+#[derive(Default)]
+struct ColumnStats {
+    nulls: usize,
+    inferred: Option<InferredType>,
+    /// Smallest and largest values by text
+    min_text: Option<String>,
+    max_text: Option<String>,
+    /// Smallest and largest numeric values, with their text
+    min_number: Option<(f64, String)>,
+    max_number: Option<(f64, String)>,
+}
+
+impl ColumnStats {
+    fn add(&mut self, value: &str) {
+        if value.is_empty() || value == "NULL" {
+            self.nulls += 1;
+            return;
+        }
+        let value_type = InferredType::of_value(value);
+        self.inferred = Some(match self.inferred {
+            Some(inferred) => inferred.widen(value_type),
+            None => value_type,
+        });
+        keep(&mut self.min_text, value, |value, min| value < min.as_str());
+        keep(&mut self.max_text, value, |value, max| value > max.as_str());
+        if let Ok(number) = value.trim().parse::<f64>() {
+            let compare = |(kept, _): &(f64, String)| number.partial_cmp(kept);
+            if self.min_number.as_ref().is_none_or(|kept| compare(kept) == Some(Ordering::Less)) {
+                self.min_number = Some((number, value.to_string()));
+            }
+            if self.max_number.as_ref().is_none_or(|kept| compare(kept) == Some(Ordering::Greater)) {
+                self.max_number = Some((number, value.to_string()));
+            }
+        }
+    }
+    
+    fn into_metadata(self, name: String, declared_type: Option<String>) -> ColumnMetadata {
+        let inferred = self.inferred.unwrap_or(InferredType::String);
+        // Numbers are compared by value, everything else by text, which orders
+        // ISO dates and timestamps correctly
+        let (min, max) = match inferred {
+            InferredType::Integer | InferredType::Float => (
+                self.min_number.map(|(_, text)| text),
+                self.max_number.map(|(_, text)| text),
+            ),
+            _ => (self.min_text, self.max_text),
+        };
+        ColumnMetadata { name, declared_type, inferred_type: inferred.name(), nulls: self.nulls, min, max }
+    }
+}
+
+/// Replace `kept` with `value` when there is none yet or `replaces` says so
+fn keep(kept: &mut Option<String>, value: &str, replaces: impl Fn(&str, &String) -> bool) {
+    if kept.as_ref().is_none_or(|kept| replaces(value, kept)) {
+        *kept = Some(value.to_string());
+    }
+}
+
+/// Path of a table's metadata sidecar: `<name>.meta.json` in the directory of
+/// the file at `location`
+pub fn metadata_path(location: &str, name: &str) -> PathBuf {
+    let stem = Path::new(name).file_name().map(|stem| stem.to_string_lossy()).unwrap_or_default();
+    Path::new(location).with_file_name(format!("{}.meta.json", stem))
+}
+
+/// Wraps a sink and writes a `.meta.json` sidecar for every table it finishes.
+/// Outputs that are not local files, such as uploads, are left without one.
+pub struct MetadataSink {
+    inner: Box<dyn OutputSink>,
+    header_case: HeaderCase,
+}
+
+impl MetadataSink {
+    pub fn new(inner: Box<dyn OutputSink>, header_case: HeaderCase) -> Self {
+        MetadataSink { inner, header_case }
+    }
+}
+
+impl OutputSink for MetadataSink {
+    fn open_table<'a>(&'a self, table: &Table, name: &str, headers: &[String]) -> Result<Box<dyn TableSink + 'a>> {
+        let declared_types = headers.iter()
+            .map(|header| table.columns.iter()
+                .position(|column| column == header)
+                .and_then(|i| table.column_types.get(i))
+                .filter(|column_type| !column_type.is_empty())
+                .cloned())
+            .collect();
+        Ok(Box::new(MetadataTableSink {
+            inner: self.inner.open_table(table, name, headers)?,
+            table: table.name.clone(),
+            name: name.to_string(),
+            headers: convert_headers(headers, self.header_case)?,
+            declared_types,
+            stats: headers.iter().map(|_| ColumnStats::default()).collect(),
+            rows: 0,
+        }))
+    }
+}
+
+struct MetadataTableSink<'a> {
+    inner: Box<dyn TableSink + 'a>,
+    table: String,
+    /// File stem of the table
+    name: String,
+    headers: Vec<String>,
+    declared_types: Vec<Option<String>>,
+    stats: Vec<ColumnStats>,
+    rows: usize,
+}
+
+impl TableSink for MetadataTableSink<'_> {
+    fn write_row(&mut self, row: &[String]) -> Result<()> {
+        for (stats, value) in self.stats.iter_mut().zip(row) {
+            stats.add(value);
+        }
+        self.rows += 1;
+        self.inner.write_row(row)
+    }
+    
+    fn finish(self: Box<Self>) -> Result<String> {
+        Ok(self.finish_with_size()?.0)
+    }
+    
+    fn finish_with_size(self: Box<Self>) -> Result<(String, Option<u64>)> {
+        let MetadataTableSink { inner, table, name, headers, declared_types, stats, rows } = *self;
+        let (location, size) = inner.finish_with_size()?;
+        let path = Path::new(&location);
+        if location.contains("://") || !path.is_file() {
+            eprintln!("Warning: No metadata written for '{}', which is not a local file", location);
+            return Ok((location, size));
+        }
+        
+        let columns = headers.into_iter()
+            .zip(declared_types)
+            .zip(stats)
+            .map(|((name, declared_type), stats)| stats.into_metadata(name, declared_type))
+            .collect();
+        let file = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        let metadata = TableMetadata { table, file, rows, columns };
+        let sidecar = metadata_path(&location, &name);
+        let json = serde_json::to_string_pretty(&metadata)?;
+        fs::write(&sidecar, json + "\n")
+            .with_context(|| format!("Failed to write metadata file '{}'", sidecar.display()))?;
+        Ok((location, size))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::csv_writer::CsvSink;
+    use crate::limits::{LimitPolicy, LimitedSink, OutputLimits};
+    use crate::sink::write_table;
+    use crate::types::CsvOptions;
+    
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+    
+    #[test]
+    fn test_metadata_sidecar() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let sink = MetadataSink::new(Box::new(CsvSink { dir: dir.to_path_buf(), options: CsvOptions::default() }), HeaderCase::Preserve);
+        let table = Table {
+            name: "users".to_string(),
+            columns: strings(&["id", "score", "joined"]),
+            column_types: strings(&["INT", "", "DATE"]),
+            ..Default::default()
+        };
+        let rows = vec![
+            strings(&["9", "1.5", "2024-03-01"]),
+            strings(&["10", "NULL", "2023-12-31"]),
+            strings(&["2", "-3", ""]),
+        ];
+        
+        write_table(&sink, &table, "users", &table.columns, &rows).unwrap();
+        
+        let metadata: serde_json::Value = serde_json::from_str(&fs::read_to_string(dir.join("users.meta.json")).unwrap()).unwrap();
+        assert_eq!(metadata, serde_json::json!({
+            "table": "users",
+            "file": "users.csv",
+            "rows": 3,
+            "columns": [
+                {"name": "id", "declared_type": "INT", "inferred_type": "integer", "nulls": 0, "min": "2", "max": "10"},
+                {"name": "score", "inferred_type": "float", "nulls": 1, "min": "-3", "max": "1.5"},
+                {"name": "joined", "declared_type": "DATE", "inferred_type": "date", "nulls": 1, "min": "2023-12-31", "max": "2024-03-01"},
+            ],
+        }));
+    }
+    
+    #[test]
+    fn test_metadata_counts_rows_kept_by_limits() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let sink = MetadataSink::new(Box::new(CsvSink { dir: dir.to_path_buf(), options: CsvOptions::default() }), HeaderCase::Preserve);
+        let limits = OutputLimits { max_table_rows: Some(1), policy: LimitPolicy::StopTable, ..Default::default() };
+        let sink = LimitedSink::new(Box::new(sink), limits);
+        let table = Table { name: "users".to_string(), columns: strings(&["id"]), ..Default::default() };
+        let rows = vec![strings(&["1"]), strings(&["2"])];
+        
+        write_table(&sink, &table, "users", &table.columns, &rows).unwrap();
+        
+        let metadata: serde_json::Value = serde_json::from_str(&fs::read_to_string(dir.join("users.meta.json")).unwrap()).unwrap();
+        assert_eq!(metadata["rows"], 1);
+        assert_eq!(metadata["columns"][0]["max"], "1");
+    }
+}
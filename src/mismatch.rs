@@ -0,0 +1,177 @@
+use anyhow::Result;
+use std::fmt;
+
+use crate::args::flag_value;
+use crate::types::{MismatchPolicy, NumberedRow};
+
+/// Parse --on-mismatch option from command line
+pub fn parse_mismatch_policy(args: &[String]) -> Result<MismatchPolicy> {
+    match flag_value(args, "--on-mismatch")? {
+        None | Some("keep") => Ok(MismatchPolicy::Keep),
+        Some("error") => Ok(MismatchPolicy::Error),
+        Some("pad") => Ok(MismatchPolicy::Pad),
+        Some("truncate") => Ok(MismatchPolicy::Truncate),
+        Some("skip") => Ok(MismatchPolicy::Skip),
+        Some(other) => anyhow::bail!(
+            "Error: Invalid --on-mismatch '{}'. Use one of: keep, pad, truncate, skip, error",
+            other
+        ),
+    }
+}
+
+impl fmt::Display for MismatchPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            MismatchPolicy::Keep => "keep",
+            MismatchPolicy::Pad => "pad",
+            MismatchPolicy::Truncate => "truncate",
+            MismatchPolicy::Skip => "skip",
+            MismatchPolicy::Error => "error",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Make every row match the number of headers according to the policy.
+/// Returns the adjusted rows and the number of rows that had the wrong number of values.
+pub fn apply_mismatch_policy(
+    headers: &[String],
+    rows: Vec<Vec<String>>,
+    policy: MismatchPolicy,
+) -> Result<(Vec<Vec<String>>, usize)> {
+    apply_mismatch_policy_to_batch(headers, rows, policy, 0)
+}
+
+/// Like `apply_mismatch_policy`, for a batch of a table's rows that follows
+/// `rows_before` others. Errors number the rows from the start of the table.
+pub fn apply_mismatch_policy_to_batch(
+    headers: &[String],
+    rows: Vec<Vec<String>>,
+    policy: MismatchPolicy,
+    rows_before: usize,
+) -> Result<(Vec<Vec<String>>, usize)> {
+    let width = headers.len();
+    let mut mismatches = 0;
+    let mut adjusted = Vec::with_capacity(rows.len());
+    
+    for (i, mut row) in rows.into_iter().enumerate() {
+        if row.len() == width {
+            adjusted.push(row);
+            continue;
+        }
+        
+        mismatches += 1;
+        let values = row.len();
+        match fit_row(&mut row, width, policy) {
+            Some(true) => adjusted.push(row),
+            Some(false) => {}
+            None => anyhow::bail!(
+                "Row {} has {} values but the table has {} columns (use --on-mismatch pad|truncate|skip)",
+                rows_before + i + 1, values, width
+            ),
+        }
+    }
+    
+    Ok((adjusted, mismatches))
+}
+
+/// Like `apply_mismatch_policy`, for rows paired with the line of the dump they
+/// start on. Errors name the line instead of the row number.
+pub fn apply_mismatch_policy_with_lines(
+    headers: &[String],
+    rows: Vec<NumberedRow>,
+    policy: MismatchPolicy,
+) -> Result<(Vec<NumberedRow>, usize)> {
+    let width = headers.len();
+    let mut mismatches = 0;
+    let mut adjusted = Vec::with_capacity(rows.len());
+    
+    for (line, mut row) in rows {
+        if row.len() == width {
+            adjusted.push((line, row));
+            continue;
+        }
+        
+        mismatches += 1;
+        let values = row.len();
+        match fit_row(&mut row, width, policy) {
+            Some(true) => adjusted.push((line, row)),
+            Some(false) => {}
+            None => anyhow::bail!(
+                "Row at line {} has {} values but the table has {} columns (use --on-mismatch pad|truncate|skip)",
+                line, values, width
+            ),
+        }
+    }
+    
+    Ok((adjusted, mismatches))
+}
+
+/// Fit a row with the wrong number of values to `width` according to the
+/// policy. Returns whether to keep it, or `None` if the policy is to fail.
+fn fit_row(row: &mut Vec<String>, width: usize, policy: MismatchPolicy) -> Option<bool> {
+    match policy {
+        MismatchPolicy::Error => None,
+        MismatchPolicy::Keep => Some(true),
+        MismatchPolicy::Skip => Some(false),
+        MismatchPolicy::Truncate if row.len() < width => Some(false),
+        MismatchPolicy::Truncate | MismatchPolicy::Pad => {
+            row.resize(width, String::new());
+            Some(true)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn rows() -> Vec<Vec<String>> {
+        vec![
+            vec!["1".to_string(), "a".to_string()],
+            vec!["2".to_string()],
+            vec!["3".to_string(), "c".to_string(), "extra".to_string()],
+        ]
+    }
+    
+    #[test]
+    fn test_apply_mismatch_policy() {
+        let headers = vec!["id".to_string(), "name".to_string()];
+        
+        let (padded, mismatches) = apply_mismatch_policy(&headers, rows(), MismatchPolicy::Pad).unwrap();
+        assert_eq!(mismatches, 2);
+        assert_eq!(padded, vec![vec!["1", "a"], vec!["2", ""], vec!["3", "c"]]);
+        
+        let (truncated, _) = apply_mismatch_policy(&headers, rows(), MismatchPolicy::Truncate).unwrap();
+        assert_eq!(truncated, vec![vec!["1", "a"], vec!["3", "c"]]);
+        
+        let (skipped, _) = apply_mismatch_policy(&headers, rows(), MismatchPolicy::Skip).unwrap();
+        assert_eq!(skipped, vec![vec!["1", "a"]]);
+        
+        let (kept, mismatches) = apply_mismatch_policy(&headers, rows(), MismatchPolicy::Keep).unwrap();
+        assert_eq!(mismatches, 2);
+        assert_eq!(kept, rows());
+        
+        assert!(apply_mismatch_policy(&headers, rows(), MismatchPolicy::Error).is_err());
+    }
+    
+    #[test]
+    fn test_parse_mismatch_policy_defaults_to_keep() {
+        assert_eq!(parse_mismatch_policy(&[]).unwrap(), MismatchPolicy::Keep);
+        let args = vec!["--on-mismatch".to_string(), "error".to_string()];
+        assert_eq!(parse_mismatch_policy(&args).unwrap(), MismatchPolicy::Error);
+    }
+    
+    #[test]
+    fn test_mismatch_errors_name_the_line() {
+        let headers = vec!["id".to_string(), "name".to_string()];
+        let rows: Vec<NumberedRow> = rows().into_iter().zip([3, 7, 12]).map(|(row, line)| (line, row)).collect();
+        
+        let (skipped, mismatches) = apply_mismatch_policy_with_lines(&headers, rows.clone(), MismatchPolicy::Skip).unwrap();
+        assert_eq!(mismatches, 2);
+        assert_eq!(skipped, vec![(3, vec!["1".to_string(), "a".to_string()])]);
+        
+        let error = apply_mismatch_policy_with_lines(&headers, rows, MismatchPolicy::Error).unwrap_err();
+        assert!(error.to_string().starts_with("Row at line 7 has 1 values"));
+    }
+}
@@ -0,0 +1,59 @@
+//! Line breaks inside cells (`--newline-in-cell`).
+//!
+//! Quoted values may span several lines of the dump, and SQLite writes them as
+//! `replace('a\nb','\n',char(10))`; either way the cell gets the line breaks.
+//! `keep` writes them as they are, quoted in the CSV, `space` turns every line
+//! break into a single space and `escape` writes them as the two characters
+//! `\n` (and `\r`), for consumers that read one record per line.
+
+use anyhow::Result;
+use std::borrow::Cow;
+
+use crate::args::flag_value;
+use crate::types::NewlinePolicy;
+
+/// Parse --newline-in-cell option from command line
+pub fn parse_newline_policy(args: &[String]) -> Result<NewlinePolicy> {
+    match flag_value(args, "--newline-in-cell")? {
+        None | Some("keep") => Ok(NewlinePolicy::Keep),
+        Some("space") => Ok(NewlinePolicy::Space),
+        Some("escape") => Ok(NewlinePolicy::Escape),
+        Some(other) => anyhow::bail!("Error: Invalid --newline-in-cell '{}'. Use one of: keep, space, escape", other),
+    }
+}
+
+/// Apply the policy to the line breaks of a value. CRLF counts as one line break.
+pub fn normalize_newlines(text: &str, policy: NewlinePolicy) -> Cow<'_, str> {
+    if policy == NewlinePolicy::Keep || !text.contains(['\n', '\r']) {
+        return Cow::Borrowed(text);
+    }
+    let mut normalized = String::with_capacity(text.len() + 8);
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, policy) {
+            ('\r', NewlinePolicy::Space) => {
+                chars.next_if_eq(&'\n');
+                normalized.push(' ');
+            }
+            ('\n', NewlinePolicy::Space) => normalized.push(' '),
+            ('\r', _) => normalized.push_str("\\r"),
+            ('\n', _) => normalized.push_str("\\n"),
+            (c, _) => normalized.push(c),
+        }
+    }
+    Cow::Owned(normalized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_normalize_newlines() {
+        let text = "one\ntwo\r\nthree";
+        assert_eq!(normalize_newlines(text, NewlinePolicy::Keep), text);
+        assert_eq!(normalize_newlines(text, NewlinePolicy::Space), "one two three");
+        assert_eq!(normalize_newlines(text, NewlinePolicy::Escape), "one\\ntwo\\r\\nthree");
+        assert!(parse_newline_policy(&["--newline-in-cell".to_string(), "drop".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,334 @@
+//! Files written by the directory sinks (CSV, templates, Avro).
+//!
+//! Files are written under a temporary name and renamed when complete, so an
+//! interrupted run never leaves a truncated file that looks finished. With
+//! `--encrypt` the bytes are encrypted on their way to the file.
+
+use anyhow::{Context, Result};
+use std::borrow::Cow;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::args::{flag_value, has_flag};
+use crate::encrypt::ENCRYPTED_EXTENSION;
+use crate::sink::TableSink;
+use crate::types::CsvOptions;
+use crate::workspace::{track_partial, untrack_partial};
+
+/// Parse --buffer-size option from command line
+pub fn parse_buffer_size(args: &[String]) -> Result<Option<usize>> {
+    flag_value(args, "--buffer-size")?
+        .map(|size| size.parse::<usize>()
+            .ok()
+            .filter(|&n| n > 0)
+            .ok_or_else(|| anyhow::anyhow!("Error: Invalid --buffer-size '{}'. Use a positive number of bytes", size)))
+        .transpose()
+}
+
+/// Parse --fsync flag from command line
+pub fn parse_fsync(args: &[String]) -> bool {
+    has_flag(args, "--fsync")
+}
+
+/// Suffix of files that are still being written
+pub const TEMP_SUFFIX: &str = ".tmp";
+
+/// Destination of an output file's bytes
+enum FileWriter {
+    Plain(BufWriter<File>),
+    #[cfg(feature = "encrypt")]
+    Encrypted(age::stream::StreamWriter<BufWriter<File>>),
+}
+
+impl FileWriter {
+    fn as_write(&mut self) -> &mut dyn Write {
+        match self {
+            FileWriter::Plain(writer) => writer,
+            #[cfg(feature = "encrypt")]
+            FileWriter::Encrypted(writer) => writer,
+        }
+    }
+    
+    /// Write the last encrypted block and the buffer, giving back the file
+    fn into_file(self) -> Result<File> {
+        let flush = |writer: BufWriter<File>| writer.into_inner()
+            .map_err(|e| anyhow::anyhow!("Failed to flush output file: {}", e.error()));
+        match self {
+            FileWriter::Plain(writer) => flush(writer),
+            #[cfg(feature = "encrypt")]
+            FileWriter::Encrypted(writer) => flush(writer.finish().context("Failed to finish encrypting output file")?),
+        }
+    }
+}
+
+/// A buffered output file, written as `<path>.<pid>.tmp` and renamed to `<path>` by
+/// `finish`, so a file under its final name is always complete. With `fsync`
+/// set, `finish` only returns once the data is on disk. A file dropped without
+/// `finish` (after an error) is removed.
+pub struct OutputFile {
+    writer: Option<FileWriter>,
+    /// Bytes written so far, before encryption
+    written: u64,
+    path: PathBuf,
+    temp_path: PathBuf,
+    fsync: bool,
+}
+
+impl OutputFile {
+    /// Create the file with the buffer size, sync behavior and encryption from
+    /// the options. Encrypted files get an `.age` extension after `path`'s.
+    pub fn create(path: &Path, options: &CsvOptions) -> Result<Self> {
+        let path = &output_path(path, options);
+        let temp_path = temp_path(path);
+        let file = File::create(&temp_path)
+            .with_context(|| format!("Failed to create output file '{}'", temp_path.display()))?;
+        track_partial(&temp_path);
+        let writer = match options.buffer_size {
+            Some(size) => BufWriter::with_capacity(size, file),
+            None => BufWriter::new(file),
+        };
+        let writer = if options.encrypt_to.is_empty() {
+            FileWriter::Plain(writer)
+        } else {
+            encrypted(writer, &options.encrypt_to)?
+        };
+        Ok(OutputFile {
+            writer: Some(writer),
+            written: 0,
+            path: path.to_path_buf(),
+            temp_path,
+            fsync: options.fsync,
+        })
+    }
+    
+    /// Final path of the file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    
+    /// Write out the buffer, wait for the file to reach the disk with `fsync`,
+    /// and move it to its final name. Returns the number of bytes written to
+    /// it, before encryption.
+    pub fn finish(mut self) -> Result<u64> {
+        let Some(writer) = self.writer.take() else {
+            return Ok(self.written);
+        };
+        let file = writer.into_file()?;
+        if self.fsync {
+            file.sync_all().context("Failed to sync output file to disk")?;
+        }
+        drop(file);
+        
+        fs::rename(&self.temp_path, &self.path)
+            .with_context(|| format!("Failed to move '{}' to '{}'", self.temp_path.display(), self.path.display()))?;
+        untrack_partial(&self.temp_path);
+        if self.fsync {
+            // Make the rename itself durable
+            sync_dir(&self.path);
+        }
+        Ok(self.written)
+    }
+}
+
+impl Write for OutputFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self.writer.as_mut() {
+            Some(writer) => {
+                let written = writer.as_write().write(buf)?;
+                self.written += written as u64;
+                Ok(written)
+            }
+            None => Err(std::io::Error::other("output file already finished")),
+        }
+    }
+    
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self.writer.as_mut() {
+            Some(writer) => writer.as_write().flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for OutputFile {
+    fn drop(&mut self) {
+        if self.writer.take().is_some() {
+            let _ = fs::remove_file(&self.temp_path);
+            untrack_partial(&self.temp_path);
+        }
+    }
+}
+
+#[cfg(feature = "encrypt")]
+fn encrypted(writer: BufWriter<File>, recipients: &[String]) -> Result<FileWriter> {
+    Ok(FileWriter::Encrypted(crate::encrypt::encrypting_writer(recipients, writer)?))
+}
+
+#[cfg(not(feature = "encrypt"))]
+fn encrypted(_writer: BufWriter<File>, _recipients: &[String]) -> Result<FileWriter> {
+    anyhow::bail!("Encrypted output needs a build with --features encrypt")
+}
+
+/// Path a file is written under: `path`, with `.age` appended when encrypting
+pub fn output_path(path: &Path, options: &CsvOptions) -> PathBuf {
+    if options.encrypt_to.is_empty() {
+        return path.to_path_buf();
+    }
+    let mut name = path.as_os_str().to_os_string();
+    name.push(".");
+    name.push(ENCRYPTED_EXTENSION);
+    PathBuf::from(name)
+}
+
+/// Name `path` is written under until it is complete, tagged with the id of
+/// the process writing it
+pub(crate) fn temp_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(format!(".{}{}", std::process::id(), TEMP_SUFFIX));
+    PathBuf::from(name)
+}
+
+#[cfg(unix)]
+fn sync_dir(path: &Path) {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        let _ = File::open(dir).and_then(|dir| dir.sync_all());
+    } else {
+        let _ = File::open(".").and_then(|dir| dir.sync_all());
+    }
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_path: &Path) {}
+
+/// Remove `*.<extension>.<pid>.tmp` files left in a directory by a run that
+/// was killed while writing. Only files tagged with this process's id or with
+/// the id of a process that is no longer running are removed, so the files of
+/// another run writing to the same directory are left alone. Returns the
+/// number of files removed.
+pub fn remove_stale_temp_files(dir: &Path, extension: &str) -> Result<usize> {
+    let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+    let mut removed = 0;
+    
+    for entry in fs::read_dir(dir).with_context(|| format!("Failed to read directory '{}'", dir.display()))? {
+        let path = entry?.path();
+        let is_stale = path.file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| temp_file_pid(name, extension))
+            .is_some_and(|pid| pid == std::process::id() || !process_is_running(pid));
+        if is_stale && path.is_file() {
+            fs::remove_file(&path)
+                .with_context(|| format!("Failed to remove stale temporary file '{}'", path.display()))?;
+            removed += 1;
+        }
+    }
+    
+    Ok(removed)
+}
+
+/// Id of the process that wrote a `<name>.<extension>.<pid>.tmp` file
+fn temp_file_pid(name: &str, extension: &str) -> Option<u32> {
+    let (rest, pid) = name.strip_suffix(TEMP_SUFFIX)?.rsplit_once('.')?;
+    rest.strip_suffix(extension)?.strip_suffix('.')?;
+    pid.parse().ok()
+}
+
+#[cfg(target_os = "linux")]
+fn process_is_running(pid: u32) -> bool {
+    Path::new("/proc").join(pid.to_string()).exists()
+}
+
+/// Without a portable way to tell, any other process may still be writing
+#[cfg(not(target_os = "linux"))]
+fn process_is_running(_pid: u32) -> bool {
+    true
+}
+
+/// Table sink writing a format to an `OutputFile`, which is finished after the
+/// format's own trailer is written
+pub(crate) struct FileTableSink<S> {
+    sink: S,
+    /// Completes the format and gives back the file
+    into_file: fn(S) -> Result<OutputFile>,
+    location: String,
+}
+
+impl<S: TableSink> FileTableSink<S> {
+    pub(crate) fn new(sink: S, into_file: fn(S) -> Result<OutputFile>, location: String) -> Self {
+        FileTableSink { sink, into_file, location }
+    }
+}
+
+impl<S: TableSink> TableSink for FileTableSink<S> {
+    fn write_row(&mut self, row: &[String]) -> Result<()> {
+        self.sink.write_row(row)
+    }
+    
+    fn write_fields(&mut self, row: &[Cow<'_, str>]) -> Result<()> {
+        self.sink.write_fields(row)
+    }
+    
+    fn finish(self: Box<Self>) -> Result<String> {
+        Ok(self.finish_with_size()?.0)
+    }
+    
+    fn finish_with_size(self: Box<Self>) -> Result<(String, Option<u64>)> {
+        let bytes = (self.into_file)(self.sink)?.finish()?;
+        Ok((self.location, Some(bytes)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_output_file_with_options() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("output.txt");
+        let options = CsvOptions { buffer_size: Some(4), fsync: true, ..Default::default() };
+        
+        let mut file = OutputFile::create(&path, &options).unwrap();
+        file.write_all(b"hello, world").unwrap();
+        assert_eq!(file.finish().unwrap(), 12);
+        
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "hello, world");
+        assert!(!temp_path(&path).exists());
+    }
+    
+    #[test]
+    fn test_unfinished_output_file_is_not_visible() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let path = dir.join("users.csv");
+        
+        let mut file = OutputFile::create(&path, &CsvOptions::default()).unwrap();
+        file.write_all(b"id\n1\n").unwrap();
+        assert!(!path.exists());
+        assert!(temp_path(&path).exists());
+        drop(file);
+        assert!(!temp_path(&path).exists());
+        
+        // A temporary file left by a killed run with this process's id, one
+        // of a run that is still writing, and files that are not ours
+        std::fs::write(temp_path(&dir.join("orders.csv")), "id\n").unwrap();
+        std::fs::write(dir.join("items.csv.1.tmp"), "id\n").unwrap();
+        std::fs::write(dir.join("orders.csv.tmp"), "id\n").unwrap();
+        std::fs::write(dir.join("notes.tmp"), "keep").unwrap();
+        assert_eq!(remove_stale_temp_files(dir, "csv").unwrap(), 1);
+        assert!(!temp_path(&dir.join("orders.csv")).exists());
+        assert!(dir.join("items.csv.1.tmp").exists());
+        assert!(dir.join("orders.csv.tmp").exists());
+        assert!(dir.join("notes.tmp").exists());
+    }
+    
+    #[test]
+    fn test_parse_buffer_size() {
+        let args: Vec<String> = ["--buffer-size", "1048576"].iter().map(|s| s.to_string()).collect();
+        assert_eq!(parse_buffer_size(&args).unwrap(), Some(1048576));
+        assert_eq!(parse_buffer_size(&[]).unwrap(), None);
+        
+        let args: Vec<String> = ["--buffer-size", "1MB"].iter().map(|s| s.to_string()).collect();
+        assert!(parse_buffer_size(&args).is_err());
+    }
+}
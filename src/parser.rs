@@ -1,193 +1,1210 @@
-use anyhow::{Context, Result};
+use anyhow::Result;
+use memchr::{memchr, memmem};
 use regex::Regex;
-use std::fs;
+use std::borrow::Cow;
+use std::io::BufRead;
 use std::path::Path;
+use std::sync::LazyLock;
 
-use crate::types::Table;
+use crate::input::{read_sql, read_sql_file};
+use crate::newlines::normalize_newlines;
+use crate::pg_types::{apply_pg_type_format, PgTypeFormatter};
+use crate::report::SkippedStatement;
+use crate::value::{ParsedValue, SqlValue};
+use crate::sql_expr::evaluate_expression;
+use crate::statements::{dollar_quote_delimiter, dump_databases, quoted_span_end, split_statements, split_statements_in_databases, statement_kind, DumpDialect, Statement, StatementKind};
+use crate::types::{ExpressionPolicy, ForeignKey, Index, InputOptions, InsertStatement, NewlinePolicy, NumberedRow, Table, ValueOptions};
 
 /// Parse SQL file and extract table schemas and data
 pub fn parse_sql_file<P: AsRef<Path>>(sql_file_path: P) -> Result<(Vec<Table>, String)> {
-    let content = fs::read_to_string(sql_file_path)
-        .context("Failed to read SQL file")?;
+    let (content, _) = read_sql_file(sql_file_path, &InputOptions::default())?;
+    parse_sql_content(content)
+}
+
+/// Parse a dump from any source, such as stdin or a decompression stream
+pub fn parse_sql<R: BufRead>(source: R) -> Result<(Vec<Table>, String)> {
+    let (content, _) = read_sql(source, &InputOptions::default())?;
+    parse_sql_content(content)
+}
+
+/// Extract table schemas from already decoded SQL content
+pub fn parse_sql_content(content: String) -> Result<(Vec<Table>, String)> {
+    let mut tables: Vec<Table> = Vec::new();
+    
+    let create_table_regex = create_table_regex()?;
+    let drop_table_regex = Regex::new(r#"(?i)^DROP\s+TABLE\s+(?:IF\s+EXISTS\s+)?([`"\[\]\w.,\s]+?)\s*(?:CASCADE|RESTRICT)?\s*$"#)?;
     
-    let mut tables = Vec::new();
+    // Replay CREATE and DROP statements in file order so a table that is dropped
+    // and re-created is only recorded once, with its last definition. Other
+    // statements, such as function bodies, are never searched for tables.
+    let namespaced = dump_databases(&content).len() > 1;
+    let backslash_escapes = DumpDialect::detect(&content).backslash_escapes();
+    for (database, statement) in split_statements_in_databases(&content) {
+        let text = statement.text;
+        if statement.kind() == StatementKind::DropTable {
+            let Some(captures) = drop_table_regex.captures(text) else {
+                continue;
+            };
+            for name in captures[1].split(',') {
+                let schema = unquote_identifier(name).rsplit_once('.').map(|(schema, _)| unqualified_name(schema));
+                let name = namespaced_name(&unqualified_name(name), database, namespaced);
+                // Unqualified names match tables in any schema
+                tables.retain(|t| t.name != name || (schema.is_some() && t.schema.is_some() && t.schema != schema));
+            }
+            continue;
+        }
+        
+        if statement.kind() != StatementKind::CreateTable {
+            continue;
+        }
+        let Some(mut table) = create_table(text, &create_table_regex, backslash_escapes) else {
+            continue;
+        };
+        table.database = database.map(str::to_string);
+        table.name = namespaced_name(&table.name, database, namespaced);
+        for foreign_key in &mut table.foreign_keys {
+            foreign_key.references_table = namespaced_name(&foreign_key.references_table, database, namespaced);
+        }
+        
+        // INSERTs are matched by table name, so rows of every definition end up in one CSV
+        match tables.iter_mut().find(|t| t.name == table.name && t.schema == table.schema) {
+            Some(existing) => *existing = table,
+            None => tables.push(table),
+        }
+    }
     
-    // Generic regex to match any CREATE TABLE statement
-    let create_table_regex = Regex::new(r"(?i)(?s)CREATE TABLE\s+(\w+)\s*\((.*?)\)(?:\s|;)")?;
+    synthesize_insert_only_tables(&content, &mut tables, namespaced)?;
+    attach_create_indexes(&content, &mut tables, namespaced)?;
     
-    for captures in create_table_regex.captures_iter(&content) {
-        let table_name = captures.get(1).unwrap().as_str();
-        let columns_text = captures.get(2).unwrap().as_str();
-        let columns = parse_table_columns(columns_text);
+    Ok((tables, content))
+}
+
+/// Name of a table of `database`: `db.table` when the dump covers several
+/// databases, so tables of the same name in different databases stay apart
+fn namespaced_name(name: &str, database: Option<&str>, namespaced: bool) -> String {
+    match database {
+        Some(database) if namespaced => format!("{}.{}", database, name),
+        _ => name.to_string(),
+    }
+}
+
+/// The database of a table and its name in the dump's statements. Without the
+/// table's schema, a name such as `shop.orders` is taken apart when the dump
+/// covers several `databases` (see `dump_databases`) and `shop` is one of them.
+fn database_and_name<'a>(databases: &[&str], table_name: &'a str, table: Option<&'a Table>) -> (Option<&'a str>, &'a str) {
+    let database = match table {
+        Some(table) => table.database.as_deref(),
+        None => table_name.split_once('.')
+            .map(|(database, _)| database)
+            .filter(|database| databases.len() > 1 && databases.contains(database)),
+    };
+    let name = database
+        .and_then(|database| table_name.strip_prefix(database)?.strip_prefix('.'))
+        .unwrap_or(table_name);
+    (database, name)
+}
+
+/// Parse a single CREATE TABLE statement without a dump around it. Use
+/// `Table::column_definitions` for everything declared about each column.
+pub fn parse_create_table(statement: &str) -> Result<Table> {
+    let mut statements = split_statements(statement);
+    let (Some(text), None) = (statements.next().map(|s| s.text), statements.next()) else {
+        anyhow::bail!("Error: Expected a single CREATE TABLE statement");
+    };
+    if statement_kind(text) != StatementKind::CreateTable {
+        anyhow::bail!("Error: Not a CREATE TABLE statement: {}", text.chars().take(SKIPPED_TEXT_CHARS).collect::<String>());
+    }
+    
+    create_table(text, &create_table_regex()?, DumpDialect::detect(statement).backslash_escapes())
+        .ok_or_else(|| anyhow::anyhow!("Error: Could not read the column list of: {}", text.chars().take(SKIPPED_TEXT_CHARS).collect::<String>()))
+}
+
+/// Generic regex to match the start of any CREATE TABLE statement; the body
+/// is found by matching parentheses so nested constraint lists stay intact
+fn create_table_regex() -> Result<Regex> {
+    Ok(Regex::new(&format!(
+        r"(?i)^CREATE\s+TABLE\s+(?:IF\s+NOT\s+EXISTS\s+)?(?:({})\.)?({})\s*\(", IDENTIFIER, IDENTIFIER
+    ))?)
+}
+
+/// The table a CREATE TABLE statement defines, or `None` if it has no column list
+fn create_table(text: &str, create_table_regex: &Regex, backslash_escapes: bool) -> Option<Table> {
+    let captures = create_table_regex.captures(text)?;
+    let table_name = unquote_identifier(captures.get(2).unwrap().as_str());
+    let body_start = captures.get(0).unwrap().end();
+    let body_len = find_closing_paren(&text[body_start..], backslash_escapes)?;
+    let mut table = table_definition(&table_name, &text[body_start..body_start + body_len], backslash_escapes);
+    table.schema = captures.get(1).map(|schema| unquote_identifier(schema.as_str()));
+    // Kept so the table is reported instead of vanishing; its columns come from its INSERTs
+    table.headerless = table.columns.is_empty();
+    Some(table)
+}
+
+/// Add a table for every INSERT target without a CREATE TABLE so INSERT-only dumps
+/// are still exported, and give headerless tables columns. Headers come from the
+/// INSERT column list, or are `col1`..`colN` after the number of values in the first row.
+fn synthesize_insert_only_tables(content: &str, tables: &mut Vec<Table>, namespaced: bool) -> Result<()> {
+    let insert_regex = Regex::new(&format!(
+        r#"^{}(?:{}\.)?({})\s*(?:\(([^)]*)\))?\s*(?i:VALUES?)\s*\("#, INSERT_INTO, IDENTIFIER, IDENTIFIER
+    ))?;
+    let backslash_escapes = DumpDialect::detect(content).backslash_escapes();
+    
+    for (database, statement) in split_statements_in_databases(content).filter(|(_, statement)| statement.kind() == StatementKind::Insert) {
+        let Some(captures) = insert_regex.captures(statement.text) else {
+            continue;
+        };
+        let name = namespaced_name(&unquote_identifier(&captures[1]), database, namespaced);
+        let headerless = tables.iter().position(|t| t.name == name && t.headerless && t.columns.is_empty());
+        if headerless.is_none() && tables.iter().any(|t| t.name == name) {
+            continue;
+        }
         
-        if !columns.is_empty() {
-            let num_columns = columns.len();
-            println!("Found table: {} with {} columns", table_name, num_columns);
-            tables.push(Table {
-                name: table_name.to_string(),
+        let columns = match captures.get(2) {
+            Some(column_list) => parse_identifier_list(column_list.as_str()),
+            None => {
+                let values_start = captures.get(0).unwrap().end();
+                let Some(first_row) = split_row_tuples(&statement.text[values_start - 1..], backslash_escapes).into_iter().next() else {
+                    continue;
+                };
+                let count = split_values(first_row, backslash_escapes).len();
+                (1..=count).map(|i| format!("col{}", i)).collect()
+            }
+        };
+        match headerless {
+            Some(position) => tables[position].columns = columns,
+            None => tables.push(Table {
+                name,
+                database: database.map(str::to_string),
                 columns,
-            });
+                synthesized: true,
+                ..Default::default()
+            }),
         }
     }
     
-    Ok((tables, content))
+    Ok(())
 }
 
 /// Parse column definitions from CREATE TABLE statement
 pub fn parse_table_columns(columns_text: &str) -> Vec<String> {
-    let mut columns = Vec::new();
-    
-    // Join all lines and split by comma to handle multi-line column definitions
-    let joined = columns_text.lines()
-        .map(|l| l.trim())
-        .collect::<Vec<_>>()
-        .join(" ");
-    
-    // Split by comma and extract column names
-    for part in joined.split(',') {
-        let part = part.trim();
-        if !part.is_empty() 
-            && !part.starts_with("FOREIGN KEY") 
-            && !part.starts_with("PRIMARY KEY") {
-            // Extract the column name (first word)
-            if let Some(first_word) = part.split_whitespace().next() {
-                let col_name = first_word.trim();
-                if !col_name.is_empty() && col_name.chars().all(|c| c.is_alphanumeric() || c == '_') {
-                    columns.push(col_name.to_string());
-                }
+    parse_table_definition("", columns_text).columns
+}
+
+/// Parse column definitions and key constraints from the body of a CREATE TABLE statement
+pub fn parse_table_definition(table_name: &str, columns_text: &str) -> Table {
+    table_definition(table_name, columns_text, DumpDialect::detect(columns_text).backslash_escapes())
+}
+
+/// The table a CREATE TABLE body defines, reading strings such as DEFAULTs
+/// and COMMENTs the way the dump's dialect writes them
+fn table_definition(table_name: &str, columns_text: &str, backslash_escapes: bool) -> Table {
+    let mut table = Table {
+        name: table_name.to_string(),
+        ..Default::default()
+    };
+    
+    for part in split_top_level(columns_text, backslash_escapes) {
+        // Normalize whitespace so multi-line definitions behave like single-line ones
+        let part = part.split_whitespace().collect::<Vec<_>>().join(" ");
+        if part.is_empty() {
+            continue;
+        }
+        
+        // Named constraints: CONSTRAINT <name> PRIMARY KEY (...) etc.
+        let part = if part.to_uppercase().starts_with("CONSTRAINT ") {
+            part.splitn(3, ' ').nth(2).unwrap_or("").to_string()
+        } else {
+            part
+        };
+        let upper = part.to_uppercase();
+        
+        if upper.starts_with("PRIMARY KEY") {
+            table.primary_key = first_identifier_list(&part);
+        } else if upper.starts_with("FOREIGN KEY") {
+            if let Some(foreign_key) = parse_foreign_key(&part) {
+                table.foreign_keys.push(foreign_key);
+            }
+        } else if upper.starts_with("UNIQUE") {
+            // UNIQUE (a, b), UNIQUE KEY name (a, b) or UNIQUE INDEX name (a, b)
+            let columns = first_identifier_list(&part);
+            if !columns.is_empty() {
+                table.unique_constraints.push(columns);
+            }
+        } else if is_index_definition(&part) {
+            // MySQL inline index: KEY name (a, b)
+            let name = part.split_whitespace()
+                .skip(1)
+                .find(|word| !matches!(word.to_uppercase().as_str(), "KEY" | "INDEX"))
+                .filter(|word| !word.starts_with('('))
+                .map(|word| unquote_identifier(word.split('(').next().unwrap_or(word)))
+                .unwrap_or_default();
+            table.indexes.push(Index {
+                name,
+                columns: first_identifier_list(&part),
+                unique: false,
+            });
+        } else if upper.starts_with("CHECK") {
+            continue;
+        } else if let Some(first_word) = part.split_whitespace().next() {
+            // Column definition: the column name is the first word
+            let col_name = unquote_identifier(first_word);
+            if col_name.is_empty() || !col_name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                continue;
+            }
+            
+            let words: Vec<&str> = upper.split_whitespace().collect();
+            if words.windows(2).any(|pair| pair == ["PRIMARY", "KEY"]) {
+                table.primary_key.push(col_name.clone());
+            }
+            if words.contains(&"UNIQUE") {
+                table.unique_constraints.push(vec![col_name.clone()]);
             }
+            if is_generated_column(&part) {
+                table.generated_columns.push(col_name.clone());
+            }
+            if words.windows(2).any(|pair| pair == ["NOT", "NULL"]) {
+                table.not_null_columns.push(col_name.clone());
+            }
+            if words.contains(&"REFERENCES")
+                && let Some((references_table, references_columns)) = parse_references(&part)
+            {
+                table.foreign_keys.push(ForeignKey {
+                    columns: vec![col_name.clone()],
+                    references_table,
+                    references_columns,
+                });
+            }
+            
+            table.columns.push(col_name);
+            table.column_types.push(column_type(&part[first_word.len()..]));
+            table.column_defaults.push(column_default(&part));
         }
     }
     
-    columns
+    table
+}
+
+/// Get the declared type at the start of a column definition (after the column
+/// name), e.g. `DOUBLE PRECISION`, `VARCHAR(255)` or `TIMESTAMP WITH TIME ZONE`
+fn column_type(definition: &str) -> String {
+    static TYPE_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(
+        r"(?i)^\s*([a-z_]\w*(?:\s+(?:precision|varying|unsigned|with(?:out)?\s+time\s+zone))*(?:\s*\([^)]*\))?(?:\s+(?:unsigned|with(?:out)?\s+time\s+zone))*(?:\s*\[\])?)"
+    ).unwrap());
+    
+    TYPE_REGEX.captures(definition)
+        .map(|captures| captures[1].to_string())
+        .filter(|column_type| !matches!(
+            column_type.to_uppercase().as_str(),
+            "NOT" | "NULL" | "PRIMARY" | "UNIQUE" | "DEFAULT" | "REFERENCES" | "CHECK" | "CONSTRAINT" | "GENERATED"
+        ))
+        .unwrap_or_default()
+}
+
+/// Get the constant DEFAULT of a column definition: a string, number, boolean or
+/// NULL. Expressions such as `CURRENT_TIMESTAMP` or `nextval(...)` give an empty value.
+fn column_default(definition: &str) -> String {
+    static DEFAULT_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)\bDEFAULT\s+('(?:[^']|'')*'|-?[\w.]+)(\s*\()?").unwrap());
+    
+    let Some(captures) = DEFAULT_REGEX.captures(definition) else {
+        return String::new();
+    };
+    let value = &captures[1];
+    let is_constant = value.starts_with('\'')
+        || value.parse::<f64>().is_ok()
+        || ["NULL", "TRUE", "FALSE"].iter().any(|word| value.eq_ignore_ascii_case(word));
+    if is_constant && captures.get(2).is_none() {
+        clean_value(value, false).into_owned()
+    } else {
+        String::new()
+    }
+}
+
+/// Check whether a column definition computes its value from other columns:
+/// `GENERATED ALWAYS AS (expr) STORED`, or MySQL's `AS (expr) [VIRTUAL|STORED]`.
+/// Identity columns (`GENERATED ALWAYS AS IDENTITY`) are stored like any other.
+fn is_generated_column(definition: &str) -> bool {
+    static GENERATED_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)\bAS\s*\(").unwrap());
+    
+    // Only look after the column name, which could itself be called `as`
+    let rest = definition.split_once(' ').map(|(_, rest)| rest).unwrap_or_default();
+    GENERATED_REGEX.find_iter(rest).any(|found| !in_string_literal(&rest[..found.start()]))
 }
 
-/// Extract INSERT VALUES from SQL for a specific table
+/// Whether the end of `text` is inside a single-quoted string, e.g. a DEFAULT or COMMENT
+fn in_string_literal(text: &str) -> bool {
+    text.matches('\'').count() % 2 == 1
+}
+
+/// Check whether a definition is a MySQL inline index (`KEY name (a, b)`) rather
+/// than a column that happens to be called `key` or `index` (`key VARCHAR(255)`)
+fn is_index_definition(definition: &str) -> bool {
+    static INDEX_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(
+        r#"(?i)^(?:(?:FULLTEXT|SPATIAL)\s+)?(?:KEY|INDEX)\s+(?:[`"]?\w+[`"]?\s*)?\(\s*([^\s,)]+)"#
+    ).unwrap());
+    
+    INDEX_REGEX.captures(definition)
+        .is_some_and(|captures| !captures[1].chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Attach CREATE INDEX statements to the tables they belong to
+fn attach_create_indexes(content: &str, tables: &mut [Table], namespaced: bool) -> Result<()> {
+    let create_index_regex = Regex::new(
+        r#"(?i)^CREATE\s+(UNIQUE\s+)?INDEX\s+(?:IF\s+NOT\s+EXISTS\s+)?([`"\[]?[\w.]+[`"\]]?)\s+ON\s+(?:ONLY\s+)?([`"\[]?[\w.]+[`"\]]?)\s*(?:USING\s+\w+\s*)?\("#
+    )?;
+    
+    for (database, statement) in split_statements_in_databases(content).filter(|(_, statement)| statement.kind() == StatementKind::CreateIndex) {
+        let Some(captures) = create_index_regex.captures(statement.text) else {
+            continue;
+        };
+        let body_start = captures.get(0).unwrap().end();
+        let Some(body_len) = find_closing_paren(&statement.text[body_start..], false) else {
+            continue;
+        };
+        let table_name = namespaced_name(&unqualified_name(captures.get(3).unwrap().as_str()), database, namespaced);
+        let index = Index {
+            name: unqualified_name(captures.get(2).unwrap().as_str()),
+            columns: parse_identifier_list(&statement.text[body_start..body_start + body_len]),
+            unique: captures.get(1).is_some(),
+        };
+        
+        let position = tables.iter().position(|t| t.name == table_name)
+            .or_else(|| tables.iter().position(|t| t.name.eq_ignore_ascii_case(&table_name)));
+        if let Some(position) = position {
+            tables[position].indexes.push(index);
+        }
+    }
+    
+    Ok(())
+}
+
+/// Parse a table-level FOREIGN KEY (a, b) REFERENCES other(x, y) constraint
+fn parse_foreign_key(definition: &str) -> Option<ForeignKey> {
+    let columns = first_identifier_list(definition);
+    let (references_table, references_columns) = parse_references(definition)?;
+    if columns.is_empty() {
+        return None;
+    }
+    
+    Some(ForeignKey {
+        columns,
+        references_table,
+        references_columns,
+    })
+}
+
+/// Parse the REFERENCES other(x, y) clause of a column or constraint definition
+fn parse_references(definition: &str) -> Option<(String, Vec<String>)> {
+    static REFERENCES_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"(?i)REFERENCES\s+([`"\[]?[\w.]+[`"\]]?)\s*(?:\(([^)]*)\))?"#).unwrap());
+    let captures = REFERENCES_REGEX.captures(definition)?;
+    let references_table = unqualified_name(captures.get(1).unwrap().as_str());
+    let references_columns = captures.get(2)
+        .map(|m| parse_identifier_list(m.as_str()))
+        .unwrap_or_default();
+    
+    Some((references_table, references_columns))
+}
+
+/// Extract the identifiers from the first parenthesized list in a definition
+fn first_identifier_list(definition: &str) -> Vec<String> {
+    definition.find('(')
+        .and_then(|start| {
+            let body = &definition[start + 1..];
+            find_closing_paren(body, false).map(|len| parse_identifier_list(&body[..len]))
+        })
+        .unwrap_or_default()
+}
+
+/// Parse a comma-separated identifier list such as `a, "b" DESC, c(10)`
+fn parse_identifier_list(text: &str) -> Vec<String> {
+    split_top_level(text, false).into_iter()
+        .filter_map(|item| item.split_whitespace().next())
+        .map(|word| unquote_identifier(word.split('(').next().unwrap_or(word)))
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+/// Strip schema qualifiers and identifier quotes (`public.users` -> `users`)
+fn unqualified_name(name: &str) -> String {
+    let name = unquote_identifier(name);
+    match name.rsplit_once('.') {
+        Some((_, last)) => unquote_identifier(last),
+        None => name,
+    }
+}
+
+/// Remove identifier quoting ("name", `name` or [name])
+fn unquote_identifier(identifier: &str) -> String {
+    identifier.trim()
+        .trim_matches(|c| c == '"' || c == '`' || c == '[' || c == ']')
+        .to_string()
+}
+
+/// Split text on commas that are not nested inside parentheses or quotes
+fn split_top_level(text: &str, backslash_escapes: bool) -> Vec<&str> {
+    let bytes = text.as_bytes();
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    let mut i = 0;
+    
+    while i < bytes.len() {
+        if let Some(end) = quoted_span_end(bytes, i, backslash_escapes) {
+            i = end;
+            continue;
+        }
+        match bytes[i] {
+            b'(' => depth += 1,
+            b')' => depth -= 1,
+            b',' if depth == 0 => {
+                parts.push(&text[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    parts.push(&text[start..]);
+    
+    parts
+}
+
+/// Extract INSERT VALUES from SQL for a specific table. `DEFAULT VALUES` and
+/// `VALUES ()` statements give an empty row, as the columns are not known here;
+/// use `extract_table_rows` to have them filled with the column defaults.
 pub fn extract_insert_values(content: &str, table_name: &str) -> Result<Vec<Vec<String>>> {
+    extract_insert_values_with_options(content, table_name, &ValueOptions::default())
+}
+
+/// Extract INSERT VALUES from SQL for a specific table using the given value options
+pub fn extract_insert_values_with_options(content: &str, table_name: &str, options: &ValueOptions) -> Result<Vec<Vec<String>>> {
+    Ok(extract_rows(content, table_name, None, &dump_databases(content), options, false)?
+        .into_iter()
+        .map(|(_, row)| csv_row(row))
+        .collect())
+}
+
+/// Extract INSERT VALUES for a specific table as typed values, so NULL, numbers
+/// and blobs are told apart from strings
+pub fn extract_insert_typed_values(content: &str, table_name: &str, options: &ValueOptions) -> Result<Vec<Vec<SqlValue>>> {
+    Ok(extract_rows(content, table_name, None, &dump_databases(content), options, false)?
+        .into_iter()
+        .map(|(_, row)| row.iter().map(ParsedValue::value).collect())
+        .collect())
+}
+
+/// Extract INSERT VALUES for a table, each row paired with the 1-based line of
+/// the dump it starts on
+pub fn extract_insert_values_with_lines(content: &str, table_name: &str, options: &ValueOptions) -> Result<Vec<NumberedRow>> {
+    Ok(extract_rows(content, table_name, None, &dump_databases(content), options, true)?
+        .into_iter()
+        .map(|(line, row)| (line, csv_row(row)))
+        .collect())
+}
+
+/// The CSV text of a row's values
+fn csv_row(row: Vec<ParsedValue>) -> Vec<String> {
+    row.into_iter().map(ParsedValue::into_csv_string).collect()
+}
+
+/// Parse a single INSERT statement, such as one line of a statement log,
+/// without a dump around it. Values are read as in a dump.
+pub fn parse_insert_statement(statement: &str) -> Result<InsertStatement> {
+    parse_insert_statement_with_options(statement, &ValueOptions::default())
+}
+
+/// Parse a single INSERT statement using the given value options
+pub fn parse_insert_statement_with_options(statement: &str, options: &ValueOptions) -> Result<InsertStatement> {
+    let mut statements = split_statements(statement);
+    let (Some(text), None) = (statements.next().map(|s| s.text), statements.next()) else {
+        anyhow::bail!("Error: Expected a single INSERT statement");
+    };
+    let insert_regex = Regex::new(&format!(
+        r"^{}(?:({})\.)?({}){}", INSERT_INTO, IDENTIFIER, IDENTIFIER, ROWS_START
+    ))?;
+    let captures = insert_regex.captures(text)
+        .ok_or_else(|| anyhow::anyhow!("Error: Not an INSERT ... VALUES statement: {}", text.chars().take(SKIPPED_TEXT_CHARS).collect::<String>()))?;
+    let found = captures.get(0).unwrap();
+    
+    let backslash_escapes = DumpDialect::detect(statement).backslash_escapes();
+    let rows = if found.as_str().ends_with('(') {
+        let tuples = split_row_tuples(&text[found.end() - 1..], backslash_escapes);
+        if tuples.is_empty() {
+            anyhow::bail!("Error: The first row of the INSERT into '{}' is not closed", unquote_identifier(&captures[2]));
+        }
+        tuples.into_iter()
+            .map(|tuple| parse_values(tuple, options, backslash_escapes))
+            .collect()
+    } else {
+        vec![Vec::new()]
+    };
+    
+    Ok(InsertStatement {
+        table: unquote_identifier(&captures[2]),
+        schema: captures.get(1).map(|schema| unquote_identifier(schema.as_str())),
+        columns: captures.get(3).map(|list| parse_identifier_list(list.as_str())).unwrap_or_default(),
+        rows,
+    })
+}
+
+/// The CREATE TABLE and INSERT statements of a table, in dump order. In dumps
+/// of several databases, `db.table` names the table of one database; pass the
+/// dump's `databases` from `dump_databases`, so looking up many tables reads
+/// them once.
+pub fn table_statements<'a>(content: &'a str, databases: &[&str], table_name: &str) -> Result<Vec<Statement<'a>>> {
+    let (database, table_name) = database_and_name(databases, table_name, None);
+    let create_regex = Regex::new(&format!(
+        r"(?i)^CREATE\s+TABLE\s+(?:IF\s+NOT\s+EXISTS\s+)?(?:{}\.)?({})", IDENTIFIER, IDENTIFIER
+    ))?;
+    let insert_regex = Regex::new(&format!(r"^{}(?:{}\.)?({})", INSERT_INTO, IDENTIFIER, IDENTIFIER))?;
+    
+    Ok(split_statements_in_databases(content)
+        .filter(|(in_database, _)| database.is_none() || *in_database == database)
+        .map(|(_, statement)| statement)
+        .filter(|statement| {
+            let regex = match statement.kind() {
+                StatementKind::CreateTable => &create_regex,
+                StatementKind::Insert => &insert_regex,
+                _ => return false,
+            };
+            regex.captures(statement.text).is_some_and(|captures| unquote_identifier(&captures[1]) == table_name)
+        })
+        .collect())
+}
+
+/// INSERT statements whose table and rows cannot be read, such as `INSERT ...
+/// SELECT` or a first row that is never closed. Each one is skipped on its own
+/// while the statements around it are read as usual.
+pub fn find_skipped_inserts(content: &str) -> Result<Vec<SkippedStatement>> {
+    let insert_regex = Regex::new(&format!(
+        r"^{}(?:{}\.)?{}{}", INSERT_INTO, IDENTIFIER, IDENTIFIER, ROWS_START
+    ))?;
+    
+    let backslash_escapes = DumpDialect::detect(content).backslash_escapes();
+    Ok(split_statements(content)
+        .filter(|statement| statement.kind() == StatementKind::Insert)
+        .filter(|statement| match insert_regex.find(statement.text) {
+            // DEFAULT VALUES has no rows to read
+            Some(found) => found.as_str().ends_with('(') && split_row_tuples(&statement.text[found.end() - 1..], backslash_escapes).is_empty(),
+            None => true,
+        })
+        .map(|statement| SkippedStatement {
+            start: statement.offset,
+            end: statement.offset + statement.text.len(),
+            text: statement.text.chars().take(SKIPPED_TEXT_CHARS).collect(),
+        })
+        .collect())
+}
+
+/// Rows of a table's INSERT statements with their line numbers, or 0 for every
+/// line unless `track_lines` is set. With the table's schema, the values of
+/// INSERTs with a column list are placed under the columns they name; without
+/// it, the dump's `databases` tell a `db.table` name apart.
+fn extract_rows<'c>(
+    content: &'c str,
+    table_name: &str,
+    table: Option<&Table>,
+    databases: &[&str],
+    options: &ValueOptions,
+    track_lines: bool,
+) -> Result<Vec<(usize, Vec<ParsedValue<'c>>)>> {
     let mut rows = Vec::new();
+    visit_rows(content, table_name, table, databases, options, track_lines, |line, row| {
+        rows.push((line, row));
+        true
+    })?;
+    Ok(rows)
+}
+
+/// Hand the rows of a table's INSERT statements to `visit` one at a time, as
+/// `extract_rows` reads them. Returns `false` as soon as `visit` does, without
+/// reading the rest of the dump.
+fn visit_rows<'c>(
+    content: &'c str,
+    table_name: &str,
+    table: Option<&Table>,
+    databases: &[&str],
+    options: &ValueOptions,
+    track_lines: bool,
+    mut visit: impl FnMut(usize, Vec<ParsedValue<'c>>) -> bool,
+) -> Result<bool> {
+    // Lines are counted from the last row found, as rows only move forward
+    let mut counted = (0, 1);
+    let mut line_at = |offset: usize| {
+        if !track_lines {
+            return 0;
+        }
+        let (from, line) = counted;
+        counted = (offset, line + content.as_bytes()[from..offset].iter().filter(|&&b| b == b'\n').count());
+        counted.1
+    };
     
-    // Pattern to match INSERT statements - try both with and without quotes
+    // Pattern to match the start of INSERT statements, with the table name quoted or not
     // This handles different SQL dialects (PostgreSQL uses ", MySQL uses `, SQLite supports both)
-    let patterns = vec![
-        format!(r#"(?s)INSERT INTO "{}" VALUES\((.*?)\);"#, regex::escape(table_name)),
-        format!(r"(?s)INSERT INTO {} VALUES\((.*?)\);", regex::escape(table_name)),
-        format!(r#"(?s)INSERT INTO `{}` VALUES\((.*?)\);"#, regex::escape(table_name)),
-    ];
-    
-    for pattern in patterns.iter() {
-        match Regex::new(pattern) {
-            Ok(insert_regex) => {
-                let matches = insert_regex.captures_iter(content);
-                for captures in matches {
-                    let values_str = captures.get(1).unwrap().as_str();
-                    let values_str = handle_replace_function(values_str);
-                    let values = parse_values(&values_str);
-                    rows.push(values);
-                }
-                if !rows.is_empty() {
-                    break;
-                }
+    let (database, table_name) = database_and_name(databases, table_name, table);
+    let name = regex::escape(table_name);
+    let insert_regex = Regex::new(&format!(
+        r#"^{}(?:{}\.)?(?:"{}"|`{}`|{}){}"#, INSERT_INTO, IDENTIFIER, name, name, name, ROWS_START
+    ))?;
+    let backslash_escapes = DumpDialect::detect(content).backslash_escapes();
+    // The pattern only runs on INSERTs naming the table before their rows
+    let name_finder = memmem::Finder::new(table_name);
+    
+    for (in_database, statement) in split_statements_in_databases(content) {
+        if statement.kind() != StatementKind::Insert || (database.is_some() && in_database != database) {
+            continue;
+        }
+        let bytes = statement.text.as_bytes();
+        if name_finder.find(&bytes[..memchr(b'(', bytes).unwrap_or(bytes.len())]).is_none() {
+            continue;
+        }
+        let Some(captures) = insert_regex.captures(statement.text) else {
+            continue;
+        };
+        let found = captures.get(0).unwrap();
+        if !found.as_str().ends_with('(') {
+            // DEFAULT VALUES
+            if !visit(line_at(statement.offset), Vec::new()) {
+                return Ok(false);
+            }
+            continue;
+        }
+        let positions = table.zip(captures.get(1))
+            .and_then(|(table, column_list)| column_positions(table, &parse_identifier_list(column_list.as_str())));
+        
+        // Every row of a multi-row INSERT, starting at the first row's parenthesis
+        for tuple in split_row_tuples(&statement.text[found.end() - 1..], backslash_escapes) {
+            let offset = statement.offset + (tuple.as_ptr() as usize - statement.text.as_ptr() as usize);
+            let mut row = parse_typed_values(tuple, options, backslash_escapes);
+            if let (Some(table), Some(positions)) = (table, &positions) {
+                row = place_values(table, positions, row);
             }
-            Err(e) => {
-                eprintln!("Warning: Failed to compile regex pattern: {}", e);
+            if !visit(line_at(offset), row) {
+                return Ok(false);
             }
         }
     }
     
-    Ok(rows)
+    Ok(true)
+}
+
+/// Positions in the table of the columns an INSERT lists, matched by name (or
+/// ignoring case). `None` if a listed column is not in the table, or the list
+/// is the table's own columns in order, so the values are kept as written.
+fn column_positions(table: &Table, column_list: &[String]) -> Option<Vec<usize>> {
+    let positions = column_list.iter()
+        .map(|name| table.columns.iter().position(|c| c == name)
+            .or_else(|| table.columns.iter().position(|c| c.eq_ignore_ascii_case(name))))
+        .collect::<Option<Vec<usize>>>()?;
+    let in_order = positions.len() == table.columns.len() && positions.iter().enumerate().all(|(i, &p)| i == p);
+    (!in_order).then_some(positions)
+}
+
+/// Place the values of a row under the columns its INSERT lists. Columns left
+/// out, such as serial ids, get their constant DEFAULT or an empty cell. Rows
+/// whose values do not match the column list are kept as written.
+fn place_values<'c>(table: &Table, positions: &[usize], row: Vec<ParsedValue<'c>>) -> Vec<ParsedValue<'c>> {
+    if row.len() != positions.len() {
+        return row;
+    }
+    let mut placed: Vec<ParsedValue> = (0..table.columns.len())
+        .map(|i| ParsedValue::text(Cow::Owned(table.column_defaults.get(i).cloned().unwrap_or_default())))
+        .collect();
+    for (value, &position) in row.into_iter().zip(positions) {
+        placed[position] = value;
+    }
+    placed
+}
+
+/// Extract the rows of a table, filling `DEFAULT VALUES` rows with the column defaults
+pub fn extract_table_rows(content: &str, table: &Table) -> Result<Vec<Vec<String>>> {
+    extract_table_rows_with_options(content, table, &ValueOptions::default())
+}
+
+/// Extract the rows of a table using the given value options, rendering Postgres
+/// types according to the declared column types
+pub fn extract_table_rows_with_options(content: &str, table: &Table, options: &ValueOptions) -> Result<Vec<Vec<String>>> {
+    let rows = extract_rows(content, &table.name, Some(table), &[], options, false)?
+        .into_iter()
+        .map(|(_, row)| csv_row(row))
+        .collect();
+    Ok(complete_table_rows(table, rows, options))
+}
+
+/// Extract the rows of a table as `extract_table_rows_with_options` does, each
+/// paired with the 1-based line of the dump it starts on
+pub fn extract_table_rows_with_lines(content: &str, table: &Table, options: &ValueOptions) -> Result<Vec<NumberedRow>> {
+    let (lines, rows): (Vec<usize>, Vec<Vec<String>>) = extract_rows(content, &table.name, Some(table), &[], options, true)?
+        .into_iter()
+        .map(|(line, row)| (line, csv_row(row)))
+        .unzip();
+    Ok(lines.into_iter().zip(complete_table_rows(table, rows, options)).collect())
+}
+
+/// Hand the rows of a table to `visit` one at a time, completed as
+/// `extract_table_rows_with_options` completes them. Values that need no
+/// unescaping are borrowed from `content`, so a row reaches a sink's
+/// `write_fields` without being copied. Returns `false` as soon as `visit`
+/// does, without reading the rest of the dump.
+pub fn for_each_table_row<'c>(
+    content: &'c str,
+    table: &Table,
+    options: &ValueOptions,
+    mut visit: impl FnMut(Vec<Cow<'c, str>>) -> bool,
+) -> Result<bool> {
+    visit_table_rows(content, table, options, false, |_, row| visit(row))
+}
+
+/// Hand the rows of a table to `visit` one at a time like `for_each_table_row`,
+/// each with the 1-based line of the dump it starts on
+pub fn for_each_table_row_with_lines<'c>(
+    content: &'c str,
+    table: &Table,
+    options: &ValueOptions,
+    visit: impl FnMut(usize, Vec<Cow<'c, str>>) -> bool,
+) -> Result<bool> {
+    visit_table_rows(content, table, options, true, visit)
 }
 
-/// Handle replace() function in SQL values
-fn handle_replace_function(values_str: &str) -> String {
-    if !values_str.contains("replace(") {
-        return values_str.to_string();
+fn visit_table_rows<'c>(
+    content: &'c str,
+    table: &Table,
+    options: &ValueOptions,
+    track_lines: bool,
+    mut visit: impl FnMut(usize, Vec<Cow<'c, str>>) -> bool,
+) -> Result<bool> {
+    let pg_types = PgTypeFormatter::new(&table.column_types, options.pg_types);
+    visit_rows(content, &table.name, Some(table), &[], options, track_lines, |line, row| {
+        let mut row: Vec<Cow<'c, str>> = row.into_iter().map(ParsedValue::into_text).collect();
+        complete_row(table, &mut row);
+        if let Some(ref pg_types) = pg_types {
+            pg_types.format_row(&mut row);
+        }
+        visit(line, row)
+    })
+}
+
+/// Fill `DEFAULT VALUES` rows with the column defaults, give generated columns
+/// an empty cell in rows that leave them out, and render Postgres types
+fn complete_table_rows(table: &Table, mut rows: Vec<Vec<String>>, options: &ValueOptions) -> Vec<Vec<String>> {
+    for row in rows.iter_mut() {
+        complete_row(table, row);
+    }
+    apply_pg_type_format(&table.column_types, &mut rows, options.pg_types);
+    rows
+}
+
+/// Fill a `DEFAULT VALUES` row with the column defaults, and give generated
+/// columns an empty cell in a row that leaves them out
+fn complete_row<S: From<String> + Default>(table: &Table, row: &mut Vec<S>) {
+    if row.is_empty() && !table.columns.is_empty() {
+        *row = (0..table.columns.len())
+            .map(|i| S::from(table.column_defaults.get(i).cloned().unwrap_or_default()))
+            .collect();
     }
+    if !table.generated_columns.is_empty() && row.len() == table.columns.len() - table.generated_columns.len() {
+        let mut values = std::mem::take(row).into_iter();
+        *row = table.columns.iter()
+            .map(|column| if table.generated_columns.contains(column) {
+                S::default()
+            } else {
+                values.next().unwrap_or_default()
+            })
+            .collect();
+    }
+}
+
+/// Split the rows of a VALUES list, `(1, 'a'), (2, 'b')`, into the text inside
+/// each row's parentheses. String state is tracked throughout, including doubled
+/// and backslash-escaped quotes, so parentheses and commas inside values do not
+/// end a row. Anything after the last row, such as `ON CONFLICT ...`, is ignored.
+fn split_row_tuples(text: &str, backslash_escapes: bool) -> Vec<&str> {
+    let mut tuples = Vec::new();
+    let mut rest = text;
     
-    let replace_regex = Regex::new(r"(?s)replace\('(.*?)',.*?\)").unwrap();
+    while let Some(after_paren) = rest.strip_prefix('(') {
+        let Some(length) = find_closing_paren(after_paren, backslash_escapes) else {
+            break;
+        };
+        tuples.push(&after_paren[..length]);
+        
+        let after_row = after_paren[length + 1..].trim_start();
+        match after_row.strip_prefix(',') {
+            Some(next) => rest = next.trim_start(),
+            None => break,
+        }
+    }
     
-    replace_regex.replace_all(values_str, |caps: &regex::Captures| {
-        let json_content = caps.get(1)
-            .map_or("", |m| m.as_str())
-            .replace("\\'", "'")
-            .replace("\\n", "\n");
-        format!("'{}'", json_content)
-    }).to_string()
+    tuples
 }
 
-/// Parse comma-separated values from INSERT statement
-fn parse_values(values_str: &str) -> Vec<String> {
-    let mut values = Vec::new();
-    let mut current_value = String::new();
-    let mut in_quotes = false;
-    let mut quote_char = ' ';
-    let chars: Vec<char> = values_str.chars().collect();
+/// Find the parenthesis closing an already opened one, such as a row's or a
+/// CREATE TABLE body's, given the text after it. Nested parentheses and
+/// quoted text are skipped the same way the statement splitter skips them.
+fn find_closing_paren(text: &str, backslash_escapes: bool) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let mut depth = 1;
     let mut i = 0;
     
-    while i < chars.len() {
-        let char = chars[i];
-        
-        if !in_quotes {
-            if char == '\'' || char == '"' {
-                in_quotes = true;
-                quote_char = char;
-                current_value.push(char);
-            } else if char == ',' {
-                values.push(current_value.trim().to_string());
-                current_value.clear();
-            } else {
-                current_value.push(char);
-            }
-        } else {
-            if char == quote_char {
-                // Check if it's an escaped quote
-                if i + 1 < chars.len() && chars[i + 1] == quote_char {
-                    current_value.push(char);
-                    current_value.push(char);
-                    i += 1; // Skip the next quote
-                } else {
-                    in_quotes = false;
-                    quote_char = ' ';
-                    current_value.push(char);
+    while i < bytes.len() {
+        if let Some(end) = quoted_span_end(bytes, i, backslash_escapes) {
+            i = end;
+            continue;
+        }
+        match bytes[i] {
+            b'(' => depth += 1,
+            b')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
                 }
-            } else {
-                current_value.push(char);
             }
+            _ => {}
+        }
+        i += 1;
+    }
+    
+    None
+}
+
+/// A table or schema name, quoted in any dialect's style or bare
+const IDENTIFIER: &str = r#"(?:`[^`]+`|"[^"]+"|\[[^\]]+\]|\w+)"#;
+
+/// Characters of a skipped statement kept in the report
+const SKIPPED_TEXT_CHARS: usize = 200;
+
+/// Start of an INSERT statement up to the table name, in any case and spacing
+const INSERT_INTO: &str = r"(?i:INSERT\s+INTO)\s+";
+
+/// After the table name: an optional column list (captured) and `VALUES` (or MySQL's `VALUE`)
+/// up to the first row, or `DEFAULT VALUES` ending the statement
+const ROWS_START: &str = r"(?:(?:\s*\(([^)]*)\))?\s*(?i:VALUES?)\s*\(|\s+(?i:DEFAULT\s+VALUES)\s*$)";
+
+/// Evaluate the `replace()` calls SQLite's `.dump` writes for strings holding
+/// line breaks, `replace('a\nb','\n',char(10))`, possibly nested for `\r`.
+/// The replacement is a string or `char(N, ...)`. Returns `None` for anything else.
+fn evaluate_replace(value: &str, backslash_escapes: bool) -> Option<String> {
+    let value = value.trim();
+    let arguments = value.get(..8)
+        .filter(|name| name.eq_ignore_ascii_case("replace("))
+        .and_then(|_| value[8..].strip_suffix(')'))?;
+    let [text, from, to] = split_values(arguments, backslash_escapes).try_into().ok()?;
+    let text = evaluate_replace(text, backslash_escapes).or_else(|| string_literal(text, backslash_escapes))?;
+    let from = string_literal(from, backslash_escapes)?;
+    let to = string_literal(to, backslash_escapes).or_else(|| char_function(to))?;
+    Some(if from.is_empty() { text } else { text.replace(&from, &to) })
+}
+
+/// The text of a quoted string literal
+fn string_literal(value: &str, backslash_escapes: bool) -> Option<String> {
+    let value = value.trim();
+    (value.len() >= 2 && value.starts_with('\'') && value.ends_with('\'')).then(|| clean_value(value, backslash_escapes).into_owned())
+}
+
+/// The text of SQLite's `char(N, ...)`, the characters with the given code points
+fn char_function(value: &str) -> Option<String> {
+    let value = value.trim();
+    let codes = value.get(..5)
+        .filter(|name| name.eq_ignore_ascii_case("char("))
+        .and_then(|_| value[5..].strip_suffix(')'))?;
+    codes.split(',')
+        .map(|code| code.trim().parse::<u32>().ok().and_then(char::from_u32))
+        .collect()
+}
+
+/// Parse comma-separated values from INSERT statement. Commas inside function
+/// calls and parentheses, as in `CAST('5' AS INT)`, do not separate values.
+fn parse_values(values_str: &str, options: &ValueOptions, backslash_escapes: bool) -> Vec<String> {
+    parse_typed_values(values_str, options, backslash_escapes).into_iter().map(ParsedValue::into_csv_string).collect()
+}
+
+/// Parse the values of a row into typed values. Values stay borrowed from the
+/// row's text unless unescaping or evaluating them changes it. With
+/// `backslash_escapes`, strings are read as MySQL writes them.
+fn parse_typed_values<'c>(values_str: &'c str, options: &ValueOptions, backslash_escapes: bool) -> Vec<ParsedValue<'c>> {
+    split_values(values_str, backslash_escapes)
+        .into_iter()
+        .map(|value| {
+            if let Some(literal) = ParsedValue::from_literal(value) {
+                return literal;
+            }
+            // Computing expressions if asked to
+            let evaluated = match options.expressions {
+                ExpressionPolicy::Evaluate => evaluate_expression(value),
+                ExpressionPolicy::Verbatim => None,
+            };
+            if let Some(result) = evaluated {
+                return match normalize_newlines(&result, options.newlines) {
+                    Cow::Owned(normalized) => ParsedValue::computed(normalized),
+                    Cow::Borrowed(_) => ParsedValue::computed(result),
+                };
+            }
+            let text = match evaluate_replace(value, backslash_escapes) {
+                Some(replaced) => Cow::Owned(replaced),
+                None => clean_value(value, backslash_escapes),
+            };
+            match options.newlines {
+                NewlinePolicy::Keep => ParsedValue::text(text),
+                policy => match normalize_newlines(&text, policy) {
+                    Cow::Owned(normalized) => ParsedValue::text(Cow::Owned(normalized)),
+                    Cow::Borrowed(_) => ParsedValue::text(text),
+                },
+            }
+        })
+        .collect()
+}
+
+/// Split a row's text at the commas between its values, outside strings and
+/// parentheses. The values are trimmed slices of the text.
+fn split_values(values_str: &str, backslash_escapes: bool) -> Vec<&str> {
+    let bytes = values_str.as_bytes();
+    let mut values = Vec::new();
+    let mut start = 0;
+    let mut depth = 0;
+    let mut i = 0;
+    
+    while i < bytes.len() {
+        // Strings are skipped whole, with MySQL's backslash escapes, and Postgres
+        // dollar-quoted strings up to their closing tag
+        if let Some(end) = quoted_span_end(bytes, i, backslash_escapes) {
+            i = end;
+            continue;
+        }
+        match bytes[i] {
+            b'(' => depth += 1,
+            // A closing parenthesis without an opening one is kept as text
+            b')' => depth = (depth - 1).max(0),
+            b',' if depth == 0 => {
+                values.push(values_str[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
         }
-        
         i += 1;
     }
     
     // Add the last value
-    if !current_value.is_empty() {
-        values.push(current_value.trim().to_string());
+    if start < bytes.len() {
+        values.push(values_str[start..].trim());
     }
     
-    // Clean up values
-    values.into_iter()
-        .map(|val| clean_value(val))
-        .collect()
+    values
 }
 
-/// Clean up a single value (remove quotes, unescape)
-fn clean_value(val: String) -> String {
+/// Clean up a single value (remove quotes, unescape). With `backslash_escapes`
+/// the backslash escapes MySQL writes in strings are read. Values without
+/// escapes are borrowed from the input.
+fn clean_value(val: &str, backslash_escapes: bool) -> Cow<'_, str> {
     let val = val.trim();
     
+    // N'...', E'...' and _utf8mb4'...' are strings with a prefix; E'' strings
+    // read backslash escapes in every dialect
+    if let Some((prefix, literal)) = split_string_prefix(val) {
+        if prefix.eq_ignore_ascii_case("E") {
+            return Cow::Owned(unescape_backslashes(&clean_value(literal, false)));
+        }
+        return clean_value(literal, backslash_escapes);
+    }
+    
+    // Dollar-quoted strings have no escapes
+    if let Some(len) = dollar_quote_delimiter(val.as_bytes(), 0) {
+        let delimiter = &val[..len];
+        if val.len() >= 2 * len && val.ends_with(delimiter) {
+            return Cow::Borrowed(&val[len..val.len() - len]);
+        }
+    }
+    
     // Remove surrounding quotes if present
-    let cleaned = if (val.starts_with('\'') && val.ends_with('\'')) || 
-                    (val.starts_with('"') && val.ends_with('"')) {
-        let inner = &val[1..val.len()-1];
-        // Unescape doubled quotes
-        inner.replace("''", "'").replace("\"\"", "\"")
-    } else {
-        val.to_string()
+    let quote = ['\'', '"'].into_iter()
+        .find(|&q| val.len() >= 2 && val.starts_with(q) && val.ends_with(q));
+    let Some(q) = quote else {
+        return Cow::Borrowed(val);
     };
+    let inner = &val[1..val.len() - 1];
+    if backslash_escapes && inner.contains('\\') {
+        return Cow::Owned(unescape_mysql(inner, q));
+    }
+    // Unescape doubled quotes of the kind that encloses the value
+    let doubled = [q, q].iter().collect::<String>();
+    if inner.contains(&doubled) {
+        Cow::Owned(inner.replace(&doubled, &q.to_string()))
+    } else {
+        Cow::Borrowed(inner)
+    }
+}
+
+/// Split a prefixed string literal into its prefix and the quoted string: a
+/// national character string (`N'...'`), a Postgres escape string (`E'...'`)
+/// or a MySQL charset introducer (`_utf8mb4'...'`, `_binary'...'`)
+fn split_string_prefix(val: &str) -> Option<(&str, &str)> {
+    let quote = val.find('\'')?;
+    let (prefix, literal) = val.split_at(quote);
+    let is_prefix = prefix.eq_ignore_ascii_case("N")
+        || prefix.eq_ignore_ascii_case("E")
+        || prefix.strip_prefix('_').is_some_and(|charset| !charset.is_empty() && charset.chars().all(|c| c.is_ascii_alphanumeric()));
+    (is_prefix && literal.len() >= 2 && literal.ends_with('\'')).then_some((prefix, literal))
+}
+
+/// Read the text of a MySQL string between its quotes: backslash escapes
+/// (`\0`, `\b`, `\n`, `\r`, `\t`, `\Z`, and any other character standing for
+/// itself) and doubled `quote`s. `\%` and `\_` keep their backslash, as in MySQL.
+fn unescape_mysql(text: &str, quote: char) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    
+    while let Some(c) = chars.next() {
+        if c == quote {
+            chars.next_if_eq(&quote);
+            result.push(c);
+            continue;
+        }
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('0') => result.push('\0'),
+            Some('b') => result.push('\u{8}'),
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some('t') => result.push('\t'),
+            Some('Z') => result.push('\u{1a}'),
+            Some(escaped @ ('%' | '_')) => {
+                result.push(c);
+                result.push(escaped);
+            }
+            Some(escaped) => result.push(escaped),
+            None => result.push(c),
+        }
+    }
+    
+    result
+}
+
+/// Read the backslash escapes of a Postgres `E''` string: `\n`, `\t`, `\r`,
+/// `\b`, `\f`, octal `\ooo`, hex `\xhh` and unicode `\uXXXX`/`\UXXXXXXXX`.
+/// Any other escaped character stands for itself.
+fn unescape_backslashes(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        let Some(escaped) = chars.next() else {
+            result.push(c);
+            break;
+        };
+        let mut digits = |radix: u32, max: usize, first: Option<char>| {
+            let mut code = first.and_then(|d| d.to_digit(radix)).unwrap_or(0);
+            let mut count = usize::from(first.is_some());
+            while count < max {
+                let Some(digit) = chars.peek().and_then(|d| d.to_digit(radix)) else {
+                    break;
+                };
+                code = code * radix + digit;
+                chars.next();
+                count += 1;
+            }
+            (count > 0).then(|| char::from_u32(code)).flatten()
+        };
+        let decoded = match escaped {
+            'n' => Some('\n'),
+            't' => Some('\t'),
+            'r' => Some('\r'),
+            'b' => Some('\u{8}'),
+            'f' => Some('\u{c}'),
+            '0'..='7' => digits(8, 3, Some(escaped)),
+            'x' => digits(16, 2, None),
+            'u' => digits(16, 4, None),
+            'U' => digits(16, 8, None),
+            other => Some(other),
+        };
+        match decoded {
+            Some(decoded) => result.push(decoded),
+            // An escape without digits, such as `\x` alone, is kept as written
+            None => {
+                result.push(c);
+                result.push(escaped);
+            }
+        }
+    }
     
-    cleaned
+    result
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::Column;
+    
+    #[test]
+    fn test_parse_sql_content_drop_and_recreate() {
+        let content = r#"
+DROP TABLE IF EXISTS `users`;
+CREATE TABLE users (id INT, name TEXT);
+CREATE TABLE IF NOT EXISTS orders (id INT);
+DROP TABLE users;
+CREATE TABLE users (id INT, name TEXT, email TEXT);
+DROP TABLE IF EXISTS audit, "public"."orders" CASCADE;
+"#;
+        
+        let (tables, _) = parse_sql_content(content.to_string()).unwrap();
+        assert_eq!(tables.len(), 1);
+        assert_eq!(tables[0].name, "users");
+        assert_eq!(tables[0].columns, vec!["id", "name", "email"]);
+    }
+    
+    #[test]
+    fn test_insert_only_tables_get_synthesized_headers() {
+        let content = "\
+CREATE TABLE users (id INT, name TEXT);
+INSERT INTO users VALUES (1, 'a');
+INSERT INTO `events` (id, kind, payload) VALUES (1, 'click', '{\"x\": 1}');
+INSERT INTO metrics VALUES (1, 'cpu, total', 0.5);
+INSERT INTO metrics VALUES (2, 'mem', 0.7);
+";
+        
+        let (tables, content) = parse_sql_content(content.to_string()).unwrap();
+        assert_eq!(tables.len(), 3);
+        assert!(!tables[0].synthesized);
+        assert_eq!(tables[1].name, "events");
+        assert_eq!(tables[1].columns, vec!["id", "kind", "payload"]);
+        assert!(tables[1].synthesized);
+        assert_eq!(tables[2].columns, vec!["col1", "col2", "col3"]);
+        
+        assert_eq!(extract_insert_values(&content, "events").unwrap(), vec![vec!["1", "click", "{\"x\": 1}"]]);
+        assert_eq!(extract_insert_values(&content, "metrics").unwrap().len(), 2);
+    }
+    
+    #[test]
+    fn test_tables_without_parsed_columns_are_kept_headerless() {
+        let content = "CREATE TABLE odd (\"e-mail\" TEXT, \"full.name\" TEXT);\n\
+            INSERT INTO odd VALUES (1, 'a');\n\
+            CREATE TABLE empty_odd (CHECK (true));\n\
+            CREATE TABLE named (x INT);\n\
+            INSERT INTO named VALUES (1);\n".to_string();
+        let (tables, content) = parse_sql_content(content).unwrap();
+        
+        assert_eq!(tables.len(), 3);
+        assert!(tables[0].headerless && !tables[0].synthesized);
+        assert_eq!(tables[0].columns, vec!["col1", "col2"]);
+        assert_eq!(extract_table_rows(&content, &tables[0]).unwrap(), vec![vec!["1", "a"]]);
+        assert!(tables[1].headerless && tables[1].columns.is_empty());
+        assert!(!tables[2].headerless);
+    }
+    
+    #[test]
+    fn test_column_type() {
+        assert_eq!(column_type(" DOUBLE PRECISION NOT NULL"), "DOUBLE PRECISION");
+        assert_eq!(column_type(" character varying(255)"), "character varying(255)");
+        assert_eq!(column_type(" DECIMAL(10, 2) DEFAULT 0"), "DECIMAL(10, 2)");
+        assert_eq!(column_type(" int(11) unsigned NOT NULL"), "int(11) unsigned");
+        assert_eq!(column_type(" timestamp with time zone"), "timestamp with time zone");
+        assert_eq!(column_type(" text[]"), "text[]");
+        assert_eq!(column_type(" NOT NULL"), "");
+    }
     
     #[test]
     fn test_parse_table_columns() {
@@ -202,19 +1219,496 @@ mod tests {
         assert_eq!(columns, vec!["id", "callSessionId", "type"]);
     }
     
+    #[test]
+    fn test_parse_table_definition_keys_and_indexes() {
+        let columns_text = r#"
+            `order_id` INT NOT NULL,
+            `line_no` INT NOT NULL,
+            `sku` VARCHAR(32) UNIQUE,
+            `key` VARCHAR(255),
+            user_id INT REFERENCES users(id),
+            PRIMARY KEY (`order_id`, `line_no`),
+            CONSTRAINT uq_line UNIQUE (order_id, sku),
+            KEY idx_user (user_id),
+            FOREIGN KEY (order_id) REFERENCES orders(id)
+        "#;
+        
+        let table = parse_table_definition("order_lines", columns_text);
+        assert_eq!(table.columns, vec!["order_id", "line_no", "sku", "key", "user_id"]);
+        assert_eq!(table.column_types, vec!["INT", "INT", "VARCHAR(32)", "VARCHAR(255)", "INT"]);
+        assert_eq!(table.primary_key, vec!["order_id", "line_no"]);
+        assert_eq!(table.unique_constraints, vec![vec!["sku"], vec!["order_id", "sku"]]);
+        assert_eq!(table.indexes, vec![Index {
+            name: "idx_user".to_string(),
+            columns: vec!["user_id".to_string()],
+            unique: false,
+        }]);
+        assert_eq!(table.foreign_keys.len(), 2);
+        assert_eq!(table.foreign_keys[0].references_table, "users");
+        assert_eq!(table.foreign_keys[1].columns, vec!["order_id"]);
+        assert_eq!(table.foreign_keys[1].references_columns, vec!["id"]);
+    }
+    
     #[test]
     fn test_clean_value() {
-        assert_eq!(clean_value("'test'".to_string()), "test");
-        assert_eq!(clean_value("\"test\"".to_string()), "test");
-        assert_eq!(clean_value("'test''s'".to_string()), "test's");
-        assert_eq!(clean_value("test".to_string()), "test");
+        assert_eq!(clean_value("'test'", true), "test");
+        assert_eq!(clean_value("\"test\"", true), "test");
+        assert_eq!(clean_value("'test''s'", true), "test's");
+        assert_eq!(clean_value("test", true), "test");
+        // Only values with escapes are copied
+        assert!(matches!(clean_value(" 'plain' ", true), Cow::Borrowed("plain")));
+        assert!(matches!(clean_value("'it''s'", true), Cow::Owned(_)));
+    }
+    
+    #[test]
+    fn test_mysql_backslash_escapes() {
+        let values_str = r#"'O\'Brien', '\"express\"', 'a\nb\tc', 'C:\\temp\\', 'it''s', '50\%', "say \"hi\"""#;
+        assert_eq!(parse_values(values_str, &ValueOptions::default(), true), vec![
+            "O'Brien", "\"express\"", "a\nb\tc", "C:\\temp\\", "it's", "50\\%", "say \"hi\"",
+        ]);
+        // Postgres and SQLite keep backslashes as they are
+        assert_eq!(clean_value(r"'C:\temp\'", false), r"C:\temp\");
+        
+        let content = "-- MySQL dump 10.13\nINSERT INTO `t` VALUES (1,'O\\'Brien'),(2,'C:\\\\');\n";
+        assert_eq!(extract_insert_values(content, "t").unwrap(), vec![vec!["1", "O'Brien"], vec!["2", "C:\\"]]);
+    }
+    
+    #[test]
+    fn test_trailing_backslash_in_sqlite_and_postgres_values() {
+        let sqlite = "PRAGMA foreign_keys=OFF;\nBEGIN TRANSACTION;\n\
+            INSERT INTO paths VALUES(1,'C:\\temp\\');\n\
+            INSERT INTO paths VALUES(2,'a\\'),(3,'b');\nCOMMIT;\n";
+        assert_eq!(extract_insert_values(sqlite, "paths").unwrap(), vec![
+            vec!["1", "C:\\temp\\"],
+            vec!["2", "a\\"],
+            vec!["3", "b"],
+        ]);
+        
+        let postgres = "-- PostgreSQL database dump\nSET standard_conforming_strings = on;\n\
+            INSERT INTO public.paths VALUES (1, 'C:\\temp\\');\n\
+            INSERT INTO public.paths VALUES (2, E'it\\'s');\n";
+        assert_eq!(extract_insert_values(postgres, "paths").unwrap(), vec![
+            vec!["1", "C:\\temp\\"],
+            vec!["2", "it's"],
+        ]);
+    }
+    
+    #[test]
+    fn test_prefixed_string_literals() {
+        let values_str = r"N'Zoë', E'two\nlines\tand \'quotes\'', _utf8mb4'text', _binary'x''y', e'\x41\101\u00e9', E'C:\\dir', 'kept\n'";
+        assert_eq!(parse_values(values_str, &ValueOptions::default(), false), vec![
+            "Zoë", "two\nlines\tand 'quotes'", "text", "x'y", "AAé", "C:\\dir", "kept\\n",
+        ]);
+        
+        // Not prefixes
+        assert_eq!(clean_value("NE'x'", true), "NE'x'");
+        assert_eq!(clean_value("_'x'", true), "_'x'");
     }
     
     #[test]
     fn test_parse_values() {
         let values_str = "'value1', 'value2', 'value''3'";
-        let values = parse_values(values_str);
+        let values = parse_values(values_str, &ValueOptions::default(), true);
         assert_eq!(values, vec!["value1", "value2", "value'3"]);
     }
-}
+    
+    #[test]
+    fn test_extract_insert_values_with_statement_end_in_strings() {
+        let content = "\
+INSERT INTO notes VALUES (1,'end of note);',' a;b ');
+INSERT INTO notes VALUES (2,'it\\'s done);\nINSERT INTO notes VALUES (9,''x'');','ok');
+INSERT INTO notes VALUES (3,'plain','last');
+";
+        let rows = extract_insert_values(content, "notes").unwrap();
+        assert_eq!(rows, vec![
+            vec!["1", "end of note);", " a;b "],
+            vec!["2", "it's done);\nINSERT INTO notes VALUES (9,'x');", "ok"],
+            vec!["3", "plain", "last"],
+        ]);
+    }
+    
+    #[test]
+    fn test_insert_keyword_variants_and_default_values() {
+        let content = "\
+CREATE TABLE t (id INT, status VARCHAR(10) DEFAULT 'new', score INT DEFAULT 0, created TIMESTAMP DEFAULT CURRENT_TIMESTAMP);
+insert  into t VALUE (1,'a',2,'2024-01-01');
+INSERT INTO `t` (id, status, score, created)
+  values(2,'b',3,'2024-01-02');
+INSERT INTO t DEFAULT VALUES;
+";
+        let (tables, content) = parse_sql_content(content.to_string()).unwrap();
+        assert_eq!(tables[0].column_defaults, vec!["", "new", "0", ""]);
+        
+        let rows = extract_table_rows(&content, &tables[0]).unwrap();
+        assert_eq!(rows, vec![
+            vec!["1", "a", "2", "2024-01-01"],
+            vec!["2", "b", "3", "2024-01-02"],
+            vec!["", "new", "0", ""],
+        ]);
+    }
+    
+    #[test]
+    fn test_generated_columns_get_empty_cells() {
+        let content = "CREATE TABLE items (\n\
+            id INT PRIMARY KEY,\n\
+            price DECIMAL(10,2),\n\
+            qty INT,\n\
+            total DECIMAL(10,2) GENERATED ALWAYS AS (price * qty) STORED,\n\
+            label VARCHAR(20) AS (CONCAT('#', id)) VIRTUAL,\n\
+            note VARCHAR(20) DEFAULT 'as (x)',\n\
+            seq INT GENERATED ALWAYS AS IDENTITY\n\
+            );\n\
+            INSERT INTO items VALUES (1, 2.50, 4, 'a', 7), (2, 1.00, 3, 'b', 8, 9, 10);\n".to_string();
+        let (tables, content) = parse_sql_content(content).unwrap();
+        
+        assert_eq!(tables[0].generated_columns, vec!["total", "label"]);
+        assert_eq!(tables[0].column_types[3], "DECIMAL(10,2)");
+        let rows = extract_table_rows(&content, &tables[0]).unwrap();
+        assert_eq!(rows[0], vec!["1", "2.50", "4", "", "", "a", "7"]);
+        // Rows that do include values for every column are kept as written
+        assert_eq!(rows[1], vec!["2", "1.00", "3", "b", "8", "9", "10"]);
+    }
+    
+    #[test]
+    fn test_insert_column_lists_are_placed_under_their_columns() {
+        let content = "CREATE TABLE users (id SERIAL PRIMARY KEY, name TEXT, role TEXT DEFAULT 'member', email TEXT);\n\
+            INSERT INTO users (name, email) VALUES ('Ann', 'ann@x.io'), ('Bob', 'bob@x.io');\n\
+            INSERT INTO users (EMAIL, id, role, name) VALUES ('cy@x.io', 3, 'admin', 'Cy');\n\
+            INSERT INTO users (id, name, role, email) VALUES (4, 'Di', 'member', 'di@x.io');\n\
+            INSERT INTO users (name, nickname) VALUES ('Ed', 'eddie');\n\
+            INSERT INTO users (name, email) VALUES ('Fay');\n".to_string();
+        let (tables, content) = parse_sql_content(content).unwrap();
+        
+        let rows = extract_table_rows(&content, &tables[0]).unwrap();
+        assert_eq!(rows, vec![
+            vec!["", "Ann", "member", "ann@x.io"],
+            vec!["", "Bob", "member", "bob@x.io"],
+            vec!["3", "Cy", "admin", "cy@x.io"],
+            vec!["4", "Di", "member", "di@x.io"],
+            // Unknown columns or a wrong number of values: kept as written
+            vec!["Ed", "eddie"],
+            vec!["Fay"],
+        ]);
+        // Without the schema, values are positional
+        assert_eq!(extract_insert_values(&content, "users").unwrap()[0], vec!["Ann", "ann@x.io"]);
+    }
+    
+    #[test]
+    fn test_extract_table_rows_with_lines() {
+        let content = "\
+CREATE TABLE t (id INT, note TEXT DEFAULT 'none');
+-- data
+INSERT INTO t VALUES
+(1,'two\nlines'),
+(2,'b');
+INSERT INTO t DEFAULT VALUES;
+";
+        let (tables, content) = parse_sql_content(content.to_string()).unwrap();
+        
+        let rows = extract_table_rows_with_lines(&content, &tables[0], &ValueOptions::default()).unwrap();
+        let lines: Vec<usize> = rows.iter().map(|(line, _)| *line).collect();
+        assert_eq!(lines, vec![4, 6, 7]);
+        assert_eq!(rows[2].1, vec!["", "none"]);
+    }
+    
+    #[test]
+    fn test_parse_values_with_expressions() {
+        let values_str = "1, CAST('5' AS INT), NULLIF('a', 'b'), 1+2, 'x'";
+        assert_eq!(parse_values(values_str, &ValueOptions::default(), true), vec!["1", "CAST('5' AS INT)", "NULLIF('a', 'b')", "1+2", "x"]);
+        
+        let options = ValueOptions { expressions: ExpressionPolicy::Evaluate, ..Default::default() };
+        assert_eq!(parse_values(values_str, &options, true), vec!["1", "5", "a", "3", "x"]);
+    }
+    
+    #[test]
+    fn test_values_with_line_breaks() {
+        let content = "\
+PRAGMA foreign_keys=OFF;
+BEGIN TRANSACTION;
+INSERT INTO notes VALUES(1,replace(replace('one\\r\\ntwo','\\r',char(13)),'\\n',char(10)),'it''s\\n');
+INSERT INTO notes VALUES(2,REPLACE('a\\nb', '\\n', CHAR(10)),'first
+second');
+";
+        let rows = extract_insert_values(content, "notes").unwrap();
+        assert_eq!(rows, vec![
+            vec!["1", "one\r\ntwo", "it's\\n"],
+            vec!["2", "a\nb", "first\nsecond"],
+        ]);
+        
+        let options = ValueOptions { newlines: NewlinePolicy::Escape, ..Default::default() };
+        assert_eq!(parse_values("replace('a\\nb','\\n',char(10)), 'c\r\nd'", &options, false), vec!["a\\nb", "c\\r\\nd"]);
+        let options = ValueOptions { newlines: NewlinePolicy::Space, ..Default::default() };
+        assert_eq!(parse_values("replace('a\\nb','\\n',char(10)), 'c\r\nd'", &options, false), vec!["a b", "c d"]);
+        
+        // Evaluated expressions follow the policy too
+        let options = ValueOptions { newlines: NewlinePolicy::Escape, expressions: ExpressionPolicy::Evaluate, ..Default::default() };
+        assert_eq!(parse_values("COALESCE(NULL, 'a\nb')", &options, false), vec!["a\\nb"]);
+    }
+    
+    #[test]
+    fn test_clean_value_edge_cases() {
+        assert_eq!(clean_value("'", true), "'");
+        assert_eq!(clean_value("'say \"\"hi\"\"'", true), "say \"\"hi\"\"");
+        assert_eq!(clean_value("\"it''s\"", true), "it''s");
+    }
+    
+    /// A value as it would be written in a dump, and the cell it should become
+    #[derive(Debug, Clone)]
+    enum Literal {
+        Integer(i64),
+        Null,
+        /// Quoted with the given quote character, which is doubled inside
+        Text(String, char),
+    }
+    
+    impl Literal {
+        fn render(&self) -> String {
+            match self {
+                Literal::Integer(n) => n.to_string(),
+                Literal::Null => "NULL".to_string(),
+                Literal::Text(text, q) => format!("{}{}{}", q, text.replace(*q, &format!("{}{}", q, q)), q),
+            }
+        }
+        
+        fn expected(&self) -> String {
+            match self {
+                Literal::Integer(n) => n.to_string(),
+                Literal::Null => "NULL".to_string(),
+                Literal::Text(text, _) => text.clone(),
+            }
+        }
+    }
+    
+    fn literal() -> impl proptest::strategy::Strategy<Value = Literal> {
+        use proptest::prelude::*;
+        prop_oneof![
+            any::<i64>().prop_map(Literal::Integer),
+            Just(Literal::Null),
+            ("[A-Z0-9 ,;()'\"\n]{0,24}", prop_oneof![Just('\''), Just('"')])
+                .prop_map(|(text, q)| Literal::Text(text, q)),
+        ]
+    }
+    
+    proptest::proptest! {
+        #[test]
+        fn prop_rendered_literals_round_trip(rows in proptest::collection::vec(proptest::collection::vec(literal(), 1..6), 1..4)) {
+            let content: String = rows.iter()
+                .map(|row| {
+                    let values: Vec<String> = row.iter().map(Literal::render).collect();
+                    format!("INSERT INTO t VALUES ({});\n", values.join(","))
+                })
+                .collect();
+            let expected: Vec<Vec<String>> = rows.iter()
+                .map(|row| row.iter().map(Literal::expected).collect())
+                .collect();
+            
+            proptest::prop_assert_eq!(extract_insert_values(&content, "t").unwrap(), expected);
+        }
+        
+        #[test]
+        fn prop_arbitrary_values_do_not_panic(values in "\\PC{0,64}") {
+            parse_values(&values, &ValueOptions::default(), true);
+            let _ = extract_insert_values(&format!("INSERT INTO t VALUES ({});", values), "t");
+        }
+    }
+    
+    #[test]
+    fn test_multi_row_and_minified_inserts() {
+        let content = "CREATE\nTABLE t (id int, name text);\n\
+INSERT INTO t VALUES (1,'a'),(2,'b, (c)');INSERT INTO t VALUES (3,'c');\n\
+INSERT\nINTO\nt\nVALUES\n(4,\n'd'\n)\n;\n\
+INSERT INTO t (id, name) VALUES (5, 'e') ON CONFLICT DO NOTHING;\n";
+        
+        let (tables, content) = parse_sql_content(content.to_string()).unwrap();
+        assert_eq!(tables.len(), 1);
+        assert!(!tables[0].synthesized);
+        let rows = extract_insert_values(&content, "t").unwrap();
+        assert_eq!(rows, vec![
+            vec!["1", "a"],
+            vec!["2", "b, (c)"],
+            vec!["3", "c"],
+            vec!["4", "d"],
+            vec!["5", "e"],
+        ]);
+    }
+    
+    #[test]
+    fn test_dollar_quoted_values() {
+        let content = "CREATE TABLE docs (id int, body text, note text);\n\
+INSERT INTO docs VALUES (1, $$It's a \"test\", (really)$$, $tag$ends with $$ and ); $tag$);\n\
+INSERT INTO docs VALUES (2, $$$$, 'plain');\n";
+        
+        let (_, content) = parse_sql_content(content.to_string()).unwrap();
+        assert_eq!(extract_insert_values(&content, "docs").unwrap(), vec![
+            vec!["1", "It's a \"test\", (really)", "ends with $$ and ); "],
+            vec!["2", "", "plain"],
+        ]);
+    }
+    
+    #[test]
+    fn test_non_data_statements_are_skipped() {
+        let content = "SET NAMES utf8mb4;\nSTART TRANSACTION;\n\
+CREATE TABLE t (id int, name text);\n\
+CREATE OR REPLACE FUNCTION audit() RETURNS trigger AS $$\nBEGIN\n  CREATE TABLE tmp (x int);\n  INSERT INTO t VALUES (99, 'from function');\nEND;\n$$ LANGUAGE plpgsql;\n\
+DELIMITER ;;\nCREATE TRIGGER tr AFTER UPDATE ON t FOR EACH ROW BEGIN INSERT INTO t VALUES (98, 'from trigger'); END;;\nDELIMITER ;\n\
+CREATE INDEX t_name ON t (name);\n\
+INSERT INTO t VALUES (1, 'a');\nCOMMIT;\n";
+
+        let (tables, content) = parse_sql_content(content.to_string()).unwrap();
+        assert_eq!(tables.len(), 1);
+        assert_eq!(tables[0].indexes.len(), 1);
+        assert_eq!(extract_insert_values(&content, "t").unwrap(), vec![vec!["1", "a"]]);
+    }
+    
+    #[test]
+    fn test_table_statements() {
+        let content = "CREATE TABLE t (id int);\nCREATE TABLE `t2` (id int);\nINSERT INTO t2 VALUES (1);\nINSERT INTO public.\"t\" VALUES (2);\nINSERT INTO log VALUES ('INSERT INTO t VALUES (3)');\n";
+        let texts: Vec<&str> = table_statements(content, &dump_databases(content), "t").unwrap().iter().map(|s| s.text).collect();
+        assert_eq!(texts, vec!["CREATE TABLE t (id int)", "INSERT INTO public.\"t\" VALUES (2)"]);
+    }
+    
+    #[test]
+    fn test_unparsable_inserts_are_skipped_and_reported() {
+        let content = format!("CREATE TABLE t (id int, name text);\n\
+INSERT INTO t VALUES (1, 'a');\n\
+INSERT INTO t SELECT id, name FROM staging;\n\
+INSERT INTO t VALUES (2, 'b');\n\
+INSERT INTO t VALUES (3, 'c', lower('{}');\n\
+INSERT INTO t DEFAULT VALUES;\n", "x".repeat(300));
+
+        let skipped = find_skipped_inserts(&content).unwrap();
+        
+        assert_eq!(skipped.len(), 2);
+        assert_eq!(skipped[0].text, "INSERT INTO t SELECT id, name FROM staging");
+        assert_eq!(&content[skipped[0].start..skipped[0].end], skipped[0].text);
+        assert!(content[skipped[1].end..].starts_with(";\nINSERT INTO t DEFAULT VALUES"));
+        assert_eq!(skipped[1].text.chars().count(), 200);
+        
+        let (tables, content) = parse_sql_content(content).unwrap();
+        let ids: Vec<String> = extract_table_rows(&content, &tables[0]).unwrap().into_iter().map(|row| row[0].clone()).collect();
+        assert_eq!(ids, vec!["1", "2", ""]);
+    }
+    
+    #[test]
+    fn test_parse_insert_statement() {
+        let insert = parse_insert_statement("INSERT INTO `shop`.`orders` (`id`, note) VALUES (1, 'a;b'), (2, NULL);").unwrap();
+        assert_eq!(insert, InsertStatement {
+            table: "orders".to_string(),
+            schema: Some("shop".to_string()),
+            columns: vec!["id".to_string(), "note".to_string()],
+            rows: vec![vec!["1".to_string(), "a;b".to_string()], vec!["2".to_string(), "NULL".to_string()]],
+        });
+        
+        let insert = parse_insert_statement("-- from the log\ninsert into t DEFAULT VALUES").unwrap();
+        assert_eq!((insert.table.as_str(), insert.rows), ("t", vec![Vec::new()]));
+        
+        assert!(parse_insert_statement("UPDATE t SET a = 1").is_err());
+        assert!(parse_insert_statement("INSERT INTO t SELECT * FROM s").is_err());
+        assert!(parse_insert_statement("INSERT INTO t VALUES (1, 'a'").is_err());
+        assert!(parse_insert_statement("INSERT INTO t VALUES (1); INSERT INTO t VALUES (2);").is_err());
+    }
+    
+    #[test]
+    fn test_table_definition_reads_strings_like_statements() {
+        let mysql = "CREATE TABLE t (\n  a int COMMENT 'it\\'s (a), b',\n  b text\n);";
+        assert_eq!(parse_create_table(mysql).unwrap().columns, vec!["a", "b"]);
+        
+        let postgres = "-- PostgreSQL database dump\nCREATE TABLE t (a text DEFAULT 'C:\\', b text);";
+        let (tables, _) = parse_sql_content(postgres.to_string()).unwrap();
+        assert_eq!(tables[0].columns, vec!["a", "b"]);
+        assert_eq!(tables[0].column_defaults[0], "C:\\");
+    }
+    
+    #[test]
+    fn test_parse_create_table() {
+        let table = parse_create_table("CREATE TABLE IF NOT EXISTS shop.orders (\n\
+            id BIGINT NOT NULL AUTO_INCREMENT,\n\
+            code VARCHAR(20) NOT NULL UNIQUE,\n\
+            status VARCHAR(10) DEFAULT 'new',\n\
+            created TIMESTAMP DEFAULT CURRENT_TIMESTAMP,\n\
+            total DECIMAL(10,2) GENERATED ALWAYS AS (price * qty) STORED,\n\
+            PRIMARY KEY (id)\n\
+        );").unwrap();
+        assert_eq!(table.name, "orders");
+        assert_eq!(table.schema.as_deref(), Some("shop"));
+        
+        let columns = table.column_definitions();
+        assert_eq!(columns[0], Column {
+            name: "id".to_string(),
+            data_type: "BIGINT".to_string(),
+            nullable: false,
+            default: None,
+            primary_key: true,
+            unique: false,
+            generated: false,
+        });
+        assert!(!columns[1].nullable && columns[1].unique);
+        assert_eq!(columns[2].default.as_deref(), Some("new"));
+        assert!(columns[3].nullable && columns[3].default.is_none());
+        assert!(columns[4].generated);
+        
+        assert!(parse_create_table("CREATE INDEX i ON t (a)").is_err());
+        assert!(parse_create_table("CREATE TABLE t AS SELECT 1").is_err());
+    }
+    
+    #[test]
+    fn test_table_rows_borrow_values_from_the_dump() {
+        let content = "CREATE TABLE t (id int, name text);\nINSERT INTO t VALUES (1,'Ann'),(2,'O''Brien');\n";
+        let (tables, content) = parse_sql_content(content.to_string()).unwrap();
+        let mut rows = Vec::new();
+        let finished = for_each_table_row(&content, &tables[0], &ValueOptions::default(), |row| {
+            rows.push(row);
+            true
+        }).unwrap();
+        
+        assert!(finished);
+        assert!(matches!(rows[0][1], Cow::Borrowed("Ann")));
+        assert!(matches!(rows[1][1], Cow::Owned(ref name) if name == "O'Brien"));
+        
+        let mut visited = 0;
+        assert!(!for_each_table_row(&content, &tables[0], &ValueOptions::default(), |_| {
+            visited += 1;
+            false
+        }).unwrap());
+        assert_eq!(visited, 1);
+    }
+    
+    #[test]
+    fn test_typed_values() {
+        let content = "INSERT INTO t VALUES (1, 'NULL', NULL, 2.5, '7', 0x0A0B, true, 1.50, CAST('5' AS INT), TRUE, null, 0xab, X'0a');\n";
+        let rows = extract_insert_typed_values(content, "t", &ValueOptions::default()).unwrap();
+        assert_eq!(rows[0], vec![
+            SqlValue::Integer(1),
+            SqlValue::Text("NULL".to_string()),
+            SqlValue::Null,
+            SqlValue::Float(2.5),
+            SqlValue::Text("7".to_string()),
+            SqlValue::Blob(vec![0x0A, 0x0B]),
+            SqlValue::Bool(true),
+            SqlValue::Text("1.50".to_string()),
+            SqlValue::Text("CAST('5' AS INT)".to_string()),
+            SqlValue::Bool(true),
+            SqlValue::Null,
+            SqlValue::Blob(vec![0xAB]),
+            SqlValue::Blob(vec![0x0A]),
+        ]);
+        assert_eq!(extract_insert_values(content, "t").unwrap()[0], vec![
+            "1", "NULL", "NULL", "2.5", "7", "0x0A0B", "true", "1.50", "CAST('5' AS INT)", "TRUE", "null", "0xab", "X'0a'",
+        ]);
+    }
+    
+    #[test]
+    fn test_create_table_in_strings_and_comments_is_ignored() {
+        let content = "-- CREATE TABLE commented (id int);\n\
+/* CREATE TABLE blocked (id int); */\n\
+CREATE TABLE migrations (id int, statement text);\n\
+INSERT INTO migrations VALUES (1, 'CREATE TABLE users (id int, name text);'),\n\
+(2, '\nCREATE TABLE orders (id int);\n'), (3, E'CREATE TABLE \\'quoted\\' (id int);');\n\
+INSERT INTO migrations VALUES (4, $$CREATE TABLE dollar (id int);$$);\n";
 
+        let (tables, content) = parse_sql_content(content.to_string()).unwrap();
+        let names: Vec<&str> = tables.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["migrations"]);
+        assert_eq!(extract_insert_values(&content, "migrations").unwrap().len(), 4);
+    }
+}
@@ -0,0 +1,407 @@
+use anyhow::Result;
+use regex::Regex;
+use serde_json::{json, Value};
+
+use crate::args::flag_value;
+use crate::types::PgTypeFormat;
+
+/// Parse --pg-types option from command line
+pub fn parse_pg_type_format(args: &[String]) -> Result<PgTypeFormat> {
+    match flag_value(args, "--pg-types")? {
+        None | Some("raw") => Ok(PgTypeFormat::Raw),
+        Some("json") => Ok(PgTypeFormat::Json),
+        Some(other) => anyhow::bail!("Error: Invalid --pg-types '{}'. Use one of: raw, json", other),
+    }
+}
+
+/// Postgres types whose text form has a JSON rendering
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PgKind {
+    /// Array of the given element kind: `{1,2,3}`
+    Array { numeric: bool, boolean: bool },
+    /// `[1,10)`, `(,2024-01-01]` or `empty`
+    Range,
+    Point,
+    Line,
+    Circle,
+    /// lseg, box, path and polygon: a list of points
+    Points,
+    /// PostGIS geometry or geography written as hex (E)WKB
+    Geometry,
+}
+
+fn pg_kind(column_type: &str) -> Option<PgKind> {
+    let column_type = column_type.trim().to_lowercase();
+    let base = column_type.split('(').next().unwrap_or("").trim();
+    
+    if let Some(element) = base.strip_suffix("[]").or_else(|| base.strip_prefix('_')) {
+        let element = element.split('(').next().unwrap_or("").trim();
+        let numeric = ["int", "integer", "bigint", "smallint", "int2", "int4", "int8", "numeric", "decimal", "real", "float4", "float8", "double precision"]
+            .contains(&element);
+        let boolean = element == "boolean" || element == "bool";
+        return Some(PgKind::Array { numeric, boolean });
+    }
+    if column_type.ends_with("[]") {
+        // Arrays of types with a length, e.g. varchar(20)[]
+        return Some(PgKind::Array { numeric: false, boolean: false });
+    }
+    
+    match base {
+        "int4range" | "int8range" | "numrange" | "tsrange" | "tstzrange" | "daterange" => Some(PgKind::Range),
+        "point" => Some(PgKind::Point),
+        "line" => Some(PgKind::Line),
+        "circle" => Some(PgKind::Circle),
+        "lseg" | "box" | "path" | "polygon" => Some(PgKind::Points),
+        "geometry" | "geography" => Some(PgKind::Geometry),
+        _ => None,
+    }
+}
+
+/// Rewrite Postgres array, range, geometric and PostGIS values as JSON according
+/// to their declared column types. `NULL` and values that don't parse are left as they are.
+pub fn apply_pg_type_format(column_types: &[String], rows: &mut [Vec<String>], format: PgTypeFormat) {
+    let Some(formatter) = PgTypeFormatter::new(column_types, format) else {
+        return;
+    };
+    for row in rows.iter_mut() {
+        formatter.format_row(row);
+    }
+}
+
+/// Rewrites the Postgres values of rows handed over one at a time
+pub(crate) struct PgTypeFormatter {
+    kinds: Vec<Option<PgKind>>,
+}
+
+impl PgTypeFormatter {
+    /// `None` when no column of these types is rewritten
+    pub(crate) fn new(column_types: &[String], format: PgTypeFormat) -> Option<Self> {
+        if format == PgTypeFormat::Raw {
+            return None;
+        }
+        let kinds: Vec<Option<PgKind>> = column_types.iter().map(|t| pg_kind(t)).collect();
+        kinds.iter().any(Option::is_some).then_some(PgTypeFormatter { kinds })
+    }
+    
+    pub(crate) fn format_row<S: AsRef<str> + From<String>>(&self, row: &mut [S]) {
+        for (cell, kind) in row.iter_mut().zip(&self.kinds) {
+            let Some(kind) = kind else { continue };
+            if cell.as_ref() == "NULL" {
+                continue;
+            }
+            if let Some(json) = render_json(cell.as_ref(), *kind) {
+                *cell = S::from(json.to_string());
+            }
+        }
+    }
+}
+
+fn render_json(value: &str, kind: PgKind) -> Option<Value> {
+    match kind {
+        PgKind::Array { numeric, boolean } => {
+            let mut parser = ArrayParser { chars: value.trim().chars().collect(), position: 0, numeric, boolean };
+            let array = parser.array()?;
+            (parser.position == parser.chars.len()).then_some(array)
+        }
+        PgKind::Range => range_json(value.trim()),
+        PgKind::Point => match numbers(value).as_slice() {
+            [x, y] => Some(json!({"x": x, "y": y})),
+            _ => None,
+        },
+        PgKind::Line => match numbers(value).as_slice() {
+            [a, b, c] => Some(json!({"a": a, "b": b, "c": c})),
+            _ => None,
+        },
+        PgKind::Circle => match numbers(value).as_slice() {
+            [x, y, radius] => Some(json!({"x": x, "y": y, "radius": radius})),
+            _ => None,
+        },
+        PgKind::Points => {
+            let numbers = numbers(value);
+            (!numbers.is_empty() && numbers.len().is_multiple_of(2))
+                .then(|| Value::from(numbers.chunks(2).map(|pair| json!([pair[0], pair[1]])).collect::<Vec<_>>()))
+        }
+        PgKind::Geometry => geojson_from_wkb(value.trim()),
+    }
+}
+
+/// All numbers in a geometric value, e.g. `((0,0),(1,1))`
+fn numbers(value: &str) -> Vec<f64> {
+    let number_regex = Regex::new(r"-?(?:\d+\.?\d*|\.\d+)(?:[eE][-+]?\d+)?").unwrap();
+    number_regex.find_iter(value)
+        .filter_map(|n| n.as_str().parse().ok())
+        .collect()
+}
+
+/// Parser for the Postgres array text form, including nested arrays, quoted
+/// elements with backslash escapes and unquoted `NULL`
+struct ArrayParser {
+    chars: Vec<char>,
+    position: usize,
+    numeric: bool,
+    boolean: bool,
+}
+
+impl ArrayParser {
+    fn array(&mut self) -> Option<Value> {
+        // Arrays with explicit bounds start with e.g. `[1:3]=`
+        if self.chars.get(self.position) == Some(&'[') {
+            while *self.chars.get(self.position)? != '=' {
+                self.position += 1;
+            }
+            self.position += 1;
+        }
+        if self.chars.get(self.position) != Some(&'{') {
+            return None;
+        }
+        self.position += 1;
+        
+        let mut elements = Vec::new();
+        if self.chars.get(self.position) == Some(&'}') {
+            self.position += 1;
+            return Some(Value::Array(elements));
+        }
+        loop {
+            let element = match self.chars.get(self.position)? {
+                '{' => self.array()?,
+                '"' => Value::String(self.quoted()?),
+                _ => self.unquoted()?,
+            };
+            elements.push(element);
+            match self.chars.get(self.position)? {
+                ',' => self.position += 1,
+                '}' => {
+                    self.position += 1;
+                    return Some(Value::Array(elements));
+                }
+                _ => return None,
+            }
+        }
+    }
+    
+    fn quoted(&mut self) -> Option<String> {
+        let mut text = String::new();
+        self.position += 1;
+        loop {
+            match *self.chars.get(self.position)? {
+                '\\' => {
+                    text.push(*self.chars.get(self.position + 1)?);
+                    self.position += 2;
+                }
+                '"' => {
+                    self.position += 1;
+                    return Some(text);
+                }
+                c => {
+                    text.push(c);
+                    self.position += 1;
+                }
+            }
+        }
+    }
+    
+    fn unquoted(&mut self) -> Option<Value> {
+        let start = self.position;
+        while !matches!(self.chars.get(self.position)?, ',' | '}') {
+            self.position += 1;
+        }
+        let text: String = self.chars[start..self.position].iter().collect();
+        let text = text.trim();
+        
+        Some(if text.eq_ignore_ascii_case("NULL") {
+            Value::Null
+        } else if self.numeric && let Ok(number) = text.parse::<serde_json::Number>() {
+            Value::Number(number)
+        } else if self.boolean && (text == "t" || text == "f") {
+            Value::Bool(text == "t")
+        } else {
+            Value::String(text.to_string())
+        })
+    }
+}
+
+/// Range as `{"lower", "upper", "lower_inclusive", "upper_inclusive"}`; a missing
+/// bound (unbounded) is null, and `empty` becomes `{"empty": true}`
+fn range_json(value: &str) -> Option<Value> {
+    if value.eq_ignore_ascii_case("empty") {
+        return Some(json!({"empty": true}));
+    }
+    
+    let lower_inclusive = match value.chars().next()? {
+        '[' => true,
+        '(' => false,
+        _ => return None,
+    };
+    let upper_inclusive = match value.chars().last()? {
+        ']' => true,
+        ')' => false,
+        _ => return None,
+    };
+    let inner = value.get(1..value.len() - 1)?;
+    let (lower, upper) = inner.split_once(',')?;
+    let bound = |text: &str| {
+        let text = text.trim().trim_matches('"');
+        if text.is_empty() { Value::Null } else { Value::String(text.to_string()) }
+    };
+    
+    Some(json!({
+        "lower": bound(lower),
+        "upper": bound(upper),
+        "lower_inclusive": lower_inclusive,
+        "upper_inclusive": upper_inclusive,
+    }))
+}
+
+/// Convert hex-encoded WKB or PostGIS EWKB to a GeoJSON geometry. Points,
+/// line strings, polygons and their multi variants are supported.
+fn geojson_from_wkb(hex: &str) -> Option<Value> {
+    let hex = hex.strip_prefix("\\x").unwrap_or(hex);
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    let bytes = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    
+    let mut reader = WkbReader { bytes: &bytes, position: 0, little_endian: true };
+    let geometry = reader.geometry()?;
+    (reader.position == bytes.len()).then_some(geometry)
+}
+
+struct WkbReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    little_endian: bool,
+}
+
+impl WkbReader<'_> {
+    fn take<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let bytes = self.bytes.get(self.position..self.position + N)?.try_into().ok()?;
+        self.position += N;
+        Some(bytes)
+    }
+    
+    fn u32(&mut self) -> Option<u32> {
+        let bytes = self.take::<4>()?;
+        Some(if self.little_endian { u32::from_le_bytes(bytes) } else { u32::from_be_bytes(bytes) })
+    }
+    
+    fn f64(&mut self) -> Option<f64> {
+        let bytes = self.take::<8>()?;
+        Some(if self.little_endian { f64::from_le_bytes(bytes) } else { f64::from_be_bytes(bytes) })
+    }
+    
+    fn geometry(&mut self) -> Option<Value> {
+        self.little_endian = match self.take::<1>()?[0] {
+            0 => false,
+            1 => true,
+            _ => return None,
+        };
+        let raw_type = self.u32()?;
+        
+        // EWKB flags, or ISO WKB dimensions encoded as 1000s
+        let has_srid = raw_type & 0x2000_0000 != 0;
+        let iso_dimensions = (raw_type & 0xFFFF) / 1000;
+        let has_z = raw_type & 0x8000_0000 != 0 || iso_dimensions == 1 || iso_dimensions == 3;
+        let has_m = raw_type & 0x4000_0000 != 0 || iso_dimensions == 2 || iso_dimensions == 3;
+        let base_type = (raw_type & 0xFFFF) % 1000;
+        if has_srid {
+            self.u32()?;
+        }
+        
+        let dimensions = (has_z, has_m);
+        let (kind, coordinates) = match base_type {
+            1 => ("Point", self.point(dimensions)?),
+            2 => ("LineString", self.points(dimensions)?),
+            3 => ("Polygon", self.rings(dimensions)?),
+            4..=6 => {
+                let count = self.u32()?;
+                let members = (0..count)
+                    .map(|_| self.geometry().map(|member| member["coordinates"].clone()))
+                    .collect::<Option<Vec<Value>>>()?;
+                let kind = ["MultiPoint", "MultiLineString", "MultiPolygon"][base_type as usize - 4];
+                (kind, Value::Array(members))
+            }
+            _ => return None,
+        };
+        
+        Some(json!({"type": kind, "coordinates": coordinates}))
+    }
+    
+    fn point(&mut self, (has_z, has_m): (bool, bool)) -> Option<Value> {
+        let mut coordinates = vec![self.f64()?, self.f64()?];
+        if has_z {
+            coordinates.push(self.f64()?);
+        }
+        if has_m {
+            // GeoJSON has no measure, so it is dropped
+            self.f64()?;
+        }
+        Some(json!(coordinates))
+    }
+    
+    fn points(&mut self, dimensions: (bool, bool)) -> Option<Value> {
+        let count = self.u32()?;
+        (0..count).map(|_| self.point(dimensions)).collect::<Option<Vec<Value>>>().map(Value::Array)
+    }
+    
+    fn rings(&mut self, dimensions: (bool, bool)) -> Option<Value> {
+        let count = self.u32()?;
+        (0..count).map(|_| self.points(dimensions)).collect::<Option<Vec<Value>>>().map(Value::Array)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn convert(column_type: &str, value: &str) -> String {
+        let mut rows = vec![vec![value.to_string()]];
+        apply_pg_type_format(&[column_type.to_string()], &mut rows, PgTypeFormat::Json);
+        rows.remove(0).remove(0)
+    }
+    
+    #[test]
+    fn test_arrays_become_json() {
+        assert_eq!(convert("integer[]", "{1,2,3}"), "[1,2,3]");
+        assert_eq!(convert("text[]", r#"{"a b",NULL,"say \"hi\"",c}"#), r#"["a b",null,"say \"hi\"","c"]"#);
+        assert_eq!(convert("_int4", "{{1,2},{3,4}}"), "[[1,2],[3,4]]");
+        assert_eq!(convert("boolean[]", "{t,f}"), "[true,false]");
+        assert_eq!(convert("integer[]", "{}"), "[]");
+        assert_eq!(convert("integer[]", "{1,2"), "{1,2");
+        assert_eq!(convert("integer[]", "NULL"), "NULL");
+    }
+    
+    #[test]
+    fn test_ranges_and_geometric_types_become_json() {
+        assert_eq!(convert("int4range", "[1,10)"), r#"{"lower":"1","lower_inclusive":true,"upper":"10","upper_inclusive":false}"#);
+        assert_eq!(convert("daterange", "(,2024-01-01]"), r#"{"lower":null,"lower_inclusive":false,"upper":"2024-01-01","upper_inclusive":true}"#);
+        assert_eq!(convert("tsrange", "empty"), r#"{"empty":true}"#);
+        assert_eq!(convert("point", "(1.5,-2)"), r#"{"x":1.5,"y":-2.0}"#);
+        assert_eq!(convert("box", "(1,1),(0,0)"), "[[1.0,1.0],[0.0,0.0]]");
+        assert_eq!(convert("circle", "<(0,0),2>"), r#"{"radius":2.0,"x":0.0,"y":0.0}"#);
+    }
+    
+    #[test]
+    fn test_wkb_becomes_geojson() {
+        // SRID=4326;POINT(1 2) as written by pg_dump
+        assert_eq!(
+            convert("geometry(Point,4326)", "0101000020E6100000000000000000F03F0000000000000040"),
+            r#"{"coordinates":[1.0,2.0],"type":"Point"}"#
+        );
+        // LINESTRING(0 0, 1 1), big-endian
+        assert_eq!(
+            convert("geometry", "000000000200000002000000000000000000000000000000003FF00000000000003FF0000000000000"),
+            r#"{"coordinates":[[0.0,0.0],[1.0,1.0]],"type":"LineString"}"#
+        );
+        assert_eq!(convert("geometry", "not wkb"), "not wkb");
+    }
+    
+    #[test]
+    fn test_raw_format_keeps_values() {
+        let mut rows = vec![vec!["{1,2}".to_string()]];
+        apply_pg_type_format(&["integer[]".to_string()], &mut rows, PgTypeFormat::Raw);
+        assert_eq!(rows[0][0], "{1,2}");
+    }
+}
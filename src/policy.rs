@@ -0,0 +1,273 @@
+//! Column-level allowlist for compliance reviews (`--policy`).
+//!
+//! A TOML file declares, table by table, which columns may be exported as
+//! they are, which only masked, and which never:
+//!
+//! ```toml
+//! # Columns the policy does not list: fail (default), drop or mask
+//! unreviewed = "fail"
+//!
+//! [tables.users]
+//! export = ["id", "country", "created_at"]
+//! mask = { email = "fake:email", ssn = "hash" }
+//! drop = ["password_hash"]
+//!
+//! [tables.audit_log]
+//! skip = true
+//! ```
+//!
+//! A column is reviewed once it is listed under `export`, `mask` or `drop`,
+//! or its table is skipped. By default a dump with any unreviewed column is
+//! rejected before anything is written, with a list of the columns to review.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::args::flag_value;
+use crate::mask::{mask_value, parse_mask_strategy, MaskStrategy};
+use crate::types::Table;
+
+/// What happens to columns the policy does not list
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnreviewedAction {
+    /// Reject the dump before writing anything
+    #[default]
+    Fail,
+    /// Leave them out; tables the policy does not list are not written
+    Drop,
+    /// Write them hashed
+    Mask,
+}
+
+/// How a column is exported
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnRule {
+    Export,
+    Mask(MaskStrategy),
+    Drop,
+}
+
+/// The reviewed columns of a table
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TablePolicy {
+    /// Never export the table
+    pub skip: bool,
+    pub columns: BTreeMap<String, ColumnRule>,
+}
+
+/// Reviewed tables and columns, from --policy
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Policy {
+    pub unreviewed: UnreviewedAction,
+    pub tables: BTreeMap<String, TablePolicy>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawPolicy {
+    #[serde(default)]
+    unreviewed: Option<String>,
+    #[serde(default)]
+    tables: BTreeMap<String, RawTablePolicy>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawTablePolicy {
+    #[serde(default)]
+    skip: bool,
+    #[serde(default)]
+    export: Vec<String>,
+    #[serde(default)]
+    mask: BTreeMap<String, String>,
+    #[serde(default)]
+    drop: Vec<String>,
+}
+
+/// Parse the --policy option from command line and load the file
+pub fn parse_policy(args: &[String]) -> Result<Option<Policy>> {
+    flag_value(args, "--policy")?
+        .map(load_policy)
+        .transpose()
+}
+
+/// Read a policy from a TOML file
+pub fn load_policy(path: impl AsRef<Path>) -> Result<Policy> {
+    let path = path.as_ref();
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read policy file '{}'", path.display()))?;
+    parse_policy_str(&text)
+        .with_context(|| format!("Error in policy file '{}'", path.display()))
+}
+
+/// Parse a policy from TOML text
+pub fn parse_policy_str(text: &str) -> Result<Policy> {
+    let raw: RawPolicy = toml::from_str(text)?;
+    let unreviewed = match raw.unreviewed.as_deref() {
+        None | Some("fail") => UnreviewedAction::Fail,
+        Some("drop") => UnreviewedAction::Drop,
+        Some("mask") => UnreviewedAction::Mask,
+        Some(other) => anyhow::bail!("Invalid unreviewed '{}'. Use one of: fail, drop, mask", other),
+    };
+    let mut policy = Policy { unreviewed, tables: BTreeMap::new() };
+    
+    for (table, raw_table) in raw.tables {
+        let mut rules: Vec<(String, ColumnRule)> = raw_table.export.into_iter()
+            .map(|column| (column, ColumnRule::Export))
+            .collect();
+        for (column, strategy) in raw_table.mask {
+            let strategy = parse_mask_strategy(&strategy)
+                .with_context(|| format!("Invalid mask of column '{}' in table '{}'", column, table))?;
+            rules.push((column, ColumnRule::Mask(strategy)));
+        }
+        rules.extend(raw_table.drop.into_iter().map(|column| (column, ColumnRule::Drop)));
+        
+        let mut columns = BTreeMap::new();
+        for (column, rule) in rules {
+            if columns.insert(column.clone(), rule).is_some() {
+                anyhow::bail!("Column '{}' of table '{}' is listed more than once", column, table);
+            }
+        }
+        policy.tables.insert(table, TablePolicy { skip: raw_table.skip, columns });
+    }
+    
+    Ok(policy)
+}
+
+impl Policy {
+    /// How a column is exported, or `None` if the policy does not list it
+    pub fn column_rule(&self, table: &str, column: &str) -> Option<ColumnRule> {
+        self.tables.get(table)?.columns.get(column).copied()
+    }
+    
+    /// Whether a table is left out of the export: skipped by the policy, or
+    /// not listed while unreviewed columns are dropped
+    pub fn skips_table(&self, table: &str) -> bool {
+        match self.tables.get(table) {
+            Some(table) => table.skip,
+            None => self.unreviewed == UnreviewedAction::Drop,
+        }
+    }
+    
+    /// Columns of the tables, as `table.column`, that the policy has not reviewed
+    pub fn unreviewed_columns(&self, tables: &[Table]) -> Vec<String> {
+        tables.iter()
+            .filter(|table| !self.tables.get(&table.name).is_some_and(|t| t.skip))
+            .flat_map(|table| table.columns.iter()
+                .filter(|column| self.column_rule(&table.name, column).is_none())
+                .map(|column| format!("{}.{}", table.name, column)))
+            .collect()
+    }
+}
+
+/// Drop and mask a table's columns as the policy says. Unreviewed columns are
+/// dropped or hashed following `unreviewed`; columns that are not in the
+/// table's schema, such as `_source_line`, are kept.
+pub fn apply_policy(
+    policy: &Policy,
+    table: &Table,
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+    seed: u64,
+) -> (Vec<String>, Vec<Vec<String>>) {
+    let rules: Vec<ColumnRule> = headers.iter()
+        .map(|header| match policy.column_rule(&table.name, header) {
+            Some(rule) => rule,
+            None if !table.columns.contains(header) => ColumnRule::Export,
+            None => match policy.unreviewed {
+                UnreviewedAction::Mask => ColumnRule::Mask(MaskStrategy::Hash),
+                UnreviewedAction::Fail | UnreviewedAction::Drop => ColumnRule::Drop,
+            },
+        })
+        .collect();
+    if rules.iter().all(|rule| *rule == ColumnRule::Export) {
+        return (headers, rows);
+    }
+    
+    let keep = |values: Vec<String>| -> Vec<String> {
+        values.into_iter().zip(&rules)
+            .filter_map(|(value, rule)| match rule {
+                ColumnRule::Export => Some(value),
+                ColumnRule::Mask(_) if value.is_empty() || value.eq_ignore_ascii_case("NULL") => Some(value),
+                ColumnRule::Mask(strategy) => Some(mask_value(&value, *strategy, seed)),
+                ColumnRule::Drop => None,
+            })
+            .collect()
+    };
+    let headers = headers.into_iter().zip(&rules)
+        .filter(|(_, rule)| **rule != ColumnRule::Drop)
+        .map(|(header, _)| header)
+        .collect();
+    (headers, rows.into_iter().map(keep).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_sql_content;
+    
+    const POLICY: &str = r#"
+[tables.users]
+export = ["id", "country"]
+mask = { email = "fake:email" }
+drop = ["password_hash"]
+
+[tables.audit_log]
+skip = true
+"#;
+
+    fn tables() -> Vec<Table> {
+        let sql = "CREATE TABLE users (id int, email text, password_hash text, country text, phone text);\n\
+            CREATE TABLE audit_log (id int, payload text);\n\
+            CREATE TABLE invoices (id int, amount int);\n";
+        parse_sql_content(sql.to_string()).unwrap().0
+    }
+    
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+    
+    #[test]
+    fn test_parse_policy() {
+        let policy = parse_policy_str(POLICY).unwrap();
+        assert_eq!(policy.unreviewed, UnreviewedAction::Fail);
+        assert_eq!(policy.column_rule("users", "password_hash"), Some(ColumnRule::Drop));
+        assert_eq!(policy.column_rule("users", "phone"), None);
+        assert!(policy.skips_table("audit_log"));
+        assert!(!policy.skips_table("invoices"));
+        
+        assert!(parse_policy_str("[tables.t]\nexport = [\"a\"]\ndrop = [\"a\"]").is_err());
+        assert!(parse_policy_str("[tables.t]\nmask = { a = \"blur\" }").is_err());
+        assert!(parse_policy_str("unreviewed = \"ignore\"").is_err());
+    }
+    
+    #[test]
+    fn test_unreviewed_columns() {
+        let policy = parse_policy_str(POLICY).unwrap();
+        assert_eq!(policy.unreviewed_columns(&tables()), vec!["users.phone", "invoices.id", "invoices.amount"]);
+        
+        let dropping = parse_policy_str(&format!("unreviewed = \"drop\"\n{}", POLICY)).unwrap();
+        assert!(dropping.skips_table("invoices"));
+    }
+    
+    #[test]
+    fn test_apply_policy() {
+        let policy = parse_policy_str(&format!("unreviewed = \"mask\"\n{}", POLICY)).unwrap();
+        let users = &tables()[0];
+        let headers = strings(&["id", "email", "password_hash", "country", "phone", "_source_line"]);
+        let rows = vec![strings(&["1", "ann@corp.example", "$2b$10$x", "DE", "555-0100", "3"])];
+        
+        let (headers, rows) = apply_policy(&policy, users, headers, rows, 0);
+        
+        assert_eq!(headers, strings(&["id", "email", "country", "phone", "_source_line"]));
+        assert_eq!(rows[0][0], "1");
+        assert_ne!(rows[0][1], "ann@corp.example");
+        assert!(rows[0][1].contains('@'));
+        assert_eq!(rows[0][2], "DE");
+        assert_eq!(rows[0][3], mask_value("555-0100", MaskStrategy::Hash, 0));
+        assert_eq!(rows[0][4], "3");
+    }
+}
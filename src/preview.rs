@@ -0,0 +1,105 @@
+use anyhow::Result;
+
+use crate::args::flag_value;
+
+/// Rows shown by `head` when `-n` is not given
+pub const DEFAULT_PREVIEW_ROWS: usize = 10;
+
+/// Widest a column is drawn before its cells are cut off
+const MAX_COLUMN_WIDTH: usize = 40;
+
+/// Parse -n option of the head command
+pub fn parse_preview_rows(args: &[String]) -> Result<usize> {
+    match flag_value(args, "-n")? {
+        Some(count) => count.parse::<usize>()
+            .map_err(|_| anyhow::anyhow!("Error: Invalid -n '{}'. Use a number of rows", count)),
+        None => Ok(DEFAULT_PREVIEW_ROWS),
+    }
+}
+
+/// Lay out rows as an aligned text table. Columns whose values are all numbers
+/// are right-aligned; line breaks are shown as `\n` and long cells are cut off with `…`.
+pub fn render_text_table(headers: &[String], rows: &[Vec<String>]) -> String {
+    let cells: Vec<Vec<String>> = rows.iter()
+        .map(|row| (0..headers.len()).map(|i| display_cell(row.get(i).map(String::as_str).unwrap_or(""))).collect())
+        .collect();
+    let headers: Vec<String> = headers.iter().map(|h| display_cell(h)).collect();
+    
+    let widths: Vec<usize> = headers.iter()
+        .enumerate()
+        .map(|(i, header)| cells.iter()
+            .map(|row| row[i].chars().count())
+            .chain(std::iter::once(header.chars().count()))
+            .max()
+            .unwrap_or(0))
+        .collect();
+    let numeric: Vec<bool> = (0..headers.len())
+        .map(|i| !cells.is_empty() && cells.iter().all(|row| row[i] == "NULL" || row[i].parse::<f64>().is_ok()))
+        .collect();
+    
+    let line = |row: &[String]| -> String {
+        row.iter()
+            .enumerate()
+            .map(|(i, cell)| if numeric[i] {
+                format!("{:>width$}", cell, width = widths[i])
+            } else {
+                format!("{:<width$}", cell, width = widths[i])
+            })
+            .collect::<Vec<_>>()
+            .join(" | ")
+            .trim_end()
+            .to_string()
+    };
+    
+    let mut output = line(&headers);
+    output.push('\n');
+    output.push_str(&widths.iter().map(|w| "-".repeat(*w)).collect::<Vec<_>>().join("-+-"));
+    output.push('\n');
+    for row in &cells {
+        output.push_str(&line(row));
+        output.push('\n');
+    }
+    
+    output
+}
+
+/// Make a value safe to print on one line and no wider than `MAX_COLUMN_WIDTH`
+fn display_cell(value: &str) -> String {
+    let value = value.replace('\r', "\\r").replace('\n', "\\n").replace('\t', " ");
+    if value.chars().count() > MAX_COLUMN_WIDTH {
+        let mut cut: String = value.chars().take(MAX_COLUMN_WIDTH - 1).collect();
+        cut.push('…');
+        cut
+    } else {
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+    
+    #[test]
+    fn test_render_text_table() {
+        let headers = strings(&["id", "name"]);
+        let rows = vec![strings(&["1", "Alice Smith"]), strings(&["10", "Bo\nb"])];
+        
+        assert_eq!(render_text_table(&headers, &rows), "\
+id | name
+---+------------
+ 1 | Alice Smith
+10 | Bo\\nb
+");
+    }
+    
+    #[test]
+    fn test_display_cell_cuts_long_values() {
+        let cell = display_cell(&"x".repeat(100));
+        assert_eq!(cell.chars().count(), MAX_COLUMN_WIDTH);
+        assert!(cell.ends_with('…'));
+    }
+}
@@ -0,0 +1,157 @@
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::args::flag_value;
+use crate::infer::infer_column_types;
+use crate::preview::render_text_table;
+
+/// Most frequent values listed per column
+pub const TOP_VALUES: usize = 10;
+
+/// A value and how many rows hold it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ValueCount {
+    pub value: String,
+    pub count: usize,
+}
+
+/// Data-quality summary of one column. NULL and empty cells count as nulls;
+/// lengths are in characters, over the other values.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ColumnProfile {
+    pub column: String,
+    pub inferred_type: &'static str,
+    pub nulls: usize,
+    pub null_percent: f64,
+    pub min_length: Option<usize>,
+    pub max_length: Option<usize>,
+    pub avg_length: Option<f64>,
+    pub distinct: usize,
+    pub top_values: Vec<ValueCount>,
+}
+
+/// Summary of every column of a table
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TableProfile {
+    pub table: String,
+    /// Rows profiled
+    pub rows: usize,
+    /// Rows in the table, which can be more than `rows` with --max-rows
+    pub total_rows: usize,
+    pub columns: Vec<ColumnProfile>,
+}
+
+/// Parse the --max-rows option of `profile`: profile only the first rows of each table
+pub fn parse_max_rows(args: &[String]) -> Result<Option<usize>> {
+    flag_value(args, "--max-rows")?
+        .map(|value| value.parse::<usize>()
+            .ok()
+            .filter(|&n| n > 0)
+            .ok_or_else(|| anyhow::anyhow!("Error: Invalid --max-rows '{}'. Use a positive number", value)))
+        .transpose()
+}
+
+/// Profile the columns of a table from its rows
+pub fn profile_table(table: &str, headers: &[String], rows: &[Vec<String>], total_rows: usize) -> TableProfile {
+    let types = infer_column_types(headers.len(), rows);
+    let columns = headers.iter().zip(types).enumerate()
+        .map(|(i, (column, inferred_type))| {
+            let mut counts: HashMap<&str, usize> = HashMap::new();
+            let mut nulls = 0;
+            let mut lengths: Vec<usize> = Vec::new();
+            for value in rows.iter().map(|row| row.get(i).map(String::as_str).unwrap_or("")) {
+                if value.is_empty() || value == "NULL" {
+                    nulls += 1;
+                    continue;
+                }
+                lengths.push(value.chars().count());
+                *counts.entry(value).or_default() += 1;
+            }
+            
+            let distinct = counts.len();
+            let mut top_values: Vec<ValueCount> = counts.into_iter()
+                .map(|(value, count)| ValueCount { value: value.to_string(), count })
+                .collect();
+            top_values.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+            top_values.truncate(TOP_VALUES);
+            
+            ColumnProfile {
+                column: column.clone(),
+                inferred_type: inferred_type.name(),
+                nulls,
+                null_percent: if rows.is_empty() { 0.0 } else { nulls as f64 * 100.0 / rows.len() as f64 },
+                min_length: lengths.iter().copied().min(),
+                max_length: lengths.iter().copied().max(),
+                avg_length: (!lengths.is_empty()).then(|| lengths.iter().sum::<usize>() as f64 / lengths.len() as f64),
+                distinct,
+                top_values,
+            }
+        })
+        .collect();
+    
+    TableProfile { table: table.to_string(), rows: rows.len(), total_rows, columns }
+}
+
+/// Lay out a table's profile as an aligned text table, one line per column
+pub fn render_profile(profile: &TableProfile) -> String {
+    let headers: Vec<String> = ["column", "type", "null %", "min len", "max len", "avg len", "distinct", "top values"]
+        .iter().map(|h| h.to_string()).collect();
+    let length = |length: Option<usize>| length.map(|n| n.to_string()).unwrap_or_default();
+    let rows: Vec<Vec<String>> = profile.columns.iter()
+        .map(|column| vec![
+            column.column.clone(),
+            column.inferred_type.to_string(),
+            format!("{:.1}", column.null_percent),
+            length(column.min_length),
+            length(column.max_length),
+            column.avg_length.map(|avg| format!("{:.1}", avg)).unwrap_or_default(),
+            column.distinct.to_string(),
+            column.top_values.iter()
+                .map(|top| format!("{} ({})", top.value, top.count))
+                .collect::<Vec<_>>()
+                .join(", "),
+        ])
+        .collect();
+    
+    let sampled = if profile.rows < profile.total_rows {
+        format!("first {} of {} rows", profile.rows, profile.total_rows)
+    } else {
+        format!("{} rows", profile.rows)
+    };
+    format!("{} ({})\n{}", profile.table, sampled, render_text_table(&headers, &rows))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+    
+    #[test]
+    fn test_profile_table() {
+        let headers = strings(&["id", "country"]);
+        let rows = vec![
+            strings(&["1", "DE"]),
+            strings(&["2", "FR"]),
+            strings(&["3", "DE"]),
+            strings(&["4", "NULL"]),
+        ];
+        
+        let profile = profile_table("users", &headers, &rows, 10);
+        let country = &profile.columns[1];
+        assert_eq!(profile.columns[0].inferred_type, "integer");
+        assert_eq!(country.inferred_type, "string");
+        assert_eq!(country.nulls, 1);
+        assert_eq!(country.null_percent, 25.0);
+        assert_eq!((country.min_length, country.max_length, country.avg_length), (Some(2), Some(2), Some(2.0)));
+        assert_eq!(country.distinct, 2);
+        assert_eq!(country.top_values[0], ValueCount { value: "DE".to_string(), count: 2 });
+        
+        let text = render_profile(&profile);
+        assert!(text.starts_with("users (first 4 of 10 rows)\n"));
+        assert!(text.contains("DE (2), FR (1)"));
+    }
+}
@@ -0,0 +1,146 @@
+//! Python bindings, built with `maturin build --features python`.
+//!
+//! ```python
+//! import parsley_csv
+//! tables = parsley_csv.parse_sql_file("dump.sql")
+//! rows = parsley_csv.extract_rows("dump.sql", "users")          # list of dicts
+//! df = pandas.DataFrame(parsley_csv.extract_columns("dump.sql", "users"))
+//! files = parsley_csv.convert("dump.sql", "out/")
+//! ```
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::types::Table;
+
+fn to_py_err(error: anyhow::Error) -> PyErr {
+    PyValueError::new_err(format!("{:#}", error))
+}
+
+fn table_to_dict<'py>(py: Python<'py>, table: &Table) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new(py);
+    dict.set_item("name", &table.name)?;
+    dict.set_item("columns", &table.columns)?;
+    dict.set_item("primary_key", &table.primary_key)?;
+    dict.set_item("unique_constraints", &table.unique_constraints)?;
+    let foreign_keys: Vec<(Vec<String>, String, Vec<String>)> = table.foreign_keys.iter()
+        .map(|fk| (fk.columns.clone(), fk.references_table.clone(), fk.references_columns.clone()))
+        .collect();
+    dict.set_item("foreign_keys", foreign_keys)?;
+    Ok(dict)
+}
+
+/// Parse a dump and return the headers and rows of one table
+fn load_table(path: &str, table_name: &str) -> anyhow::Result<(Vec<String>, Vec<Vec<String>>)> {
+    let (tables, content) = crate::parse_sql_file(path)?;
+    let table = tables.into_iter()
+        .find(|t| t.name == table_name)
+        .ok_or_else(|| anyhow::anyhow!("Table '{}' not found in {}", table_name, path))?;
+    let rows = crate::extract_table_rows(&content, &table)?;
+    Ok((table.columns, rows))
+}
+
+/// Parse a SQL dump and return its tables as dicts with name, columns and keys
+#[pyfunction]
+fn parse_sql_file<'py>(py: Python<'py>, path: &str) -> PyResult<Vec<Bound<'py, PyDict>>> {
+    let (tables, _) = crate::parse_sql_file(path).map_err(to_py_err)?;
+    tables.iter().map(|table| table_to_dict(py, table)).collect()
+}
+
+/// Return the rows of a table as a list of dicts keyed by column name
+#[pyfunction]
+fn extract_rows(path: &str, table: &str) -> PyResult<Vec<HashMap<String, String>>> {
+    let (headers, rows) = load_table(path, table).map_err(to_py_err)?;
+    Ok(rows.into_iter()
+        .map(|row| headers.iter().cloned().zip(row).collect())
+        .collect())
+}
+
+/// Return the rows of a table column-wise (`{column: [values]}`), ready for `pandas.DataFrame`
+#[pyfunction]
+fn extract_columns(path: &str, table: &str) -> PyResult<HashMap<String, Vec<String>>> {
+    let (headers, rows) = load_table(path, table).map_err(to_py_err)?;
+    Ok(headers.iter()
+        .enumerate()
+        .map(|(i, header)| {
+            let values = rows.iter().map(|row| row.get(i).cloned().unwrap_or_default()).collect();
+            (header.clone(), values)
+        })
+        .collect())
+}
+
+/// Convert every table of a dump to CSV files and return their paths
+#[pyfunction]
+#[pyo3(signature = (path, output_dir = "."))]
+fn convert(path: &str, output_dir: &str) -> PyResult<Vec<String>> {
+    let (tables, content) = crate::parse_sql_file(path).map_err(to_py_err)?;
+    let mut files = Vec::new();
+    
+    for (table, filename) in tables.iter().zip(crate::csv_filenames(&tables, Default::default())) {
+        let rows = crate::extract_table_rows(&content, table).map_err(to_py_err)?;
+        if rows.is_empty() {
+            continue;
+        }
+        let csv_path = Path::new(output_dir).join(filename);
+        let csv_path = csv_path.to_string_lossy().to_string();
+        crate::write_csv(&csv_path, &table.columns, &rows).map_err(to_py_err)?;
+        files.push(csv_path);
+    }
+    
+    Ok(files)
+}
+
+#[pymodule]
+fn parsley_csv(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(parse_sql_file, m)?)?;
+    m.add_function(wrap_pyfunction!(extract_rows, m)?)?;
+    m.add_function(wrap_pyfunction!(extract_columns, m)?)?;
+    m.add_function(wrap_pyfunction!(convert, m)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    
+    fn dump(dir: &Path) -> String {
+        let path = dir.join("dump.sql");
+        fs::write(&path, "CREATE TABLE users (id INT PRIMARY KEY, name TEXT);\nINSERT INTO users VALUES (1, 'Al'), (2, 'Bo');\n").unwrap();
+        path.to_string_lossy().to_string()
+    }
+    
+    #[test]
+    fn test_extract_rows_and_columns() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dump(dir.path());
+        
+        let rows = extract_rows(&path, "users").unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1]["name"], "Bo");
+        let columns = extract_columns(&path, "users").unwrap();
+        assert_eq!(columns["id"], vec!["1", "2"]);
+        
+        let files = convert(&path, &dir.path().to_string_lossy()).unwrap();
+        assert_eq!(fs::read_to_string(&files[0]).unwrap(), "id,name\n1,Al\n2,Bo\n");
+    }
+    
+    #[test]
+    fn test_errors_become_value_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dump(dir.path());
+        
+        Python::initialize();
+        Python::attach(|py| {
+            let error = extract_rows(&path, "orders").unwrap_err();
+            assert!(error.is_instance_of::<PyValueError>(py));
+            assert_eq!(error.value(py).to_string(), format!("Table 'orders' not found in {}", path));
+            
+            let error = parse_sql_file(py, "missing.sql").unwrap_err();
+            assert!(error.is_instance_of::<PyValueError>(py));
+        });
+    }
+}
@@ -0,0 +1,759 @@
+use anyhow::{Context, Result};
+use regex::Regex;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+use crate::aggregate::{aggregate_rows, compare_values};
+use crate::types::Aggregate;
+
+/// Headers and rows of a table or query result
+pub type RowSet = (Vec<String>, Vec<Vec<String>>);
+
+/// Keep the rows matching a WHERE condition, written as in a query without the
+/// `WHERE` keyword, e.g. `status = 'paid' AND amount > 10`
+pub fn filter_rows(condition: &str, headers: &[String], rows: Vec<Vec<String>>) -> Result<Vec<Vec<String>>> {
+    let mut parser = Parser {
+        tokens: tokenize(condition)?,
+        pos: 0,
+    };
+    let parsed = parser.condition()?;
+    if let Some(token) = parser.peek() {
+        anyhow::bail!("Unexpected {:?} at end of condition", token);
+    }
+    
+    let column_index = |name: &str| {
+        headers.iter().position(|h| h == name)
+            .ok_or_else(|| anyhow::anyhow!("Column '{}' not found", name))
+    };
+    let resolved = resolve_condition(&parsed, &column_index)?;
+    Ok(rows.into_iter().filter(|row| evaluate(&resolved, row)).collect())
+}
+
+/// Run a read-only SELECT over one table of the dump, e.g.
+/// `SELECT status, count(*) FROM orders WHERE amount > 10 GROUP BY 1 ORDER BY 2 DESC LIMIT 5`.
+/// `load_table` is called with the table name from the FROM clause and returns its headers and rows.
+pub fn execute_query<F>(sql: &str, load_table: F) -> Result<RowSet>
+where
+    F: FnOnce(&str) -> Result<RowSet>,
+{
+    let query = parse_query(sql)?;
+    let (headers, rows) = load_table(&query.table)?;
+    
+    let column_index = |name: &str| {
+        headers.iter().position(|h| h == name)
+            .ok_or_else(|| anyhow::anyhow!("Column '{}' not found in table '{}'", name, query.table))
+    };
+    
+    // WHERE
+    let mut rows = match &query.filter {
+        Some(condition) => {
+            let condition = resolve_condition(condition, &column_index)?;
+            rows.into_iter().filter(|row| evaluate(&condition, row)).collect()
+        }
+        None => rows,
+    };
+    
+    let is_grouped = !query.group_by.is_empty()
+        || query.items.iter().any(|item| matches!(item, SelectItem::Aggregate { .. }));
+    
+    let (output_headers, mut output_rows) = if is_grouped {
+        run_grouped(&query, &headers, &rows)?
+    } else {
+        // Sort before projecting so ORDER BY may use columns that are not selected
+        let select_headers = select_headers(&query.items, &headers);
+        let order_in_output = query.order_by.iter()
+            .all(|(key, _)| resolve_output_key(key, &select_headers).is_ok());
+        if !order_in_output {
+            let keys = query.order_by.iter()
+                .map(|(key, descending)| Ok((column_index(key)?, *descending)))
+                .collect::<Result<Vec<_>>>()?;
+            sort_rows(&mut rows, &keys);
+        }
+        
+        let indexes = select_indexes(&query.items, &headers, &column_index)?;
+        let mut projected: Vec<Vec<String>> = rows.into_iter()
+            .map(|row| indexes.iter().map(|&i| row.get(i).cloned().unwrap_or_default()).collect())
+            .collect();
+        if order_in_output {
+            sort_output(&mut projected, &query.order_by, &select_headers)?;
+        }
+        (select_headers, projected)
+    };
+    
+    if is_grouped {
+        sort_output(&mut output_rows, &query.order_by, &output_headers)?;
+    }
+    
+    if query.distinct {
+        let mut seen = HashSet::new();
+        output_rows.retain(|row| seen.insert(row.clone()));
+    }
+    
+    let output_rows = output_rows.into_iter()
+        .skip(query.offset)
+        .take(query.limit.unwrap_or(usize::MAX))
+        .collect();
+    
+    Ok((output_headers, output_rows))
+}
+
+/// Evaluate GROUP BY and aggregate functions
+fn run_grouped(query: &Query, headers: &[String], rows: &[Vec<String>]) -> Result<RowSet> {
+    // GROUP BY entries may be column names or 1-based positions in the select list
+    let group_by: Vec<String> = query.group_by.iter()
+        .map(|key| match key.parse::<usize>() {
+            Ok(position) => match query.items.get(position.wrapping_sub(1)) {
+                Some(SelectItem::Column { name, .. }) => Ok(name.clone()),
+                _ => anyhow::bail!("GROUP BY position {} does not refer to a selected column", position),
+            },
+            Err(_) => Ok(key.clone()),
+        })
+        .collect::<Result<_>>()?;
+    
+    let aggregates: Vec<Aggregate> = query.items.iter()
+        .filter_map(|item| match item {
+            SelectItem::Aggregate { aggregate, .. } => Some(aggregate.clone()),
+            _ => None,
+        })
+        .collect();
+    
+    let (_, mut grouped) = aggregate_rows(headers, rows, &group_by, &aggregates)?;
+    if grouped.is_empty() && group_by.is_empty() {
+        // Aggregates over no rows still produce a single row, as in SQL
+        grouped = vec![aggregates.iter()
+            .map(|aggregate| match aggregate {
+                Aggregate::Count | Aggregate::CountValues(_) => "0".to_string(),
+                _ => String::new(),
+            })
+            .collect()];
+    }
+    
+    // Map each select item onto the group columns followed by the aggregates
+    let mut aggregate_position = group_by.len();
+    let mut indexes = Vec::new();
+    let mut output_headers = Vec::new();
+    for item in &query.items {
+        match item {
+            SelectItem::Column { name, alias } => {
+                let index = group_by.iter().position(|g| g == name)
+                    .ok_or_else(|| anyhow::anyhow!("Column '{}' must appear in GROUP BY or be used in an aggregate", name))?;
+                indexes.push(index);
+                output_headers.push(alias.clone().unwrap_or_else(|| name.clone()));
+            }
+            SelectItem::Aggregate { display, alias, .. } => {
+                indexes.push(aggregate_position);
+                aggregate_position += 1;
+                output_headers.push(alias.clone().unwrap_or_else(|| display.clone()));
+            }
+            SelectItem::All => anyhow::bail!("SELECT * cannot be combined with GROUP BY or aggregates"),
+        }
+    }
+    
+    let output_rows = grouped.into_iter()
+        .map(|row| indexes.iter().map(|&i| row.get(i).cloned().unwrap_or_default()).collect())
+        .collect();
+    
+    Ok((output_headers, output_rows))
+}
+
+fn select_headers(items: &[SelectItem], headers: &[String]) -> Vec<String> {
+    items.iter()
+        .flat_map(|item| match item {
+            SelectItem::All => headers.to_vec(),
+            SelectItem::Column { name, alias } => vec![alias.clone().unwrap_or_else(|| name.clone())],
+            SelectItem::Aggregate { display, alias, .. } => vec![alias.clone().unwrap_or_else(|| display.clone())],
+        })
+        .collect()
+}
+
+fn select_indexes<F>(items: &[SelectItem], headers: &[String], column_index: &F) -> Result<Vec<usize>>
+where
+    F: Fn(&str) -> Result<usize>,
+{
+    let mut indexes = Vec::new();
+    for item in items {
+        match item {
+            SelectItem::All => indexes.extend(0..headers.len()),
+            SelectItem::Column { name, .. } => indexes.push(column_index(name)?),
+            SelectItem::Aggregate { .. } => unreachable!("aggregates are handled by run_grouped"),
+        }
+    }
+    Ok(indexes)
+}
+
+/// Resolve an ORDER BY key against the output columns (by name, alias or 1-based position)
+fn resolve_output_key(key: &str, headers: &[String]) -> Result<usize> {
+    if let Ok(position) = key.parse::<usize>() {
+        if position >= 1 && position <= headers.len() {
+            return Ok(position - 1);
+        }
+        anyhow::bail!("ORDER BY position {} is out of range", position);
+    }
+    headers.iter().position(|h| h == key)
+        .ok_or_else(|| anyhow::anyhow!("ORDER BY column '{}' is not in the result", key))
+}
+
+fn sort_output(rows: &mut [Vec<String>], order_by: &[(String, bool)], headers: &[String]) -> Result<()> {
+    let keys = order_by.iter()
+        .map(|(key, descending)| Ok((resolve_output_key(key, headers)?, *descending)))
+        .collect::<Result<Vec<_>>>()?;
+    sort_rows(rows, &keys);
+    Ok(())
+}
+
+fn sort_rows(rows: &mut [Vec<String>], keys: &[(usize, bool)]) {
+    if keys.is_empty() {
+        return;
+    }
+    rows.sort_by(|a, b| {
+        keys.iter()
+            .map(|&(i, descending)| {
+                let ordering = compare_nullable(a.get(i).map(|v| v.as_str()), b.get(i).map(|v| v.as_str()));
+                if descending { ordering.reverse() } else { ordering }
+            })
+            .find(|ordering| *ordering != Ordering::Equal)
+            .unwrap_or(Ordering::Equal)
+    });
+}
+
+/// Compare two cells with NULLs sorting first
+fn compare_nullable(a: Option<&str>, b: Option<&str>) -> Ordering {
+    match (a.filter(|v| !is_null(v)), b.filter(|v| !is_null(v))) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Less,
+        (Some(_), None) => Ordering::Greater,
+        (Some(a), Some(b)) => compare_values(a, b),
+    }
+}
+
+fn is_null(value: &str) -> bool {
+    value.eq_ignore_ascii_case("NULL")
+}
+
+/// Parsed SELECT statement
+#[derive(Debug, Clone)]
+struct Query {
+    distinct: bool,
+    items: Vec<SelectItem>,
+    table: String,
+    filter: Option<Condition<String>>,
+    group_by: Vec<String>,
+    order_by: Vec<(String, bool)>,
+    limit: Option<usize>,
+    offset: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum SelectItem {
+    All,
+    Column { name: String, alias: Option<String> },
+    Aggregate { aggregate: Aggregate, display: String, alias: Option<String> },
+}
+
+/// WHERE condition; `C` is a column name while parsing and a column index once resolved
+#[derive(Debug, Clone)]
+enum Condition<C> {
+    And(Box<Condition<C>>, Box<Condition<C>>),
+    Or(Box<Condition<C>>, Box<Condition<C>>),
+    Not(Box<Condition<C>>),
+    Compare(Operand<C>, String, Operand<C>),
+    IsNull(Operand<C>, bool),
+    Like(Operand<C>, Regex, bool),
+    In(Operand<C>, Vec<Operand<C>>, bool),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Operand<C> {
+    Column(C),
+    Literal(String),
+    Null,
+}
+
+fn resolve_condition<F>(condition: &Condition<String>, column_index: &F) -> Result<Condition<usize>>
+where
+    F: Fn(&str) -> Result<usize>,
+{
+    let operand = |operand: &Operand<String>| -> Result<Operand<usize>> {
+        Ok(match operand {
+            Operand::Column(name) => Operand::Column(column_index(name)?),
+            Operand::Literal(value) => Operand::Literal(value.clone()),
+            Operand::Null => Operand::Null,
+        })
+    };
+    
+    Ok(match condition {
+        Condition::And(a, b) => Condition::And(Box::new(resolve_condition(a, column_index)?), Box::new(resolve_condition(b, column_index)?)),
+        Condition::Or(a, b) => Condition::Or(Box::new(resolve_condition(a, column_index)?), Box::new(resolve_condition(b, column_index)?)),
+        Condition::Not(a) => Condition::Not(Box::new(resolve_condition(a, column_index)?)),
+        Condition::Compare(a, op, b) => Condition::Compare(operand(a)?, op.clone(), operand(b)?),
+        Condition::IsNull(a, negated) => Condition::IsNull(operand(a)?, *negated),
+        Condition::Like(a, pattern, negated) => Condition::Like(operand(a)?, pattern.clone(), *negated),
+        Condition::In(a, list, negated) => Condition::In(
+            operand(a)?,
+            list.iter().map(operand).collect::<Result<_>>()?,
+            *negated,
+        ),
+    })
+}
+
+fn evaluate(condition: &Condition<usize>, row: &[String]) -> bool {
+    let value = |operand: &Operand<usize>| -> Option<String> {
+        match operand {
+            Operand::Column(i) => row.get(*i).filter(|v| !is_null(v)).cloned(),
+            Operand::Literal(value) => Some(value.clone()),
+            Operand::Null => None,
+        }
+    };
+    
+    match condition {
+        Condition::And(a, b) => evaluate(a, row) && evaluate(b, row),
+        Condition::Or(a, b) => evaluate(a, row) || evaluate(b, row),
+        Condition::Not(a) => !evaluate(a, row),
+        Condition::Compare(a, op, b) => {
+            // Comparisons involving NULL are never true
+            let (Some(a), Some(b)) = (value(a), value(b)) else {
+                return false;
+            };
+            let ordering = compare_values(&a, &b);
+            match op.as_str() {
+                "=" => ordering == Ordering::Equal,
+                "!=" | "<>" => ordering != Ordering::Equal,
+                "<" => ordering == Ordering::Less,
+                "<=" => ordering != Ordering::Greater,
+                ">" => ordering == Ordering::Greater,
+                ">=" => ordering != Ordering::Less,
+                _ => false,
+            }
+        }
+        Condition::IsNull(a, negated) => value(a).is_none() != *negated,
+        Condition::Like(a, pattern, negated) => value(a)
+            .is_some_and(|v| pattern.is_match(&v) != *negated),
+        Condition::In(a, list, negated) => value(a).is_some_and(|v| {
+            let found = list.iter()
+                .filter_map(value)
+                .any(|candidate| compare_values(&v, &candidate) == Ordering::Equal);
+            found != *negated
+        }),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    QuotedIdentifier(String),
+    Text(String),
+    Number(String),
+    Symbol(String),
+}
+
+fn tokenize(sql: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '\'' || c == '"' || c == '`' {
+            // Quoted string or identifier, with doubled quotes as escapes
+            let mut value = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    Some(&q) if q == c && chars.get(i + 1) == Some(&c) => {
+                        value.push(c);
+                        i += 2;
+                    }
+                    Some(&q) if q == c => break,
+                    Some(&ch) => {
+                        value.push(ch);
+                        i += 1;
+                    }
+                    None => anyhow::bail!("Unterminated quoted value in query"),
+                }
+            }
+            i += 1;
+            tokens.push(if c == '\'' { Token::Text(value) } else { Token::QuotedIdentifier(value) });
+        } else if c.is_ascii_digit() || (c == '-' && chars.get(i + 1).is_some_and(|n| n.is_ascii_digit())) {
+            let start = i;
+            i += 1;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            tokens.push(Token::Number(chars[start..i].iter().collect()));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '.') {
+                i += 1;
+            }
+            tokens.push(Token::Word(chars[start..i].iter().collect()));
+        } else {
+            let two: String = chars[i..(i + 2).min(chars.len())].iter().collect();
+            if ["<=", ">=", "!=", "<>"].contains(&two.as_str()) {
+                tokens.push(Token::Symbol(two));
+                i += 2;
+            } else if "(),*=<>;".contains(c) {
+                tokens.push(Token::Symbol(c.to_string()));
+                i += 1;
+            } else {
+                anyhow::bail!("Unexpected character '{}' in query", c);
+            }
+        }
+    }
+    
+    Ok(tokens)
+}
+
+/// Recursive descent parser over query tokens
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+    
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+    
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword))
+    }
+    
+    fn accept_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.peek_keyword(keyword);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+    
+    fn expect_keyword(&mut self, keyword: &str) -> Result<()> {
+        if self.accept_keyword(keyword) {
+            Ok(())
+        } else {
+            anyhow::bail!("Expected {} in query", keyword)
+        }
+    }
+    
+    fn accept_symbol(&mut self, symbol: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Symbol(s)) if s == symbol);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+    
+    fn expect_symbol(&mut self, symbol: &str) -> Result<()> {
+        if self.accept_symbol(symbol) {
+            Ok(())
+        } else {
+            anyhow::bail!("Expected '{}' in query", symbol)
+        }
+    }
+    
+    fn identifier(&mut self) -> Result<String> {
+        match self.next() {
+            Some(Token::Word(word)) if !is_reserved(&word) => Ok(unqualify(&word)),
+            Some(Token::QuotedIdentifier(name)) => Ok(name),
+            other => anyhow::bail!("Expected a column or table name, found {:?}", other),
+        }
+    }
+    
+    fn number(&mut self) -> Result<usize> {
+        match self.next() {
+            Some(Token::Number(value)) => value.parse().context("Expected a whole number"),
+            other => anyhow::bail!("Expected a number, found {:?}", other),
+        }
+    }
+    
+    fn alias(&mut self) -> Result<Option<String>> {
+        if self.accept_keyword("AS") {
+            return self.identifier().map(Some);
+        }
+        match self.peek() {
+            Some(Token::Word(word)) if !is_reserved(word) => self.identifier().map(Some),
+            Some(Token::QuotedIdentifier(_)) => self.identifier().map(Some),
+            _ => Ok(None),
+        }
+    }
+    
+    fn select_item(&mut self) -> Result<SelectItem> {
+        if self.accept_symbol("*") {
+            return Ok(SelectItem::All);
+        }
+        
+        let name = self.identifier()?;
+        if !self.accept_symbol("(") {
+            let alias = self.alias()?;
+            return Ok(SelectItem::Column { name, alias });
+        }
+        
+        let function = name.to_lowercase();
+        let argument = if self.accept_symbol("*") { "*".to_string() } else { self.identifier()? };
+        self.expect_symbol(")")?;
+        let aggregate = match (function.as_str(), argument.as_str()) {
+            ("count", "*") => Aggregate::Count,
+            ("count", _) => Aggregate::CountValues(argument.clone()),
+            ("sum", _) => Aggregate::Sum(argument.clone()),
+            ("avg", _) => Aggregate::Avg(argument.clone()),
+            ("min", _) => Aggregate::Min(argument.clone()),
+            ("max", _) => Aggregate::Max(argument.clone()),
+            _ => anyhow::bail!("Unsupported function {}({}) in query", function, argument),
+        };
+        let alias = self.alias()?;
+        
+        Ok(SelectItem::Aggregate {
+            aggregate,
+            display: format!("{}({})", function, argument),
+            alias,
+        })
+    }
+    
+    fn operand(&mut self) -> Result<Operand<String>> {
+        match self.next() {
+            Some(Token::Text(value)) | Some(Token::Number(value)) => Ok(Operand::Literal(value)),
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case("NULL") => Ok(Operand::Null),
+            Some(Token::Word(word)) if !is_reserved(&word) => Ok(Operand::Column(unqualify(&word))),
+            Some(Token::QuotedIdentifier(name)) => Ok(Operand::Column(name)),
+            other => anyhow::bail!("Expected a value or column, found {:?}", other),
+        }
+    }
+    
+    fn condition(&mut self) -> Result<Condition<String>> {
+        let mut left = self.and_condition()?;
+        while self.accept_keyword("OR") {
+            left = Condition::Or(Box::new(left), Box::new(self.and_condition()?));
+        }
+        Ok(left)
+    }
+    
+    fn and_condition(&mut self) -> Result<Condition<String>> {
+        let mut left = self.not_condition()?;
+        while self.accept_keyword("AND") {
+            left = Condition::And(Box::new(left), Box::new(self.not_condition()?));
+        }
+        Ok(left)
+    }
+    
+    fn not_condition(&mut self) -> Result<Condition<String>> {
+        if self.accept_keyword("NOT") {
+            return Ok(Condition::Not(Box::new(self.not_condition()?)));
+        }
+        if self.accept_symbol("(") {
+            let condition = self.condition()?;
+            self.expect_symbol(")")?;
+            return Ok(condition);
+        }
+        
+        let left = self.operand()?;
+        if self.accept_keyword("IS") {
+            let negated = self.accept_keyword("NOT");
+            self.expect_keyword("NULL")?;
+            return Ok(Condition::IsNull(left, negated));
+        }
+        
+        let negated = self.accept_keyword("NOT");
+        if self.accept_keyword("LIKE") {
+            let Operand::Literal(pattern) = self.operand()? else {
+                anyhow::bail!("LIKE requires a string pattern");
+            };
+            return Ok(Condition::Like(left, like_to_regex(&pattern)?, negated));
+        }
+        if self.accept_keyword("IN") {
+            self.expect_symbol("(")?;
+            let mut list = vec![self.operand()?];
+            while self.accept_symbol(",") {
+                list.push(self.operand()?);
+            }
+            self.expect_symbol(")")?;
+            return Ok(Condition::In(left, list, negated));
+        }
+        if negated {
+            anyhow::bail!("Expected LIKE or IN after NOT");
+        }
+        
+        match self.next() {
+            Some(Token::Symbol(op)) if ["=", "!=", "<>", "<", "<=", ">", ">="].contains(&op.as_str()) => {
+                Ok(Condition::Compare(left, op, self.operand()?))
+            }
+            other => anyhow::bail!("Expected a comparison operator, found {:?}", other),
+        }
+    }
+}
+
+/// Keywords that can't be used as bare column names or aliases
+fn is_reserved(word: &str) -> bool {
+    const RESERVED: &[&str] = &[
+        "SELECT", "DISTINCT", "FROM", "WHERE", "GROUP", "BY", "ORDER", "ASC", "DESC", "LIMIT",
+        "OFFSET", "AND", "OR", "NOT", "IS", "NULL", "LIKE", "IN", "AS",
+    ];
+    RESERVED.iter().any(|keyword| keyword.eq_ignore_ascii_case(word))
+}
+
+/// Drop a table qualifier from `orders.status`
+fn unqualify(name: &str) -> String {
+    name.rsplit('.').next().unwrap_or(name).to_string()
+}
+
+/// Translate a LIKE pattern (`%` and `_` wildcards) into a case-insensitive regex
+fn like_to_regex(pattern: &str) -> Result<Regex> {
+    let mut regex = String::from("(?is)^");
+    for c in pattern.chars() {
+        match c {
+            '%' => regex.push_str(".*"),
+            '_' => regex.push('.'),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    Regex::new(&regex).context("Invalid LIKE pattern")
+}
+
+fn parse_query(sql: &str) -> Result<Query> {
+    let mut parser = Parser {
+        tokens: tokenize(sql)?,
+        pos: 0,
+    };
+    
+    parser.expect_keyword("SELECT")?;
+    let distinct = parser.accept_keyword("DISTINCT");
+    let mut items = vec![parser.select_item()?];
+    while parser.accept_symbol(",") {
+        items.push(parser.select_item()?);
+    }
+    
+    parser.expect_keyword("FROM")?;
+    let table = parser.identifier()?;
+    
+    let filter = if parser.accept_keyword("WHERE") {
+        Some(parser.condition()?)
+    } else {
+        None
+    };
+    
+    let mut group_by = Vec::new();
+    if parser.accept_keyword("GROUP") {
+        parser.expect_keyword("BY")?;
+        loop {
+            group_by.push(match parser.peek() {
+                Some(Token::Number(_)) => parser.number()?.to_string(),
+                _ => parser.identifier()?,
+            });
+            if !parser.accept_symbol(",") {
+                break;
+            }
+        }
+    }
+    
+    let mut order_by = Vec::new();
+    if parser.accept_keyword("ORDER") {
+        parser.expect_keyword("BY")?;
+        loop {
+            let key = match parser.peek() {
+                Some(Token::Number(_)) => parser.number()?.to_string(),
+                Some(Token::Word(word)) if !is_reserved(word) => {
+                    // Allow ordering by an aggregate as written, e.g. ORDER BY count(*) DESC
+                    let name = parser.identifier()?;
+                    if parser.accept_symbol("(") {
+                        let argument = if parser.accept_symbol("*") { "*".to_string() } else { parser.identifier()? };
+                        parser.expect_symbol(")")?;
+                        format!("{}({})", name.to_lowercase(), argument)
+                    } else {
+                        name
+                    }
+                }
+                _ => parser.identifier()?,
+            };
+            let descending = parser.accept_keyword("DESC");
+            if !descending {
+                parser.accept_keyword("ASC");
+            }
+            order_by.push((key, descending));
+            if !parser.accept_symbol(",") {
+                break;
+            }
+        }
+    }
+    
+    let limit = if parser.accept_keyword("LIMIT") { Some(parser.number()?) } else { None };
+    let offset = if parser.accept_keyword("OFFSET") { parser.number()? } else { 0 };
+    
+    parser.accept_symbol(";");
+    if let Some(token) = parser.peek() {
+        anyhow::bail!("Unexpected {:?} at end of query", token);
+    }
+    
+    Ok(Query {
+        distinct,
+        items,
+        table,
+        filter,
+        group_by,
+        order_by,
+        limit,
+        offset,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+    
+    fn orders(table: &str) -> Result<RowSet> {
+        assert_eq!(table, "orders");
+        Ok((
+            strings(&["id", "status", "amount", "note"]),
+            vec![
+                strings(&["1", "paid", "10", "first order"]),
+                strings(&["2", "open", "25", "NULL"]),
+                strings(&["3", "paid", "5", "Rush delivery"]),
+                strings(&["4", "refunded", "7", "NULL"]),
+            ],
+        ))
+    }
+    
+    #[test]
+    fn test_group_by_position() {
+        let (headers, rows) = execute_query(
+            "SELECT status, count(*) AS n, sum(amount) FROM orders GROUP BY 1 ORDER BY n DESC, status",
+            orders,
+        ).unwrap();
+        assert_eq!(headers, vec!["status", "n", "sum(amount)"]);
+        assert_eq!(rows, vec![
+            strings(&["paid", "2", "15"]),
+            strings(&["open", "1", "25"]),
+            strings(&["refunded", "1", "7"]),
+        ]);
+    }
+    
+    #[test]
+    fn test_where_order_limit() {
+        let (headers, rows) = execute_query(
+            "select id from orders where (amount >= 7 and status <> 'open') or note like '%rush%' order by amount desc limit 2",
+            orders,
+        ).unwrap();
+        assert_eq!(headers, vec!["id"]);
+        assert_eq!(rows, vec![strings(&["1"]), strings(&["4"])]);
+        
+        let (_, rows) = execute_query("SELECT id FROM orders WHERE note IS NULL AND status IN ('open', 'x')", orders).unwrap();
+        assert_eq!(rows, vec![strings(&["2"])]);
+    }
+    
+    #[test]
+    fn test_invalid_queries() {
+        assert!(execute_query("SELECT status, amount FROM orders GROUP BY status", orders).is_err());
+        assert!(execute_query("SELECT missing FROM orders", orders).is_err());
+        assert!(execute_query("DELETE FROM orders", orders).is_err());
+    }
+}
@@ -0,0 +1,153 @@
+//! Scrubbing sensitive text inside values (`--redact`).
+//!
+//! Each rule replaces every match of a regex, e.g. SSNs or email addresses
+//! written into free-text columns, wherever it occurs in a value. Rules apply to
+//! every column unless `--redact-columns` names the ones to scrub.
+
+use anyhow::Result;
+use regex::Regex;
+use std::borrow::Cow;
+
+use crate::args::{flag_value, flag_values};
+
+/// A pattern and what to write in place of its matches
+#[derive(Debug, Clone)]
+pub struct RedactRule {
+    pub pattern: Regex,
+    /// Replacement text; `$1` or `${name}` insert groups of the match
+    pub replacement: String,
+}
+
+/// Redaction rules and the columns they apply to
+#[derive(Debug, Clone, Default)]
+pub struct Redaction {
+    pub rules: Vec<RedactRule>,
+    /// Columns to scrub; empty for every column
+    pub columns: Vec<String>,
+}
+
+impl Redaction {
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+    
+    fn applies_to(&self, column: &str) -> bool {
+        self.columns.is_empty() || self.columns.iter().any(|c| c == column)
+    }
+}
+
+/// Parse --redact options (repeatable) and --redact-columns from command line
+pub fn parse_redaction(args: &[String]) -> Result<Redaction> {
+    let rules = flag_values(args, "--redact")?
+        .into_iter()
+        .map(parse_redact_rule)
+        .collect::<Result<Vec<_>>>()?;
+    let columns: Vec<String> = flag_value(args, "--redact-columns")?
+        .map(|list| list.split(',').map(|c| c.trim().to_string()).filter(|c| !c.is_empty()).collect())
+        .unwrap_or_default();
+    if rules.is_empty() && !columns.is_empty() {
+        anyhow::bail!("Error: --redact-columns requires at least one --redact rule");
+    }
+    
+    Ok(Redaction { rules, columns })
+}
+
+/// Parse a single `<regex>=<replacement>` rule. The rule is split at its last
+/// `=`, so the regex may contain `=` but the replacement may not.
+pub fn parse_redact_rule(spec: &str) -> Result<RedactRule> {
+    let Some((pattern, replacement)) = spec.rsplit_once('=').filter(|(pattern, _)| !pattern.is_empty()) else {
+        anyhow::bail!("Error: Invalid --redact '{}'. Use <regex>=<replacement>, e.g. '\\b\\d{{3}}-\\d{{2}}-\\d{{4}}\\b'=XXX-XX-XXXX", spec);
+    };
+    let pattern = Regex::new(pattern)
+        .map_err(|e| anyhow::anyhow!("Error: Invalid regex in --redact '{}': {}", spec, e))?;
+    
+    Ok(RedactRule { pattern, replacement: replacement.to_string() })
+}
+
+/// Apply every rule to a single value, in order
+pub fn redact_value<'a>(value: &'a str, rules: &[RedactRule]) -> Cow<'a, str> {
+    let mut value = Cow::Borrowed(value);
+    for rule in rules {
+        if let Cow::Owned(redacted) = rule.pattern.replace_all(&value, rule.replacement.as_str()) {
+            value = Cow::Owned(redacted);
+        }
+    }
+    value
+}
+
+/// Scrub the values of the columns the rules apply to. NULL values are kept.
+/// Returns the number of values that changed.
+pub fn apply_redaction(headers: &[String], rows: &mut [Vec<String>], redaction: &Redaction) -> usize {
+    if redaction.is_empty() {
+        return 0;
+    }
+    let columns: Vec<usize> = headers.iter()
+        .enumerate()
+        .filter(|(_, header)| redaction.applies_to(header))
+        .map(|(i, _)| i)
+        .collect();
+    let mut redacted = 0;
+    
+    for row in rows.iter_mut() {
+        for &i in &columns {
+            let Some(cell) = row.get_mut(i) else {
+                continue;
+            };
+            if cell.eq_ignore_ascii_case("NULL") {
+                continue;
+            }
+            if let Cow::Owned(value) = redact_value(cell, &redaction.rules) {
+                *cell = value;
+                redacted += 1;
+            }
+        }
+    }
+    
+    redacted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+    
+    #[test]
+    fn test_parse_redact_rule() {
+        let rule = parse_redact_rule(r"\b\d{3}-\d{2}-\d{4}\b=XXX-XX-XXXX").unwrap();
+        assert_eq!(rule.pattern.as_str(), r"\b\d{3}-\d{2}-\d{4}\b");
+        assert_eq!(rule.replacement, "XXX-XX-XXXX");
+        
+        let rule = parse_redact_rule("password=\\S+=***").unwrap();
+        assert_eq!(rule.pattern.as_str(), "password=\\S+");
+        assert_eq!(rule.replacement, "***");
+        
+        assert!(parse_redact_rule("no-replacement").is_err());
+        assert!(parse_redact_rule("=XXX").is_err());
+        assert!(parse_redact_rule("(unclosed=XXX").is_err());
+    }
+    
+    #[test]
+    fn test_apply_redaction_to_listed_columns() {
+        let redaction = parse_redaction(&args(&[
+            "dump.sql",
+            "--redact", r"\b\d{3}-\d{2}-\d{4}\b=XXX-XX-XXXX",
+            "--redact", r"[\w.+-]+@([\w-]+\.[\w.]+)=***@$1",
+            "--redact-columns", "notes",
+        ])).unwrap();
+        let headers = args(&["email", "notes"]);
+        let mut rows = vec![
+            args(&["ann@example.com", "SSN 123-45-6789, mail ann@example.com"]),
+            args(&["bob@example.com", "nothing to hide"]),
+            args(&["NULL", "NULL"]),
+        ];
+        
+        let redacted = apply_redaction(&headers, &mut rows, &redaction);
+        
+        assert_eq!(redacted, 1);
+        assert_eq!(rows[0], args(&["ann@example.com", "SSN XXX-XX-XXXX, mail ***@example.com"]));
+        assert_eq!(rows[1][1], "nothing to hide");
+    }
+}
@@ -0,0 +1,333 @@
+//! What a conversion wrote, for callers that act on the results instead of
+//! reading the "Created ..." lines.
+
+use serde::Serialize;
+
+use crate::preview::render_text_table;
+
+/// A file (or other destination) written for one table
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct WrittenFile {
+    /// Path or URL the rows were written to
+    pub path: String,
+    pub rows_written: usize,
+    /// Size of the output, when the sink knows it
+    pub bytes: Option<u64>,
+    /// What went wrong with some of the table's rows: dates `Converter`'s date
+    /// filter could not parse, or the CLI's counts of rows with a wrong number
+    /// of values, oversized fields and dates that were not reformatted.
+    /// `write_csv` and the other `write_*` functions write rows as given and
+    /// leave it empty.
+    pub warnings: Vec<String>,
+}
+
+/// Output of one table
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct TableReport {
+    pub table: String,
+    /// Rows found in the dump's INSERT statements
+    pub rows_in: usize,
+    /// Rows left out by filters, or skipped for a wrong number of values
+    pub rows_filtered: usize,
+    /// Time spent extracting and writing the table, when it was measured
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    /// Time spent in each phase of the table (`--timings`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timings: Option<TableTimings>,
+    #[serde(flatten)]
+    pub file: WrittenFile,
+}
+
+/// A table that could not be converted (`--on-table-error`)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct FailedTable {
+    pub table: String,
+    pub error: String,
+    /// File its statements were written to with `--on-table-error quarantine`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quarantined: Option<String>,
+}
+
+/// An INSERT statement that could not be parsed, whose rows are in no table
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SkippedStatement {
+    /// Byte range of the statement in the decoded dump, with line endings normalized
+    pub start: usize,
+    pub end: usize,
+    /// The statement's first 200 characters
+    pub text: String,
+}
+
+/// Milliseconds a table spent in each phase of its conversion
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct TableTimings {
+    /// Finding the table's INSERT statements and parsing their values
+    pub extract_ms: u64,
+    /// Watermark, date, key and configured filters
+    pub filter_ms: u64,
+    /// Value rewrites, masking, policy and column changes
+    pub transform_ms: u64,
+    /// Waiting for a place in the write queue while the writers were busy
+    pub queue_ms: u64,
+    /// Writing the rows to the output
+    pub write_ms: u64,
+}
+
+impl TableTimings {
+    pub fn add(&mut self, other: &TableTimings) {
+        self.extract_ms += other.extract_ms;
+        self.filter_ms += other.filter_ms;
+        self.transform_ms += other.transform_ms;
+        self.queue_ms += other.queue_ms;
+        self.write_ms += other.write_ms;
+    }
+}
+
+/// Milliseconds of the phases of a whole conversion (`--timings`). The table
+/// phases are summed over all tables, which are converted in parallel, so
+/// they can add up to more than the run took.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct RunTimings {
+    /// Reading and decoding the dump
+    pub read_ms: u64,
+    /// Splitting statements and parsing table definitions
+    pub parse_ms: u64,
+    #[serde(flatten)]
+    pub tables: TableTimings,
+}
+
+impl RunTimings {
+    /// The phase that took longest, as a hint where to tune a slow run
+    pub fn bottleneck(&self) -> &'static str {
+        let phases = [
+            (self.read_ms, "reading the dump (I/O or decompression)"),
+            (self.parse_ms, "parsing table definitions"),
+            (self.tables.extract_ms, "extracting values (parsing-bound; more --threads can help)"),
+            (self.tables.filter_ms, "filtering rows"),
+            (self.tables.transform_ms, "transforming values"),
+            (self.tables.queue_ms + self.tables.write_ms, "writing output (I/O-bound; try more --write-threads or a faster destination)"),
+        ];
+        phases.iter().max_by_key(|(ms, _)| *ms).map(|(_, phase)| *phase).unwrap_or_default()
+    }
+}
+
+/// Outputs of a whole conversion
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ConversionReport {
+    /// Completed tables, in the order they were written
+    pub tables: Vec<TableReport>,
+    /// Tables that failed and were left out of the output
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub failed: Vec<FailedTable>,
+    /// INSERT statements skipped because they could not be parsed
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub skipped_statements: Vec<SkippedStatement>,
+    /// Invalid byte sequences of the dump replaced with U+FFFD (`--lossy-utf8`)
+    #[serde(skip_serializing_if = "is_zero")]
+    pub replaced_sequences: usize,
+    /// The conversion was cancelled before every table was written. The table
+    /// being written at that point is not finished, so sinks writing files
+    /// leave no partial file behind.
+    pub cancelled: bool,
+    /// Tables left out because the run was interrupted with Ctrl-C: the ones
+    /// being extracted at that point and the ones not started yet
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub incomplete: Vec<String>,
+    /// Time the whole conversion took, when it was measured
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    /// Time spent in each phase (`--timings`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timings: Option<RunTimings>,
+}
+
+fn is_zero(count: &usize) -> bool {
+    *count == 0
+}
+
+impl ConversionReport {
+    pub fn rows_written(&self) -> usize {
+        self.tables.iter().map(|table| table.file.rows_written).sum()
+    }
+    
+    /// An aligned text table of every table's rows, output size and duration,
+    /// with a total line when there is more than one table
+    pub fn render_summary(&self) -> String {
+        let headers: Vec<String> = ["table", "rows in", "filtered", "written", "size", "duration", "output"]
+            .iter()
+            .map(|header| header.to_string())
+            .collect();
+        let line = |table: &str, rows_in: usize, filtered: usize, written: usize, bytes: Option<u64>, duration_ms: Option<u64>, output: &str| vec![
+            table.to_string(),
+            rows_in.to_string(),
+            filtered.to_string(),
+            written.to_string(),
+            bytes.map(format_size).unwrap_or_else(|| "-".to_string()),
+            duration_ms.map(format_duration).unwrap_or_else(|| "-".to_string()),
+            output.to_string(),
+        ];
+        
+        let mut rows: Vec<Vec<String>> = self.tables.iter()
+            .map(|table| line(
+                &table.table,
+                table.rows_in,
+                table.rows_filtered,
+                table.file.rows_written,
+                table.file.bytes,
+                table.duration_ms,
+                &table.file.path,
+            ))
+            .collect();
+        if self.tables.len() > 1 {
+            let bytes = self.tables.iter().map(|table| table.file.bytes).sum::<Option<u64>>();
+            rows.push(line(
+                "total",
+                self.tables.iter().map(|table| table.rows_in).sum(),
+                self.tables.iter().map(|table| table.rows_filtered).sum(),
+                self.rows_written(),
+                bytes,
+                self.duration_ms,
+                "",
+            ));
+        }
+        render_text_table(&headers, &rows)
+    }
+    
+    /// An aligned text table of the milliseconds every table spent in each
+    /// phase, followed by the run's phases and its slowest one
+    pub fn render_timings(&self) -> String {
+        let headers: Vec<String> = ["table", "extract", "filter", "transform", "queue", "write"]
+            .iter()
+            .map(|header| header.to_string())
+            .collect();
+        let line = |table: &str, timings: &TableTimings| vec![
+            table.to_string(),
+            timings.extract_ms.to_string(),
+            timings.filter_ms.to_string(),
+            timings.transform_ms.to_string(),
+            timings.queue_ms.to_string(),
+            timings.write_ms.to_string(),
+        ];
+        let rows: Vec<Vec<String>> = self.tables.iter()
+            .filter_map(|table| table.timings.as_ref().map(|timings| line(&table.table, timings)))
+            .collect();
+        
+        let mut output = render_text_table(&headers, &rows);
+        if let Some(ref run) = self.timings {
+            output.push_str(&format!(
+                "\nRead {} ms, parse {} ms, extract {} ms, filter {} ms, transform {} ms, queue {} ms, write {} ms",
+                run.read_ms, run.parse_ms, run.tables.extract_ms, run.tables.filter_ms, run.tables.transform_ms, run.tables.queue_ms, run.tables.write_ms,
+            ));
+            if let Some(ms) = self.duration_ms {
+                output.push_str(&format!(" ({} ms in total)", ms));
+            }
+            output.push_str(&format!("\nMost time went to {}\n", run.bottleneck()));
+        }
+        output
+    }
+}
+
+/// A byte count in B, KB, MB or GB (powers of 1024)
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// Milliseconds as seconds, or minutes and seconds for a minute or more
+fn format_duration(ms: u64) -> String {
+    if ms < 60_000 {
+        format!("{:.2}s", ms as f64 / 1000.0)
+    } else {
+        format!("{}m {:02}s", ms / 60_000, ms % 60_000 / 1000)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_render_summary() {
+        let report = ConversionReport {
+            tables: vec![
+                TableReport {
+                    table: "users".to_string(),
+                    rows_in: 120,
+                    rows_filtered: 20,
+                    duration_ms: Some(1250),
+                    timings: None,
+                    file: WrittenFile { path: "users.csv".to_string(), rows_written: 100, bytes: Some(2048), warnings: Vec::new() },
+                },
+                TableReport {
+                    table: "orders".to_string(),
+                    rows_in: 5,
+                    rows_filtered: 0,
+                    duration_ms: Some(61_000),
+                    timings: None,
+                    file: WrittenFile { path: "orders.csv".to_string(), rows_written: 5, bytes: Some(300), warnings: Vec::new() },
+                },
+            ],
+            duration_ms: Some(62_500),
+            ..Default::default()
+        };
+        
+        assert_eq!(report.render_summary(), "\
+table  | rows in | filtered | written | size   | duration | output
+-------+---------+----------+---------+--------+----------+-----------
+users  |     120 |       20 |     100 | 2.0 KB | 1.25s    | users.csv
+orders |       5 |        0 |       5 | 300 B  | 1m 01s   | orders.csv
+total  |     125 |       20 |     105 | 2.3 KB | 1m 02s   |
+");
+    }
+    
+    #[test]
+    fn test_render_timings() {
+        let timings = TableTimings { extract_ms: 900, filter_ms: 20, transform_ms: 5, queue_ms: 0, write_ms: 75 };
+        let report = ConversionReport {
+            tables: vec![TableReport { table: "users".to_string(), timings: Some(timings), ..Default::default() }],
+            duration_ms: Some(1200),
+            timings: Some(RunTimings { read_ms: 150, parse_ms: 40, tables: timings }),
+            ..Default::default()
+        };
+        
+        assert_eq!(report.render_timings(), "\
+table | extract | filter | transform | queue | write
+------+---------+--------+-----------+-------+------
+users |     900 |     20 |         5 |     0 |    75
+
+Read 150 ms, parse 40 ms, extract 900 ms, filter 20 ms, transform 5 ms, queue 0 ms, write 75 ms (1200 ms in total)
+Most time went to extracting values (parsing-bound; more --threads can help)
+");
+        assert!(serde_json::to_string(&report).unwrap().contains(r#""timings":{"read_ms":150,"parse_ms":40,"extract_ms":900"#));
+    }
+    
+    #[test]
+    fn test_incomplete_tables_are_only_in_interrupted_manifests() {
+        let mut report = ConversionReport::default();
+        assert!(!serde_json::to_string(&report).unwrap().contains("incomplete"));
+        
+        report.cancelled = true;
+        report.incomplete = vec!["orders".to_string(), "users".to_string()];
+        assert!(serde_json::to_string(&report).unwrap().contains(r#""cancelled":true,"incomplete":["orders","users"]"#));
+    }
+    
+    #[test]
+    fn test_replaced_sequences_are_only_in_lossy_manifests() {
+        let mut report = ConversionReport::default();
+        assert!(!serde_json::to_string(&report).unwrap().contains("replaced_sequences"));
+        
+        report.replaced_sequences = 3;
+        assert!(serde_json::to_string(&report).unwrap().contains(r#""replaced_sequences":3"#));
+    }
+}
@@ -0,0 +1,228 @@
+//! Strict RFC 4180 output (`--rfc4180-strict`).
+//!
+//! RFC 4180 CSV separates fields with commas and ends every line with CRLF.
+//! Fields holding a comma, a double quote, CR or LF are quoted, quotes inside
+//! them are doubled, and every record has as many fields as the header. In
+//! strict mode the files are written that way, without a byte order mark, and
+//! each file is read back once it is complete and checked against the grammar,
+//! so a file that is not conformant fails its table instead of reaching a
+//! consumer.
+
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use crate::args::has_flag;
+use crate::sink::{OutputSink, TableSink};
+use crate::types::{CsvLocale, OutputFormat, Table};
+
+/// Comma delimiter and CRLF line endings, without a byte order mark
+pub const RFC4180_LOCALE: CsvLocale = CsvLocale { delimiter: b',', decimal_comma: false, crlf: true, bom: false };
+
+/// Parse --rfc4180-strict flag from command line
+pub fn parse_rfc4180_strict(args: &[String]) -> bool {
+    has_flag(args, "--rfc4180-strict")
+}
+
+/// Where the validator is in the file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    FieldStart,
+    Unquoted,
+    Quoted,
+    /// A quote inside a quoted field: the closing quote or the first of a pair
+    QuoteInQuoted,
+    /// A CR that must be followed by LF
+    AfterCr,
+}
+
+/// Checks bytes against the RFC 4180 grammar as they are fed to it
+#[derive(Debug)]
+pub struct Rfc4180Validator {
+    state: State,
+    /// Line of the current byte, counting line breaks inside quoted fields
+    line: usize,
+    /// Fields of the current record so far
+    fields: usize,
+    /// Fields of the first record, which every other record must have
+    expected_fields: Option<usize>,
+    records: usize,
+}
+
+impl Default for Rfc4180Validator {
+    fn default() -> Self {
+        Rfc4180Validator { state: State::FieldStart, line: 1, fields: 0, expected_fields: None, records: 0 }
+    }
+}
+
+impl Rfc4180Validator {
+    pub fn feed(&mut self, bytes: &[u8]) -> Result<()> {
+        for &byte in bytes {
+            self.state = match (self.state, byte) {
+                (State::Quoted, b'"') => State::QuoteInQuoted,
+                (State::Quoted, b'\n') => {
+                    self.line += 1;
+                    State::Quoted
+                }
+                (State::Quoted, _) => State::Quoted,
+                (State::QuoteInQuoted, b'"') => State::Quoted,
+                (State::FieldStart, b'"') => State::Quoted,
+                (State::FieldStart | State::Unquoted | State::QuoteInQuoted, b',') => {
+                    self.fields += 1;
+                    State::FieldStart
+                }
+                (State::FieldStart | State::Unquoted | State::QuoteInQuoted, b'\r') => State::AfterCr,
+                (State::AfterCr, b'\n') => {
+                    self.end_record()?;
+                    self.line += 1;
+                    State::FieldStart
+                }
+                (State::AfterCr, _) => return self.violation("CR without LF"),
+                (_, b'\n') => return self.violation("line ending without CR"),
+                (State::Unquoted, b'"') => return self.violation("quote inside an unquoted field"),
+                (State::QuoteInQuoted, _) => return self.violation("text after the closing quote of a field"),
+                (State::FieldStart | State::Unquoted, _) => State::Unquoted,
+            };
+        }
+        Ok(())
+    }
+    
+    /// Check the end of the file. Returns the number of records, including the header.
+    pub fn finish(mut self) -> Result<usize> {
+        match self.state {
+            State::Quoted => return self.violation("quoted field is never closed"),
+            State::AfterCr => return self.violation("CR without LF"),
+            // The last record may end without a line break
+            State::Unquoted | State::QuoteInQuoted => self.end_record()?,
+            State::FieldStart if self.fields > 0 => self.end_record()?,
+            State::FieldStart => {}
+        }
+        Ok(self.records)
+    }
+    
+    fn end_record(&mut self) -> Result<()> {
+        let fields = self.fields + 1;
+        self.fields = 0;
+        self.records += 1;
+        match self.expected_fields {
+            Some(expected) if expected != fields => {
+                anyhow::bail!("record at line {} has {} fields but the header has {}", self.line, fields, expected)
+            }
+            Some(_) => Ok(()),
+            None => {
+                self.expected_fields = Some(fields);
+                Ok(())
+            }
+        }
+    }
+    
+    fn violation<T>(&self, problem: &str) -> Result<T> {
+        anyhow::bail!("{} at line {}", problem, self.line)
+    }
+}
+
+/// Check a file against RFC 4180. Returns the number of records, including the header.
+pub fn validate_rfc4180_file(path: &Path) -> Result<usize> {
+    let file = File::open(path)
+        .with_context(|| format!("Failed to read '{}' for validation", path.display()))?;
+    let mut reader = BufReader::new(file);
+    let mut validator = Rfc4180Validator::default();
+    let mut buffer = [0u8; 64 * 1024];
+    let validated = loop {
+        let read = reader.read(&mut buffer)
+            .with_context(|| format!("Failed to read '{}' for validation", path.display()))?;
+        if read == 0 {
+            break validator.finish();
+        }
+        if let Err(e) = validator.feed(&buffer[..read]) {
+            break Err(e);
+        }
+    };
+    validated.map_err(|e| anyhow::anyhow!("Error: '{}' is not RFC 4180 CSV: {}", path.display(), e))
+}
+
+/// Wraps a sink and validates every file it finishes against RFC 4180
+pub struct Rfc4180Sink {
+    inner: Box<dyn OutputSink>,
+}
+
+impl Rfc4180Sink {
+    /// Validate the files of a sink, which must write CSV
+    pub fn new(inner: Box<dyn OutputSink>, format: &OutputFormat) -> Result<Self> {
+        if !matches!(format, OutputFormat::Csv) {
+            anyhow::bail!("Error: --rfc4180-strict only applies to --format csv");
+        }
+        Ok(Rfc4180Sink { inner })
+    }
+}
+
+impl OutputSink for Rfc4180Sink {
+    fn open_table<'a>(&'a self, table: &Table, name: &str, headers: &[String]) -> Result<Box<dyn TableSink + 'a>> {
+        Ok(Box::new(Rfc4180TableSink { inner: self.inner.open_table(table, name, headers)? }))
+    }
+}
+
+struct Rfc4180TableSink<'a> {
+    inner: Box<dyn TableSink + 'a>,
+}
+
+impl TableSink for Rfc4180TableSink<'_> {
+    fn write_row(&mut self, row: &[String]) -> Result<()> {
+        self.inner.write_row(row)
+    }
+    
+    fn finish(self: Box<Self>) -> Result<String> {
+        Ok(self.finish_with_size()?.0)
+    }
+    
+    fn finish_with_size(self: Box<Self>) -> Result<(String, Option<u64>)> {
+        let (location, size) = self.inner.finish_with_size()?;
+        let path = Path::new(&location);
+        if location.contains("://") || !path.is_file() || !location.ends_with(".csv") {
+            eprintln!("Warning: '{}' was not validated against RFC 4180, as it is not a local CSV file", location);
+        } else {
+            validate_rfc4180_file(path)?;
+        }
+        Ok((location, size))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn validate(csv: &str) -> Result<usize> {
+        let mut validator = Rfc4180Validator::default();
+        validator.feed(csv.as_bytes())?;
+        validator.finish()
+    }
+    
+    #[test]
+    fn test_validator() {
+        assert_eq!(validate("id,note\r\n1,\"a, \"\"b\"\"\r\nc\"\r\n2,\r\n").unwrap(), 3);
+        assert_eq!(validate("id\r\n1").unwrap(), 2);
+        assert_eq!(validate("").unwrap(), 0);
+        
+        let error = |csv: &str| validate(csv).unwrap_err().to_string();
+        assert_eq!(error("id\n1\n"), "line ending without CR at line 1");
+        assert_eq!(error("id\r\n1\r2\r\n"), "CR without LF at line 2");
+        assert_eq!(error("id\r\na\"b\r\n"), "quote inside an unquoted field at line 2");
+        assert_eq!(error("id\r\n\"a\"b\r\n"), "text after the closing quote of a field at line 2");
+        assert_eq!(error("id\r\n\"a\r\n"), "quoted field is never closed at line 3");
+        assert_eq!(error("id,name\r\n1\r\n"), "record at line 2 has 1 fields but the header has 2");
+    }
+    
+    #[test]
+    fn test_strict_csv_output_validates() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = crate::types::CsvOptions { locale: RFC4180_LOCALE, ..Default::default() };
+        let sink = Rfc4180Sink::new(Box::new(crate::csv_writer::CsvSink { dir: dir.path().to_path_buf(), options }), &OutputFormat::Csv).unwrap();
+        let table = Table { name: "t".to_string(), columns: vec!["id".to_string(), "note".to_string()], ..Default::default() };
+        let rows = vec![vec!["1".to_string(), "line\nbreak, \"quoted\"".to_string()], vec!["2".to_string(), String::new()]];
+        
+        let location = crate::sink::write_table(&sink, &table, "t", &table.columns, &rows).unwrap();
+        
+        assert_eq!(std::fs::read_to_string(&location).unwrap(), "id,note\r\n1,\"line\nbreak, \"\"quoted\"\"\"\r\n2,\r\n");
+    }
+}
@@ -0,0 +1,22 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::fs;
+
+use crate::types::Table;
+
+/// Top-level layout of the exported schema file
+#[derive(Serialize)]
+struct SchemaExport<'a> {
+    tables: &'a [Table],
+}
+
+/// Write table schemas, key constraints and relationships to a JSON file
+pub fn write_schema_json(filename: &str, tables: &[Table]) -> Result<()> {
+    let json = serde_json::to_string_pretty(&SchemaExport { tables })
+        .context("Failed to serialize schema")?;
+    
+    fs::write(filename, json)
+        .context("Failed to write schema file")?;
+    
+    Ok(())
+}
@@ -0,0 +1,258 @@
+//! Pluggable destinations for converted tables.
+//!
+//! Every output format and location (CSV files, templates, Avro, object
+//! storage, HTTP) implements [`OutputSink`]. Library users can implement it
+//! to send rows somewhere else, such as a message queue or a database.
+
+use anyhow::{Context, Result};
+use std::borrow::Cow;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::args::flag_value;
+use crate::csv_writer::{CsvSink, CsvTableSink};
+use crate::output_file::remove_stale_temp_files;
+use crate::template::TemplateSink;
+use crate::types::{CsvOptions, OutputFormat, Table};
+
+/// A destination that tables are written to, one at a time or in parallel
+pub trait OutputSink: Send + Sync {
+    /// Start writing a table. `name` is the file stem chosen for it
+    /// (see `csv_filenames`) and `headers` are the output columns.
+    fn open_table<'a>(&'a self, table: &Table, name: &str, headers: &[String]) -> Result<Box<dyn TableSink + 'a>>;
+}
+
+/// Receives the rows of a single table
+pub trait TableSink {
+    fn write_row(&mut self, row: &[String]) -> Result<()>;
+    
+    /// Write a row whose values may be borrowed from the dump, as
+    /// `for_each_table_row` hands them over. Sinks that can write borrowed
+    /// text override this; by default the values are copied for `write_row`.
+    fn write_fields(&mut self, row: &[Cow<'_, str>]) -> Result<()> {
+        let row: Vec<String> = row.iter().map(|value| value.to_string()).collect();
+        self.write_row(&row)
+    }
+    
+    /// Complete the table. Returns where it was written, such as a path or URL.
+    fn finish(self: Box<Self>) -> Result<String>;
+    
+    /// Complete the table like `finish`, also returning the size of the
+    /// output in bytes when the sink knows it
+    fn finish_with_size(self: Box<Self>) -> Result<(String, Option<u64>)> {
+        Ok((self.finish()?, None))
+    }
+}
+
+/// Write all rows of a table to a sink. Returns where the table was written.
+pub fn write_table(
+    sink: &dyn OutputSink,
+    table: &Table,
+    name: &str,
+    headers: &[String],
+    rows: &[Vec<String>],
+) -> Result<String> {
+    let mut table_sink = sink.open_table(table, name, headers)?;
+    for row in rows {
+        table_sink.write_row(row)?;
+    }
+    table_sink.finish()
+}
+
+/// Keeps every table as CSV text in memory
+#[derive(Default)]
+pub struct MemorySink {
+    options: CsvOptions,
+    files: Mutex<Vec<(String, String)>>,
+}
+
+impl MemorySink {
+    pub fn new(options: CsvOptions) -> Self {
+        MemorySink {
+            options,
+            files: Mutex::new(Vec::new()),
+        }
+    }
+    
+    /// The written tables as (file name, CSV content) pairs, in the order they finished
+    pub fn into_files(self) -> Vec<(String, String)> {
+        self.files.into_inner().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl OutputSink for MemorySink {
+    fn open_table<'a>(&'a self, table: &Table, name: &str, headers: &[String]) -> Result<Box<dyn TableSink + 'a>> {
+        let filename = format!("{}.csv", name);
+        Ok(Box::new(MemoryTableSink {
+            csv: CsvTableSink::new(Vec::new(), headers, &self.options, filename.clone())?
+                .with_column_types(table),
+            filename,
+            files: &self.files,
+        }))
+    }
+}
+
+struct MemoryTableSink<'a> {
+    csv: CsvTableSink<Vec<u8>>,
+    filename: String,
+    files: &'a Mutex<Vec<(String, String)>>,
+}
+
+impl TableSink for MemoryTableSink<'_> {
+    fn write_row(&mut self, row: &[String]) -> Result<()> {
+        self.csv.write_row(row)
+    }
+    
+    fn write_fields(&mut self, row: &[Cow<'_, str>]) -> Result<()> {
+        self.csv.write_fields(row)
+    }
+    
+    fn finish(self: Box<Self>) -> Result<String> {
+        Ok(self.finish_with_size()?.0)
+    }
+    
+    fn finish_with_size(self: Box<Self>) -> Result<(String, Option<u64>)> {
+        let content = String::from_utf8(self.csv.into_inner()?)
+            .context("CSV output is not valid UTF-8")?;
+        let bytes = content.len() as u64;
+        self.files.lock().unwrap_or_else(|e| e.into_inner())
+            .push((self.filename.clone(), content));
+        Ok((self.filename, Some(bytes)))
+    }
+}
+
+/// Build the sink for the --output location and --format of the command line.
+/// Local directories are created if needed.
+pub fn parse_output_sink(
+    args: &[String],
+    format: &OutputFormat,
+    options: &CsvOptions,
+) -> Result<Box<dyn OutputSink>> {
+    let location = flag_value(args, "--output")?;
+    if !options.encrypt_to.is_empty() && location.is_some_and(|l| l.contains("://")) {
+        anyhow::bail!("Error: --encrypt only supports output to local files");
+    }
+    
+    #[cfg(feature = "object-store")]
+    if let Some(location) = location.filter(|l| crate::cloud::is_object_store_url(l)) {
+        if !matches!(format, OutputFormat::Csv) {
+            anyhow::bail!("Error: Object storage output only supports --format csv");
+        }
+        return Ok(Box::new(crate::cloud::ObjectStoreOutput::from_url(location, options.clone())?));
+    }
+    #[cfg(feature = "http")]
+    if let Some(location) = location.filter(|l| crate::http_sink::is_http_url(l)) {
+        if !matches!(format, OutputFormat::Csv) {
+            anyhow::bail!("Error: HTTP output only supports --format csv");
+        }
+        return Ok(Box::new(crate::http_sink::HttpOutput::from_args(location, args, options.clone())?));
+    }
+    
+    #[cfg(feature = "duckdb")]
+    if matches!(format, OutputFormat::DuckDb) {
+        let path = location.filter(|l| !l.contains("://"))
+            .ok_or_else(|| anyhow::anyhow!("Error: --format duckdb requires --output <file.duckdb>"))?;
+        if !options.encrypt_to.is_empty() {
+            anyhow::bail!("Error: --encrypt does not support --format duckdb");
+        }
+        return Ok(Box::new(crate::duckdb::DuckDbSink::new(path, options)?));
+    }
+    if matches!(format, OutputFormat::Jsonl { .. }) && !options.encrypt_to.is_empty() {
+        anyhow::bail!("Error: --encrypt does not support --format jsonl");
+    }
+    
+    let dir = match location {
+        Some(location) if location.contains("://") => {
+            anyhow::bail!("Error: Unsupported --output '{}'. Build with --features object-store for s3:// and gs://, or --features http for http(s)://", location)
+        }
+        Some(location) => {
+            std::fs::create_dir_all(location)
+                .with_context(|| format!("Failed to create output directory '{}'", location))?;
+            PathBuf::from(location)
+        }
+        None => PathBuf::new(),
+    };
+    
+    let extension = match format {
+        OutputFormat::Csv => "csv",
+        OutputFormat::Template { extension, .. } => extension.as_str(),
+        #[cfg(feature = "avro")]
+        OutputFormat::Avro => "avro",
+        #[cfg(feature = "duckdb")]
+        OutputFormat::DuckDb => unreachable!("DuckDB output is a single file"),
+        // Written in place, so no temporary files are left behind
+        OutputFormat::Jsonl { .. } => "jsonl",
+    };
+    let extension = match options.encrypt_to.is_empty() {
+        true => extension.to_string(),
+        false => format!("{}.{}", extension, crate::encrypt::ENCRYPTED_EXTENSION),
+    };
+    let removed = remove_stale_temp_files(&dir, &extension)?;
+    if removed > 0 {
+        eprintln!("Warning: Removed {} unfinished .{}.tmp files left by an earlier run", removed, extension);
+    }
+    
+    Ok(match format {
+        OutputFormat::Csv => Box::new(CsvSink { dir, options: options.clone() }),
+        OutputFormat::Template { source, extension } => Box::new(TemplateSink {
+            dir,
+            source: source.clone(),
+            extension: extension.clone(),
+            options: options.clone(),
+        }),
+        #[cfg(feature = "avro")]
+        OutputFormat::Avro => Box::new(crate::avro::AvroSink { dir, options: options.clone() }),
+        #[cfg(feature = "duckdb")]
+        OutputFormat::DuckDb => unreachable!("DuckDB output is a single file"),
+        OutputFormat::Jsonl { flush_every, append } => Box::new(crate::jsonl::JsonlSink {
+            dir,
+            options: options.clone(),
+            flush_every: *flush_every,
+            append: *append,
+        }),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_memory_sink() {
+        let sink = MemorySink::new(CsvOptions::default());
+        let table = Table { name: "users".to_string(), ..Default::default() };
+        let headers = vec!["id".to_string(), "name".to_string()];
+        let rows = vec![vec!["1".to_string(), "Alice".to_string()]];
+        
+        let location = write_table(&sink, &table, "users", &headers, &rows).unwrap();
+        assert_eq!(location, "users.csv");
+        assert_eq!(sink.into_files(), vec![("users.csv".to_string(), "id,name\n1,Alice\n".to_string())]);
+    }
+    
+    #[test]
+    fn test_custom_sink() {
+        struct Counter(Mutex<usize>);
+        struct CountingTable<'a>(&'a Mutex<usize>);
+        
+        impl OutputSink for Counter {
+            fn open_table<'a>(&'a self, _table: &Table, name: &str, _headers: &[String]) -> Result<Box<dyn TableSink + 'a>> {
+                assert_eq!(name, "users");
+                Ok(Box::new(CountingTable(&self.0)))
+            }
+        }
+        impl TableSink for CountingTable<'_> {
+            fn write_row(&mut self, _row: &[String]) -> Result<()> {
+                *self.0.lock().unwrap() += 1;
+                Ok(())
+            }
+            fn finish(self: Box<Self>) -> Result<String> {
+                Ok("counter".to_string())
+            }
+        }
+        
+        let sink = Counter(Mutex::new(0));
+        let rows = vec![vec!["1".to_string()], vec!["2".to_string()]];
+        write_table(&sink, &Table::default(), "users", &["id".to_string()], &rows).unwrap();
+        assert_eq!(*sink.0.lock().unwrap(), 2);
+    }
+}
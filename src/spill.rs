@@ -0,0 +1,412 @@
+//! Memory budget for rows waiting to be written, with spilling to disk.
+//!
+//! With `--max-memory`, the rows of a table are kept in memory only while the
+//! budget allows; once it is used up they are written to temporary files in
+//! batches and read back, in order, when the table is written. Tables are
+//! extracted in batches that are added to their buffer as they are prepared,
+//! so beyond the budget only a batch of a table is held in memory.
+//! Spill files go to the run's temporary workspace (`--temp-dir`). When the
+//! output is encrypted (`--encrypt`), so are they, with a key that is made
+//! for the run and never leaves memory.
+
+use anyhow::{Context, Result};
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crate::args::{flag_value, has_flag};
+use crate::sink::OutputSink;
+use crate::types::Table;
+use crate::workspace::temp_file_path;
+
+/// Rows per temporary file of a spilled table
+pub const SPILL_BATCH_ROWS: usize = 10_000;
+
+/// Bytes of buffered rows allowed in memory at once
+#[derive(Debug)]
+pub struct MemoryBudget {
+    limit: u64,
+    used: AtomicU64,
+    /// Encrypt spill files, for runs whose output is encrypted
+    encrypt: bool,
+}
+
+impl MemoryBudget {
+    pub fn new(limit: u64) -> Self {
+        MemoryBudget { limit, used: AtomicU64::new(0), encrypt: false }
+    }
+    
+    /// Encrypt spill files with a key kept in memory for the run, so rows
+    /// never reach the disk as plaintext
+    pub fn encrypted(mut self) -> Self {
+        self.encrypt = true;
+        self
+    }
+    
+    /// Bytes currently reserved by rows in memory
+    pub fn used(&self) -> u64 {
+        self.used.load(Ordering::SeqCst)
+    }
+    
+    /// Reserve `bytes` if they fit in what is left of the budget
+    fn try_reserve(&self, bytes: u64) -> bool {
+        self.used.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |used| {
+            (used + bytes <= self.limit).then_some(used + bytes)
+        }).is_ok()
+    }
+    
+    fn release(&self, bytes: u64) {
+        self.used.fetch_sub(bytes, Ordering::SeqCst);
+    }
+}
+
+/// Parse the --max-memory option, a number of bytes with an optional `KB`,
+/// `MB` or `GB` suffix (powers of 1024). With --encrypt, spill files are
+/// encrypted too.
+pub fn parse_max_memory(args: &[String]) -> Result<Option<MemoryBudget>> {
+    let Some(value) = flag_value(args, "--max-memory")? else {
+        return Ok(None);
+    };
+    let encrypt = has_flag(args, "--encrypt");
+    parse_size(value)
+        .filter(|&bytes| bytes > 0)
+        .map(|bytes| Some(MemoryBudget { encrypt, ..MemoryBudget::new(bytes) }))
+        .ok_or_else(|| anyhow::anyhow!("Error: Invalid --max-memory '{}'. Use a size such as 512MB or 2GB", value))
+}
+
+fn parse_size(value: &str) -> Option<u64> {
+    let upper = value.trim().to_ascii_uppercase();
+    let digits = upper.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let multiplier: u64 = match upper[digits.len()..].trim_end_matches('B') {
+        "" => 1,
+        "K" => 1 << 10,
+        "M" => 1 << 20,
+        "G" => 1 << 30,
+        _ => return None,
+    };
+    digits.trim().parse::<u64>().ok()?.checked_mul(multiplier)
+}
+
+/// Approximate memory used by a row: its text plus the `String` and `Vec` headers
+fn row_size(row: &[String]) -> u64 {
+    let cells: usize = row.iter().map(|cell| cell.len() + size_of::<String>()).sum();
+    (cells + size_of::<Vec<String>>()) as u64
+}
+
+/// The rows of a table waiting to be written, in memory or spilled to
+/// temporary files. Dropping it gives the memory back to the budget and
+/// removes the files.
+#[derive(Debug)]
+pub struct RowBuffer {
+    rows: Vec<Vec<String>>,
+    spill_files: Vec<PathBuf>,
+    /// Whether the spill files are encrypted
+    encrypted: bool,
+    len: usize,
+    budget: Option<Arc<MemoryBudget>>,
+    /// Bytes of `budget` reserved by the rows in memory
+    reserved: u64,
+}
+
+impl From<Vec<Vec<String>>> for RowBuffer {
+    fn from(rows: Vec<Vec<String>>) -> Self {
+        RowBuffer { len: rows.len(), rows, spill_files: Vec::new(), encrypted: false, budget: None, reserved: 0 }
+    }
+}
+
+impl RowBuffer {
+    /// Keep the rows in memory if the budget allows, or spill them to disk.
+    /// Without a budget the rows always stay in memory.
+    pub fn new(rows: Vec<Vec<String>>, budget: Option<&Arc<MemoryBudget>>) -> Result<Self> {
+        let mut buffer = RowBuffer::with_budget(budget);
+        buffer.push(rows)?;
+        Ok(buffer)
+    }
+    
+    /// An empty buffer for rows added with `push`
+    pub fn with_budget(budget: Option<&Arc<MemoryBudget>>) -> Self {
+        RowBuffer {
+            rows: Vec::new(),
+            spill_files: Vec::new(),
+            encrypted: budget.is_some_and(|budget| budget.encrypt),
+            len: 0,
+            budget: budget.cloned(),
+            reserved: 0,
+        }
+    }
+    
+    /// Add rows after the ones already buffered. They stay in memory while
+    /// the budget allows; once it is used up, these and all later rows are
+    /// spilled to disk.
+    pub fn push(&mut self, rows: Vec<Vec<String>>) -> Result<()> {
+        self.len += rows.len();
+        let Some(ref budget) = self.budget else {
+            self.rows.extend(rows);
+            return Ok(());
+        };
+        let size: u64 = rows.iter().map(|row| row_size(row)).sum();
+        if !self.is_spilled() && budget.try_reserve(size) {
+            self.reserved += size;
+            self.rows.extend(rows);
+            return Ok(());
+        }
+        
+        for batch in rows.chunks(SPILL_BATCH_ROWS) {
+            let path = spill_path()?;
+            // Registered before writing so a failed write is cleaned up too
+            self.spill_files.push(path.clone());
+            write_batch(&path, batch, self.encrypted)?;
+        }
+        Ok(())
+    }
+    
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    
+    /// Whether the rows were written to temporary files
+    pub fn is_spilled(&self) -> bool {
+        !self.spill_files.is_empty()
+    }
+    
+    /// Call `f` with every row, in order, reading spilled batches back one at a time
+    pub fn for_each_row(&self, mut f: impl FnMut(&[String]) -> Result<()>) -> Result<()> {
+        for row in &self.rows {
+            f(row)?;
+        }
+        for path in &self.spill_files {
+            let mut reader = csv::ReaderBuilder::new()
+                .has_headers(false)
+                .flexible(true)
+                .from_reader(spill_reader(path, self.encrypted)?);
+            let mut record = csv::StringRecord::new();
+            let mut row = Vec::new();
+            while reader.read_record(&mut record)? {
+                row.clear();
+                row.extend(record.iter().map(str::to_string));
+                f(&row)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for RowBuffer {
+    fn drop(&mut self) {
+        if let Some(ref budget) = self.budget {
+            budget.release(self.reserved);
+        }
+        for path in &self.spill_files {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// A new temporary file in the run's workspace, unique within the process
+fn spill_path() -> Result<PathBuf> {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    let n = NEXT.fetch_add(1, Ordering::SeqCst);
+    temp_file_path(&format!("spill-{}.csv", n))
+}
+
+fn write_batch(path: &Path, rows: &[Vec<String>], encrypt: bool) -> Result<()> {
+    let file = File::create(path)
+        .with_context(|| format!("Failed to create spill file '{}'", path.display()))?;
+    let mut writer = csv::WriterBuilder::new()
+        .flexible(true)
+        .from_writer(SpillWriter::new(BufWriter::new(file), encrypt)?);
+    for row in rows {
+        writer.write_record(row)?;
+    }
+    writer.into_inner()
+        .map_err(|e| e.into_error())
+        .and_then(SpillWriter::finish)
+        .with_context(|| format!("Failed to write spill file '{}'", path.display()))?;
+    Ok(())
+}
+
+/// Destination of a spill file's rows
+enum SpillWriter {
+    Plain(BufWriter<File>),
+    #[cfg(feature = "encrypt")]
+    Encrypted(age::stream::StreamWriter<BufWriter<File>>),
+}
+
+impl SpillWriter {
+    #[cfg(feature = "encrypt")]
+    fn new(file: BufWriter<File>, encrypt: bool) -> Result<Self> {
+        if !encrypt {
+            return Ok(SpillWriter::Plain(file));
+        }
+        let recipient = spill_key().to_public();
+        let encryptor = age::Encryptor::with_recipients(std::iter::once(&recipient as &dyn age::Recipient))?;
+        Ok(SpillWriter::Encrypted(encryptor.wrap_output(file)?))
+    }
+    
+    #[cfg(not(feature = "encrypt"))]
+    fn new(file: BufWriter<File>, encrypt: bool) -> Result<Self> {
+        if encrypt {
+            anyhow::bail!("Error: This build has no encryption support (build with --features encrypt)");
+        }
+        Ok(SpillWriter::Plain(file))
+    }
+    
+    /// Write the last encrypted block and flush the file
+    fn finish(self) -> io::Result<()> {
+        match self {
+            SpillWriter::Plain(mut file) => file.flush(),
+            #[cfg(feature = "encrypt")]
+            SpillWriter::Encrypted(stream) => stream.finish()?.flush(),
+        }
+    }
+}
+
+impl Write for SpillWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            SpillWriter::Plain(file) => file.write(buf),
+            #[cfg(feature = "encrypt")]
+            SpillWriter::Encrypted(stream) => stream.write(buf),
+        }
+    }
+    
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            SpillWriter::Plain(file) => file.flush(),
+            #[cfg(feature = "encrypt")]
+            SpillWriter::Encrypted(stream) => stream.flush(),
+        }
+    }
+}
+
+/// Read a spill file back, decrypting it if it was encrypted
+fn spill_reader(path: &Path, encrypted: bool) -> Result<Box<dyn Read>> {
+    let file = File::open(path)
+        .with_context(|| format!("Failed to read spilled rows from '{}'", path.display()))?;
+    let file = BufReader::new(file);
+    if !encrypted {
+        return Ok(Box::new(file));
+    }
+    #[cfg(feature = "encrypt")]
+    {
+        let key = spill_key();
+        let decryptor = age::Decryptor::new_buffered(file)
+            .with_context(|| format!("Failed to decrypt spilled rows from '{}'", path.display()))?;
+        let reader = decryptor.decrypt(std::iter::once(key as &dyn age::Identity))
+            .with_context(|| format!("Failed to decrypt spilled rows from '{}'", path.display()))?;
+        Ok(Box::new(reader))
+    }
+    #[cfg(not(feature = "encrypt"))]
+    anyhow::bail!("Error: This build has no encryption support (build with --features encrypt)")
+}
+
+/// Key of the run's encrypted spill files. It is made on first use and only
+/// kept in memory, so the files cannot be read after the run.
+#[cfg(feature = "encrypt")]
+fn spill_key() -> &'static age::x25519::Identity {
+    static KEY: std::sync::OnceLock<age::x25519::Identity> = std::sync::OnceLock::new();
+    KEY.get_or_init(age::x25519::Identity::generate)
+}
+
+/// Write all rows of a buffered table to a sink. Returns where the table was
+/// written and the size of the output, when the sink knows it.
+pub fn write_buffered_table(
+    sink: &dyn OutputSink,
+    table: &Table,
+    name: &str,
+    headers: &[String],
+    rows: &RowBuffer,
+) -> Result<(String, Option<u64>)> {
+    let mut table_sink = sink.open_table(table, name, headers)?;
+    rows.for_each_row(|row| table_sink.write_row(row))?;
+    table_sink.finish_with_size()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn collect(buffer: &RowBuffer) -> Vec<Vec<String>> {
+        let mut rows = Vec::new();
+        buffer.for_each_row(|row| {
+            rows.push(row.to_vec());
+            Ok(())
+        }).unwrap();
+        rows
+    }
+    
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("2GB"), Some(2 << 30));
+        assert_eq!(parse_size("512mb"), Some(512 << 20));
+        assert_eq!(parse_size("64K"), Some(64 << 10));
+        assert_eq!(parse_size("1000"), Some(1000));
+        assert_eq!(parse_size("2TB"), None);
+        assert_eq!(parse_size("GB"), None);
+    }
+    
+    #[test]
+    fn test_rows_over_budget_are_spilled_and_read_back() {
+        let budget = Arc::new(MemoryBudget::new(1 << 10));
+        let small: Vec<Vec<String>> = vec![vec!["1".to_string(), "a".to_string()]];
+        let large: Vec<Vec<String>> = (0..SPILL_BATCH_ROWS + 5)
+            .map(|i| vec![i.to_string(), format!("line\n{}, \"quoted\"", i), String::new()])
+            .collect();
+        
+        let kept = RowBuffer::new(small.clone(), Some(&budget)).unwrap();
+        assert!(!kept.is_spilled());
+        assert!(budget.used() > 0);
+        
+        let spilled = RowBuffer::new(large.clone(), Some(&budget)).unwrap();
+        assert!(spilled.is_spilled());
+        assert_eq!(spilled.spill_files.len(), 2);
+        assert_eq!(spilled.len(), large.len());
+        assert_eq!(collect(&spilled), large);
+        assert_eq!(collect(&kept), small);
+        
+        let files = spilled.spill_files.clone();
+        drop(spilled);
+        drop(kept);
+        assert!(files.iter().all(|path| !path.exists()));
+        assert_eq!(budget.used(), 0);
+    }
+    
+    #[test]
+    fn test_pushed_rows_spill_once_the_budget_is_used() {
+        let budget = Arc::new(MemoryBudget::new(100));
+        let row = |i: usize| vec![i.to_string(), "ab".to_string()];
+        let mut buffer = RowBuffer::with_budget(Some(&budget));
+        
+        buffer.push(vec![row(1)]).unwrap();
+        assert!(!buffer.is_spilled());
+        buffer.push(vec![row(2), row(3)]).unwrap();
+        assert!(buffer.is_spilled());
+        // Later rows follow the spilled ones even if they would fit
+        buffer.push(vec![row(4)]).unwrap();
+        assert_eq!(buffer.spill_files.len(), 2);
+        assert_eq!(buffer.len(), 4);
+        assert_eq!(collect(&buffer), (1..=4).map(row).collect::<Vec<_>>());
+        
+        drop(buffer);
+        assert_eq!(budget.used(), 0);
+    }
+    
+    #[cfg(feature = "encrypt")]
+    #[test]
+    fn test_spill_files_of_encrypted_runs_are_encrypted() {
+        let budget = Arc::new(MemoryBudget::new(1).encrypted());
+        let rows: Vec<Vec<String>> = vec![vec!["1".to_string(), "secret".to_string()]];
+        
+        let spilled = RowBuffer::new(rows.clone(), Some(&budget)).unwrap();
+        assert!(spilled.is_spilled());
+        let written = fs::read(&spilled.spill_files[0]).unwrap();
+        assert!(!written.windows(6).any(|window| window == b"secret"));
+        assert_eq!(collect(&spilled), rows);
+    }
+}
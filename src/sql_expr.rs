@@ -0,0 +1,327 @@
+use anyhow::Result;
+use chrono::Local;
+use rust_decimal::{Decimal, RoundingStrategy};
+
+use crate::args::flag_value;
+use crate::types::ExpressionPolicy;
+
+/// Parse --expressions option from command line
+pub fn parse_expression_policy(args: &[String]) -> Result<ExpressionPolicy> {
+    match flag_value(args, "--expressions")? {
+        None | Some("verbatim") => Ok(ExpressionPolicy::Verbatim),
+        Some("evaluate") => Ok(ExpressionPolicy::Evaluate),
+        Some(other) => anyhow::bail!("Error: Invalid --expressions '{}'. Use one of: verbatim, evaluate", other),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(String),
+    Str(String),
+    Word(String),
+    Symbol(char),
+    /// Postgres `::type` cast
+    Cast,
+}
+
+/// Result of evaluating part of an expression. Numbers are exact decimals that
+/// keep the scale they were written with, so `1.50 + 1` is `2.50`.
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Number(Decimal),
+    Text(String),
+    Null,
+}
+
+impl Value {
+    fn render(self) -> String {
+        match self {
+            Value::Number(n) => n.to_string(),
+            Value::Text(text) => text,
+            Value::Null => "NULL".to_string(),
+        }
+    }
+}
+
+/// Evaluate a simple SQL expression written as an INSERT value: arithmetic on
+/// numbers, `CAST(x AS type)` / `x::type`, `NULLIF`, `COALESCE` and the current
+/// date/time functions. Returns `None` for plain literals, including negative
+/// numbers, and anything else, which is then kept as written.
+pub fn evaluate_expression(expression: &str) -> Option<String> {
+    let tokens = tokenize(expression)?;
+    match tokens.as_slice() {
+        [Token::Number(_) | Token::Str(_)] | [Token::Symbol('-'), Token::Number(_)] => return None,
+        [Token::Word(word)] if !["CURRENT_TIMESTAMP", "LOCALTIMESTAMP", "CURRENT_DATE"].contains(&word.as_str()) => return None,
+        _ => {}
+    }
+    
+    let mut parser = Parser { tokens, position: 0 };
+    let value = parser.expression()?;
+    (parser.position == parser.tokens.len()).then(|| value.render())
+}
+
+fn tokenize(text: &str) -> Option<Vec<Token>> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || (c == '.' && chars.get(i + 1).is_some_and(char::is_ascii_digit)) {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            tokens.push(Token::Number(chars[start..i].iter().collect()));
+        } else if c == '\'' {
+            let mut value = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    Some('\'') if chars.get(i + 1) == Some(&'\'') => {
+                        value.push('\'');
+                        i += 2;
+                    }
+                    Some('\'') => break,
+                    Some(ch) => {
+                        value.push(*ch);
+                        i += 1;
+                    }
+                    None => return None,
+                }
+            }
+            i += 1;
+            tokens.push(Token::Str(value));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Word(chars[start..i].iter().collect::<String>().to_uppercase()));
+        } else if c == ':' && chars.get(i + 1) == Some(&':') {
+            tokens.push(Token::Cast);
+            i += 2;
+        } else if "+-*/%(),".contains(c) {
+            tokens.push(Token::Symbol(c));
+            i += 1;
+        } else {
+            return None;
+        }
+    }
+    
+    Some(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+    
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+    
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+    
+    fn expect(&mut self, token: &Token) -> Option<()> {
+        self.eat(token).then_some(())
+    }
+    
+    fn expression(&mut self) -> Option<Value> {
+        let mut value = self.term()?;
+        while let Some(Token::Symbol(op @ ('+' | '-'))) = self.peek().cloned() {
+            self.position += 1;
+            value = arithmetic(value, op, self.term()?)?;
+        }
+        Some(value)
+    }
+    
+    fn term(&mut self) -> Option<Value> {
+        let mut value = self.unary()?;
+        while let Some(Token::Symbol(op @ ('*' | '/' | '%'))) = self.peek().cloned() {
+            self.position += 1;
+            value = arithmetic(value, op, self.unary()?)?;
+        }
+        Some(value)
+    }
+    
+    fn unary(&mut self) -> Option<Value> {
+        if self.eat(&Token::Symbol('-')) {
+            return match self.unary()? {
+                Value::Number(n) => Some(Value::Number(-n)),
+                _ => None,
+            };
+        }
+        let mut value = self.primary()?;
+        while self.eat(&Token::Cast) {
+            value = cast(value, &self.type_name()?)?;
+        }
+        Some(value)
+    }
+    
+    fn primary(&mut self) -> Option<Value> {
+        match self.next()? {
+            Token::Number(n) => n.parse().ok().map(Value::Number),
+            Token::Str(s) => Some(Value::Text(s)),
+            Token::Symbol('(') => {
+                let value = self.expression()?;
+                self.expect(&Token::Symbol(')'))?;
+                Some(value)
+            }
+            Token::Word(word) => self.word(&word),
+            _ => None,
+        }
+    }
+    
+    fn word(&mut self, word: &str) -> Option<Value> {
+        let called = self.eat(&Token::Symbol('('));
+        match (word, called) {
+            ("NULL", false) => Some(Value::Null),
+            ("TRUE" | "FALSE", false) => Some(Value::Text(word.to_string())),
+            ("CAST", true) => {
+                let value = self.expression()?;
+                self.expect(&Token::Word("AS".to_string()))?;
+                let type_name = self.type_name()?;
+                self.expect(&Token::Symbol(')'))?;
+                cast(value, &type_name)
+            }
+            ("NOW" | "CURRENT_TIMESTAMP" | "LOCALTIMESTAMP" | "SYSDATE", true) => {
+                self.expect(&Token::Symbol(')'))?;
+                Some(now("%Y-%m-%d %H:%M:%S"))
+            }
+            ("CURRENT_TIMESTAMP" | "LOCALTIMESTAMP", false) => Some(now("%Y-%m-%d %H:%M:%S")),
+            ("CURDATE", true) => {
+                self.expect(&Token::Symbol(')'))?;
+                Some(now("%Y-%m-%d"))
+            }
+            ("CURRENT_DATE", false) => Some(now("%Y-%m-%d")),
+            ("NULLIF", true) => {
+                let args = self.arguments()?;
+                let [first, second] = <[Value; 2]>::try_from(args).ok()?;
+                Some(if first == second { Value::Null } else { first })
+            }
+            ("COALESCE" | "IFNULL", true) => {
+                let args = self.arguments()?;
+                Some(args.into_iter().find(|v| *v != Value::Null).unwrap_or(Value::Null))
+            }
+            _ => None,
+        }
+    }
+    
+    /// Arguments of a function call, after its opening parenthesis
+    fn arguments(&mut self) -> Option<Vec<Value>> {
+        let mut args = vec![self.expression()?];
+        while self.eat(&Token::Symbol(',')) {
+            args.push(self.expression()?);
+        }
+        self.expect(&Token::Symbol(')'))?;
+        Some(args)
+    }
+    
+    /// Target type of a cast, with any length or precision skipped
+    fn type_name(&mut self) -> Option<String> {
+        let Token::Word(mut name) = self.next()? else { return None };
+        while let Some(Token::Word(word)) = self.peek().cloned() {
+            if word == "AS" {
+                break;
+            }
+            name = format!("{} {}", name, word);
+            self.position += 1;
+        }
+        if self.eat(&Token::Symbol('(')) {
+            while !self.eat(&Token::Symbol(')')) {
+                self.next()?;
+            }
+        }
+        Some(name)
+    }
+}
+
+fn arithmetic(left: Value, op: char, right: Value) -> Option<Value> {
+    let (Value::Number(a), Value::Number(b)) = (left, right) else { return None };
+    let result = match op {
+        '+' => a.checked_add(b),
+        '-' => a.checked_sub(b),
+        '*' => a.checked_mul(b),
+        '/' => a.checked_div(b).map(|n| n.normalize()),
+        '%' => a.checked_rem(b),
+        _ => None,
+    };
+    result.map(Value::Number)
+}
+
+fn cast(value: Value, type_name: &str) -> Option<Value> {
+    let is_integer = ["INT", "INTEGER", "BIGINT", "SMALLINT", "TINYINT", "SIGNED", "UNSIGNED", "SIGNED INTEGER", "UNSIGNED INTEGER"]
+        .contains(&type_name);
+    let is_number = is_integer || ["DECIMAL", "NUMERIC", "REAL", "FLOAT", "DOUBLE", "DOUBLE PRECISION"].contains(&type_name);
+    
+    match value {
+        Value::Null => Some(Value::Null),
+        Value::Text(text) if is_number => text.trim().parse::<Decimal>().ok().and_then(|n| cast(Value::Number(n), type_name)),
+        Value::Number(n) if is_integer => Some(Value::Number(n.round_dp_with_strategy(0, RoundingStrategy::MidpointAwayFromZero))),
+        Value::Number(n) if !is_number => Some(Value::Text(Value::Number(n).render())),
+        other => Some(other),
+    }
+}
+
+fn now(format: &str) -> Value {
+    Value::Text(Local::now().format(format).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_evaluate_expression() {
+        assert_eq!(evaluate_expression("1+2"), Some("3".to_string()));
+        assert_eq!(evaluate_expression("(1 + 2) * 2.5"), Some("7.5".to_string()));
+        assert_eq!(evaluate_expression("-4 / 2"), Some("-2".to_string()));
+        assert_eq!(evaluate_expression("CAST('5' AS INT)"), Some("5".to_string()));
+        assert_eq!(evaluate_expression("cast(12 as varchar(10))"), Some("12".to_string()));
+        assert_eq!(evaluate_expression("'7'::integer + 1"), Some("8".to_string()));
+        assert_eq!(evaluate_expression("NULLIF('', '')"), Some("NULL".to_string()));
+        assert_eq!(evaluate_expression("NULLIF('a', '')"), Some("a".to_string()));
+        assert_eq!(evaluate_expression("COALESCE(NULL, 'x')"), Some("x".to_string()));
+        assert_eq!(evaluate_expression("NOW()").map(|now| now.len()), Some(19));
+        assert_eq!(evaluate_expression("CURRENT_DATE").map(|today| today.len()), Some(10));
+    }
+    
+    #[test]
+    fn test_unsupported_expressions_are_not_evaluated() {
+        assert_eq!(evaluate_expression("42"), None);
+        assert_eq!(evaluate_expression("'text'"), None);
+        assert_eq!(evaluate_expression("UUID()"), None);
+        assert_eq!(evaluate_expression("'a' + 1"), None);
+        assert_eq!(evaluate_expression("1 / 0"), None);
+        assert_eq!(evaluate_expression("0x1F"), None);
+        assert_eq!(evaluate_expression("TRUE"), None);
+        assert_eq!(evaluate_expression("-12.50"), None);
+    }
+    
+    #[test]
+    fn test_numbers_are_evaluated_exactly() {
+        assert_eq!(evaluate_expression("0.1 + 0.2"), Some("0.3".to_string()));
+        assert_eq!(evaluate_expression("9007199254740993 + 0"), Some("9007199254740993".to_string()));
+        assert_eq!(evaluate_expression("-(12345678901234567890 * 10)"), Some("-123456789012345678900".to_string()));
+        assert_eq!(evaluate_expression("1.50 + 1"), Some("2.50".to_string()));
+        assert_eq!(evaluate_expression("CAST(2.5 AS INT)"), Some("3".to_string()));
+        assert_eq!(evaluate_expression("99999999999999999999999999999 * 10"), None);
+    }
+}
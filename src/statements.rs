@@ -0,0 +1,522 @@
+//! Splitting dump text into statements.
+//!
+//! Statements end at a `;` outside strings, quoted identifiers and comments,
+//! wherever the line breaks fall, so minified dumps with many statements on
+//! one line and statements wrapped across lines are read the same way.
+//! MySQL's client-side `DELIMITER` command is followed, so procedure and
+//! trigger bodies written between `DELIMITER ;;` lines stay in one statement.
+//! psql meta-commands such as `\connect shop` take their line and need no `;`.
+//! Dumps of several databases switch between them with `USE shop;` or
+//! `\connect shop`; `split_statements_in_databases` follows these switches.
+//!
+//! Only MySQL escapes characters in strings with a backslash (`'it\'s'`);
+//! Postgres and SQLite double the quote instead (`'it''s'`) and keep
+//! backslashes as they are, so `'C:\temp\'` ends at its last quote. The
+//! dialect is recognized from the markers pg_dump and sqlite3 write.
+//!
+//! Most of a dump is string data, so the scanning jumps from quote to quote
+//! with `memchr` instead of looking at every byte of a string, and finds the
+//! end of comments and dollar-quoted strings with `memmem`. Outside strings,
+//! `memchr3` jumps straight to the next byte that can start one.
+
+use memchr::{memchr, memchr2, memchr3, memmem};
+
+/// Bytes at the start of a dump searched for the markers of its dialect
+const DIALECT_MARKER_BYTES: usize = 64 * 1024;
+
+/// The SQL dialect of a dump, which decides how its strings are read
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DumpDialect {
+    /// Backslash escapes as well as doubled quotes in strings
+    #[default]
+    MySql,
+    Postgres,
+    Sqlite,
+}
+
+impl DumpDialect {
+    /// Recognize the dialect of a dump from the comments and settings its tool
+    /// writes at the start. Dumps without any are read as MySQL dumps.
+    pub fn detect(content: &str) -> DumpDialect {
+        let head = &content.as_bytes()[..content.len().min(DIALECT_MARKER_BYTES)];
+        let has = |markers: &[&str]| markers.iter().any(|marker| memmem::find(head, marker.as_bytes()).is_some());
+        if has(&["-- MySQL dump", "-- MariaDB dump", "/*!40"]) {
+            DumpDialect::MySql
+        } else if has(&["PostgreSQL database", "pg_catalog.", "standard_conforming_strings = on"]) {
+            DumpDialect::Postgres
+        } else if has(&["PRAGMA foreign_keys", "BEGIN TRANSACTION;", "sqlite_sequence"]) {
+            DumpDialect::Sqlite
+        } else {
+            DumpDialect::MySql
+        }
+    }
+    
+    /// Whether a backslash in a string escapes the character after it
+    pub fn backslash_escapes(self) -> bool {
+        self == DumpDialect::MySql
+    }
+}
+
+/// A statement of a dump, without leading comments and its terminating `;`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Statement<'a> {
+    pub text: &'a str,
+    /// Byte offset of `text` in the dump
+    pub offset: usize,
+}
+
+/// What a statement is, as far as the conversion is concerned. Only
+/// `CreateTable`, `DropTable`, `CreateIndex`, `Insert` and `UseDatabase` are
+/// read; everything else is skipped, even when it contains text that looks like table data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatementKind {
+    CreateTable,
+    DropTable,
+    CreateIndex,
+    Insert,
+    /// `USE db` or psql's `\connect db`
+    UseDatabase,
+    /// `SET`, `RESET`
+    Set,
+    /// `BEGIN`, `START TRANSACTION`, `COMMIT`, `ROLLBACK`, savepoints
+    Transaction,
+    /// `CREATE FUNCTION`, `PROCEDURE` or `TRIGGER`, whose bodies can hold statements of their own
+    Routine,
+    Other,
+}
+
+impl Statement<'_> {
+    pub fn kind(&self) -> StatementKind {
+        statement_kind(self.text)
+    }
+}
+
+/// Classify a statement by its leading keywords
+pub fn statement_kind(text: &str) -> StatementKind {
+    if use_database(text).is_some() {
+        return StatementKind::UseDatabase;
+    }
+    let mut words = text.split(|c: char| c.is_whitespace() || c == '(')
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_ascii_uppercase());
+    
+    match words.next().as_deref() {
+        Some("INSERT") => StatementKind::Insert,
+        Some("SET" | "RESET") => StatementKind::Set,
+        Some("BEGIN" | "START" | "COMMIT" | "END" | "ROLLBACK" | "SAVEPOINT" | "RELEASE") => StatementKind::Transaction,
+        Some("DROP") if words.next().as_deref() == Some("TABLE") => StatementKind::DropTable,
+        // Modifiers such as OR REPLACE, UNIQUE, TEMPORARY or DEFINER=... come before the object type
+        Some("CREATE") => words.take(6)
+            .find_map(|word| match word.as_str() {
+                "TABLE" => Some(StatementKind::CreateTable),
+                "INDEX" => Some(StatementKind::CreateIndex),
+                "FUNCTION" | "PROCEDURE" | "TRIGGER" => Some(StatementKind::Routine),
+                "AS" | "ON" | "SELECT" => Some(StatementKind::Other),
+                _ => None,
+            })
+            .unwrap_or(StatementKind::Other),
+        _ => StatementKind::Other,
+    }
+}
+
+/// The database a `USE db` or `\connect db` statement switches to, without
+/// quotes. pg_dumpall writes `\connect -reuse-previous=on "dbname='db'"`.
+pub fn use_database(text: &str) -> Option<&str> {
+    let name = if let Some(command) = text.strip_prefix('\\') {
+        let (command, rest) = command.split_once(char::is_whitespace)?;
+        if command != "connect" && command != "c" {
+            return None;
+        }
+        // Options such as -reuse-previous=on come before the database
+        let name = rest.split_whitespace().find(|word| !word.starts_with('-'))?.trim_matches('"');
+        name.strip_prefix("dbname=").unwrap_or(name)
+    } else {
+        let keyword = text.get(..3)?;
+        if !keyword.eq_ignore_ascii_case("USE") || !text[3..].starts_with(char::is_whitespace) {
+            return None;
+        }
+        text[3..].trim()
+    };
+    let name = name.trim_matches(['`', '"', '\'', '[', ']']);
+    (!name.is_empty()).then_some(name)
+}
+
+/// Split a dump into statements, each with the database it runs in: the one
+/// named by the last `USE` or `\connect` before it, if any
+pub fn split_statements_in_databases(content: &str) -> impl Iterator<Item = (Option<&str>, Statement<'_>)> {
+    let mut database = None;
+    split_statements(content).map(move |statement| {
+        if let Some(name) = use_database(statement.text) {
+            database = Some(name);
+        }
+        (database, statement)
+    })
+}
+
+/// The databases a dump switches to, in the order they first appear
+pub fn dump_databases(content: &str) -> Vec<&str> {
+    let mut databases = Vec::new();
+    for name in split_statements(content).filter_map(|statement| use_database(statement.text)) {
+        if !databases.contains(&name) {
+            databases.push(name);
+        }
+    }
+    databases
+}
+
+/// Iterator over the statements of a dump
+pub struct Statements<'a> {
+    content: &'a str,
+    position: usize,
+    delimiter: &'a str,
+    backslash_escapes: bool,
+}
+
+/// Split a dump into statements, reading strings as its dialect writes them
+pub fn split_statements(content: &str) -> Statements<'_> {
+    split_statements_as(content, DumpDialect::detect(content))
+}
+
+/// Split a dump of the given dialect into statements
+pub fn split_statements_as(content: &str, dialect: DumpDialect) -> Statements<'_> {
+    Statements { content, position: 0, delimiter: ";", backslash_escapes: dialect.backslash_escapes() }
+}
+
+impl<'a> Iterator for Statements<'a> {
+    type Item = Statement<'a>;
+    
+    fn next(&mut self) -> Option<Statement<'a>> {
+        let bytes = self.content.as_bytes();
+        loop {
+            self.position = skip_comments_and_space(bytes, self.position);
+            if self.position >= bytes.len() {
+                return None;
+            }
+            
+            let start = self.position;
+            if let Some(delimiter) = delimiter_command(&self.content[start..]) {
+                self.delimiter = delimiter;
+                self.position = memchr(b'\n', &bytes[start..]).map_or(bytes.len(), |n| start + n + 1);
+                continue;
+            }
+            // psql meta-commands end with their line
+            if bytes[start] == b'\\' {
+                let end = memchr(b'\n', &bytes[start..]).map_or(bytes.len(), |n| start + n);
+                self.position = end;
+                return Some(Statement { text: self.content[start..end].trim_end(), offset: start });
+            }
+            
+            let end = find_statement_end(bytes, start, self.delimiter.as_bytes(), self.backslash_escapes);
+            self.position = end + self.delimiter.len();
+            
+            let text = self.content[start..end.min(bytes.len())].trim_end();
+            if !text.is_empty() {
+                return Some(Statement { text, offset: start });
+            }
+        }
+    }
+}
+
+/// The new delimiter if the text starts with a `DELIMITER` command, which
+/// takes the rest of its line
+fn delimiter_command(text: &str) -> Option<&str> {
+    let keyword = text.get(..9)?;
+    if !keyword.eq_ignore_ascii_case("DELIMITER") || !text[9..].starts_with([' ', '\t']) {
+        return None;
+    }
+    let delimiter = text[10..].lines().next().unwrap_or("").trim();
+    (!delimiter.is_empty()).then_some(delimiter)
+}
+
+/// Skip whitespace, `-- ` line comments and `/* */` block comments
+fn skip_comments_and_space(bytes: &[u8], mut i: usize) -> usize {
+    while i < bytes.len() {
+        if bytes[i].is_ascii_whitespace() {
+            i += 1;
+        } else if let Some(end) = comment_end(bytes, i) {
+            i = end;
+        } else {
+            break;
+        }
+    }
+    i
+}
+
+/// If a comment starts at `i`, the position just after it
+fn comment_end(bytes: &[u8], i: usize) -> Option<usize> {
+    match (bytes[i], bytes.get(i + 1)) {
+        (b'-', Some(b'-')) if bytes.get(i + 2).is_none_or(|b| b.is_ascii_whitespace()) => {
+            Some(memchr(b'\n', &bytes[i..]).map_or(bytes.len(), |n| i + n + 1))
+        }
+        (b'/', Some(b'*')) => {
+            Some(memmem::find(&bytes[i + 2..], b"*/").map_or(bytes.len(), |n| i + 2 + n + 2))
+        }
+        _ => None,
+    }
+}
+
+/// Length of the `$tag$` delimiter if a Postgres dollar-quoted string starts at
+/// `i`. Tags follow identifier rules, so parameters such as `$1` and `$` inside
+/// identifiers are not taken for quotes.
+pub(crate) fn dollar_quote_delimiter(bytes: &[u8], i: usize) -> Option<usize> {
+    let is_identifier = |b: &u8| b.is_ascii_alphanumeric() || *b == b'_' || *b == b'$';
+    if bytes.get(i) != Some(&b'$') || (i > 0 && is_identifier(&bytes[i - 1])) {
+        return None;
+    }
+    let tag_len = bytes[i + 1..].iter().take_while(|b| b.is_ascii_alphanumeric() || **b == b'_').count();
+    if tag_len > 0 && bytes[i + 1].is_ascii_digit() {
+        return None;
+    }
+    (bytes.get(i + 1 + tag_len) == Some(&b'$')).then_some(tag_len + 2)
+}
+
+/// If a dollar-quoted string starts at `i`, the position just after its closing
+/// delimiter (or the end of the content when it is never closed)
+pub(crate) fn dollar_quote_end(bytes: &[u8], i: usize) -> Option<usize> {
+    let len = dollar_quote_delimiter(bytes, i)?;
+    let delimiter = &bytes[i..i + len];
+    let body = i + len;
+    Some(memmem::find(&bytes[body..], delimiter).map_or(bytes.len(), |n| body + n + len))
+}
+
+/// Position of the delimiter ending the statement that starts at `i`, or the
+/// end of the content
+fn find_statement_end(bytes: &[u8], mut i: usize, delimiter: &[u8], backslash_escapes: bool) -> usize {
+    let mut special = SpecialBytes::new(delimiter[0]);
+    
+    // Text that cannot start a string, comment or the delimiter is passed over at once
+    while let Some(next) = special.next(bytes, i) {
+        i = next;
+        if bytes[i..].starts_with(delimiter) {
+            return i;
+        }
+        // A doubled quote is seen as the string closing and immediately reopening
+        i = match quoted_span_end(bytes, i, backslash_escapes).or_else(|| comment_end(bytes, i)) {
+            Some(end) => end,
+            None => i + 1,
+        };
+    }
+    
+    bytes.len()
+}
+
+/// Finds the next byte that can start a string, comment, dollar quote or the
+/// delimiter, with one `memchr3` search for the quotes, one for `-`, `/` and
+/// `$` and one `memchr` for the delimiter. Each search remembers its last hit
+/// and only runs again once the scan has passed it.
+struct SpecialBytes {
+    delimiter: u8,
+    /// Position of the next hit of each search, the end of the content if there
+    /// is none, or `None` before the first search
+    found: [Option<usize>; 3],
+}
+
+impl SpecialBytes {
+    fn new(delimiter: u8) -> Self {
+        SpecialBytes { delimiter, found: [None; 3] }
+    }
+    
+    fn next(&mut self, bytes: &[u8], i: usize) -> Option<usize> {
+        for (search, found) in self.found.iter_mut().enumerate() {
+            if found.is_some_and(|position| position >= i) {
+                continue;
+            }
+            let rest = &bytes[i..];
+            let hit = match search {
+                0 => memchr3(b'\'', b'"', b'`', rest),
+                1 => memchr3(b'-', b'/', b'$', rest),
+                _ => memchr(self.delimiter, rest),
+            };
+            *found = Some(hit.map_or(bytes.len(), |n| i + n));
+        }
+        self.found.iter().flatten().copied().min().filter(|&position| position < bytes.len())
+    }
+}
+
+/// If a string, quoted identifier or dollar-quoted string starts at `i`, the
+/// position just after it. The statement splitter and the parser both skip
+/// quoted text with this, so they agree on where every string ends.
+pub(crate) fn quoted_span_end(bytes: &[u8], i: usize, backslash_escapes: bool) -> Option<usize> {
+    match bytes[i] {
+        quote @ (b'\'' | b'"' | b'`') => Some(quoted_end(bytes, i + 1, quote, backslash_escapes)),
+        b'$' => dollar_quote_end(bytes, i),
+        _ => None,
+    }
+}
+
+/// Position just past the `quote` closing the string whose text starts at
+/// `i`, or the end of the content. With `backslash_escapes` a backslash
+/// escapes the next character, except in backtick-quoted identifiers.
+/// Postgres `E'...'` strings always read backslash escapes.
+pub(crate) fn quoted_end(bytes: &[u8], mut i: usize, quote: u8, backslash_escapes: bool) -> usize {
+    let backslash_escapes = backslash_escapes || (quote == b'\'' && is_escape_string(bytes, i - 1));
+    loop {
+        let rest = &bytes[i..];
+        let found = if quote == b'`' || !backslash_escapes { memchr(quote, rest) } else { memchr2(quote, b'\\', rest) };
+        let Some(n) = found else {
+            return bytes.len();
+        };
+        i += n;
+        if bytes[i] == quote {
+            return i + 1;
+        }
+        i = (i + 2).min(bytes.len());
+    }
+}
+
+/// Whether the quote at `i` opens a Postgres escape string, `E'...'`
+fn is_escape_string(bytes: &[u8], i: usize) -> bool {
+    let is_identifier = |b: u8| b.is_ascii_alphanumeric() || b == b'_' || b == b'$';
+    i >= 1 && matches!(bytes[i - 1], b'E' | b'e') && (i < 2 || !is_identifier(bytes[i - 2]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn texts(content: &str) -> Vec<&str> {
+        split_statements(content).map(|statement| statement.text).collect()
+    }
+    
+    #[test]
+    fn test_split_statements_ignores_line_structure() {
+        let content = "INSERT INTO t VALUES (1);INSERT INTO t VALUES ('a;b');\nINSERT\nINTO t\nVALUES (2)\n;";
+        assert_eq!(texts(content), vec![
+            "INSERT INTO t VALUES (1)",
+            "INSERT INTO t VALUES ('a;b')",
+            "INSERT\nINTO t\nVALUES (2)",
+        ]);
+    }
+    
+    #[test]
+    fn test_split_statements_skips_comments() {
+        let content = "-- header; not a statement\n/* block; comment */ SET x = 1;\n/*!40101 SET NAMES utf8 */;\nSELECT 'it''s -- fine', \"q\\\"\";";
+        let statements: Vec<Statement> = split_statements(content).collect();
+        assert_eq!(statements.len(), 2);
+        assert_eq!(statements[0].text, "SET x = 1");
+        assert_eq!(&content[statements[0].offset..statements[0].offset + 9], "SET x = 1");
+        assert_eq!(statements[1].text, "SELECT 'it''s -- fine', \"q\\\"\"");
+    }
+    
+    #[test]
+    fn test_split_statements_with_dollar_quotes() {
+        let content = "CREATE FUNCTION f() RETURNS int AS $body$ SELECT 1; $body$ LANGUAGE sql;\n\
+            INSERT INTO t VALUES ($$it's; done$$, $1, price$usd);";
+        assert_eq!(texts(content), vec![
+            "CREATE FUNCTION f() RETURNS int AS $body$ SELECT 1; $body$ LANGUAGE sql",
+            "INSERT INTO t VALUES ($$it's; done$$, $1, price$usd)",
+        ]);
+        assert_eq!(dollar_quote_delimiter(b"$1", 0), None);
+        assert_eq!(dollar_quote_delimiter(b"$tag$", 0), Some(5));
+    }
+    
+    #[test]
+    fn test_quoted_end() {
+        assert_eq!(quoted_end(b"'ab;c' x", 1, b'\'', true), 6);
+        assert_eq!(quoted_end(b"'a\\'b' x", 1, b'\'', true), 6);
+        // Backslashes are plain characters in backtick identifiers
+        assert_eq!(quoted_end(b"`a\\` x", 1, b'`', true), 4);
+        // Unclosed strings and a trailing backslash run to the end
+        assert_eq!(quoted_end(b"'abc", 1, b'\'', true), 4);
+        assert_eq!(quoted_end(b"'abc\\", 1, b'\'', true), 5);
+        // Without backslash escapes the string ends at the next quote
+        assert_eq!(quoted_end(b"'C:\\temp\\' x", 1, b'\'', false), 10);
+        assert_eq!(quoted_end(b"E'it\\'s' x", 2, b'\'', false), 8);
+        assert_eq!(quoted_end(b"NE'a\\' x", 3, b'\'', false), 6);
+        assert_eq!(texts("INSERT INTO t VALUES ('a\\';b', 'it''s;');SELECT 1"), vec![
+            "INSERT INTO t VALUES ('a\\';b', 'it''s;')",
+            "SELECT 1",
+        ]);
+    }
+    
+    #[test]
+    fn test_trailing_backslash_in_postgres_and_sqlite_strings() {
+        let sqlite = "PRAGMA foreign_keys=OFF;\nBEGIN TRANSACTION;\nINSERT INTO t VALUES(1,'C:\\temp\\');\nINSERT INTO t VALUES(2,'x');\nCOMMIT;";
+        assert_eq!(DumpDialect::detect(sqlite), DumpDialect::Sqlite);
+        assert_eq!(texts(sqlite), vec![
+            "PRAGMA foreign_keys=OFF",
+            "BEGIN TRANSACTION",
+            "INSERT INTO t VALUES(1,'C:\\temp\\')",
+            "INSERT INTO t VALUES(2,'x')",
+            "COMMIT",
+        ]);
+        
+        let postgres = "-- PostgreSQL database dump\nINSERT INTO public.t VALUES (1, 'a\\');\nINSERT INTO public.t VALUES (2, 'b');";
+        assert_eq!(DumpDialect::detect(postgres), DumpDialect::Postgres);
+        assert_eq!(texts(postgres).len(), 2);
+        assert_eq!(DumpDialect::detect("-- MySQL dump 10.13\nSET x = 1;"), DumpDialect::MySql);
+        assert_eq!(DumpDialect::detect("INSERT INTO t VALUES (1);"), DumpDialect::MySql);
+    }
+    
+    #[test]
+    fn test_special_bytes_found_past_earlier_hits() {
+        let content = "INSERT INTO t VALUES (1,'a;b',\"c;d\");/* x; */ SELECT $$;$$ -- ;\n;SELECT 1";
+        assert_eq!(texts(content), vec![
+            "INSERT INTO t VALUES (1,'a;b',\"c;d\")",
+            "SELECT $$;$$ -- ;",
+            "SELECT 1",
+        ]);
+    }
+    
+    #[test]
+    fn test_statement_kinds() {
+        let kinds: Vec<StatementKind> = [
+            "insert into t values (1)",
+            "SET NAMES utf8mb4",
+            "START TRANSACTION",
+            "COMMIT",
+            "DROP TABLE IF EXISTS t",
+            "DROP INDEX i",
+            "CREATE TABLE IF NOT EXISTS t (id int)",
+            "CREATE UNIQUE INDEX i ON t (id)",
+            "CREATE OR REPLACE FUNCTION f() RETURNS trigger AS $$ BEGIN INSERT INTO t VALUES (1); END $$ LANGUAGE plpgsql",
+            "CREATE DEFINER=`root`@`localhost` TRIGGER tr AFTER INSERT ON t FOR EACH ROW INSERT INTO log VALUES (NEW.id)",
+            "CREATE VIEW v AS SELECT * FROM t",
+            "USE `shop`",
+            "\\connect crm",
+        ].iter().map(|text| statement_kind(text)).collect();
+        
+        assert_eq!(kinds, vec![
+            StatementKind::Insert,
+            StatementKind::Set,
+            StatementKind::Transaction,
+            StatementKind::Transaction,
+            StatementKind::DropTable,
+            StatementKind::Other,
+            StatementKind::CreateTable,
+            StatementKind::CreateIndex,
+            StatementKind::Routine,
+            StatementKind::Routine,
+            StatementKind::Other,
+            StatementKind::UseDatabase,
+            StatementKind::UseDatabase,
+        ]);
+    }
+    
+    #[test]
+    fn test_split_statements_in_databases() {
+        let content = "CREATE TABLE t (id int);\nUSE `shop`;\nINSERT INTO t VALUES (1);\n\\connect -reuse-previous=on \"dbname='crm'\"\nINSERT INTO t VALUES (2);\n\\connect shop\nINSERT INTO t VALUES (3);";
+        let databases: Vec<(Option<&str>, &str)> = split_statements_in_databases(content)
+            .filter(|(_, statement)| statement.kind() != StatementKind::UseDatabase)
+            .map(|(database, statement)| (database, statement.text))
+            .collect();
+        assert_eq!(databases, vec![
+            (None, "CREATE TABLE t (id int)"),
+            (Some("shop"), "INSERT INTO t VALUES (1)"),
+            (Some("crm"), "INSERT INTO t VALUES (2)"),
+            (Some("shop"), "INSERT INTO t VALUES (3)"),
+        ]);
+        assert_eq!(dump_databases(content), vec!["shop", "crm"]);
+        assert_eq!(use_database("user_id = 1"), None);
+        assert_eq!(use_database("\\restrict abc"), None);
+    }
+    
+    #[test]
+    fn test_split_statements_follows_delimiter_command() {
+        let content = "DELIMITER ;;\nCREATE PROCEDURE p() BEGIN INSERT INTO t VALUES (1); END;;\nDELIMITER ;\nINSERT INTO t VALUES (2);";
+        assert_eq!(texts(content), vec![
+            "CREATE PROCEDURE p() BEGIN INSERT INTO t VALUES (1); END",
+            "INSERT INTO t VALUES (2)",
+        ]);
+    }
+}
@@ -0,0 +1,126 @@
+//! What happens to a table that fails to convert (`--on-table-error`).
+//!
+//! A table fails when its rows cannot be converted, e.g. a row with the wrong
+//! number of values under `--on-mismatch error`. With `skip` the
+//! table is reported and the other tables are converted as usual; `fail` stops
+//! the run with an error instead. `quarantine` skips the table like `skip` and
+//! writes its CREATE TABLE and INSERT statements to `<table>.errors.sql` in the
+//! output directory, so they can be inspected, fixed and converted again.
+
+use anyhow::{Context, Result};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::args::flag_value;
+use crate::parser::table_statements;
+
+/// How a failed table is handled
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum TableErrorPolicy {
+    /// Report the table and convert the others
+    #[default]
+    Skip,
+    /// Stop the run with an error; tables not started yet are not converted
+    Fail,
+    /// Like `Skip`, and write the table's statements to the directory
+    Quarantine(PathBuf),
+}
+
+/// Parse --on-table-error option from command line
+pub fn parse_table_error_policy(args: &[String]) -> Result<TableErrorPolicy> {
+    match flag_value(args, "--on-table-error")? {
+        None | Some("skip") => Ok(TableErrorPolicy::Skip),
+        Some("fail") => Ok(TableErrorPolicy::Fail),
+        Some("quarantine") => {
+            let output_dir = flag_value(args, "--output")?.unwrap_or(".");
+            if output_dir.contains("://") {
+                anyhow::bail!("Error: --on-table-error quarantine only supports output to a local directory");
+            }
+            Ok(TableErrorPolicy::Quarantine(PathBuf::from(output_dir)))
+        }
+        Some(other) => anyhow::bail!(
+            "Error: Invalid --on-table-error '{}'. Use one of: skip, fail, quarantine",
+            other
+        ),
+    }
+}
+
+impl fmt::Display for TableErrorPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            TableErrorPolicy::Skip => "skip",
+            TableErrorPolicy::Fail => "fail",
+            TableErrorPolicy::Quarantine(_) => "quarantine",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Write the CREATE TABLE and INSERT statements of a failed table to
+/// `<table>.errors.sql` in `dir`, after a comment with the error. `databases`
+/// are the dump's databases, as `table_statements` takes them. Returns the
+/// path of the file.
+pub fn quarantine_table(table: &str, error: &str, content: &str, databases: &[&str], dir: &Path) -> Result<PathBuf> {
+    let statements = table_statements(content, databases, table)?;
+    let mut sql = format!("-- Table '{}' could not be converted: {}\n", table, error.replace('\n', " "));
+    for statement in statements {
+        sql.push_str(statement.text);
+        sql.push_str(";\n");
+    }
+    
+    fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create directory '{}'", dir.display()))?;
+    let path = dir.join(format!("{}.errors.sql", quarantine_stem(table)));
+    fs::write(&path, sql)
+        .with_context(|| format!("Failed to write '{}'", path.display()))?;
+    Ok(path)
+}
+
+/// File name of a table's quarantine file; characters that are not safe in
+/// file names become `_`
+fn quarantine_stem(table: &str) -> String {
+    table.chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' || c == '.' { c } else { '_' })
+        .collect::<String>()
+        .trim_start_matches('.')
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+    
+    #[test]
+    fn test_parse_table_error_policy() {
+        assert_eq!(parse_table_error_policy(&args("t dump.sql")).unwrap(), TableErrorPolicy::Skip);
+        assert_eq!(parse_table_error_policy(&args("t dump.sql --on-table-error fail")).unwrap(), TableErrorPolicy::Fail);
+        assert_eq!(
+            parse_table_error_policy(&args("t dump.sql --output out --on-table-error quarantine")).unwrap(),
+            TableErrorPolicy::Quarantine(PathBuf::from("out"))
+        );
+        assert!(parse_table_error_policy(&args("t dump.sql --on-table-error retry")).is_err());
+        assert!(parse_table_error_policy(&args("t dump.sql --output s3://b/x --on-table-error quarantine")).is_err());
+    }
+    
+    #[test]
+    fn test_quarantine_table() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path().join("quarantine");
+        let content = "CREATE TABLE t (id int, name text);\nINSERT INTO t VALUES (1, 'a');\nINSERT INTO other VALUES (1);\nINSERT INTO t VALUES (2);\n";
+        
+        let path = quarantine_table("t", "Row 2 has 1 values but the table has 2 columns", content, &[], &dir).unwrap();
+        
+        assert_eq!(path, dir.join("t.errors.sql"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "\
+-- Table 't' could not be converted: Row 2 has 1 values but the table has 2 columns
+CREATE TABLE t (id int, name text);
+INSERT INTO t VALUES (1, 'a');
+INSERT INTO t VALUES (2);
+");
+    }
+}
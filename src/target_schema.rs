@@ -0,0 +1,159 @@
+//! Map output columns onto a destination table definition (`--target-schema`).
+//!
+//! The target is a JSON file in the `--schema-out` layout or a file of
+//! `CREATE TABLE` statements. For every table it defines, the output columns
+//! are reordered to the target's order and renamed to its names; target
+//! columns without a source are written empty, and source columns without a
+//! target are dropped. Both are reported so mapping mistakes are caught before
+//! the data is loaded.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+use crate::args::flag_value;
+use crate::parser::parse_sql_content;
+use crate::types::Table;
+
+#[derive(Deserialize)]
+struct SchemaFile {
+    tables: Vec<SchemaTable>,
+}
+
+#[derive(Deserialize)]
+struct SchemaTable {
+    name: String,
+    columns: Vec<String>,
+}
+
+/// How the output columns of a table map onto its target definition
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnMapping {
+    /// The target's column names, in its order
+    pub headers: Vec<String>,
+    /// Index of the source column for each target column
+    pub sources: Vec<Option<usize>>,
+    /// Source columns the target has no column for; they are dropped
+    pub unmapped: Vec<String>,
+    /// Target columns no source column maps to; they are written empty
+    pub missing: Vec<String>,
+}
+
+/// Parse the --target-schema option from command line and load the file
+pub fn parse_target_schema(args: &[String]) -> Result<Option<Vec<Table>>> {
+    flag_value(args, "--target-schema")?
+        .map(load_target_schema)
+        .transpose()
+}
+
+/// Read target table definitions from a `--schema-out` JSON file or, for any
+/// other extension, from `CREATE TABLE` statements
+pub fn load_target_schema(path: impl AsRef<Path>) -> Result<Vec<Table>> {
+    let path = path.as_ref();
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read target schema '{}'", path.display()))?;
+    
+    if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json")) {
+        let schema: SchemaFile = serde_json::from_str(&text)
+            .with_context(|| format!("Error in target schema '{}'", path.display()))?;
+        Ok(schema.tables.into_iter()
+            .map(|table| Table { name: table.name, columns: table.columns, ..Default::default() })
+            .collect())
+    } else {
+        let (tables, _) = parse_sql_content(text)
+            .with_context(|| format!("Error in target schema '{}'", path.display()))?;
+        Ok(tables)
+    }
+}
+
+/// The target definition of a table, matched by name ignoring case
+pub fn find_target<'a>(targets: &'a [Table], table: &str) -> Option<&'a Table> {
+    targets.iter().find(|t| t.name == table)
+        .or_else(|| targets.iter().find(|t| t.name.eq_ignore_ascii_case(table)))
+}
+
+/// Match output columns to target columns: by exact name, then ignoring case
+/// and `_`/`-`, so `createdAt` maps to `created_at`
+pub fn map_columns(target: &Table, headers: &[String]) -> ColumnMapping {
+    let normalize = |name: &str| name.chars()
+        .filter(|c| *c != '_' && *c != '-')
+        .flat_map(char::to_lowercase)
+        .collect::<String>();
+    let mut used = vec![false; headers.len()];
+    let mut sources: Vec<Option<usize>> = target.columns.iter()
+        .map(|column| {
+            let index = headers.iter().position(|h| h == column)?;
+            used[index] = true;
+            Some(index)
+        })
+        .collect();
+    
+    for (column, source) in target.columns.iter().zip(sources.iter_mut()).filter(|(_, source)| source.is_none()) {
+        let wanted = normalize(column);
+        *source = headers.iter().enumerate()
+            .position(|(i, h)| !used[i] && normalize(h) == wanted);
+        if let Some(index) = *source {
+            used[index] = true;
+        }
+    }
+    
+    ColumnMapping {
+        headers: target.columns.clone(),
+        missing: target.columns.iter().zip(&sources)
+            .filter(|(_, source)| source.is_none())
+            .map(|(column, _)| column.clone())
+            .collect(),
+        unmapped: headers.iter().zip(&used)
+            .filter(|(_, used)| !**used)
+            .map(|(header, _)| header.clone())
+            .collect(),
+        sources,
+    }
+}
+
+/// Rearrange rows into the target's columns
+pub fn apply_column_mapping(mapping: &ColumnMapping, rows: Vec<Vec<String>>) -> Vec<Vec<String>> {
+    rows.into_iter()
+        .map(|mut row| mapping.sources.iter()
+            .map(|source| source.and_then(|i| row.get_mut(i)).map(std::mem::take).unwrap_or_default())
+            .collect())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+    
+    #[test]
+    fn test_map_columns_reorders_renames_and_pads() {
+        let (targets, _) = parse_sql_content(
+            "CREATE TABLE Orders (order_id INT, created_at DATE, amount DECIMAL(10,2), currency CHAR(3));".to_string()
+        ).unwrap();
+        let target = find_target(&targets, "orders").unwrap();
+        let headers = strings(&["amount", "createdAt", "order_id", "internal_note"]);
+        
+        let mapping = map_columns(target, &headers);
+        assert_eq!(mapping.headers, strings(&["order_id", "created_at", "amount", "currency"]));
+        assert_eq!(mapping.missing, strings(&["currency"]));
+        assert_eq!(mapping.unmapped, strings(&["internal_note"]));
+        
+        let rows = apply_column_mapping(&mapping, vec![strings(&["9.50", "2024-01-01", "7", "call back"])]);
+        assert_eq!(rows, vec![strings(&["7", "2024-01-01", "9.50", ""])]);
+    }
+    
+    #[test]
+    fn test_load_json_target_schema() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("target.json");
+        fs::write(&path, r#"{"tables": [{"name": "users", "columns": ["id", "email"], "primary_key": ["id"]}]}"#).unwrap();
+        
+        let targets = load_target_schema(&path).unwrap();
+        assert_eq!(targets[0].name, "users");
+        assert_eq!(targets[0].columns, strings(&["id", "email"]));
+    }
+}
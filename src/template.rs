@@ -0,0 +1,232 @@
+use anyhow::{Context, Result};
+use minijinja::{context, Environment};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::args::flag_value;
+use crate::csv_writer::convert_headers;
+use crate::jsonl::{check_jsonl_flags, parse_jsonl_format};
+use crate::output_file::{FileTableSink, OutputFile};
+use crate::report::WrittenFile;
+use crate::sink::{OutputSink, TableSink};
+use crate::types::{CsvOptions, OutputFormat, Table};
+
+/// Parse --format and --template options from command line
+pub fn parse_output_format(args: &[String]) -> Result<OutputFormat> {
+    let template = flag_value(args, "--template")?;
+    let format = flag_value(args, "--format")?;
+    if template.is_some() && format != Some("template") {
+        anyhow::bail!("Error: --template requires --format template");
+    }
+    
+    let format = match format {
+        None | Some("csv") => Ok(OutputFormat::Csv),
+        Some("template") => {
+            let path = template
+                .ok_or_else(|| anyhow::anyhow!("Error: --format template requires --template <file>"))?;
+            let source = fs::read_to_string(path)
+                .with_context(|| format!("Failed to read template '{}'", path))?;
+            Ok(OutputFormat::Template { source, extension: template_extension(path) })
+        }
+        #[cfg(feature = "avro")]
+        Some("avro") => Ok(OutputFormat::Avro),
+        #[cfg(not(feature = "avro"))]
+        Some("avro") => anyhow::bail!("Error: --format avro requires building with --features avro"),
+        #[cfg(feature = "duckdb")]
+        Some("duckdb") => Ok(OutputFormat::DuckDb),
+        #[cfg(not(feature = "duckdb"))]
+        Some("duckdb") => anyhow::bail!("Error: --format duckdb requires building with --features duckdb"),
+        Some("jsonl") => return parse_jsonl_format(args),
+        Some(other) => anyhow::bail!("Error: Invalid --format '{}'. Use one of: csv, template, avro, duckdb, jsonl", other),
+    };
+    check_jsonl_flags(args)?;
+    format
+}
+
+/// Extension of the files generated from a template: `row.xml.tmpl` -> `xml`,
+/// `row.tmpl` -> `txt`
+fn template_extension(path: &str) -> String {
+    let path = Path::new(path);
+    let path = match path.extension().and_then(|e| e.to_str()) {
+        Some("tmpl" | "j2" | "jinja") => Path::new(path.file_stem().unwrap_or_default()),
+        _ => path,
+    };
+    path.extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("txt")
+        .to_string()
+}
+
+/// Render every row of a table through the template and write the results to a file
+pub fn write_template(
+    filename: &str,
+    template: &str,
+    table: &str,
+    headers: &[String],
+    rows: &[Vec<String>],
+    options: &CsvOptions,
+) -> Result<WrittenFile> {
+    let mut file = OutputFile::create(Path::new(filename), options)?;
+    write_template_to(&mut file, template, table, headers, rows, options)?;
+    let path = file.path().to_string_lossy().to_string();
+    let bytes = file.finish()?;
+    
+    Ok(WrittenFile {
+        path,
+        rows_written: rows.len(),
+        bytes: Some(bytes),
+        warnings: Vec::new(),
+    })
+}
+
+/// Render every row of a table through the template. Each row sees `table`,
+/// `index` (1-based), `columns`, `values` and `row` (column name -> value).
+pub fn write_template_to<W: Write>(
+    destination: W,
+    template: &str,
+    table: &str,
+    headers: &[String],
+    rows: &[Vec<String>],
+    options: &CsvOptions,
+) -> Result<()> {
+    let mut sink = TemplateTableSink::new(destination, template, table, headers, options, String::new())?;
+    for row in rows {
+        sink.write_row(row)?;
+    }
+    Box::new(sink).finish()?;
+    
+    Ok(())
+}
+
+/// Directory of files rendered from a template, one per table
+pub struct TemplateSink {
+    pub dir: PathBuf,
+    pub source: String,
+    pub extension: String,
+    pub options: CsvOptions,
+}
+
+impl OutputSink for TemplateSink {
+    fn open_table<'a>(&'a self, table: &Table, name: &str, headers: &[String]) -> Result<Box<dyn TableSink + 'a>> {
+        let path = self.dir.join(format!("{}.{}", name, self.extension));
+        let file = OutputFile::create(&path, &self.options)?;
+        let location = file.path().to_string_lossy().to_string();
+        let rendered = TemplateTableSink::new(file, &self.source, &table.name, headers, &self.options, location.clone())?;
+        Ok(Box::new(FileTableSink::new(rendered, TemplateTableSink::into_inner, location)))
+    }
+}
+
+/// Renders the rows of one table through a template
+struct TemplateTableSink<W: Write> {
+    destination: W,
+    env: Environment<'static>,
+    table: String,
+    headers: Vec<String>,
+    index: usize,
+    location: String,
+}
+
+impl<W: Write> TemplateTableSink<W> {
+    fn new(
+        destination: W,
+        template: &str,
+        table: &str,
+        headers: &[String],
+        options: &CsvOptions,
+        location: String,
+    ) -> Result<Self> {
+        let mut env = Environment::new();
+        env.add_template_owned("row", template.to_string())
+            .context("Invalid template")?;
+        
+        Ok(TemplateTableSink {
+            destination,
+            env,
+            table: table.to_string(),
+            headers: convert_headers(headers, options.header_case)?,
+            index: 0,
+            location,
+        })
+    }
+    
+    /// Give back the destination once all rows are rendered
+    fn into_inner(self) -> Result<W> {
+        Ok(self.destination)
+    }
+}
+
+impl<W: Write> TableSink for TemplateTableSink<W> {
+    fn write_row(&mut self, values: &[String]) -> Result<()> {
+        self.index += 1;
+        let row: BTreeMap<&str, &str> = self.headers.iter()
+            .map(String::as_str)
+            .zip(values.iter().map(String::as_str))
+            .collect();
+        let rendered = self.env.get_template("row")?.render(context! {
+            table => &self.table,
+            index => self.index,
+            columns => &self.headers,
+            values => values,
+            row => row,
+        }).with_context(|| format!("Failed to render row {}", self.index))?;
+        
+        self.destination.write_all(rendered.as_bytes())?;
+        if !rendered.ends_with('\n') {
+            self.destination.write_all(b"\n")?;
+        }
+        
+        Ok(())
+    }
+    
+    fn finish(mut self: Box<Self>) -> Result<String> {
+        self.destination.flush()
+            .context("Failed to flush output")?;
+        Ok(self.location)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_write_template_to() {
+        let headers = vec!["id".to_string(), "name".to_string()];
+        let rows = vec![
+            vec!["1".to_string(), "Alice".to_string()],
+            vec!["2".to_string(), "Bob".to_string()],
+        ];
+        let template = r#"<{{ table }} n="{{ index }}">{% for c in columns %}<{{ c }}>{{ values[loop.index0] }}</{{ c }}>{% endfor %}</{{ table }}>"#;
+        
+        let mut output = Vec::new();
+        write_template_to(&mut output, template, "user", &headers, &rows, &CsvOptions::default()).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "\
+<user n=\"1\"><id>1</id><name>Alice</name></user>
+<user n=\"2\"><id>2</id><name>Bob</name></user>
+");
+        
+        let mut output = Vec::new();
+        write_template_to(&mut output, "{{ row.id }}|{{ row.name }}", "user", &headers, &rows, &CsvOptions::default()).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "1|Alice\n2|Bob\n");
+    }
+    
+    #[test]
+    fn test_template_needs_template_format() {
+        for format in ["csv", "jsonl"] {
+            let args: Vec<String> = ["--format", format, "--template", "row.tmpl"].iter().map(|s| s.to_string()).collect();
+            assert_eq!(parse_output_format(&args).unwrap_err().to_string(), "Error: --template requires --format template");
+        }
+        let args: Vec<String> = ["--template", "row.tmpl"].iter().map(|s| s.to_string()).collect();
+        assert!(parse_output_format(&args).is_err());
+    }
+    
+    #[test]
+    fn test_template_extension() {
+        assert_eq!(template_extension("templates/row.xml.tmpl"), "xml");
+        assert_eq!(template_extension("row.tmpl"), "txt");
+        assert_eq!(template_extension("row.txt"), "txt");
+        assert_eq!(template_extension("fixed.dat"), "dat");
+    }
+}
@@ -0,0 +1,145 @@
+use anyhow::Result;
+use chrono::{DateTime, FixedOffset, NaiveDateTime, TimeZone};
+use chrono_tz::Tz;
+
+use crate::args::flag_values;
+use crate::types::TzConversion;
+use crate::warnings::WarningHandler;
+
+/// Layouts of datetimes without an offset, read in the source timezone. `%.f`
+/// also matches values without fractional seconds.
+const LOCAL_FORMATS: [&str; 2] = ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"];
+
+/// Layouts of datetimes with an offset, such as Postgres `timestamptz` output
+/// (`2024-01-15 14:30:00+00`) and RFC 3339
+const OFFSET_FORMATS: [&str; 2] = ["%Y-%m-%d %H:%M:%S%.f%#z", "%Y-%m-%dT%H:%M:%S%.f%#z"];
+
+/// Parse --convert-tz options from command line, e.g.
+/// `--convert-tz createdAt:UTC->America/New_York` (repeatable)
+pub fn parse_tz_conversions(args: &[String]) -> Result<Vec<TzConversion>> {
+    flag_values(args, "--convert-tz")?
+        .into_iter()
+        .map(parse_tz_conversion)
+        .collect()
+}
+
+/// Parse a single `<column>:<from>-><to>` specification
+pub fn parse_tz_conversion(spec: &str) -> Result<TzConversion> {
+    let invalid = || anyhow::anyhow!(
+        "Error: Invalid --convert-tz '{}'. Use <column>:<from>-><to>, e.g. createdAt:UTC->America/New_York",
+        spec
+    );
+    let (column, zones) = spec.rsplit_once(':').ok_or_else(invalid)?;
+    let (from, to) = zones.split_once("->").ok_or_else(invalid)?;
+    if column.is_empty() {
+        return Err(invalid());
+    }
+    let zone = |name: &str| name.trim().parse::<Tz>()
+        .map_err(|_| anyhow::anyhow!("Error: Unknown timezone '{}' in --convert-tz '{}'", name.trim(), spec));
+    
+    Ok(TzConversion {
+        column: column.to_string(),
+        from: zone(from)?,
+        to: zone(to)?,
+    })
+}
+
+/// Rewrite a datetime in the target timezone, keeping its layout. Values with
+/// an offset are converted from that offset; values without one are read in
+/// the source timezone. Returns `None` for values that are not datetimes and
+/// for local times that do not exist in the source timezone (DST gaps).
+pub fn convert_timezone(value: &str, conversion: &TzConversion) -> Option<String> {
+    if let Ok(datetime) = DateTime::<FixedOffset>::parse_from_rfc3339(value) {
+        return Some(datetime.with_timezone(&conversion.to).to_rfc3339());
+    }
+    for format in OFFSET_FORMATS {
+        if let Ok(datetime) = DateTime::<FixedOffset>::parse_from_str(value, format) {
+            let converted = datetime.with_timezone(&conversion.to);
+            return Some(converted.format(&format.replace("%#z", "%:z")).to_string());
+        }
+    }
+    for format in LOCAL_FORMATS {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(value, format) {
+            // Ambiguous times at the end of DST resolve to the earlier instant
+            let datetime = conversion.from.from_local_datetime(&naive).earliest()?;
+            return Some(datetime.with_timezone(&conversion.to).naive_local().format(format).to_string());
+        }
+    }
+    None
+}
+
+/// Convert the timezone of datetime columns. Conversions of columns a table does
+/// not have are ignored; empty and NULL values are kept, and other values that
+/// cannot be converted are kept with a warning.
+pub fn apply_tz_conversions(
+    headers: &[String],
+    rows: &mut [Vec<String>],
+    conversions: &[TzConversion],
+    warnings: &dyn WarningHandler,
+) {
+    for conversion in conversions {
+        let Some(index) = headers.iter().position(|h| h == &conversion.column) else {
+            continue;
+        };
+        for cell in rows.iter_mut().filter_map(|row| row.get_mut(index)) {
+            if cell.is_empty() || cell.eq_ignore_ascii_case("NULL") {
+                continue;
+            }
+            match convert_timezone(cell, conversion) {
+                Some(converted) => *cell = converted,
+                None => warnings.warn(format!(
+                    "Could not convert '{}' in column '{}' to {}, keeping it",
+                    cell, conversion.column, conversion.to
+                )),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::warnings::CollectedWarnings;
+    
+    #[test]
+    fn test_parse_tz_conversion() {
+        let conversion = parse_tz_conversion("createdAt:UTC->America/New_York").unwrap();
+        assert_eq!(conversion.column, "createdAt");
+        assert_eq!(conversion.from, Tz::UTC);
+        assert_eq!(conversion.to, Tz::America__New_York);
+        
+        assert!(parse_tz_conversion("createdAt:UTC").is_err());
+        assert!(parse_tz_conversion("createdAt:UTC->Mars/Olympus").is_err());
+    }
+    
+    #[test]
+    fn test_convert_timezone_keeps_layout() {
+        let conversion = parse_tz_conversion("at:UTC->America/New_York").unwrap();
+        
+        assert_eq!(convert_timezone("2024-01-15 14:30:00", &conversion).unwrap(), "2024-01-15 09:30:00");
+        assert_eq!(convert_timezone("2024-07-15T14:30:00.250", &conversion).unwrap(), "2024-07-15T10:30:00.250");
+        assert_eq!(convert_timezone("2024-01-15T14:30:00Z", &conversion).unwrap(), "2024-01-15T09:30:00-05:00");
+        assert_eq!(convert_timezone("2024-01-15 16:30:00+02", &conversion).unwrap(), "2024-01-15 09:30:00-05:00");
+        assert_eq!(convert_timezone("2024-01-15", &conversion), None);
+    }
+    
+    #[test]
+    fn test_apply_tz_conversions() {
+        let headers = vec!["id".to_string(), "at".to_string()];
+        let mut rows = vec![
+            vec!["1".to_string(), "2024-03-10 02:30:00".to_string()],
+            vec!["2".to_string(), "NULL".to_string()],
+            vec!["3".to_string(), "2024-03-10 12:00:00".to_string()],
+        ];
+        // 02:30 does not exist in New York on the day DST starts
+        let conversions = vec![parse_tz_conversion("at:America/New_York->Europe/Berlin").unwrap()];
+        let warnings = CollectedWarnings::default();
+        
+        apply_tz_conversions(&headers, &mut rows, &conversions, &warnings);
+        
+        assert_eq!(rows[0][1], "2024-03-10 02:30:00");
+        assert_eq!(rows[1][1], "NULL");
+        assert_eq!(rows[2][1], "2024-03-10 17:00:00");
+        assert_eq!(warnings.into_inner().len(), 1);
+    }
+}
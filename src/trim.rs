@@ -0,0 +1,105 @@
+//! Whitespace in cells (`--trim-cells`).
+//!
+//! Values keep their whitespace as the dump wrote it unless asked otherwise:
+//! `edges` removes it from the start and end of every value and `collapse` also
+//! turns each run of whitespace inside a value, line breaks included, into a
+//! single space. The mode applies to every column, after strings are unescaped;
+//! a table's `trim_cells` in the `--config` file sets it per column:
+//!
+//! ```toml
+//! [tables.posts]
+//! trim_cells = { body = "none", title = "collapse" }
+//! ```
+
+use anyhow::Result;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+use crate::args::flag_value;
+use crate::types::TrimCells;
+
+/// Parse --trim-cells option from command line
+pub fn parse_trim_cells(args: &[String]) -> Result<TrimCells> {
+    flag_value(args, "--trim-cells")?.map_or(Ok(TrimCells::None), parse_trim_mode)
+}
+
+/// Parse a trim mode: `none`, `edges` or `collapse`
+pub fn parse_trim_mode(mode: &str) -> Result<TrimCells> {
+    match mode {
+        "none" => Ok(TrimCells::None),
+        "edges" => Ok(TrimCells::Edges),
+        "collapse" => Ok(TrimCells::Collapse),
+        other => anyhow::bail!("Error: Invalid --trim-cells '{}'. Use one of: none, edges, collapse", other),
+    }
+}
+
+/// Trim a value according to the mode
+pub fn trim_cell(value: &str, mode: TrimCells) -> Cow<'_, str> {
+    match mode {
+        TrimCells::None => Cow::Borrowed(value),
+        TrimCells::Edges => Cow::Borrowed(value.trim()),
+        TrimCells::Collapse => {
+            let trimmed = value.trim();
+            // Only values with whitespace other than single spaces are copied
+            if trimmed.split(' ').all(|word| !word.is_empty() && !word.contains(char::is_whitespace)) {
+                return Cow::Borrowed(trimmed);
+            }
+            let mut words = trimmed.split_whitespace();
+            let mut collapsed = String::with_capacity(trimmed.len());
+            collapsed.push_str(words.next().unwrap_or_default());
+            for word in words {
+                collapsed.push(' ');
+                collapsed.push_str(word);
+            }
+            Cow::Owned(collapsed)
+        }
+    }
+}
+
+/// Trim every cell with the mode of its column: the column's entry in
+/// `columns` if it has one, otherwise `mode`
+pub fn apply_trim_cells(headers: &[String], rows: &mut [Vec<String>], mode: TrimCells, columns: &BTreeMap<String, TrimCells>) {
+    let modes: Vec<TrimCells> = headers.iter()
+        .map(|header| columns.get(header).copied().unwrap_or(mode))
+        .collect();
+    if modes.iter().all(|&mode| mode == TrimCells::None) {
+        return;
+    }
+    
+    for row in rows.iter_mut() {
+        for (value, &mode) in row.iter_mut().zip(&modes) {
+            let trimmed = trim_cell(value, mode);
+            if trimmed != value.as_str() {
+                *value = trimmed.into_owned();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_trim_cell() {
+        let value = "  a \t b\n\nc  ";
+        assert_eq!(trim_cell(value, TrimCells::None), value);
+        assert_eq!(trim_cell(value, TrimCells::Edges), "a \t b\n\nc");
+        assert_eq!(trim_cell(value, TrimCells::Collapse), "a b c");
+        assert!(matches!(trim_cell(" a b ", TrimCells::Collapse), Cow::Borrowed("a b")));
+        assert_eq!(trim_cell("a  b", TrimCells::Collapse), "a b");
+        assert_eq!(trim_cell("   ", TrimCells::Collapse), "");
+        assert!(parse_trim_mode("all").is_err());
+    }
+    
+    #[test]
+    fn test_apply_trim_cells_with_column_overrides() {
+        let headers = vec!["id".to_string(), "title".to_string(), "body".to_string()];
+        let mut rows = vec![vec![" 1 ".to_string(), " Hello   world ".to_string(), "  keep\n  this ".to_string()]];
+        let columns = BTreeMap::from([("title".to_string(), TrimCells::Collapse), ("body".to_string(), TrimCells::None)]);
+        
+        apply_trim_cells(&headers, &mut rows, TrimCells::Edges, &columns);
+        
+        assert_eq!(rows, vec![vec!["1", "Hello world", "  keep\n  this "]]);
+    }
+}
@@ -0,0 +1,260 @@
+//! Interactive table picker (feature `tui`).
+//!
+//! Lists the tables of a dump with estimated row counts and lets the user pick
+//! which ones to export and set a date filter before the conversion runs.
+
+use anyhow::{Context, Result};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use regex::Regex;
+
+use crate::date_filter::parse_date_filter;
+use crate::statements::{split_statements, StatementKind};
+use crate::types::{DateFilter, Table};
+use crate::verify::parse_row_count_hints;
+
+/// A table offered in the picker
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableChoice {
+    pub name: String,
+    pub columns: usize,
+    /// Row count from the dump's hints, or counted from its INSERT statements
+    pub estimated_rows: usize,
+    pub selected: bool,
+}
+
+/// What the user chose to export
+#[derive(Debug, Clone)]
+pub struct Selection {
+    pub tables: Vec<String>,
+    pub date_filter: Option<DateFilter>,
+}
+
+/// Build the picker entries for the tables of a dump; every table starts selected
+pub fn table_choices(tables: &[Table], content: &str) -> Vec<TableChoice> {
+    let hints = parse_row_count_hints(content);
+    tables.iter()
+        .map(|table| TableChoice {
+            name: table.name.clone(),
+            columns: table.columns.len(),
+            estimated_rows: hints.get(&table.name).copied()
+                .unwrap_or_else(|| estimate_rows(content, &table.name)),
+            selected: true,
+        })
+        .collect()
+}
+
+/// Rough row count: tuples in the table's INSERT statements, without parsing values
+fn estimate_rows(content: &str, table: &str) -> usize {
+    let insert_regex = Regex::new(&format!(
+        r#"(?i)^INSERT\s+INTO\s+[`"]?{}[`"]?[^;]*?VALUES?"#,
+        regex::escape(table)
+    )).unwrap();
+    let tuple_separator = Regex::new(r"\)\s*,\s*\(").unwrap();
+    
+    split_statements(content)
+        .filter(|statement| statement.kind() == StatementKind::Insert)
+        .filter_map(|statement| insert_regex.find(statement.text).map(|found| &statement.text[found.end()..]))
+        .map(|values| 1 + tuple_separator.find_iter(values).count())
+        .sum()
+}
+
+/// Picker state, separate from the terminal so key handling can be tested
+struct Picker {
+    choices: Vec<TableChoice>,
+    list: ListState,
+    /// Date filter being typed, as `<column> <start_date> [end_date]`
+    filter_input: String,
+    editing_filter: bool,
+    error: Option<String>,
+}
+
+/// Result of handling a key press
+#[derive(Debug, PartialEq)]
+enum Action {
+    Continue,
+    Confirm,
+    Cancel,
+}
+
+impl Picker {
+    fn new(choices: Vec<TableChoice>, date_filter: Option<&DateFilter>) -> Self {
+        let filter_input = date_filter
+            .map(|f| format!("{} {} {}", f.column_name, f.start_date, f.end_date))
+            .unwrap_or_default();
+        Picker {
+            choices,
+            list: ListState::default().with_selected(Some(0)),
+            filter_input,
+            editing_filter: false,
+            error: None,
+        }
+    }
+    
+    fn handle_key(&mut self, key: KeyCode) -> Action {
+        self.error = None;
+        if self.editing_filter {
+            match key {
+                KeyCode::Enter | KeyCode::Esc => self.editing_filter = false,
+                KeyCode::Backspace => {
+                    self.filter_input.pop();
+                }
+                KeyCode::Char(c) => self.filter_input.push(c),
+                _ => {}
+            }
+            return Action::Continue;
+        }
+        
+        match key {
+            KeyCode::Up | KeyCode::Char('k') => self.list.select_previous(),
+            KeyCode::Down | KeyCode::Char('j') => self.list.select_next(),
+            KeyCode::Char(' ') => {
+                if let Some(choice) = self.list.selected().and_then(|i| self.choices.get_mut(i)) {
+                    choice.selected = !choice.selected;
+                }
+            }
+            KeyCode::Char('a') => {
+                let select = !self.choices.iter().all(|c| c.selected);
+                self.choices.iter_mut().for_each(|c| c.selected = select);
+            }
+            KeyCode::Char('f') => self.editing_filter = true,
+            KeyCode::Enter => match self.selection() {
+                Ok(selection) if selection.tables.is_empty() => self.error = Some("Select at least one table".to_string()),
+                Ok(_) => return Action::Confirm,
+                Err(e) => self.error = Some(format!("{:#}", e)),
+            },
+            KeyCode::Esc | KeyCode::Char('q') => return Action::Cancel,
+            _ => {}
+        }
+        Action::Continue
+    }
+    
+    fn selection(&self) -> Result<Selection> {
+        let mut args = vec!["--date-filter".to_string()];
+        args.extend(self.filter_input.split_whitespace().map(str::to_string));
+        let date_filter = if args.len() > 1 { parse_date_filter(&args)? } else { None };
+        
+        Ok(Selection {
+            tables: self.choices.iter().filter(|c| c.selected).map(|c| c.name.clone()).collect(),
+            date_filter,
+        })
+    }
+    
+    fn draw(&mut self, frame: &mut Frame) {
+        let [list_area, filter_area, help_area] = Layout::vertical([
+            Constraint::Min(3),
+            Constraint::Length(3),
+            Constraint::Length(1),
+        ]).areas(frame.area());
+        
+        let items: Vec<ListItem> = self.choices.iter()
+            .map(|c| ListItem::new(format!(
+                "[{}] {:<32} {:>4} columns  ~{} rows",
+                if c.selected { "x" } else { " " },
+                c.name,
+                c.columns,
+                c.estimated_rows
+            )))
+            .collect();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(" Tables to export "))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, list_area, &mut self.list);
+        
+        let filter_title = if self.editing_filter {
+            " Date filter: <column> <start_date> [end_date] (Enter to finish) "
+        } else {
+            " Date filter "
+        };
+        let filter = Paragraph::new(self.filter_input.as_str())
+            .block(Block::default().borders(Borders::ALL).title(filter_title));
+        frame.render_widget(filter, filter_area);
+        
+        let help = self.error.clone().unwrap_or_else(|| {
+            "↑/↓ move  space toggle  a all/none  f date filter  enter export  q quit".to_string()
+        });
+        frame.render_widget(Paragraph::new(help), help_area);
+    }
+    
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<Action> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press
+            {
+                match self.handle_key(key.code) {
+                    Action::Continue => {}
+                    action => return Ok(action),
+                }
+            }
+        }
+    }
+}
+
+/// Show the picker in the terminal. Returns `None` if the user quit without exporting.
+pub fn pick_tables(choices: Vec<TableChoice>, date_filter: Option<&DateFilter>) -> Result<Option<Selection>> {
+    if choices.is_empty() {
+        anyhow::bail!("Error: No tables found to pick from");
+    }
+    
+    let mut picker = Picker::new(choices, date_filter);
+    let mut terminal = ratatui::try_init().context("Failed to start interactive mode")?;
+    let action = picker.run(&mut terminal);
+    ratatui::restore();
+    
+    match action? {
+        Action::Confirm => picker.selection().map(Some),
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn choices() -> Vec<TableChoice> {
+        ["users", "orders"].iter()
+            .map(|name| TableChoice { name: name.to_string(), columns: 2, estimated_rows: 0, selected: true })
+            .collect()
+    }
+    
+    #[test]
+    fn test_estimate_rows() {
+        let content = "INSERT INTO `users` VALUES (1,'a'),(2,'b');\nINSERT INTO users VALUES (3, 'c), (');\nINSERT INTO orders VALUES (1);\nINSERT INTO log VALUES ('INSERT INTO users VALUES (9),(10);');\n";
+        assert_eq!(estimate_rows(content, "users"), 4);
+        assert_eq!(estimate_rows(content, "orders"), 1);
+        
+        let tables = vec![Table { name: "users".to_string(), ..Default::default() }];
+        let hinted = format!("-- Dumping data for table `users` (120 rows)\n{}", content);
+        assert_eq!(table_choices(&tables, &hinted)[0].estimated_rows, 120);
+    }
+    
+    #[test]
+    fn test_picker_toggles_tables_and_sets_filter() {
+        let mut picker = Picker::new(choices(), None);
+        assert_eq!(picker.handle_key(KeyCode::Down), Action::Continue);
+        picker.handle_key(KeyCode::Char(' '));
+        picker.handle_key(KeyCode::Char('f'));
+        for c in "createdAt 2024-01-01 2024-06-30".chars() {
+            picker.handle_key(KeyCode::Char(c));
+        }
+        picker.handle_key(KeyCode::Enter);
+        assert_eq!(picker.handle_key(KeyCode::Enter), Action::Confirm);
+        
+        let selection = picker.selection().unwrap();
+        assert_eq!(selection.tables, vec!["users"]);
+        assert_eq!(selection.date_filter.unwrap().column_name, "createdAt");
+    }
+    
+    #[test]
+    fn test_picker_requires_a_table() {
+        let mut picker = Picker::new(choices(), None);
+        picker.handle_key(KeyCode::Char('a'));
+        assert_eq!(picker.handle_key(KeyCode::Enter), Action::Continue);
+        assert!(picker.error.is_some());
+        assert_eq!(picker.handle_key(KeyCode::Char('q')), Action::Cancel);
+    }
+}
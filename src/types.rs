@@ -1,10 +1,116 @@
 use chrono::NaiveDate;
+use chrono_tz::Tz;
+use encoding_rs::Encoding;
+use serde::Serialize;
+use std::collections::HashSet;
 
-/// Represents a database table with its name and column names
-#[derive(Debug, Clone)]
+/// Represents a database table with its name, column names and key constraints
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct Table {
     pub name: String,
+    /// Schema (or database) qualifier from `CREATE TABLE schema.name`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schema: Option<String>,
+    /// Database the table was created in, after `USE db` or `\connect db`. In
+    /// dumps of several databases the name is prefixed with it, e.g. `shop.orders`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub database: Option<String>,
+    pub columns: Vec<String>,
+    /// Declared SQL type of each column, e.g. `VARCHAR(255)` (empty if none was given)
+    pub column_types: Vec<String>,
+    /// Constant DEFAULT of each column, used for `DEFAULT VALUES` rows (empty if none)
+    #[serde(skip)]
+    pub column_defaults: Vec<String>,
+    /// Created from INSERT statements because the dump has no CREATE TABLE for it
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub synthesized: bool,
+    /// Has a CREATE TABLE whose columns could not be parsed; the columns are
+    /// synthesized from its INSERTs, and are empty if it has none
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub headerless: bool,
+    /// Generated columns (`GENERATED ALWAYS AS (...)`, MySQL `AS (...) STORED`),
+    /// whose values dumps leave out of INSERT statements
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub generated_columns: Vec<String>,
+    /// Columns declared `NOT NULL`; primary key columns are not null without it
+    #[serde(skip)]
+    pub not_null_columns: Vec<String>,
+    /// Columns whose type was inferred from the data (`--infer-types`)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub inferred_columns: Vec<String>,
+    /// Primary key columns (more than one for composite keys)
+    pub primary_key: Vec<String>,
+    /// Column sets declared UNIQUE, either inline or as table constraints
+    pub unique_constraints: Vec<Vec<String>>,
+    /// Indexes declared inside CREATE TABLE or via CREATE INDEX
+    pub indexes: Vec<Index>,
+    pub foreign_keys: Vec<ForeignKey>,
+}
+
+impl Table {
+    /// The columns with everything declared about each of them
+    pub fn column_definitions(&self) -> Vec<Column> {
+        self.columns.iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let primary_key = self.primary_key.contains(name);
+                Column {
+                    name: name.clone(),
+                    data_type: self.column_types.get(i).cloned().unwrap_or_default(),
+                    nullable: !primary_key && !self.not_null_columns.contains(name),
+                    default: self.column_defaults.get(i).filter(|default| !default.is_empty()).cloned(),
+                    primary_key,
+                    unique: self.unique_constraints.iter().any(|columns| columns == std::slice::from_ref(name)),
+                    generated: self.generated_columns.contains(name),
+                }
+            })
+            .collect()
+    }
+}
+
+/// A column of a table as declared in its CREATE TABLE
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Column {
+    pub name: String,
+    /// Declared SQL type, e.g. `VARCHAR(255)` (empty if none was given)
+    pub data_type: String,
+    pub nullable: bool,
+    /// Constant DEFAULT; expressions such as `CURRENT_TIMESTAMP` are left out
+    pub default: Option<String>,
+    /// Part of the primary key (alone or with other columns)
+    pub primary_key: bool,
+    /// Declared UNIQUE on its own
+    pub unique: bool,
+    /// Computed from other columns and left out of INSERT statements
+    pub generated: bool,
+}
+
+/// A single INSERT statement, parsed on its own (`parse_insert_statement`)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InsertStatement {
+    pub table: String,
+    /// Schema (or database) qualifier from `INSERT INTO schema.name`
+    pub schema: Option<String>,
+    /// The statement's column list, empty if it has none
     pub columns: Vec<String>,
+    /// Values of every row; `DEFAULT VALUES` is one row without values
+    pub rows: Vec<Vec<String>>,
+}
+
+/// Represents an index on one or more table columns
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Index {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub unique: bool,
+}
+
+/// Represents a FOREIGN KEY relationship to another table
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ForeignKey {
+    pub columns: Vec<String>,
+    pub references_table: String,
+    pub references_columns: Vec<String>,
 }
 
 /// Represents a date filter configuration
@@ -13,5 +119,300 @@ pub struct DateFilter {
     pub column_name: String,
     pub start_date: NaiveDate,
     pub end_date: NaiveDate,
+    /// Treat integer timestamps outside 1970-2100 (such as 0 or negative
+    /// values) as invalid instead of filtering them by the date they give
+    pub strict_epoch: bool,
 }
 
+/// Naming convention applied to CSV header names
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HeaderCase {
+    /// Keep column names exactly as declared
+    #[default]
+    Preserve,
+    /// `callSessionId` -> `call_session_id`
+    Snake,
+    /// `call_session_id` -> `callSessionId`
+    Camel,
+    /// `callSessionId` -> `callsessionid`
+    Lower,
+}
+
+/// How CSV file names are derived from table names
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FileNaming {
+    /// `Users` -> `users.csv`
+    #[default]
+    Lower,
+    /// `Users` -> `Users.csv`
+    Preserve,
+    /// `public.Users` -> `public.users.csv`
+    SchemaPrefix,
+}
+
+/// Options controlling how CSV files are written
+#[derive(Debug, Clone, Default)]
+pub struct CsvOptions {
+    pub header_case: HeaderCase,
+    pub file_naming: FileNaming,
+    /// Neutralize cells that spreadsheet applications would evaluate as formulas
+    pub safe_excel: bool,
+    /// Write buffer of output files in bytes (default: 8 KiB)
+    pub buffer_size: Option<usize>,
+    /// Sync every output file to disk before reporting it as written
+    pub fsync: bool,
+    /// Delimiter, number format and line endings
+    pub locale: CsvLocale,
+    /// age recipients output files are encrypted to; empty for plain files
+    pub encrypt_to: Vec<String>,
+}
+
+/// Conventions of the spreadsheet application a CSV is written for (--locale)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CsvLocale {
+    /// Field separator
+    pub delimiter: u8,
+    /// Write decimal numbers in numeric columns with a comma, e.g. `3,14`
+    pub decimal_comma: bool,
+    /// End lines with CRLF instead of LF
+    pub crlf: bool,
+    /// Start files with a UTF-8 byte order mark, which Excel needs to read them as UTF-8
+    pub bom: bool,
+}
+
+impl Default for CsvLocale {
+    fn default() -> Self {
+        CsvLocale { delimiter: b',', decimal_comma: false, crlf: false, bom: false }
+    }
+}
+
+/// File format tables are written in
+#[derive(Debug, Clone, Default)]
+pub enum OutputFormat {
+    #[default]
+    Csv,
+    /// Every row rendered through a user-supplied minijinja template
+    Template {
+        source: String,
+        /// Extension of the generated files, e.g. `xml` for `row.xml.tmpl`
+        extension: String,
+    },
+    /// Avro object container files with a schema derived from the column types
+    #[cfg(feature = "avro")]
+    Avro,
+    /// One DuckDB database file holding every table
+    #[cfg(feature = "duckdb")]
+    DuckDb,
+    /// JSON Lines files written in place, readable while the run goes on
+    Jsonl {
+        /// Rows between flushes (`--flush-every`)
+        flush_every: Option<usize>,
+        /// Add to existing files (`--append`)
+        append: bool,
+    },
+}
+
+/// Options controlling how the input SQL file is read
+#[derive(Debug, Clone, Default)]
+pub struct InputOptions {
+    /// Encoding to decode the file with when it has no byte order mark (defaults to UTF-8)
+    pub encoding: Option<&'static Encoding>,
+    /// Replace invalid byte sequences with U+FFFD instead of failing
+    pub lossy: bool,
+}
+
+/// How INSERT values that are SQL expressions, like `CAST('5' AS INT)` or `1+2`, are exported
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExpressionPolicy {
+    /// Keep the expression as written, in a single cell
+    #[default]
+    Verbatim,
+    /// Compute simple expressions and keep the rest as written
+    Evaluate,
+}
+
+/// How Postgres arrays, ranges, geometric types and PostGIS geometries are written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PgTypeFormat {
+    /// Postgres text form as found in the dump, e.g. `{1,2,3}`
+    #[default]
+    Raw,
+    /// JSON arrays and objects, with PostGIS geometries as GeoJSON
+    Json,
+}
+
+/// Options controlling how INSERT values are turned into cells
+#[derive(Debug, Clone, Default)]
+pub struct ValueOptions {
+    pub expressions: ExpressionPolicy,
+    pub pg_types: PgTypeFormat,
+    pub newlines: NewlinePolicy,
+}
+
+/// What happens to line breaks inside values (`--newline-in-cell`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NewlinePolicy {
+    /// Keep them, in a quoted CSV field
+    #[default]
+    Keep,
+    /// Replace each line break with a space
+    Space,
+    /// Write them as `\n` and `\r`
+    Escape,
+}
+
+/// How whitespace in values is trimmed (`--trim-cells`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TrimCells {
+    /// Keep values as they are
+    #[default]
+    None,
+    /// Remove whitespace at the start and end
+    Edges,
+    /// Remove it at the edges and turn each run of whitespace inside into one space
+    Collapse,
+}
+
+/// What to do with INSERT rows whose value count differs from the table's column count
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MismatchPolicy {
+    /// Pass rows on unchanged; only count them
+    #[default]
+    Keep,
+    /// Fill missing trailing values with empty cells and drop extra values
+    Pad,
+    /// Drop extra values and skip rows that are missing values
+    Truncate,
+    /// Skip every mismatched row
+    Skip,
+    /// Fail the table on the first mismatched row
+    Error,
+}
+
+/// Rewrite of a datetime column from one timezone to another
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TzConversion {
+    pub column: String,
+    /// Timezone of values written without an offset
+    pub from: Tz,
+    pub to: Tz,
+}
+
+/// A row paired with the 1-based line of the dump it starts on
+pub type NumberedRow = (usize, Vec<String>);
+
+/// Rows of a table to keep, by the values of one of its columns
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyFilter {
+    pub table: String,
+    pub column: String,
+    pub keys: HashSet<String>,
+}
+
+/// Rewrite of a date column in a single strftime format
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DateReformat {
+    pub column: String,
+    pub format: String,
+}
+
+/// Upper bound on the size of a single CSV cell
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldSizeLimit {
+    pub max_bytes: usize,
+    pub policy: OversizedFieldPolicy,
+}
+
+/// What to do with a cell larger than the field size limit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OversizedFieldPolicy {
+    /// Cut the value and end it with a truncation marker
+    #[default]
+    Truncate,
+    /// Drop the whole row
+    SkipRow,
+    /// Fail the table on the first oversized cell
+    Error,
+}
+
+/// A column computed from other values and appended to a table's output
+#[derive(Debug, Clone)]
+pub struct DerivedColumn {
+    /// Table the column is added to, or `*` for every table
+    pub table: String,
+    pub name: String,
+    pub expression: Expression,
+}
+
+/// Expression used to compute the value of a derived column
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    /// Timestamp of the export run
+    Now,
+    Literal(String),
+    Column(String),
+    Concat(Vec<Expression>),
+    /// 1-based character substring, like SQL SUBSTR
+    Substring {
+        value: Box<Expression>,
+        start: usize,
+        length: Option<usize>,
+    },
+    Year(Box<Expression>),
+    Month(Box<Expression>),
+    Day(Box<Expression>),
+    Upper(Box<Expression>),
+    Lower(Box<Expression>),
+}
+
+/// Configuration for denormalizing two tables into one CSV with an equi-join
+#[derive(Debug, Clone)]
+pub struct JoinSpec {
+    pub left_table: String,
+    pub left_column: String,
+    pub right_table: String,
+    pub right_column: String,
+    /// Selected `(table, column)` pairs, where column `*` selects every column
+    pub select: Vec<(String, String)>,
+    /// Keep left rows without a match (LEFT JOIN) instead of dropping them
+    pub keep_unmatched: bool,
+}
+
+/// Aggregate function computed per group by the `aggregate` subcommand
+#[derive(Debug, Clone, PartialEq)]
+pub enum Aggregate {
+    /// Number of rows in the group
+    Count,
+    /// Number of non-NULL values in a column
+    CountValues(String),
+    Sum(String),
+    Avg(String),
+    Min(String),
+    Max(String),
+}
+
+impl Aggregate {
+    /// Column the aggregate reads, if any
+    pub fn column(&self) -> Option<&str> {
+        match self {
+            Aggregate::Count => None,
+            Aggregate::CountValues(column)
+            | Aggregate::Sum(column)
+            | Aggregate::Avg(column)
+            | Aggregate::Min(column)
+            | Aggregate::Max(column) => Some(column),
+        }
+    }
+    
+    /// Header used for the aggregate in the output CSV, e.g. `sum_amount`
+    pub fn header(&self) -> String {
+        match self {
+            Aggregate::Count => "count".to_string(),
+            Aggregate::CountValues(column) => format!("count_{}", column),
+            Aggregate::Sum(column) => format!("sum_{}", column),
+            Aggregate::Avg(column) => format!("avg_{}", column),
+            Aggregate::Min(column) => format!("min_{}", column),
+            Aggregate::Max(column) => format!("max_{}", column),
+        }
+    }
+}
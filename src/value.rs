@@ -0,0 +1,207 @@
+//! Typed SQL values.
+//!
+//! The parser reads every value of an INSERT into a `SqlValue`, so an unquoted
+//! NULL is told apart from the string `'NULL'`, and numbers from quoted text.
+//! Rows become CSV text with `ParsedValue::into_csv_string`, which gives back
+//! every value as the dump wrote it: the typed value only says what the text
+//! is, so `TRUE`, `null` and `X'0a'` are written unchanged. Numbers are only
+//! typed when their text can be rebuilt exactly, so a DECIMAL such as `1.50`
+//! stays text instead of losing a digit.
+
+use std::borrow::Cow;
+use std::fmt;
+
+/// A single value of an INSERT row
+#[derive(Debug, Clone, PartialEq)]
+pub enum SqlValue {
+    /// Unquoted `NULL`
+    Null,
+    /// Unquoted whole number, written without leading zeros or `+`
+    Integer(i64),
+    /// Unquoted decimal number that reads back as written, e.g. `1.5` or `2.0`
+    Float(f64),
+    /// Strings, and numbers and expressions kept as written
+    Text(String),
+    /// Hex literal, `0x89AB` or `X'89AB'`
+    Blob(Vec<u8>),
+    /// Unquoted `TRUE` or `FALSE`
+    Bool(bool),
+}
+
+impl SqlValue {
+    /// Read an unquoted literal: NULL, a boolean, a number or a hex blob.
+    /// `None` for quoted strings, expressions and anything else.
+    pub fn from_literal(literal: &str) -> Option<SqlValue> {
+        let literal = literal.trim();
+        if literal.eq_ignore_ascii_case("NULL") {
+            return Some(SqlValue::Null);
+        }
+        if literal.eq_ignore_ascii_case("TRUE") || literal.eq_ignore_ascii_case("FALSE") {
+            return Some(SqlValue::Bool(literal.eq_ignore_ascii_case("TRUE")));
+        }
+        if let Some(bytes) = hex_literal(literal) {
+            return Some(SqlValue::Blob(bytes));
+        }
+        if !literal.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '.') {
+            return None;
+        }
+        let digits = literal.strip_prefix('-').unwrap_or(literal);
+        if !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) {
+            // Leading zeros and -0 would not be written back as they are
+            let canonical = if digits.starts_with('0') { literal == "0" } else { true };
+            return literal.parse::<i64>().ok().filter(|_| canonical).map(SqlValue::Integer);
+        }
+        literal.parse::<f64>().ok()
+            .filter(|float| float.is_finite() && format_float(*float) == literal)
+            .map(SqlValue::Float)
+    }
+    
+    pub fn is_null(&self) -> bool {
+        matches!(self, SqlValue::Null)
+    }
+    
+    /// The value's CSV text in its canonical spelling: NULL as `NULL`, blobs
+    /// as `0x` and uppercase hex, booleans as `true` and `false`
+    pub fn into_csv_string(self) -> String {
+        match self {
+            SqlValue::Text(text) => text,
+            other => other.to_string(),
+        }
+    }
+}
+
+/// A value of an INSERT row as read from a dump. It keeps the text its CSV
+/// cell gets, borrowed from the dump unless unescaping changed it, and is
+/// only typed when asked for its `SqlValue`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedValue<'a> {
+    text: Cow<'a, str>,
+    /// Whether `text` is an unquoted literal that `SqlValue::from_literal` reads
+    literal: bool,
+}
+
+impl<'a> ParsedValue<'a> {
+    /// An unquoted literal (NULL, a boolean, a number or a hex blob), or
+    /// `None` for anything else
+    pub fn from_literal(literal: &'a str) -> Option<ParsedValue<'a>> {
+        let literal = literal.trim();
+        SqlValue::from_literal(literal).map(|_| ParsedValue { text: Cow::Borrowed(literal), literal: true })
+    }
+    
+    /// A computed value, typed like a literal when its text is one
+    pub fn computed(text: String) -> ParsedValue<'a> {
+        let literal = SqlValue::from_literal(&text).is_some();
+        ParsedValue { text: Cow::Owned(text), literal }
+    }
+    
+    pub fn text(text: Cow<'a, str>) -> ParsedValue<'a> {
+        ParsedValue { text, literal: false }
+    }
+    
+    pub fn value(&self) -> SqlValue {
+        match self.literal {
+            true => SqlValue::from_literal(&self.text).unwrap_or_else(|| SqlValue::Text(self.text.to_string())),
+            false => SqlValue::Text(self.text.to_string()),
+        }
+    }
+    
+    /// The value's CSV text, as the dump wrote it
+    pub fn into_text(self) -> Cow<'a, str> {
+        self.text
+    }
+    
+    pub fn into_csv_string(self) -> String {
+        self.text.into_owned()
+    }
+}
+
+impl fmt::Display for SqlValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SqlValue::Null => write!(f, "NULL"),
+            SqlValue::Integer(integer) => write!(f, "{}", integer),
+            SqlValue::Float(float) => write!(f, "{}", format_float(*float)),
+            SqlValue::Text(text) => write!(f, "{}", text),
+            SqlValue::Blob(bytes) => {
+                write!(f, "0x")?;
+                bytes.iter().try_for_each(|byte| write!(f, "{:02X}", byte))
+            }
+            SqlValue::Bool(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+impl From<SqlValue> for String {
+    fn from(value: SqlValue) -> String {
+        value.into_csv_string()
+    }
+}
+
+/// Whole floats keep a `.0`, so `2.0` is written back as `2.0` and not `2`
+fn format_float(float: f64) -> String {
+    if float.fract() == 0.0 && float.abs() < 1e16 {
+        format!("{:?}", float)
+    } else {
+        float.to_string()
+    }
+}
+
+/// Bytes of a MySQL hex literal, `0x89AB` or `X'89AB'`
+fn hex_literal(literal: &str) -> Option<Vec<u8>> {
+    let digits = literal.strip_prefix("0x")
+        .or_else(|| literal.strip_prefix(['X', 'x'])?.strip_prefix('\'')?.strip_suffix('\''))?;
+    if digits.is_empty() || digits.len() % 2 != 0 {
+        return None;
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(digits.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_from_literal() {
+        assert_eq!(SqlValue::from_literal("NULL"), Some(SqlValue::Null));
+        assert_eq!(SqlValue::from_literal("true"), Some(SqlValue::Bool(true)));
+        assert_eq!(SqlValue::from_literal("-42"), Some(SqlValue::Integer(-42)));
+        assert_eq!(SqlValue::from_literal("2.5"), Some(SqlValue::Float(2.5)));
+        assert_eq!(SqlValue::from_literal("0x89504E47"), Some(SqlValue::Blob(vec![0x89, b'P', b'N', b'G'])));
+        assert_eq!(SqlValue::from_literal("X'0a'"), Some(SqlValue::Blob(vec![0x0A])));
+        // Numbers whose text would change are not typed
+        assert_eq!(SqlValue::from_literal("007"), None);
+        assert_eq!(SqlValue::from_literal("1.50"), None);
+        assert_eq!(SqlValue::from_literal("99999999999999999999"), None);
+        assert_eq!(SqlValue::from_literal("'NULL'"), None);
+        assert_eq!(SqlValue::from_literal("NOW()"), None);
+    }
+    
+    #[test]
+    fn test_into_csv_string_gives_back_literals() {
+        for literal in ["NULL", "0", "-42", "2.5", "2.0", "0.001", "1e-7", "0x89504E47", "true"] {
+            let value = SqlValue::from_literal(literal).unwrap_or_else(|| SqlValue::Text(literal.to_string()));
+            assert_eq!(value.into_csv_string(), literal);
+        }
+        assert_eq!(SqlValue::Text("a,b".to_string()).into_csv_string(), "a,b");
+    }
+    
+    #[test]
+    fn test_parsed_values_keep_their_spelling() {
+        for (literal, value) in [
+            ("TRUE", SqlValue::Bool(true)),
+            ("null", SqlValue::Null),
+            ("0xab", SqlValue::Blob(vec![0xAB])),
+            ("X'0a'", SqlValue::Blob(vec![0x0A])),
+        ] {
+            let parsed = ParsedValue::from_literal(literal).unwrap();
+            assert_eq!(parsed.value(), value);
+            assert!(matches!(parsed.into_text(), Cow::Borrowed(text) if text == literal));
+        }
+        assert_eq!(ParsedValue::from_literal("'x'"), None);
+        assert_eq!(ParsedValue::computed("2".to_string()).value(), SqlValue::Integer(2));
+        assert_eq!(ParsedValue::text(Cow::Borrowed("2")).value(), SqlValue::Text("2".to_string()));
+    }
+}
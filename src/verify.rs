@@ -0,0 +1,111 @@
+use regex::Regex;
+use std::collections::HashMap;
+
+use crate::statements::{dump_databases, split_statements_in_databases};
+
+/// Collect row-count hints left by dump tools, such as
+/// `-- Dumping data for table 'users' (1234 rows)`. Only comments between
+/// statements count, so the same text inside a string value is not a hint.
+/// In dumps of several databases, tables are named `db.table` as they are
+/// by the parser.
+pub fn parse_row_count_hints(content: &str) -> HashMap<String, usize> {
+    let hint_regex = Regex::new(
+        r#"(?im)^--\s*Dumping data for table\s+[`'"]?([\w.]+?)[`'"]?\s*\(\s*(\d+)\s+rows?\s*\)"#
+    ).unwrap();
+    let statements: Vec<(usize, usize, Option<&str>)> = split_statements_in_databases(content)
+        .map(|(database, statement)| (statement.offset, statement.offset + statement.text.len(), database))
+        .collect();
+    let namespaced = dump_databases(content).len() > 1;
+    
+    let mut hints = HashMap::new();
+    for captures in hint_regex.captures_iter(content) {
+        let position = captures.get(0).unwrap().start();
+        let previous = statements.partition_point(|&(start, _, _)| start <= position).checked_sub(1).map(|i| statements[i]);
+        if previous.is_some_and(|(_, end, _)| position < end) {
+            continue;
+        }
+        let table = match previous.and_then(|(_, _, database)| database) {
+            Some(database) if namespaced => format!("{}.{}", database, &captures[1]),
+            _ => captures[1].to_string(),
+        };
+        if let Ok(count) = captures[2].parse::<usize>() {
+            // Tables dumped in several chunks report each chunk separately
+            *hints.entry(table).or_insert(0) += count;
+        }
+    }
+    
+    hints
+}
+
+/// A table whose extracted row count differs from the dump's own hint
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowCountDiscrepancy {
+    pub table: String,
+    pub expected: usize,
+    /// Rows extracted, or `None` if the table was not found or failed to extract
+    pub extracted: Option<usize>,
+}
+
+/// Compare extracted row counts with the dump's hints
+pub fn verify_row_counts(
+    hints: &HashMap<String, usize>,
+    extracted: &HashMap<String, usize>,
+) -> Vec<RowCountDiscrepancy> {
+    let mut discrepancies: Vec<RowCountDiscrepancy> = hints.iter()
+        .filter_map(|(table, &expected)| {
+            let extracted = extracted.get(table).copied();
+            (extracted != Some(expected)).then(|| RowCountDiscrepancy {
+                table: table.clone(),
+                expected,
+                extracted,
+            })
+        })
+        .collect();
+    discrepancies.sort_by(|a, b| a.table.cmp(&b.table));
+    
+    discrepancies
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_verify_row_counts() {
+        let content = "\
+-- Dumping data for table `users` (3 rows)
+INSERT INTO users VALUES(1);
+-- Dumping data for table 'orders' (1234 rows)
+-- Dumping data for table \"audit\" (1 row)
+INSERT INTO notes VALUES('
+-- Dumping data for table `ghost` (9 rows)
+');
+";
+        let hints = parse_row_count_hints(content);
+        assert_eq!(hints.len(), 3);
+        assert_eq!(hints["orders"], 1234);
+        
+        let extracted = HashMap::from([
+            ("users".to_string(), 3),
+            ("orders".to_string(), 1200),
+        ]);
+        assert_eq!(verify_row_counts(&hints, &extracted), vec![
+            RowCountDiscrepancy { table: "audit".to_string(), expected: 1, extracted: None },
+            RowCountDiscrepancy { table: "orders".to_string(), expected: 1234, extracted: Some(1200) },
+        ]);
+    }
+    
+    #[test]
+    fn test_hints_of_several_databases() {
+        let content = "\
+USE `shop`;
+-- Dumping data for table `orders` (2 rows)
+INSERT INTO orders VALUES(1),(2);
+USE `crm`;
+-- Dumping data for table `orders` (1 row)
+INSERT INTO orders VALUES(1);
+";
+        let hints = parse_row_count_hints(content);
+        assert_eq!(hints, HashMap::from([("shop.orders".to_string(), 2), ("crm.orders".to_string(), 1)]));
+    }
+}
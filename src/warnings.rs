@@ -0,0 +1,41 @@
+//! Delivery of warnings raised while converting, so library users can capture
+//! them instead of finding them on stderr.
+
+use std::sync::Mutex;
+
+/// Receives warnings, such as date values that could not be parsed. Any
+/// `Fn(String)` closure can be used as a handler.
+pub trait WarningHandler: Sync {
+    fn warn(&self, message: String);
+}
+
+impl<F: Fn(String) + Sync> WarningHandler for F {
+    fn warn(&self, message: String) {
+        self(message)
+    }
+}
+
+/// Prints warnings to stderr, as the command line does
+pub struct StderrWarnings;
+
+impl WarningHandler for StderrWarnings {
+    fn warn(&self, message: String) {
+        eprintln!("Warning: {}", message);
+    }
+}
+
+/// Keeps warnings in memory
+#[derive(Debug, Default)]
+pub struct CollectedWarnings(Mutex<Vec<String>>);
+
+impl CollectedWarnings {
+    pub fn into_inner(self) -> Vec<String> {
+        self.0.into_inner().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl WarningHandler for CollectedWarnings {
+    fn warn(&self, message: String) {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).push(message);
+    }
+}
@@ -0,0 +1,44 @@
+//! Browser-friendly bindings, built with `wasm-pack build --target web -- --features wasm`.
+//!
+//! ```js
+//! import init, { convert_sql_to_csv } from "./pkg/table_to_csv.js";
+//! await init();
+//! for (const file of convert_sql_to_csv(sqlText)) {
+//!   download(file.name, file.csv);
+//! }
+//! ```
+
+use wasm_bindgen::prelude::*;
+
+/// A generated CSV file held in memory
+#[wasm_bindgen]
+pub struct CsvFile {
+    name: String,
+    csv: String,
+}
+
+#[wasm_bindgen]
+impl CsvFile {
+    /// Suggested file name, e.g. `users.csv`
+    #[wasm_bindgen(getter)]
+    pub fn name(&self) -> String {
+        self.name.clone()
+    }
+    
+    /// CSV text including the header row
+    #[wasm_bindgen(getter)]
+    pub fn csv(&self) -> String {
+        self.csv.clone()
+    }
+}
+
+/// Convert SQL dump text to one CSV file per table, entirely client-side
+#[wasm_bindgen]
+pub fn convert_sql_to_csv(sql: &str) -> Result<Vec<CsvFile>, JsError> {
+    let files = crate::convert::convert_sql_to_csv(sql)
+        .map_err(|e| JsError::new(&format!("{:#}", e)))?;
+    
+    Ok(files.into_iter()
+        .map(|(name, csv)| CsvFile { name, csv })
+        .collect())
+}
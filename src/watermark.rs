@@ -0,0 +1,222 @@
+//! Incremental exports from recurring dumps (`--watermark`, `--since-state`).
+//!
+//! Each watermarked table is exported only with the rows whose watermark
+//! column, such as `updated_at` or an auto-increment id, is past the largest
+//! value the previous run exported. The largest values are kept in a JSON
+//! state file, which is updated once the tables have been written:
+//!
+//! ```json
+//! { "tables": { "orders": { "column": "updated_at", "value": "2024-06-01 12:00:00" } } }
+//! ```
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::args::{flag_value, flag_values};
+use crate::date_filter::parse_datetime_value;
+
+/// The column whose largest exported value a table is resumed from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Watermark {
+    pub table: String,
+    pub column: String,
+}
+
+/// The largest value exported from a table
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableWatermark {
+    pub column: String,
+    pub value: String,
+}
+
+/// Contents of the state file, by table name
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WatermarkState {
+    #[serde(default)]
+    pub tables: BTreeMap<String, TableWatermark>,
+}
+
+/// Watermarked tables and the state of the previous run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Incremental {
+    pub state_path: PathBuf,
+    pub watermarks: Vec<Watermark>,
+    /// Empty on the first run, when the state file does not exist yet
+    pub state: WatermarkState,
+}
+
+impl Incremental {
+    /// The watermark of a table and the largest value the previous run
+    /// exported from it, if the column is the same
+    pub fn for_table(&self, table: &str) -> Option<(&Watermark, Option<&str>)> {
+        let watermark = self.watermarks.iter().find(|w| w.table == table)?;
+        let previous = self.state.tables.get(table)
+            .filter(|previous| previous.column == watermark.column)
+            .map(|previous| previous.value.as_str());
+        Some((watermark, previous))
+    }
+}
+
+/// Parse --watermark options (repeatable) and --since-state from command line,
+/// and read the state file if it exists
+pub fn parse_incremental(args: &[String]) -> Result<Option<Incremental>> {
+    let watermarks = flag_values(args, "--watermark")?
+        .into_iter()
+        .map(parse_watermark)
+        .collect::<Result<Vec<_>>>()?;
+    let state_path = flag_value(args, "--since-state")?;
+    // A table stopped at an output limit would record a watermark past rows it did not write
+    if !watermarks.is_empty() && flag_value(args, "--on-limit")? == Some("stop-table") {
+        anyhow::bail!("Error: --watermark cannot be combined with --on-limit stop-table");
+    }
+    
+    match (state_path, watermarks.is_empty()) {
+        (None, true) => Ok(None),
+        (None, false) => anyhow::bail!("Error: --watermark requires --since-state <state.json>"),
+        (Some(_), true) => anyhow::bail!("Error: --since-state requires at least one --watermark <table>:<column>"),
+        (Some(path), false) => Ok(Some(Incremental {
+            state_path: PathBuf::from(path),
+            watermarks,
+            state: load_state(Path::new(path))?,
+        })),
+    }
+}
+
+/// Parse a single `<table>:<column>` specification
+pub fn parse_watermark(spec: &str) -> Result<Watermark> {
+    let Some((table, column)) = spec.split_once(':').filter(|(table, column)| !table.is_empty() && !column.is_empty()) else {
+        anyhow::bail!("Error: Invalid --watermark '{}'. Use <table>:<column>, e.g. orders:updated_at", spec);
+    };
+    Ok(Watermark { table: table.to_string(), column: column.to_string() })
+}
+
+/// Read the state file; a missing file is an empty state
+pub fn load_state(path: &Path) -> Result<WatermarkState> {
+    if !path.exists() {
+        return Ok(WatermarkState::default());
+    }
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read state file '{}'", path.display()))?;
+    serde_json::from_str(&text)
+        .with_context(|| format!("Error in state file '{}'", path.display()))
+}
+
+/// Write the state file, replacing the previous one only once it is complete
+pub fn save_state(path: &Path, state: &WatermarkState) -> Result<()> {
+    let json = serde_json::to_string_pretty(state).context("Failed to serialize watermark state")?;
+    let mut temp = path.as_os_str().to_os_string();
+    temp.push(".tmp");
+    fs::write(&temp, json + "\n")
+        .with_context(|| format!("Failed to write state file '{}'", path.display()))?;
+    fs::rename(&temp, path)
+        .with_context(|| format!("Failed to write state file '{}'", path.display()))
+}
+
+/// Order watermark values: as numbers if both are, then as dates or datetimes,
+/// otherwise as text
+pub fn compare_values(a: &str, b: &str) -> Ordering {
+    let (a, b) = (a.trim(), b.trim());
+    if let (Ok(x), Ok(y)) = (a.parse::<i128>(), b.parse::<i128>()) {
+        return x.cmp(&y);
+    }
+    if let (Ok(x), Ok(y)) = (a.parse::<f64>(), b.parse::<f64>()) {
+        return x.total_cmp(&y);
+    }
+    if let (Some(x), Some(y)) = (parse_datetime_value(a), parse_datetime_value(b)) {
+        return x.cmp(&y);
+    }
+    a.cmp(b)
+}
+
+/// Keep the rows whose watermark column is past the previous run's largest
+/// value (all rows on the first run). Rows with an empty or NULL watermark
+/// are left out, since they cannot be placed.
+pub fn apply_watermark(
+    headers: &[String],
+    rows: Vec<Vec<String>>,
+    watermark: &Watermark,
+    previous: Option<&str>,
+) -> Result<Vec<Vec<String>>> {
+    let Some(index) = headers.iter().position(|h| h == &watermark.column) else {
+        anyhow::bail!("Watermark column '{}' not found in table '{}'", watermark.column, watermark.table);
+    };
+    
+    Ok(rows.into_iter()
+        .filter(|row| row.get(index).is_some_and(|value| {
+            !value.is_empty()
+                && !value.eq_ignore_ascii_case("NULL")
+                && previous.is_none_or(|previous| compare_values(value, previous) == Ordering::Greater)
+        }))
+        .collect())
+}
+
+/// The largest watermark among rows that `apply_watermark` kept. Take it
+/// from the rows that are written, after every other filter, so the next run
+/// does not skip rows this one left out.
+pub fn largest_watermark(headers: &[String], rows: &[Vec<String>], watermark: &Watermark) -> Option<String> {
+    let index = headers.iter().position(|h| h == &watermark.column)?;
+    rows.iter()
+        .filter_map(|row| row.get(index).map(String::as_str))
+        .max_by(|a, b| compare_values(a, b))
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn rows(values: &[(&str, &str)]) -> Vec<Vec<String>> {
+        values.iter().map(|(id, at)| vec![id.to_string(), at.to_string()]).collect()
+    }
+    
+    #[test]
+    fn test_compare_values() {
+        assert_eq!(compare_values("9", "10"), Ordering::Less);
+        assert_eq!(compare_values("2024-06-01 09:00:00", "2024-06-01T10:00:00"), Ordering::Less);
+        assert_eq!(compare_values("2024-06-02", "2024-06-01 23:59:59"), Ordering::Greater);
+        assert_eq!(compare_values("b", "a"), Ordering::Greater);
+    }
+    
+    #[test]
+    fn test_apply_watermark() {
+        let headers = vec!["id".to_string(), "updated_at".to_string()];
+        let watermark = parse_watermark("orders:updated_at").unwrap();
+        let data = rows(&[
+            ("1", "2024-06-01 09:00:00"),
+            ("2", "2024-06-01 12:00:00"),
+            ("3", "2024-06-02 08:30:00"),
+            ("4", "NULL"),
+        ]);
+        
+        let all = apply_watermark(&headers, data.clone(), &watermark, None).unwrap();
+        assert_eq!(all.len(), 3);
+        assert_eq!(largest_watermark(&headers, &all, &watermark).as_deref(), Some("2024-06-02 08:30:00"));
+        // Rows a later filter leaves out do not move the watermark
+        assert_eq!(largest_watermark(&headers, &all[..2], &watermark).as_deref(), Some("2024-06-01 12:00:00"));
+        
+        let newer = apply_watermark(&headers, data.clone(), &watermark, Some("2024-06-01 12:00:00")).unwrap();
+        assert_eq!(newer, rows(&[("3", "2024-06-02 08:30:00")]));
+        
+        let none = apply_watermark(&headers, data, &watermark, Some("2024-06-02 08:30:00")).unwrap();
+        assert!(none.is_empty());
+        assert_eq!(largest_watermark(&headers, &none, &watermark), None);
+        
+        assert!(apply_watermark(&headers, Vec::new(), &parse_watermark("orders:created").unwrap(), None).is_err());
+    }
+    
+    #[test]
+    fn test_state_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json");
+        assert_eq!(load_state(&path).unwrap(), WatermarkState::default());
+        
+        let mut state = WatermarkState::default();
+        state.tables.insert("orders".to_string(), TableWatermark { column: "id".to_string(), value: "42".to_string() });
+        save_state(&path, &state).unwrap();
+        assert_eq!(load_state(&path).unwrap(), state);
+    }
+}
@@ -0,0 +1,150 @@
+//! Temporary workspace of a run (`--temp-dir`).
+//!
+//! Temporary files that are not output, such as rows spilled under
+//! `--max-memory`, are written to a directory of the run's own,
+//! `parsley-<pid>` under `--temp-dir` or the system temp directory. The
+//! directory is created when the first file is needed and removed with all
+//! its files when the run ends, whether it succeeds or fails. When a run is
+//! interrupted (see `interrupt`) it is removed as well, together with the
+//! `.tmp` output files still being written, so no partial files are left behind.
+
+use anyhow::{Context, Result};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+use crate::args::flag_value;
+
+/// Files of the run that are removed if it is interrupted
+struct State {
+    /// Directory the workspace is created in, once a `TempWorkspace` is set up
+    base: Option<PathBuf>,
+    /// The workspace, once created
+    dir: Option<PathBuf>,
+    /// Output files still being written
+    partials: BTreeSet<PathBuf>,
+}
+
+static STATE: Mutex<State> = Mutex::new(State { base: None, dir: None, partials: BTreeSet::new() });
+
+fn state() -> MutexGuard<'static, State> {
+    STATE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl State {
+    fn file_path(&mut self, name: &str) -> Result<PathBuf> {
+        let Some(ref base) = self.base else {
+            return Ok(std::env::temp_dir().join(format!("parsley-{}-{}", std::process::id(), name)));
+        };
+        let dir = match self.dir {
+            Some(ref dir) => dir,
+            None => {
+                let dir = base.join(format!("parsley-{}", std::process::id()));
+                fs::create_dir_all(&dir)
+                    .with_context(|| format!("Failed to create temporary directory '{}'", dir.display()))?;
+                self.dir.insert(dir)
+            }
+        };
+        Ok(dir.join(name))
+    }
+    
+    /// Remove the workspace directory and the partial output files, returning
+    /// the number of output files removed
+    fn remove(&mut self) -> usize {
+        if let Some(dir) = self.dir.take() {
+            let _ = fs::remove_dir_all(dir);
+        }
+        let partials = std::mem::take(&mut self.partials);
+        partials.iter().filter(|path| fs::remove_file(path).is_ok()).count()
+    }
+}
+
+/// Parse --temp-dir option from command line. The directory must exist.
+pub fn parse_temp_dir(args: &[String]) -> Result<Option<PathBuf>> {
+    let Some(dir) = flag_value(args, "--temp-dir")? else {
+        return Ok(None);
+    };
+    if !Path::new(dir).is_dir() {
+        anyhow::bail!("Error: --temp-dir '{}' is not an existing directory", dir);
+    }
+    Ok(Some(PathBuf::from(dir)))
+}
+
+/// The run's workspace. Dropping it removes the workspace directory, so it is
+/// kept for as long as the run goes on.
+#[derive(Debug)]
+pub struct TempWorkspace {
+    _private: (),
+}
+
+impl TempWorkspace {
+    /// Have temporary files written to a workspace under `base`, or under the
+    /// system temp directory
+    pub fn new(base: Option<PathBuf>) -> Self {
+        state().base = Some(base.unwrap_or_else(std::env::temp_dir));
+        TempWorkspace { _private: () }
+    }
+}
+
+impl Drop for TempWorkspace {
+    fn drop(&mut self) {
+        let mut state = state();
+        state.base = None;
+        state.remove();
+    }
+}
+
+/// Path for a temporary file called `name`, in the workspace when there is
+/// one. Without a `TempWorkspace`, as when the crate is used as a library, the
+/// file goes straight to the system temp directory under a name with the
+/// process id.
+pub fn temp_file_path(name: &str) -> Result<PathBuf> {
+    state().file_path(name)
+}
+
+/// Remember an output file being written under a temporary name, to remove it
+/// if the run is interrupted
+pub(crate) fn track_partial(path: &Path) {
+    state().partials.insert(path.to_path_buf());
+}
+
+/// Forget an output file that was finished or removed
+pub(crate) fn untrack_partial(path: &Path) {
+    state().partials.remove(path);
+}
+
+/// Remove the workspace and the output files still being written. Returns the
+/// number of partial output files removed.
+pub fn remove_temp_files() -> usize {
+    state().remove()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_workspace_and_partial_files_are_removed() {
+        let temp = tempfile::tempdir().unwrap();
+        let base = temp.path().to_path_buf();
+        
+        let partial = base.join("users.csv.tmp");
+        fs::write(&partial, "id\n").unwrap();
+        let mut state = State { base: Some(base.clone()), dir: None, partials: BTreeSet::from([partial.clone()]) };
+        let path = state.file_path("spill-0.csv").unwrap();
+        assert_eq!(path, base.join(format!("parsley-{}", std::process::id())).join("spill-0.csv"));
+        fs::write(&path, "1,a\n").unwrap();
+        
+        assert_eq!(state.remove(), 1);
+        assert!(!path.exists());
+        assert_eq!(fs::read_dir(&base).unwrap().count(), 0);
+    }
+    
+    #[test]
+    fn test_parse_temp_dir() {
+        let args = |line: &str| line.split_whitespace().map(str::to_string).collect::<Vec<_>>();
+        assert_eq!(parse_temp_dir(&args("t dump.sql")).unwrap(), None);
+        assert!(parse_temp_dir(&args("t dump.sql --temp-dir /nonexistent/parsley")).is_err());
+    }
+}
@@ -0,0 +1,271 @@
+//! Bounded queue of write jobs between row extraction and output sinks.
+//!
+//! Extraction is usually faster than writing to slow destinations such as a
+//! network filesystem. Prepared tables wait in a queue of limited size for one
+//! of the writer threads, and extraction blocks while the queue is full, so
+//! only a bounded number of tables is held in memory at once.
+
+use anyhow::Result;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::args::{flag_value, has_flag};
+use crate::sink::OutputSink;
+use crate::spill::{write_buffered_table, RowBuffer};
+use crate::types::Table;
+
+/// Writer threads used when `--write-threads` is not given
+pub const DEFAULT_WRITE_THREADS: usize = 2;
+
+/// Tables waiting to be written when `--queue-size` is not given
+pub const DEFAULT_QUEUE_SIZE: usize = 4;
+
+/// Size of the writer pool
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WriterPoolOptions {
+    pub write_threads: usize,
+    /// Tables that can wait for a writer before extraction blocks
+    pub queue_size: usize,
+    /// Do not print a line for every written table (--quiet, --porcelain)
+    pub quiet: bool,
+}
+
+impl Default for WriterPoolOptions {
+    fn default() -> Self {
+        WriterPoolOptions {
+            write_threads: DEFAULT_WRITE_THREADS,
+            queue_size: DEFAULT_QUEUE_SIZE,
+            quiet: false,
+        }
+    }
+}
+
+/// Parse --threads and --serial: the number of threads extracting tables, when
+/// it is limited. By default rayon uses one thread per core.
+pub fn parse_threads(args: &[String]) -> Result<Option<usize>> {
+    let threads = flag_value(args, "--threads")?;
+    if has_flag(args, "--serial") {
+        if threads.is_some() {
+            anyhow::bail!("Error: --serial and --threads cannot be used together");
+        }
+        return Ok(Some(1));
+    }
+    
+    threads.map(|value| value.parse::<usize>()
+        .ok()
+        .filter(|&n| n > 0)
+        .ok_or_else(|| anyhow::anyhow!("Error: Invalid --threads '{}'. Use a positive number", value)))
+        .transpose()
+}
+
+/// Limit the global rayon pool used for extraction to the given number of threads
+pub fn configure_threads(threads: usize) -> Result<()> {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build_global()
+        .map_err(|e| anyhow::anyhow!("Error: Failed to configure {} threads: {}", threads, e))
+}
+
+/// Parse --write-threads and --queue-size options from command line. With
+/// --serial, tables are also written by a single thread.
+pub fn parse_writer_pool_options(args: &[String]) -> Result<WriterPoolOptions> {
+    let positive = |flag: &str, default: usize| -> Result<usize> {
+        match flag_value(args, flag)? {
+            Some(value) => value.parse::<usize>()
+                .ok()
+                .filter(|&n| n > 0)
+                .ok_or_else(|| anyhow::anyhow!("Error: Invalid {} '{}'. Use a positive number", flag, value)),
+            None => Ok(default),
+        }
+    };
+    
+    let default_write_threads = if has_flag(args, "--serial") { 1 } else { DEFAULT_WRITE_THREADS };
+    Ok(WriterPoolOptions {
+        write_threads: positive("--write-threads", default_write_threads)?,
+        queue_size: positive("--queue-size", DEFAULT_QUEUE_SIZE)?,
+        quiet: has_flag(args, "--quiet") || has_flag(args, "--porcelain"),
+    })
+}
+
+/// A table ready to be written
+pub struct WriteJob<'a> {
+    pub table: &'a Table,
+    /// File stem, as passed to `OutputSink::open_table`
+    pub name: String,
+    pub headers: Vec<String>,
+    pub rows: RowBuffer,
+}
+
+/// Outcome of a write job
+pub struct WriteResult {
+    pub table: String,
+    pub name: String,
+    pub rows: usize,
+    /// Where the table was written
+    pub location: Result<String>,
+    /// Size of the output, when the sink knows it
+    pub bytes: Option<u64>,
+    /// Time spent writing the table
+    pub elapsed: Duration,
+}
+
+/// Queues tables for the writer threads
+pub struct TableWriter<'a> {
+    sender: SyncSender<WriteJob<'a>>,
+    /// Set when a writer thread fails to write a table
+    failed: Arc<AtomicBool>,
+}
+
+impl<'a> TableWriter<'a> {
+    /// Queue a table, waiting while the queue is full
+    pub fn submit(&self, job: WriteJob<'a>) -> Result<()> {
+        self.sender.send(job)
+            .map_err(|_| anyhow::anyhow!("Error: The writer threads have stopped"))
+    }
+    
+    /// Whether a table queued so far could not be written
+    pub fn has_failed(&self) -> bool {
+        self.failed.load(Ordering::Relaxed)
+    }
+}
+
+/// Run `produce` with a writer that hands tables to a pool of writer threads,
+/// and wait until every queued table is written. Returns the result of
+/// `produce` and the write results in the order the tables finished.
+pub fn with_writer_pool<'a, R>(
+    sink: &'a dyn OutputSink,
+    options: WriterPoolOptions,
+    produce: impl FnOnce(&TableWriter<'a>) -> R,
+) -> (R, Vec<WriteResult>) {
+    let (sender, receiver) = sync_channel::<WriteJob<'a>>(options.queue_size);
+    let receiver = Mutex::new(receiver);
+    let results = Mutex::new(Vec::new());
+    let failed = Arc::new(AtomicBool::new(false));
+    
+    let produced = thread::scope(|scope| {
+        for _ in 0..options.write_threads.max(1) {
+            scope.spawn(|| write_jobs(sink, &receiver, &results, &failed, options.quiet));
+        }
+        
+        // The writer is dropped when `produce` returns, which closes the queue
+        // and lets the writer threads finish
+        let writer = TableWriter { sender, failed: failed.clone() };
+        produce(&writer)
+    });
+    
+    (produced, results.into_inner().unwrap_or_else(|e| e.into_inner()))
+}
+
+fn write_jobs<'a>(sink: &'a dyn OutputSink, receiver: &Mutex<Receiver<WriteJob<'a>>>, results: &Mutex<Vec<WriteResult>>, failed: &AtomicBool, quiet: bool) {
+    loop {
+        // Hold the lock only while waiting for the next job
+        let job = match receiver.lock() {
+            Ok(receiver) => receiver.recv(),
+            Err(_) => return,
+        };
+        let Ok(job) = job else { return };
+        
+        let started = Instant::now();
+        let (location, bytes) = match write_buffered_table(sink, job.table, &job.name, &job.headers, &job.rows) {
+            Ok((location, bytes)) => (Ok(location), bytes),
+            Err(e) => {
+                failed.store(true, Ordering::Relaxed);
+                (Err(e), None)
+            }
+        };
+        if let Ok(ref location) = location
+            && !quiet
+        {
+            println!("Created {} with {} rows", location, job.rows.len());
+        }
+        
+        let result = WriteResult {
+            table: job.table.name.clone(),
+            name: job.name,
+            rows: job.rows.len(),
+            location,
+            bytes,
+            elapsed: started.elapsed(),
+        };
+        results.lock().unwrap_or_else(|e| e.into_inner()).push(result);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sink::MemorySink;
+    
+    #[test]
+    fn test_writer_pool_writes_every_table() {
+        let sink = MemorySink::default();
+        let tables: Vec<Table> = (0..10)
+            .map(|i| Table { name: format!("t{}", i), columns: vec!["id".to_string()], ..Default::default() })
+            .collect();
+        let options = WriterPoolOptions { write_threads: 3, queue_size: 1, quiet: false };
+        
+        let (submitted, results) = with_writer_pool(&sink, options, |writer| {
+            tables.iter()
+                .map(|table| writer.submit(WriteJob {
+                    table,
+                    name: table.name.clone(),
+                    headers: table.columns.clone(),
+                    rows: vec![vec!["1".to_string()]].into(),
+                }))
+                .filter(Result::is_ok)
+                .count()
+        });
+        
+        assert_eq!(submitted, 10);
+        assert_eq!(results.len(), 10);
+        assert!(results.iter().all(|result| result.location.is_ok() && result.rows == 1));
+        assert_eq!(sink.into_files().len(), 10);
+    }
+    
+    #[test]
+    fn test_writer_reports_failed_tables() {
+        let sink = crate::csv_writer::CsvSink { dir: std::path::PathBuf::from("/nonexistent/parsley"), options: Default::default() };
+        let table = Table { name: "users".to_string(), columns: vec!["id".to_string()], ..Default::default() };
+        let options = WriterPoolOptions { write_threads: 1, queue_size: 1, quiet: true };
+        
+        let (failed, results) = with_writer_pool(&sink, options, |writer| {
+            assert!(!writer.has_failed());
+            writer.submit(WriteJob { table: &table, name: "users".to_string(), headers: table.columns.clone(), rows: vec![vec!["1".to_string()]].into() }).unwrap();
+            let started = Instant::now();
+            while !writer.has_failed() && started.elapsed() < Duration::from_secs(10) {
+                thread::sleep(Duration::from_millis(1));
+            }
+            writer.has_failed()
+        });
+        
+        assert!(failed);
+        assert!(results[0].location.is_err());
+    }
+    
+    #[test]
+    fn test_parse_writer_pool_options() {
+        let args: Vec<String> = ["--write-threads", "8", "--queue-size", "16"].iter().map(|s| s.to_string()).collect();
+        assert_eq!(parse_writer_pool_options(&args).unwrap(), WriterPoolOptions { write_threads: 8, queue_size: 16, quiet: false });
+        assert_eq!(parse_writer_pool_options(&[]).unwrap(), WriterPoolOptions::default());
+        
+        let args: Vec<String> = ["--queue-size", "0"].iter().map(|s| s.to_string()).collect();
+        assert!(parse_writer_pool_options(&args).is_err());
+    }
+    
+    #[test]
+    fn test_parse_threads() {
+        let args = |extra: &[&str]| -> Vec<String> {
+            ["prog", "dump.sql"].iter().chain(extra).map(|s| s.to_string()).collect()
+        };
+        
+        assert_eq!(parse_threads(&args(&[])).unwrap(), None);
+        assert_eq!(parse_threads(&args(&["--threads", "3"])).unwrap(), Some(3));
+        assert_eq!(parse_threads(&args(&["--serial"])).unwrap(), Some(1));
+        assert!(parse_threads(&args(&["--threads", "0"])).is_err());
+        assert!(parse_threads(&args(&["--serial", "--threads", "2"])).is_err());
+        assert_eq!(parse_writer_pool_options(&args(&["--serial"])).unwrap().write_threads, 1);
+    }
+}
@@ -0,0 +1,104 @@
+//! Tests of the command line tool, run as a separate process in a temporary
+//! directory.
+
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+/// Run the tool in `dir` with the given arguments, asserting that it succeeds
+fn run(dir: &Path, args: &[&str]) -> Output {
+    let output = Command::new(env!("CARGO_BIN_EXE_table-to-csv"))
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .expect("Failed to run table-to-csv");
+    assert!(output.status.success(), "table-to-csv {:?} failed:\n{}", args, String::from_utf8_lossy(&output.stderr));
+    output
+}
+
+#[test]
+fn test_quiet_diff_keeps_stdout_empty() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("old.sql"), "CREATE TABLE users (id INT PRIMARY KEY, name TEXT);\nINSERT INTO users VALUES (1, 'Al');\n").unwrap();
+    fs::write(dir.path().join("new.sql"), "CREATE TABLE users (id INT PRIMARY KEY, name TEXT);\nINSERT INTO users VALUES (1, 'Al'), (2, 'Bo');\n").unwrap();
+    
+    let output = run(dir.path(), &["diff", "old.sql", "new.sql"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Differences from old.sql to new.sql:"), "{}", stdout);
+    assert!(stdout.contains("users: 1 added, 0 removed, 0 changed"), "{}", stdout);
+    
+    let output = run(dir.path(), &["diff", "old.sql", "new.sql", "--quiet"]);
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "");
+    assert_eq!(fs::read_to_string(dir.path().join("users_added.csv")).unwrap(), "id,name\n2,Bo\n");
+}
+
+/// A dump of several tables, so parallel runs can finish them in any order
+fn several_tables() -> String {
+    ["zebras", "apples", "mangos", "kiwis", "bananas", "cherries"].iter()
+        .map(|table| format!(
+            "CREATE TABLE {table} (id INT PRIMARY KEY, name TEXT);\nINSERT INTO {table} VALUES {};\n",
+            (1..=200).map(|i| format!("({i}, '{table} {i}')")).collect::<Vec<_>>().join(", ")
+        ))
+        .collect()
+}
+
+/// The files a run wrote to `out`, by name
+fn written_files(dir: &Path) -> Vec<(String, Vec<u8>)> {
+    let mut files: Vec<(String, Vec<u8>)> = fs::read_dir(dir.join("out")).unwrap()
+        .map(|entry| entry.unwrap().path())
+        .map(|path| (path.file_name().unwrap().to_string_lossy().to_string(), fs::read(&path).unwrap()))
+        .collect();
+    files.sort();
+    files
+}
+
+#[test]
+fn test_deterministic_runs_are_identical() {
+    let runs: Vec<_> = (0..2)
+        .map(|_| {
+            let dir = tempfile::tempdir().unwrap();
+            fs::write(dir.path().join("dump.sql"), several_tables()).unwrap();
+            let summary = run(dir.path(), &["dump.sql", "--output", "out", "--deterministic"]).stdout;
+            let manifest = run(dir.path(), &["dump.sql", "--output", "out", "--deterministic", "--porcelain"]).stdout;
+            (String::from_utf8(summary).unwrap(), String::from_utf8(manifest).unwrap(), written_files(dir.path()))
+        })
+        .collect();
+    
+    assert_eq!(runs[0], runs[1]);
+    let (summary, manifest, files) = &runs[0];
+    assert_eq!(files.len(), 6);
+    // The summary lists the tables in order, without durations
+    let rows: Vec<Vec<&str>> = summary.lines()
+        .filter(|line| line.ends_with(".csv"))
+        .map(|line| line.split('|').map(str::trim).collect())
+        .collect();
+    assert_eq!(rows.iter().map(|row| row[0]).collect::<Vec<_>>(), vec!["apples", "bananas", "cherries", "kiwis", "mangos", "zebras"]);
+    assert!(rows.iter().all(|row| row[5] == "-"), "{}", summary);
+    
+    let manifest: serde_json::Value = serde_json::from_str(manifest).unwrap();
+    assert!(!manifest.to_string().contains("duration_ms"), "{}", manifest);
+    let tables: Vec<&str> = manifest["tables"].as_array().unwrap().iter().map(|table| table["table"].as_str().unwrap()).collect();
+    assert_eq!(tables, vec!["apples", "bananas", "cherries", "kiwis", "mangos", "zebras"]);
+}
+
+#[test]
+fn test_schema_only_writes_headers_without_reading_rows() {
+    let dir = tempfile::tempdir().unwrap();
+    // The short row would stop a full run under --on-table-error fail
+    fs::write(dir.path().join("dump.sql"), "CREATE TABLE users (id INT, name TEXT);\n\
+INSERT INTO users VALUES (1, 'Al'), (2);\n\
+CREATE TABLE empty (id INT);\n").unwrap();
+    
+    let output = run(dir.path(), &["dump.sql", "--output", "out", "--schema-only", "--add-source-line", "--on-mismatch", "error", "--on-table-error", "fail", "--porcelain"]);
+    
+    assert_eq!(fs::read_to_string(dir.path().join("out/users.csv")).unwrap(), "id,name,_source_line\n");
+    assert_eq!(fs::read_to_string(dir.path().join("out/empty.csv")).unwrap(), "id,_source_line\n");
+    let manifest: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    for table in manifest["tables"].as_array().unwrap() {
+        assert_eq!((&table["rows_in"], &table["rows_written"]), (&serde_json::json!(0), &serde_json::json!(0)), "{}", table);
+    }
+    
+    let output = run(dir.path(), &["dump.sql", "--output", "out", "--schema-only", "--porcelain"]);
+    assert_eq!(fs::read_to_string(dir.path().join("out/users.csv")).unwrap(), "id,name\n");
+    assert_eq!(serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap()["tables"].as_array().unwrap().len(), 2);
+}
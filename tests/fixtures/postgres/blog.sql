@@ -0,0 +1,65 @@
+--
+-- PostgreSQL database dump
+--
+
+-- Dumped from database version 15.4
+-- Dumped by pg_dump version 15.4
+
+SET statement_timeout = 0;
+SET client_encoding = 'UTF8';
+SET standard_conforming_strings = on;
+SELECT pg_catalog.set_config('search_path', '', false);
+
+SET default_tablespace = '';
+
+--
+-- Name: authors; Type: TABLE; Schema: public; Owner: blog
+--
+
+CREATE TABLE public.authors (
+    id integer NOT NULL,
+    name character varying(80) NOT NULL,
+    bio text,
+    active boolean DEFAULT true NOT NULL
+);
+
+ALTER TABLE public.authors OWNER TO blog;
+
+--
+-- Name: posts; Type: TABLE; Schema: public; Owner: blog
+--
+
+CREATE TABLE public.posts (
+    id integer NOT NULL,
+    author_id integer NOT NULL,
+    title text NOT NULL,
+    tags text[],
+    published_at timestamp with time zone
+);
+
+ALTER TABLE public.posts OWNER TO blog;
+
+--
+-- Data for Name: authors; Type: TABLE DATA; Schema: public; Owner: blog
+--
+
+INSERT INTO public.authors VALUES (1, 'Ada', 'Writes about "engines"', true);
+INSERT INTO public.authors VALUES (2, 'Grace', 'It''s compilers, mostly', false);
+
+--
+-- Data for Name: posts; Type: TABLE DATA; Schema: public; Owner: blog
+--
+
+INSERT INTO public.posts VALUES (1, 1, 'Notes on the Analytical Engine', '{math,history}', '2024-05-01 09:00:00+00');
+INSERT INTO public.posts VALUES (2, 2, 'Bugs, literally', '{}', NULL);
+
+--
+-- Name: authors authors_pkey; Type: CONSTRAINT; Schema: public; Owner: blog
+--
+
+ALTER TABLE ONLY public.authors
+    ADD CONSTRAINT authors_pkey PRIMARY KEY (id);
+
+--
+-- PostgreSQL database dump complete
+--
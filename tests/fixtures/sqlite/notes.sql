@@ -0,0 +1,21 @@
+PRAGMA foreign_keys=OFF;
+BEGIN TRANSACTION;
+CREATE TABLE notes (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  title TEXT NOT NULL,
+  body TEXT,
+  pinned INTEGER DEFAULT 0
+);
+INSERT INTO notes VALUES(1,'Groceries','milk, eggs
+bread',0);
+INSERT INTO notes VALUES(2,'Quote','She said ''hi''',1);
+INSERT INTO notes VALUES(3,'Empty',NULL,0);
+CREATE TABLE IF NOT EXISTS "tags" (
+  "note_id" INTEGER,
+  "tag" TEXT
+);
+INSERT INTO tags VALUES(1,'home');
+INSERT INTO tags VALUES(2,'people');
+DELETE FROM sqlite_sequence;
+INSERT INTO sqlite_sequence VALUES('notes',3);
+COMMIT;
@@ -0,0 +1,88 @@
+//! Golden-file tests: every `tests/fixtures/<dialect>/<name>.sql` dump is
+//! converted and compared with the CSVs in `tests/fixtures/<dialect>/<name>/`.
+//!
+//! To add a fixture, drop a dump into the dialect's directory and run
+//! `UPDATE_GOLDEN=1 cargo test --test golden` to write its expected CSVs, then
+//! review them before committing. The same command refreshes the expected
+//! output after an intended change in behavior.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use table_to_csv::convert_sql_to_csv;
+
+const FIXTURES: &str = "tests/fixtures";
+
+/// Every fixture dump, sorted by path
+fn fixture_dumps() -> Vec<PathBuf> {
+    let mut dumps: Vec<PathBuf> = fs::read_dir(FIXTURES)
+        .expect("Failed to read fixtures directory")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.is_dir())
+        .flat_map(|dialect| fs::read_dir(dialect).unwrap().map(|entry| entry.unwrap().path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "sql"))
+        .collect();
+    dumps.sort();
+    dumps
+}
+
+/// The expected CSVs of a dump, by file name
+fn read_expected(dir: &Path) -> BTreeMap<String, String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return BTreeMap::new();
+    };
+    entries
+        .map(|entry| entry.unwrap().path())
+        .map(|path| (path.file_name().unwrap().to_string_lossy().to_string(), fs::read_to_string(&path).unwrap()))
+        .collect()
+}
+
+fn write_expected(dir: &Path, files: &BTreeMap<String, String>) {
+    if dir.exists() {
+        fs::remove_dir_all(dir).unwrap();
+    }
+    fs::create_dir_all(dir).unwrap();
+    for (name, content) in files {
+        fs::write(dir.join(name), content).unwrap();
+    }
+}
+
+#[test]
+fn test_golden_fixtures() {
+    let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+    let dumps = fixture_dumps();
+    assert!(!dumps.is_empty(), "No fixtures found in {}", FIXTURES);
+    
+    let mut failures = Vec::new();
+    for dump in &dumps {
+        let sql = fs::read_to_string(dump).unwrap();
+        let actual: BTreeMap<String, String> = convert_sql_to_csv(&sql)
+            .unwrap_or_else(|e| panic!("Failed to convert {}: {:#}", dump.display(), e))
+            .into_iter()
+            .collect();
+        let expected_dir = dump.with_extension("");
+        
+        if update {
+            write_expected(&expected_dir, &actual);
+            continue;
+        }
+        
+        let expected = read_expected(&expected_dir);
+        if expected.keys().ne(actual.keys()) {
+            failures.push(format!(
+                "{}: expected files {:?}, got {:?}",
+                dump.display(), expected.keys().collect::<Vec<_>>(), actual.keys().collect::<Vec<_>>()
+            ));
+        }
+        for (name, content) in &actual {
+            if expected.get(name).is_some_and(|expected| expected != content) {
+                failures.push(format!(
+                    "{}: {} differs\n--- expected\n{}--- actual\n{}",
+                    dump.display(), name, expected[name], content
+                ));
+            }
+        }
+    }
+    
+    assert!(failures.is_empty(), "Golden files differ (run with UPDATE_GOLDEN=1 to accept):\n\n{}", failures.join("\n\n"));
+}
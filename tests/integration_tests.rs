@@ -1,4 +1,4 @@
-use table_to_csv::{parse_sql_file, extract_insert_values, write_csv, parse_table_columns};
+use table_to_csv::{parse_sql_file, parse_sql, parse_sql_content, extract_insert_values, write_csv, parse_table_columns, convert_sql_to_csv};
 use std::fs;
 use std::path::Path;
 
@@ -120,6 +120,7 @@ fn test_parse_table_columns_with_constraints() {
 }
 
 #[test]
+#[allow(clippy::expect_fun_call)]
 fn test_end_to_end_conversion() {
     let test_sql_path = "test.sql";
     
@@ -160,3 +161,47 @@ fn test_numeric_and_decimal_values() {
     assert_eq!(products_rows[2][2], "5.99");
 }
 
+#[test]
+fn test_create_index_attached_to_table() {
+    let sql = r#"
+CREATE TABLE accounts (
+    tenant_id INTEGER NOT NULL,
+    email TEXT NOT NULL,
+    PRIMARY KEY (tenant_id, email)
+);
+CREATE UNIQUE INDEX IF NOT EXISTS "accounts_email_idx" ON "accounts" ("email");
+CREATE INDEX accounts_tenant_idx ON accounts USING btree (tenant_id);
+"#;
+    
+    let (tables, _content) = parse_sql_content(sql.to_string()).expect("Failed to parse SQL");
+    
+    let accounts = &tables[0];
+    assert_eq!(accounts.primary_key, vec!["tenant_id", "email"]);
+    assert_eq!(accounts.indexes.len(), 2);
+    assert_eq!(accounts.indexes[0].name, "accounts_email_idx");
+    assert_eq!(accounts.indexes[0].columns, vec!["email"]);
+    assert!(accounts.indexes[0].unique);
+    assert_eq!(accounts.indexes[1].columns, vec!["tenant_id"]);
+    assert!(!accounts.indexes[1].unique);
+}
+
+#[test]
+fn test_convert_sql_to_csv_in_memory() {
+    let sql = fs::read_to_string("test.sql").expect("Failed to read test.sql");
+    
+    let files = convert_sql_to_csv(&sql).expect("Failed to convert SQL");
+    assert_eq!(files.len(), 2);
+    assert_eq!(files[0].0, "users.csv");
+    assert!(files[0].1.starts_with("id,name,email\n1,Alice Smith,alice@example.com\n"));
+    assert_eq!(files[1].0, "products.csv");
+    assert_eq!(files[1].1.lines().count(), 5);
+}
+
+#[test]
+fn test_parse_sql_from_reader() {
+    let file = fs::File::open("test.sql").expect("Failed to open test.sql");
+    
+    let (tables, content) = parse_sql(std::io::BufReader::new(file)).expect("Failed to parse from reader");
+    assert_eq!(tables.len(), 2);
+    assert_eq!(extract_insert_values(&content, "users").unwrap().len(), 3);
+}